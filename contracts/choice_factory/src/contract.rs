@@ -1,8 +1,8 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coin, to_json_binary, Addr, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Reply,
-    ReplyOn, Response, StdError, StdResult, SubMsg, WasmMsg, SubMsgResult
+    coin, to_json_binary, Addr, Binary, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Order,
+    Reply, ReplyOn, Response, StdError, StdResult, SubMsg, WasmMsg, SubMsgResult
 };
 use cw2::set_contract_version;
 use cw20::Cw20ExecuteMsg;
@@ -10,18 +10,19 @@ use choice::querier::{query_balance, query_pair_info_from_pair, query_token_fact
 
 use crate::response::MsgInstantiateContractResponse;
 use crate::state::{
-    add_allow_native_token, pair_key, read_pairs, Config, TmpPairInfo, ALLOW_NATIVE_TOKENS, CONFIG,
-    PAIRS, TMP_PAIR_INFO,
+    add_allow_native_token, index_pair, pair_key, read_pairs, read_pairs_by_asset, Config,
+    TmpPairInfo, ALLOW_NATIVE_TOKENS, CONFIG, PAIRS, TMP_PAIR_INFO,
 };
 
 use protobuf::Message;
-use choice::asset::{Asset, AssetInfo, AssetInfoRaw, PairInfo, PairInfoRaw};
+use choice::asset::{Asset, AssetInfo, AssetInfoRaw, PairInfo, PairInfoRaw, PairType};
 use choice::factory::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, NativeTokenDecimalsResponse,
-    PairsResponse, QueryMsg,
+    ConfigResponse, ExecuteMsg, FeeSplit, InstantiateMsg, MigrateMsg, NativeTokenDecimalsResponse,
+    PairsResponse, PausedResponse, QueryMsg,
 };
 use choice::pair::{
-    ExecuteMsg as PairExecuteMsg, InstantiateMsg as PairInstantiateMsg,
+    Cw20HookMsg as PairCw20HookMsg, ExecuteMsg as PairExecuteMsg,
+    InstantiateMsg as PairInstantiateMsg, ProtocolFeeMode,
     MigrateMsg as PairMigrateMsg,
 };
 use choice::util::migrate_version;
@@ -42,6 +43,8 @@ pub fn instantiate(
 ) -> StdResult<Response> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    assert_commission_rates(&msg.commission_rates)?;
+
     let config = Config {
         owner: deps.api.addr_canonicalize(info.sender.as_str())?,
         token_code_id: msg.token_code_id,
@@ -49,6 +52,10 @@ pub fn instantiate(
 
         burn_address: deps.api.addr_canonicalize(&msg.burn_address)?, // Store burn address
         fee_wallet_address: deps.api.addr_canonicalize(&msg.fee_wallet_address)?, // Store fee wallet address
+        commission_rates: msg.commission_rates,
+        fee_split: FeeSplit::default(),
+        guardian: None,
+        paused: false,
     };
 
     CONFIG.save(deps.storage, &config)?;
@@ -65,18 +72,51 @@ pub fn execute(deps: DepsMut<InjectiveQueryWrapper>, env: Env, info: MessageInfo
             pair_code_id,
             burn_address,       // New field
             fee_wallet_address, // New field
-        } => execute_update_config(deps, env, info, owner, token_code_id, pair_code_id, burn_address, fee_wallet_address),
-        ExecuteMsg::CreatePair { assets } => execute_create_pair(deps, env, info, assets),
+            commission_rates,
+        } => execute_update_config(
+            deps,
+            env,
+            info,
+            owner,
+            token_code_id,
+            pair_code_id,
+            burn_address,
+            fee_wallet_address,
+            commission_rates,
+        ),
+        ExecuteMsg::CreatePair {
+            assets,
+            commission_rate,
+            pair_type,
+        } => execute_create_pair(deps, env, info, assets, commission_rate, pair_type),
         ExecuteMsg::AddNativeTokenDecimals { denom, decimals } => {
             execute_add_native_token_decimals(deps, env, info, denom, decimals)
         }
         ExecuteMsg::MigratePair { contract, code_id } => {
             execute_migrate_pair(deps, env, info, contract, code_id)
         }
+        ExecuteMsg::UpdateFeeSplit { fee_split } => {
+            execute_update_fee_split(deps, env, info, fee_split)
+        }
+        ExecuteMsg::RampAmp {
+            pair,
+            future_amp,
+            future_time,
+        } => execute_ramp_amp(deps, env, info, pair, future_amp, future_time),
+        ExecuteMsg::StopRampAmp { pair } => execute_stop_ramp_amp(deps, env, info, pair),
+        ExecuteMsg::UpdateProtocolFeeMode { pair, mode } => {
+            execute_update_protocol_fee_mode(deps, env, info, pair, mode)
+        }
+        ExecuteMsg::UpdateGuardian { guardian } => {
+            execute_update_guardian(deps, env, info, guardian)
+        }
+        ExecuteMsg::PausePair { pair } => execute_set_pair_paused(deps, env, info, pair, true),
+        ExecuteMsg::UnpausePair { pair } => execute_set_pair_paused(deps, env, info, pair, false),
     }
 }
 
 // Only owner can execute it
+#[allow(clippy::too_many_arguments)]
 pub fn execute_update_config(
     deps: DepsMut<InjectiveQueryWrapper>,
     _env: Env,
@@ -86,6 +126,7 @@ pub fn execute_update_config(
     pair_code_id: Option<u64>,
     burn_address: Option<String>, // New field
     fee_wallet_address: Option<String>, // New field
+    commission_rates: Option<Vec<Decimal>>,
 ) -> StdResult<Response> {
     let mut config: Config = CONFIG.load(deps.storage)?;
 
@@ -117,17 +158,94 @@ pub fn execute_update_config(
         config.fee_wallet_address = deps.api.addr_canonicalize(&fee_wallet_address)?;
     }
 
+    if let Some(commission_rates) = commission_rates {
+        assert_commission_rates(&commission_rates)?;
+
+        config.commission_rates = commission_rates;
+    }
+
     CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new().add_attribute("action", "update_config"))
 }
 
+// Only owner can execute it
+pub fn execute_update_guardian(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    _env: Env,
+    info: MessageInfo,
+    guardian: Option<String>,
+) -> StdResult<Response> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    config.guardian = guardian
+        .as_ref()
+        .map(|guardian| {
+            // validate address format
+            deps.api.addr_validate(guardian)?;
+            deps.api.addr_canonicalize(guardian)
+        })
+        .transpose()?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_guardian"),
+        ("guardian", guardian.as_deref().unwrap_or("none")),
+    ]))
+}
+
+// Only owner can execute it
+pub fn execute_update_fee_split(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    _env: Env,
+    info: MessageInfo,
+    fee_split: FeeSplit,
+) -> StdResult<Response> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    fee_split.validate()?;
+
+    config.fee_split = fee_split;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "update_fee_split".to_string()),
+        ("lp_share", config.fee_split.lp.to_string()),
+        ("fee_wallet_share", config.fee_split.fee_wallet.to_string()),
+        ("burn_share", config.fee_split.burn.to_string()),
+    ]))
+}
+
+fn assert_commission_rates(commission_rates: &[Decimal]) -> StdResult<()> {
+    if commission_rates.is_empty() {
+        return Err(StdError::generic_err("at least one commission rate is required"));
+    }
+
+    if commission_rates.iter().any(|rate| *rate >= Decimal::one()) {
+        return Err(StdError::generic_err("commission rate must be less than 1"));
+    }
+
+    Ok(())
+}
+
 // Anyone can execute it to create swap pair
 pub fn execute_create_pair(
     deps: DepsMut<InjectiveQueryWrapper>,
     env: Env,
     info: MessageInfo,
     assets: [Asset; 2],
+    commission_rate: Option<Decimal>,
+    pair_type: Option<PairType>,
 ) -> StdResult<Response> {
     let config: Config = CONFIG.load(deps.storage)?;
 
@@ -135,6 +253,20 @@ pub fn execute_create_pair(
         return Err(StdError::generic_err("same asset"));
     }
 
+    let commission_rate = match commission_rate {
+        Some(commission_rate) => {
+            if !config.commission_rates.contains(&commission_rate) {
+                return Err(StdError::generic_err(format!(
+                    "commission rate {} is not allowed",
+                    commission_rate
+                )));
+            }
+
+            commission_rate
+        }
+        None => config.commission_rates[0],
+    };
+
     let asset_1_decimal = match assets[0]
         .info
         .query_decimals(env.contract.address.clone(), &deps.querier)
@@ -209,6 +341,8 @@ pub fn execute_create_pair(
                     asset_decimals,
                     burn_address: deps.api.addr_humanize(&config.burn_address)?.to_string(), // Pass burn address
                     fee_wallet_address: deps.api.addr_humanize(&config.fee_wallet_address)?.to_string(), // Pass fee wallet address
+                    commission_rate,
+                    pair_type: pair_type.unwrap_or_default(),
                 })?,
             }),
             reply_on: ReplyOn::Success,
@@ -286,6 +420,151 @@ pub fn execute_migrate_pair(
     )
 }
 
+// Only owner can execute it
+pub fn execute_ramp_amp(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    _env: Env,
+    info: MessageInfo,
+    pair: String,
+    future_amp: u64,
+    future_time: u64,
+) -> StdResult<Response> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let pair = deps.api.addr_validate(&pair)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: pair.to_string(),
+            msg: to_json_binary(&PairExecuteMsg::RampAmp {
+                future_amp,
+                future_time,
+            })?,
+            funds: vec![],
+        }))
+        .add_attributes(vec![("action", "ramp_amp"), ("pair", pair.as_str())]))
+}
+
+// Only owner can execute it
+pub fn execute_stop_ramp_amp(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    _env: Env,
+    info: MessageInfo,
+    pair: String,
+) -> StdResult<Response> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let pair = deps.api.addr_validate(&pair)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: pair.to_string(),
+            msg: to_json_binary(&PairExecuteMsg::StopRampAmp {})?,
+            funds: vec![],
+        }))
+        .add_attributes(vec![("action", "stop_ramp_amp"), ("pair", pair.as_str())]))
+}
+
+pub fn execute_update_protocol_fee_mode(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    _env: Env,
+    info: MessageInfo,
+    pair: String,
+    mode: ProtocolFeeMode,
+) -> StdResult<Response> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let pair = deps.api.addr_validate(&pair)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: pair.to_string(),
+            msg: to_json_binary(&PairExecuteMsg::UpdateProtocolFeeMode { mode })?,
+            funds: vec![],
+        }))
+        .add_attributes(vec![
+            ("action", "update_protocol_fee_mode"),
+            ("pair", pair.as_str()),
+        ]))
+}
+
+// Owner or guardian can execute it
+pub fn execute_set_pair_paused(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    _env: Env,
+    info: MessageInfo,
+    pair: Option<String>,
+    paused: bool,
+) -> StdResult<Response> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+
+    // permission check
+    let sender = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if sender != config.owner && Some(&sender) != config.guardian.as_ref() {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let action = if paused { "pause_pair" } else { "unpause_pair" };
+
+    // the global flag is read by every pair, nothing to iterate
+    let pair = match pair {
+        Some(pair) => pair,
+        None => {
+            config.paused = paused;
+            CONFIG.save(deps.storage, &config)?;
+
+            return Ok(Response::new().add_attributes(vec![("action", action), ("pair", "all")]));
+        }
+    };
+
+    // the pair tells its assets, the stored entry must point back at it
+    let pair_addr = deps.api.addr_validate(&pair)?;
+    let asset_infos = query_pair_info_from_pair(&deps.querier, pair_addr.clone())
+        .map_err(|_| StdError::generic_err("pair not found"))?
+        .asset_infos;
+    let pair_addr = deps.api.addr_canonicalize(pair_addr.as_str())?;
+    let pair_key = pair_key(&[
+        asset_infos[0].to_raw(deps.api)?,
+        asset_infos[1].to_raw(deps.api)?,
+    ]);
+    let mut pair_info = PAIRS
+        .may_load(deps.storage, &pair_key)?
+        .filter(|pair_info| pair_info.contract_addr == pair_addr)
+        .ok_or_else(|| StdError::generic_err("pair not found"))?;
+
+    pair_info.paused = paused;
+    PAIRS.save(deps.storage, &pair_key, &pair_info)?;
+
+    let pair_msg = if paused {
+        PairExecuteMsg::Pause {}
+    } else {
+        PairExecuteMsg::Unpause {}
+    };
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: pair.clone(),
+            msg: to_json_binary(&pair_msg)?,
+            funds: vec![],
+        }))
+        .add_attributes(vec![("action", action), ("pair", pair.as_str())]))
+}
+
 /// This just stores the result for future query
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut<InjectiveQueryWrapper>, env: Env, msg: Reply) -> StdResult<Response> {
@@ -324,18 +603,19 @@ pub fn reply(deps: DepsMut<InjectiveQueryWrapper>, env: Env, msg: Reply) -> StdR
     let burn_address = factory_config.burn_address.clone();
     let fee_wallet_address = factory_config.fee_wallet_address.clone();
 
-    PAIRS.save(
-        deps.storage,
-        &tmp_pair_info.pair_key,
-        &PairInfoRaw {
-            liquidity_token: pair_info.liquidity_token.clone(),
-            contract_addr: deps.api.addr_canonicalize(pair_contract)?,
-            asset_infos: raw_infos,
-            asset_decimals: tmp_pair_info.asset_decimals,
-            burn_address,       // Add burn address
-            fee_wallet_address, // Add fee wallet address
-        },
-    )?;
+    let pair_info_raw = PairInfoRaw {
+        liquidity_token: pair_info.liquidity_token.clone(),
+        contract_addr: deps.api.addr_canonicalize(pair_contract)?,
+        asset_infos: raw_infos,
+        asset_decimals: tmp_pair_info.asset_decimals,
+        burn_address,       // Add burn address
+        fee_wallet_address, // Add fee wallet address
+        commission_rate: pair_info.commission_rate,
+        pair_type: pair_info.pair_type,
+        paused: pair_info.paused,
+    };
+    PAIRS.save(deps.storage, &tmp_pair_info.pair_key, &pair_info_raw)?;
+    index_pair(deps.storage, &tmp_pair_info.pair_key, &pair_info_raw)?;
 
     let mut messages: Vec<CosmosMsg> = vec![];
     if !tmp_pair_info.assets[0].amount.is_zero() || !tmp_pair_info.assets[1].amount.is_zero() {
@@ -344,6 +624,7 @@ pub fn reply(deps: DepsMut<InjectiveQueryWrapper>, env: Env, msg: Reply) -> StdR
             tmp_pair_info.assets[1].to_normal(deps.api)?,
         ];
         let mut funds: Vec<Coin> = vec![];
+        let mut provide_messages: Vec<CosmosMsg> = vec![];
         for asset in tmp_pair_info.assets.iter() {
             if let AssetInfoRaw::NativeToken { denom, .. } = &asset.info {
                 funds.push(coin(asset.amount.u128(), denom.to_string()));
@@ -351,15 +632,6 @@ pub fn reply(deps: DepsMut<InjectiveQueryWrapper>, env: Env, msg: Reply) -> StdR
                 let contract_addr = deps.api.addr_humanize(contract_addr)?.to_string();
                 messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
                     contract_addr: contract_addr.to_string(),
-                    msg: to_json_binary(&Cw20ExecuteMsg::IncreaseAllowance {
-                        spender: pair_contract.to_string(),
-                        amount: asset.amount,
-                        expires: None,
-                    })?,
-                    funds: vec![],
-                }));
-                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr,
                     msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
                         owner: tmp_pair_info.sender.to_string(),
                         recipient: env.contract.address.to_string(),
@@ -367,20 +639,54 @@ pub fn reply(deps: DepsMut<InjectiveQueryWrapper>, env: Env, msg: Reply) -> StdR
                     })?,
                     funds: vec![],
                 }));
+
+                if provide_messages.is_empty() {
+                    // the first token leg is sent with the provide hook
+                    provide_messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr,
+                        msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                            contract: pair_contract.to_string(),
+                            amount: asset.amount,
+                            msg: to_json_binary(&PairCw20HookMsg::ProvideLiquidity {
+                                assets: assets.clone(),
+                                receiver: Some(tmp_pair_info.sender.to_string()),
+                                deadline: None,
+                                slippage_tolerance: None,
+                            })?,
+                        })?,
+                        funds: vec![],
+                    }));
+                } else {
+                    // the second one is pulled by the pair
+                    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr,
+                        msg: to_json_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+                            spender: pair_contract.to_string(),
+                            amount: asset.amount,
+                            expires: None,
+                        })?,
+                        funds: vec![],
+                    }));
+                }
             }
         }
 
-        funds.sort_by(|a, b| a.denom.cmp(&b.denom));
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: pair_contract.to_string(),
-            msg: to_json_binary(&PairExecuteMsg::ProvideLiquidity {
-                assets,
-                receiver: Some(tmp_pair_info.sender.to_string()),
-                deadline: None,
-                slippage_tolerance: None,
-            })?,
-            funds,
-        }));
+        // native legs follow the deposit as funds
+        if provide_messages.is_empty() || funds.iter().any(|coin| !coin.amount.is_zero()) {
+            funds.sort_by(|a, b| a.denom.cmp(&b.denom));
+            provide_messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: pair_contract.to_string(),
+                msg: to_json_binary(&PairExecuteMsg::ProvideLiquidity {
+                    assets,
+                    receiver: Some(tmp_pair_info.sender.to_string()),
+                    deadline: None,
+                    slippage_tolerance: None,
+                })?,
+                funds,
+            }));
+        }
+
+        messages.extend(provide_messages);
     }
 
     Ok(Response::new()
@@ -399,9 +705,16 @@ pub fn query(deps: Deps<InjectiveQueryWrapper>, _env: Env, msg: QueryMsg) -> Std
         QueryMsg::Pairs { start_after, limit } => {
             to_json_binary(&query_pairs(deps, start_after, limit)?)
         }
+        QueryMsg::PairsByAsset {
+            asset_info,
+            start_after,
+            limit,
+        } => to_json_binary(&query_pairs_by_asset(deps, asset_info, start_after, limit)?),
         QueryMsg::NativeTokenDecimals { denom } => {
             to_json_binary(&query_native_token_decimal(deps, denom)?)
         }
+        QueryMsg::FeeSplit {} => to_json_binary(&query_fee_split(deps)?),
+        QueryMsg::Paused {} => to_json_binary(&query_paused(deps)?),
     }
 }
 
@@ -414,11 +727,29 @@ pub fn query_config(deps: Deps<InjectiveQueryWrapper>) -> StdResult<ConfigRespon
 
         burn_address: deps.api.addr_humanize(&state.burn_address)?.to_string(), // Return burn address
         fee_wallet_address: deps.api.addr_humanize(&state.fee_wallet_address)?.to_string(), // Return fee wallet address
+        commission_rates: state.commission_rates,
+        guardian: state
+            .guardian
+            .map(|guardian| deps.api.addr_humanize(&guardian))
+            .transpose()?
+            .map(|guardian| guardian.to_string()),
     };
 
     Ok(resp)
 }
 
+pub fn query_paused(deps: Deps<InjectiveQueryWrapper>) -> StdResult<PausedResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    Ok(PausedResponse {
+        paused: config.paused,
+    })
+}
+
+pub fn query_fee_split(deps: Deps<InjectiveQueryWrapper>) -> StdResult<FeeSplit> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    Ok(config.fee_split)
+}
+
 pub fn query_pair(deps: Deps<InjectiveQueryWrapper>, asset_infos: [AssetInfo; 2]) -> StdResult<PairInfo> {
     let pair_key = pair_key(&[
         asset_infos[0].to_raw(deps.api)?,
@@ -448,6 +779,32 @@ pub fn query_pairs(
     Ok(resp)
 }
 
+pub fn query_pairs_by_asset(
+    deps: Deps<InjectiveQueryWrapper>,
+    asset_info: AssetInfo,
+    start_after: Option<[AssetInfo; 2]>,
+    limit: Option<u32>,
+) -> StdResult<PairsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some([
+            start_after[0].to_raw(deps.api)?,
+            start_after[1].to_raw(deps.api)?,
+        ])
+    } else {
+        None
+    };
+
+    let pairs: Vec<PairInfo> = read_pairs_by_asset(
+        deps.storage,
+        deps.api,
+        asset_info.to_raw(deps.api)?,
+        start_after,
+        limit,
+    )?;
+
+    Ok(PairsResponse { pairs })
+}
+
 pub fn query_native_token_decimal(
     deps: Deps<InjectiveQueryWrapper>,
     denom: String,
@@ -459,13 +816,21 @@ pub fn query_native_token_decimal(
 
 const TARGET_CONTRACT_VERSION: &str = "0.1.0";
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut<InjectiveQueryWrapper>, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(mut deps: DepsMut<InjectiveQueryWrapper>, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
     migrate_version(
-        deps,
+        deps.branch(),
         TARGET_CONTRACT_VERSION,
         CONTRACT_NAME,
         CONTRACT_VERSION,
     )?;
 
+    // pairs created before the asset index existed
+    let pairs: Vec<(Vec<u8>, PairInfoRaw)> = PAIRS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<_>>()?;
+    for (pair_key, pair_info) in pairs {
+        index_pair(deps.storage, &pair_key, &pair_info)?;
+    }
+
     Ok(Response::default())
 }
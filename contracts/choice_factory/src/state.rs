@@ -1,9 +1,10 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Api, CanonicalAddr, Order, StdResult, Storage};
+use cosmwasm_std::{Addr, Api, CanonicalAddr, Decimal, Order, StdResult, Storage};
 use cw_storage_plus::{Bound, Item, Map};
-use choice::asset::{AssetInfoRaw, AssetRaw, PairInfo, PairInfoRaw};
+use choice::asset::{default_commission_rate, AssetInfoRaw, AssetRaw, PairInfo, PairInfoRaw};
+use choice::factory::FeeSplit;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Config {
@@ -13,6 +14,23 @@ pub struct Config {
 
     pub burn_address: CanonicalAddr, // New field
     pub fee_wallet_address: CanonicalAddr, // New field
+
+    #[serde(default = "default_commission_rates")]
+    pub commission_rates: Vec<Decimal>,
+
+    #[serde(default)]
+    pub fee_split: FeeSplit,
+
+    #[serde(default)]
+    pub guardian: Option<CanonicalAddr>,
+
+    /// Pauses every pair at once, pairs read it from the factory
+    #[serde(default)]
+    pub paused: bool,
+}
+
+fn default_commission_rates() -> Vec<Decimal> {
+    vec![default_commission_rate()]
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
@@ -35,6 +53,17 @@ pub fn pair_key(asset_infos: &[AssetInfoRaw; 2]) -> Vec<u8> {
     [asset_infos[0].as_bytes(), asset_infos[1].as_bytes()].concat()
 }
 
+/// Secondary index of `PAIRS`, key: asset info / pair key
+pub const PAIRS_BY_ASSET: Map<(&[u8], &[u8]), ()> = Map::new("pairs_by_asset");
+
+pub fn index_pair(storage: &mut dyn Storage, pair_key: &[u8], pair_info: &PairInfoRaw) -> StdResult<()> {
+    for asset_info in pair_info.asset_infos.iter() {
+        PAIRS_BY_ASSET.save(storage, (asset_info.as_bytes(), pair_key), &())?;
+    }
+
+    Ok(())
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
@@ -57,6 +86,25 @@ pub fn read_pairs(
         .collect::<StdResult<Vec<PairInfo>>>()
 }
 
+pub fn read_pairs_by_asset(
+    storage: &dyn Storage,
+    api: &dyn Api,
+    asset_info: AssetInfoRaw,
+    start_after: Option<[AssetInfoRaw; 2]>,
+    limit: Option<u32>,
+) -> StdResult<Vec<PairInfo>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.map(|asset_infos| pair_key(&asset_infos));
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    PAIRS_BY_ASSET
+        .prefix(asset_info.as_bytes())
+        .keys(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| PAIRS.load(storage, &item?)?.to_normal(api))
+        .collect::<StdResult<Vec<PairInfo>>>()
+}
+
 // this will set the first key after the provided key, by appending a 1 byte
 fn calc_range_start(start_after: Option<[AssetInfoRaw; 2]>) -> Option<Vec<u8>> {
     start_after.map(|asset_infos| {
@@ -1,21 +1,24 @@
-use crate::contract::{execute, execute_add_native_token_decimals, instantiate, query, reply};
+use crate::contract::{
+    execute, execute_add_native_token_decimals, instantiate, migrate, query, reply,
+};
 use choice::mock_querier::{mock_dependencies, WasmMockQuerier};
 use injective_cosmwasm::InjectiveQueryWrapper;
 
-use crate::state::{pair_key, TmpPairInfo, TMP_PAIR_INFO, CONFIG, Config};
+use crate::state::{pair_key, TmpPairInfo, TMP_PAIR_INFO, CONFIG, Config, PAIRS};
 
 use cosmwasm_std::testing::{mock_env, message_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, coin, coins, from_json, to_json_binary, Api, Binary, Coin, CosmosMsg, MsgResponse, OwnedDeps, Reply, ReplyOn, Response, StdError, SubMsg, SubMsgResponse, SubMsgResult, Uint128, WasmMsg
+    attr, coin, coins, from_json, to_json_binary, Api, Binary, Coin, CosmosMsg, Decimal, MsgResponse, Order, OwnedDeps, Reply, ReplyOn, Response, StdError, SubMsg, SubMsgResponse, SubMsgResult, Uint128, WasmMsg
 };
 use cw20::Cw20ExecuteMsg;
-use choice::asset::{Asset, AssetInfo, PairInfo};
+use choice::asset::{Asset, AssetInfo, PairInfo, PairInfoRaw, PairType};
 use choice::factory::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, NativeTokenDecimalsResponse, QueryMsg,
+    ConfigResponse, ExecuteMsg, FeeSplit, InstantiateMsg, MigrateMsg, NativeTokenDecimalsResponse,
+    PairsResponse, PausedResponse, QueryMsg,
 };
 use choice::pair::{
-    ExecuteMsg as PairExecuteMsg, InstantiateMsg as PairInstantiateMsg,
-    MigrateMsg as PairMigrateMsg,
+    Cw20HookMsg as PairCw20HookMsg, ExecuteMsg as PairExecuteMsg, InstantiateMsg as PairInstantiateMsg,
+    MigrateMsg as PairMigrateMsg, ProtocolFeeMode,
 };
 use crate::response::MsgInstantiateContractResponse;
 use protobuf::Message;
@@ -29,6 +32,7 @@ fn proper_initialization() {
         token_code_id: 123u64,
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+        commission_rates: vec![Decimal::permille(3)],
     };
 
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -54,6 +58,7 @@ fn update_config() {
         token_code_id: 123u64,
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+        commission_rates: vec![Decimal::permille(3)],
     };
 
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -70,6 +75,7 @@ fn update_config() {
         token_code_id: None,
         burn_address: None,
         fee_wallet_address: None,
+        commission_rates: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -91,6 +97,7 @@ fn update_config() {
         token_code_id: Some(200u64),
         burn_address: None,
         fee_wallet_address: None,
+        commission_rates: None,
     };
 
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
@@ -112,6 +119,7 @@ fn update_config() {
         token_code_id: None,
         burn_address: None,
         fee_wallet_address: None,
+        commission_rates: None,
     };
 
     let res = execute(deps.as_mut(), env, info, msg);
@@ -121,6 +129,65 @@ fn update_config() {
     }
 }
 
+#[test]
+fn update_fee_split() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::FeeSplit {}).unwrap();
+    let fee_split: FeeSplit = from_json(&query_res).unwrap();
+    assert_eq!(fee_split, FeeSplit::default());
+
+    let new_fee_split = FeeSplit {
+        lp: Decimal::percent(50),
+        fee_wallet: Decimal::percent(30),
+        burn: Decimal::percent(20),
+    };
+
+    // Unauthorized err
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    let msg = ExecuteMsg::UpdateFeeSplit {
+        fee_split: new_fee_split.clone(),
+    };
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    // shares must sum to 1
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let msg = ExecuteMsg::UpdateFeeSplit {
+        fee_split: FeeSplit {
+            lp: Decimal::percent(50),
+            fee_wallet: Decimal::percent(30),
+            burn: Decimal::percent(30),
+        },
+    };
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "fee split shares must sum to 1"),
+        _ => panic!("Must return generic error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let msg = ExecuteMsg::UpdateFeeSplit {
+        fee_split: new_fee_split.clone(),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_fee_split"),
+            attr("lp_share", "0.5"),
+            attr("fee_wallet_share", "0.3"),
+            attr("burn_share", "0.2"),
+        ]
+    );
+
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::FeeSplit {}).unwrap();
+    let fee_split: FeeSplit = from_json(&query_res).unwrap();
+    assert_eq!(fee_split, new_fee_split);
+}
+
 fn init(
     mut deps: OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper>,
 ) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper> {
@@ -131,6 +198,7 @@ fn init(
         token_code_id: 123u64,
         burn_address: mock_api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: mock_api.addr_make("feeaddr0000").to_string(), // New field
+        commission_rates: vec![Decimal::permille(3)],
     };
 
     let env = mock_env();
@@ -155,7 +223,7 @@ fn create_pair() {
         .with_choice_factory(&[], &[("uusd".to_string(), 6u8)]);
 
     deps.querier.with_token_factory_denom_create_fee(&[
-        (&"inj", Uint128::from(1_000_000_000_000_000_000u128))
+        ("inj", Uint128::from(1_000_000_000_000_000_000u128))
     ]);
     
     let assets = [
@@ -175,15 +243,15 @@ fn create_pair() {
 
     let msg = ExecuteMsg::CreatePair {
         assets: assets.clone(),
+        commission_rate: None,
+        pair_type: None,
     };
 
     let env = mock_env();
-    let info = message_info(&deps.api.addr_make("addr0000"), &vec![
-        Coin{
+    let info = message_info(&deps.api.addr_make("addr0000"), &[Coin{
             denom: "inj".to_string(),
             amount: Uint128::from(1_000_000_000_000_000_000u128)
-        }
-    ]);
+        }]);
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
     
     assert_eq!(
@@ -215,6 +283,8 @@ fn create_pair() {
                     asset_decimals: [6u8, 8u8],
                     burn_address: deps.api.addr_make("burnaddr0000").to_string(), // Add burn address
                     fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // Add fee wallet address
+                    commission_rate: Decimal::permille(3),
+                    pair_type: PairType::Xyk {},
                 })
                 .unwrap(),
                 code_id: 321u64,
@@ -252,6 +322,505 @@ fn create_pair() {
     );
 }
 
+#[test]
+fn create_pair_with_commission_rate_tier() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_choice_factory(&[], &[("uusd".to_string(), 6u8)]);
+    deps.querier.with_token_factory_denom_create_fee(&[
+        ("inj", Uint128::from(1_000_000_000_000_000_000u128))
+    ]);
+
+    // whitelist a stable tier next to the default one
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        pair_code_id: None,
+        token_code_id: None,
+        burn_address: None,
+        fee_wallet_address: None,
+        commission_rates: Some(vec![Decimal::permille(3), Decimal::bps(5)]),
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_json(&query_res).unwrap();
+    assert_eq!(
+        config_res.commission_rates,
+        vec![Decimal::permille(3), Decimal::bps(5)]
+    );
+
+    // rates of 100% or more cannot be whitelisted
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        pair_code_id: None,
+        token_code_id: None,
+        burn_address: None,
+        fee_wallet_address: None,
+        commission_rates: Some(vec![Decimal::one()]),
+    };
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => {
+            assert_eq!(msg, "commission rate must be less than 1")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    let assets = [
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: Uint128::zero(),
+        },
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0001").to_string(),
+            },
+            amount: Uint128::zero(),
+        },
+    ];
+
+    // not whitelisted
+    let msg = ExecuteMsg::CreatePair {
+        assets: assets.clone(),
+        commission_rate: Some(Decimal::percent(1)),
+        pair_type: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => {
+            assert_eq!(msg, "commission rate 0.01 is not allowed")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    let msg = ExecuteMsg::CreatePair {
+        assets,
+        commission_rate: Some(Decimal::bps(5)),
+        pair_type: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[Coin{
+            denom: "inj".to_string(),
+            amount: Uint128::from(1_000_000_000_000_000_000u128)
+        }]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => {
+            let msg: PairInstantiateMsg = from_json(msg).unwrap();
+            assert_eq!(msg.commission_rate, Decimal::bps(5));
+        }
+        _ => panic!("Must instantiate the pair"),
+    }
+}
+
+#[test]
+fn create_stable_pair_and_ramp_amp() {
+    let mut deps = mock_dependencies(&[coin(10u128, "uusd".to_string())]);
+    deps = init(deps);
+    deps.querier
+        .with_choice_factory(&[], &[("uusd".to_string(), 6u8), ("uusdt".to_string(), 6u8)]);
+    deps.querier.with_token_factory_denom_create_fee(&[
+        ("inj", Uint128::from(1_000_000_000_000_000_000u128))
+    ]);
+
+    let msg = ExecuteMsg::CreatePair {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusdt".to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+        ],
+        commission_rate: None,
+        pair_type: Some(PairType::Stable { amp: 100 }),
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[Coin{
+            denom: "inj".to_string(),
+            amount: Uint128::from(1_000_000_000_000_000_000u128)
+        }]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => {
+            let msg: PairInstantiateMsg = from_json(msg).unwrap();
+            assert_eq!(msg.pair_type, PairType::Stable { amp: 100 });
+        }
+        _ => panic!("Must instantiate the pair"),
+    }
+
+    let pair = deps.api.addr_make("pair0000").to_string();
+
+    // Unauthorized err
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    let msg = ExecuteMsg::StopRampAmp { pair: pair.clone() };
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let msg = ExecuteMsg::RampAmp {
+        pair: pair.clone(),
+        future_amp: 200,
+        future_time: 1_700_000_000,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pair,
+            msg: to_json_binary(&PairExecuteMsg::RampAmp {
+                future_amp: 200,
+                future_time: 1_700_000_000,
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+}
+
+#[test]
+fn update_protocol_fee_mode() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let pair = deps.api.addr_make("pair0000").to_string();
+
+    // Unauthorized err
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    let msg = ExecuteMsg::UpdateProtocolFeeMode {
+        pair: pair.clone(),
+        mode: ProtocolFeeMode::Accrue {},
+    };
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pair.clone(),
+            msg: to_json_binary(&PairExecuteMsg::UpdateProtocolFeeMode {
+                mode: ProtocolFeeMode::Accrue {},
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_protocol_fee_mode"),
+            attr("pair", pair),
+        ]
+    );
+}
+
+#[test]
+fn pause_pair() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    let pair0 = deps.api.addr_make("pair0000");
+    let pair1 = deps.api.addr_make("pair0001");
+    let mut pair_infos: Vec<(String, PairInfo)> = vec![];
+    for (pair, denom) in [(&pair0, "uusd"), (&pair1, "ukrw")] {
+        let raw_infos = [
+            AssetInfo::NativeToken {
+                denom: "inj".to_string(),
+            }
+            .to_raw(deps.as_ref().api)
+            .unwrap(),
+            AssetInfo::NativeToken {
+                denom: denom.to_string(),
+            }
+            .to_raw(deps.as_ref().api)
+            .unwrap(),
+        ];
+        PAIRS
+            .save(
+                &mut deps.storage,
+                &pair_key(&raw_infos),
+                &PairInfoRaw {
+                    asset_infos: raw_infos.clone(),
+                    contract_addr: deps.api.addr_canonicalize(pair.as_str()).unwrap(),
+                    liquidity_token: format!("factory/{}/lp", pair),
+                    asset_decimals: [18u8, 6u8],
+                    burn_address: deps
+                        .api
+                        .addr_canonicalize(deps.api.addr_make("burnaddr0000").as_str())
+                        .unwrap(),
+                    fee_wallet_address: deps
+                        .api
+                        .addr_canonicalize(deps.api.addr_make("feeaddr0000").as_str())
+                        .unwrap(),
+                    commission_rate: Decimal::permille(3),
+                    pair_type: PairType::Xyk {},
+                    paused: false,
+                },
+            )
+            .unwrap();
+        let pair_info = PAIRS
+            .load(&deps.storage, &pair_key(&raw_infos))
+            .unwrap()
+            .to_normal(deps.as_ref().api)
+            .unwrap();
+        pair_infos.push((format!("inj{}", denom), pair_info));
+    }
+    // the pairs answer the factory with their assets
+    deps.querier.with_choice_factory(
+        &pair_infos
+            .iter()
+            .map(|(key, pair_info)| (key, pair_info))
+            .collect::<Vec<_>>(),
+        &[],
+    );
+
+    // Unauthorized err
+    let guardian = deps.api.addr_make("guardian0000");
+    let info = message_info(&guardian, &[]);
+    let msg = ExecuteMsg::PausePair {
+        pair: Some(pair0.to_string()),
+    };
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    // only the owner appoints the guardian
+    let msg_guardian = ExecuteMsg::UpdateGuardian {
+        guardian: Some(guardian.to_string()),
+    };
+    let info = message_info(&guardian, &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg_guardian.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    execute(deps.as_mut(), mock_env(), info, msg_guardian).unwrap();
+
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_json(&query_res).unwrap();
+    assert_eq!(config_res.guardian, Some(guardian.to_string()));
+
+    // guardian pauses a single pair
+    let info = message_info(&guardian, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: pair0.to_string(),
+            msg: to_json_binary(&PairExecuteMsg::Pause {}).unwrap(),
+            funds: vec![],
+        })]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "pause_pair"), attr("pair", pair0.to_string())]
+    );
+
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Pair {
+            asset_infos: [
+                AssetInfo::NativeToken {
+                    denom: "inj".to_string(),
+                },
+                AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+            ],
+        },
+    )
+    .unwrap();
+    let pair_res: PairInfo = from_json(&query_res).unwrap();
+    assert!(pair_res.paused);
+
+    // unknown pair
+    let info = message_info(&guardian, &[]);
+    let msg = ExecuteMsg::PausePair {
+        pair: Some(deps.api.addr_make("pair0002").to_string()),
+    };
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "pair not found"),
+        _ => panic!("Must return generic error"),
+    }
+
+    // guardian pauses every pair with the global flag, no pair is touched
+    let info = message_info(&guardian, &[]);
+    let msg = ExecuteMsg::PausePair { pair: None };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "pause_pair"), attr("pair", "all")]
+    );
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Paused {}).unwrap();
+    let paused_res: PausedResponse = from_json(&query_res).unwrap();
+    assert!(paused_res.paused);
+
+    // owner lifts the global pause, the pair paused on its own stays paused
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let msg = ExecuteMsg::UnpausePair { pair: None };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert!(res.messages.is_empty());
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "unpause_pair"), attr("pair", "all")]
+    );
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Paused {}).unwrap();
+    let paused_res: PausedResponse = from_json(&query_res).unwrap();
+    assert!(!paused_res.paused);
+    let pairs: Vec<PairInfoRaw> = PAIRS
+        .range(&deps.storage, None, None, Order::Ascending)
+        .map(|item| item.unwrap().1)
+        .collect();
+    for pair_info in pairs {
+        let pair = deps.api.addr_humanize(&pair_info.contract_addr).unwrap();
+        assert_eq!(pair_info.paused, pair == pair0);
+    }
+
+    // owner removes the guardian
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::UpdateGuardian { guardian: None },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![attr("action", "update_guardian"), attr("guardian", "none")]
+    );
+    let query_res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_json(&query_res).unwrap();
+    assert_eq!(config_res.guardian, None);
+
+    let info = message_info(&guardian, &[]);
+    let msg = ExecuteMsg::UnpausePair {
+        pair: Some(pair0.to_string()),
+    };
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return unauthorized error"),
+    }
+}
+
+#[test]
+fn query_pairs_by_asset_after_migration() {
+    let mut deps = mock_dependencies(&[]);
+    deps = init(deps);
+
+    // pairs saved before the asset index existed
+    for (pair, denoms) in [
+        ("pair0000", ["inj", "uusd"]),
+        ("pair0001", ["inj", "ukrw"]),
+        ("pair0002", ["ukrw", "uusd"]),
+    ] {
+        let raw_infos = [
+            AssetInfo::NativeToken {
+                denom: denoms[0].to_string(),
+            }
+            .to_raw(deps.as_ref().api)
+            .unwrap(),
+            AssetInfo::NativeToken {
+                denom: denoms[1].to_string(),
+            }
+            .to_raw(deps.as_ref().api)
+            .unwrap(),
+        ];
+        let pair = deps.api.addr_make(pair);
+        PAIRS
+            .save(
+                &mut deps.storage,
+                &pair_key(&raw_infos),
+                &PairInfoRaw {
+                    asset_infos: raw_infos.clone(),
+                    contract_addr: deps.api.addr_canonicalize(pair.as_str()).unwrap(),
+                    liquidity_token: format!("factory/{}/lp", pair),
+                    asset_decimals: [6u8, 6u8],
+                    burn_address: deps
+                        .api
+                        .addr_canonicalize(deps.api.addr_make("burnaddr0000").as_str())
+                        .unwrap(),
+                    fee_wallet_address: deps
+                        .api
+                        .addr_canonicalize(deps.api.addr_make("feeaddr0000").as_str())
+                        .unwrap(),
+                    commission_rate: Decimal::permille(3),
+                    pair_type: PairType::Xyk {},
+                    paused: false,
+                },
+            )
+            .unwrap();
+    }
+
+    let inj = AssetInfo::NativeToken {
+        denom: "inj".to_string(),
+    };
+    let query_pairs_by_asset =
+        |deps: &OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper>,
+         start_after: Option<[AssetInfo; 2]>,
+         limit: Option<u32>| {
+            let query_res = query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::PairsByAsset {
+                    asset_info: inj.clone(),
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap();
+            from_json::<PairsResponse>(&query_res)
+                .unwrap()
+                .pairs
+                .into_iter()
+                .map(|pair| pair.contract_addr)
+                .collect::<Vec<String>>()
+        };
+    assert!(query_pairs_by_asset(&deps, None, None).is_empty());
+
+    cw2::set_contract_version(&mut deps.storage, "crates.io:choice-factory", "0.1.0").unwrap();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    // ordered by pair key, "ukrw" sorts before "uusd"
+    let pair0 = deps.api.addr_make("pair0000").to_string();
+    let pair1 = deps.api.addr_make("pair0001").to_string();
+    assert_eq!(query_pairs_by_asset(&deps, None, None), vec![pair1.clone(), pair0.clone()]);
+    assert_eq!(query_pairs_by_asset(&deps, None, Some(1)), vec![pair1]);
+    assert_eq!(
+        query_pairs_by_asset(
+            &deps,
+            Some([
+                inj.clone(),
+                AssetInfo::NativeToken {
+                    denom: "ukrw".to_string(),
+                },
+            ]),
+            None,
+        ),
+        vec![pair0]
+    );
+}
+
 #[test]
 fn create_pair_native_token_and_ibc_token() {
     let mock_api = MockApi::default();
@@ -266,7 +835,7 @@ fn create_pair_native_token_and_ibc_token() {
     );
 
     deps.querier.with_token_factory_denom_create_fee(&[
-        (&"inj", Uint128::from(1_000_000_000_000_000_000u128))
+        ("inj", Uint128::from(1_000_000_000_000_000_000u128))
     ]);
 
     let assets = [
@@ -286,15 +855,15 @@ fn create_pair_native_token_and_ibc_token() {
 
     let msg = ExecuteMsg::CreatePair {
         assets: assets.clone(),
+        commission_rate: None,
+        pair_type: None,
     };
 
     let env = mock_env();
-    let info = message_info(&mock_api.addr_make("addr0000"), &vec![
-        Coin{
+    let info = message_info(&mock_api.addr_make("addr0000"), &[Coin{
             denom: "inj".to_string(),
             amount: Uint128::from(1_000_000_000_000_000_000u128)
-        }
-    ]);
+        }]);
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
     
     assert_eq!(
@@ -323,6 +892,8 @@ fn create_pair_native_token_and_ibc_token() {
                     asset_decimals: [6u8, 6u8],
                     burn_address: mock_api.addr_make("burnaddr0000").to_string(), // Add burn address
                     fee_wallet_address: mock_api.addr_make("feeaddr0000").to_string(), // Add fee wallet address
+                    commission_rate: Decimal::permille(3),
+                    pair_type: PairType::Xyk {},
                 })
                 .unwrap(),
                 code_id: 321u64,
@@ -380,7 +951,11 @@ fn fail_to_create_same_pair() {
         },
     ];
 
-    let msg = ExecuteMsg::CreatePair { assets };
+    let msg = ExecuteMsg::CreatePair {
+        assets,
+        commission_rate: None,
+        pair_type: None,
+    };
 
     let env = mock_env();
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -414,7 +989,11 @@ fn fail_to_create_pair_with_unknown_denom() {
         },
     ];
 
-    let msg = ExecuteMsg::CreatePair { assets };
+    let msg = ExecuteMsg::CreatePair {
+        assets,
+        commission_rate: None,
+        pair_type: None,
+    };
 
     let env = mock_env();
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -448,7 +1027,11 @@ fn fail_to_create_pair_with_unknown_token() {
         },
     ];
 
-    let msg = ExecuteMsg::CreatePair { assets };
+    let msg = ExecuteMsg::CreatePair {
+        assets,
+        commission_rate: None,
+        pair_type: None,
+    };
 
     let env = mock_env();
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -467,11 +1050,15 @@ fn reply_only_create_pair() {
         .save(
             &mut deps.storage,
             &Config {
-                owner: deps.api.addr_canonicalize(&deps.api.addr_make("owner0000").to_string()).unwrap(),
+                owner: deps.api.addr_canonicalize(deps.api.addr_make("owner0000").as_ref()).unwrap(),
                 token_code_id: 123u64,
                 pair_code_id: 321u64,
-                burn_address: deps.api.addr_canonicalize(&deps.api.addr_make("burnaddr0000").to_string()).unwrap(),
-                fee_wallet_address: deps.api.addr_canonicalize(&deps.api.addr_make("feeaddr0000").to_string()).unwrap(),
+                burn_address: deps.api.addr_canonicalize(deps.api.addr_make("burnaddr0000").as_ref()).unwrap(),
+                fee_wallet_address: deps.api.addr_canonicalize(deps.api.addr_make("feeaddr0000").as_ref()).unwrap(),
+                commission_rates: vec![Decimal::permille(3)],
+                guardian: None,
+                paused: false,
+                fee_split: FeeSplit::default(),
             },
         )
         .unwrap();
@@ -566,6 +1153,9 @@ fn reply_only_create_pair() {
                 asset_decimals: [8u8, 8u8],
                 burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                 fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                commission_rate: Decimal::permille(3),
+                pair_type: PairType::Xyk {},
+                paused: false,
             },
         )],
         &[],
@@ -579,6 +1169,23 @@ fn reply_only_create_pair() {
         res.attributes[1],
         attr("liquidity_token_addr", deps.api.addr_make("liquidity0000"))
     );
+
+    // the new pair is indexed by both assets
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::PairsByAsset {
+            asset_info: AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0001").to_string(),
+            },
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let pairs_res: PairsResponse = from_json(&query_res).unwrap();
+    assert_eq!(pairs_res.pairs.len(), 1);
+    assert_eq!(pairs_res.pairs[0].contract_addr, deps.api.addr_make("pair0000").to_string());
 }
 
 #[test]
@@ -589,11 +1196,15 @@ fn reply_create_pair_with_provide() {
         .save(
             &mut deps.storage,
             &Config {
-                owner: deps.api.addr_canonicalize(&deps.api.addr_make("owner0000").to_string()).unwrap(),
+                owner: deps.api.addr_canonicalize(deps.api.addr_make("owner0000").as_ref()).unwrap(),
                 token_code_id: 123u64,
                 pair_code_id: 321u64,
-                burn_address: deps.api.addr_canonicalize(&deps.api.addr_make("burnaddr0000").to_string()).unwrap(),
-                fee_wallet_address: deps.api.addr_canonicalize(&deps.api.addr_make("feeaddr0000").to_string()).unwrap(),
+                burn_address: deps.api.addr_canonicalize(deps.api.addr_make("burnaddr0000").as_ref()).unwrap(),
+                fee_wallet_address: deps.api.addr_canonicalize(deps.api.addr_make("feeaddr0000").as_ref()).unwrap(),
+                commission_rates: vec![Decimal::permille(3)],
+                guardian: None,
+                paused: false,
+                fee_split: FeeSplit::default(),
             },
         )
         .unwrap();
@@ -688,6 +1299,9 @@ fn reply_create_pair_with_provide() {
                 asset_decimals: [18u8, 8u8],
                 burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                 fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                commission_rate: Decimal::permille(3),
+                pair_type: PairType::Xyk {},
+                paused: false,
             },
         )],
         &[("inj".to_string(), 18u8)],
@@ -703,10 +1317,10 @@ fn reply_create_pair_with_provide() {
             payload: Binary::default(),
             msg: CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: deps.api.addr_make("asset0000").to_string(),
-                msg: to_json_binary(&Cw20ExecuteMsg::IncreaseAllowance {
-                    spender: deps.api.addr_make("pair0000").to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: deps.api.addr_make("addr0000").to_string(),
                     amount: Uint128::from(100u128),
-                    expires: None,
+                    recipient: MOCK_CONTRACT_ADDR.to_string(),
                 })
                 .unwrap(),
                 funds: vec![],
@@ -722,10 +1336,16 @@ fn reply_create_pair_with_provide() {
             payload: Binary::default(),
             msg: CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: deps.api.addr_make("asset0000").to_string(),
-                msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
-                    owner: deps.api.addr_make("addr0000").to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: deps.api.addr_make("pair0000").to_string(),
                     amount: Uint128::from(100u128),
-                    recipient: MOCK_CONTRACT_ADDR.to_string(),
+                    msg: to_json_binary(&PairCw20HookMsg::ProvideLiquidity {
+                        assets: assets.clone(),
+                        receiver: Some(deps.api.addr_make("addr0000").to_string()),
+                        deadline: None,
+                        slippage_tolerance: None,
+                    })
+                    .unwrap(),
                 })
                 .unwrap(),
                 funds: vec![],
@@ -979,11 +1599,15 @@ fn test_execute_add_native_token_decimals_factory() {
         .save(
             &mut deps.storage,
             &Config {
-                owner: deps.api.addr_canonicalize(&deps.api.addr_make("owner0000").to_string()).unwrap(),
+                owner: deps.api.addr_canonicalize(deps.api.addr_make("owner0000").as_ref()).unwrap(),
                 token_code_id: 123u64,
                 pair_code_id: 321u64,
-                burn_address: deps.api.addr_canonicalize(&deps.api.addr_make("burnaddr0000").to_string()).unwrap(),
-                fee_wallet_address: deps.api.addr_canonicalize(&deps.api.addr_make("feeaddr0000").to_string()).unwrap(),
+                burn_address: deps.api.addr_canonicalize(deps.api.addr_make("burnaddr0000").as_ref()).unwrap(),
+                fee_wallet_address: deps.api.addr_canonicalize(deps.api.addr_make("feeaddr0000").as_ref()).unwrap(),
+                commission_rates: vec![Decimal::permille(3)],
+                guardian: None,
+                paused: false,
+                fee_split: FeeSplit::default(),
             },
         )
         .unwrap();
@@ -1019,7 +1643,7 @@ fn test_execute_add_native_token_decimals_factory() {
     assert!(res
         .attributes
         .iter()
-        .any(|attr| attr.key == "decimals" && &attr.value == &decimals.to_string()));
+        .any(|attr| attr.key == "decimals" && attr.value == decimals.to_string()));
 
     // Test case 2: Unauthorized sender (does not match owner in denom)
     let bad_info = message_info(&deps.api.addr_make("cosmwasm1otheraddr"), &[]);
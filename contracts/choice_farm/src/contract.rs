@@ -3,24 +3,31 @@ use cosmwasm_std::entry_point;
 
 use cosmwasm_std::{
     from_json, to_json_binary, coins, Addr, Binary, CanonicalAddr, CosmosMsg, Coin, Decimal, Deps, DepsMut, Env,
-    MessageInfo, Response, StdError, StdResult, Uint128, WasmMsg, BankMsg
+    MessageInfo, Order, Response, StdError, StdResult, Storage, Uint128, WasmMsg, BankMsg
 };
+use serde::{Deserialize, Serialize};
 
-use choice::asset::AssetInfo;
+use choice::asset::{Asset, AssetInfo};
 
 use choice::staking::{
-    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
-    StakerInfoResponse, StateResponse,
+    ConfigResponse, Cw20HookMsg, EarlyExit, ExecuteMsg, InstantiateMsg, LockPosition, MigrateMsg,
+    PendingUnbondsResponse, QueryMsg, RewardBalanceResponse, RewardSolvencyResponse,
+    RewardStateResponse, RewardStream, StakerInfoResponse, StakerRewardResponse, StateResponse,
+    UnbondClaim,
 };
 
 use crate::state::{
-        read_config, read_staker_info, read_state, remove_staker_info, store_config,
-        store_staker_info, store_state, Config, StakerInfo, State,
+        read_config, read_staker_info, read_state, read_unbonding_claims, remove_staker_info,
+        store_config, store_staker_info, store_state, store_unbonding_claims, Config, StakerInfo, StakerReward, State, LEGACY_CONFIG,
+        LEGACY_STAKER_INFO, LEGACY_STATE, STAKER_INFO,
     };
 
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 use std::collections::BTreeMap;
 
+/// Every stream is accrued on each staker action, so their number is bounded
+const MAX_REWARD_STREAMS: usize = 10;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -28,23 +35,58 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response> {
+    if msg.reward_streams.len() > MAX_REWARD_STREAMS {
+        return Err(StdError::generic_err("too many reward streams"));
+    }
 
-    store_config(
-        deps.storage,
-        &Config {
-            owner: deps.api.addr_canonicalize(_info.sender.as_str())?, 
-            reward_token: msg.reward_token,
-            staking_token: msg.staking_token,
-            distribution_schedule: msg.distribution_schedule,
-        },
-    )?;
+    for (i, stream) in msg.reward_streams.iter().enumerate() {
+        if msg.reward_streams[..i]
+            .iter()
+            .any(|other| other.reward_token.equal(&stream.reward_token))
+        {
+            return Err(StdError::generic_err("duplicate reward token"));
+        }
+    }
+
+    for (i, tier) in msg.lock_tiers.iter().enumerate() {
+        if tier.duration == 0
+            || tier.multiplier < Decimal::one()
+            || msg.lock_tiers[..i]
+                .iter()
+                .any(|other| other.duration == tier.duration)
+        {
+            return Err(StdError::generic_err("invalid lock tier"));
+        }
+    }
+
+    if let Some(early_exit) = &msg.early_exit {
+        deps.api.addr_validate(&early_exit.auction_contract)?;
+        if early_exit.penalty_rate > Decimal::one() {
+            return Err(StdError::generic_err("invalid early exit penalty rate"));
+        }
+    }
 
     store_state(
         deps.storage,
         &State {
             last_distributed: env.block.time.seconds(),
             total_bond_amount: Uint128::zero(),
-            global_reward_index: Decimal::zero(),
+            total_bond_weight: Uint128::zero(),
+            total_unbonding_amount: Uint128::zero(),
+            global_reward_indexes: vec![Decimal::zero(); msg.reward_streams.len()],
+            unclaimed_rewards: vec![Uint128::zero(); msg.reward_streams.len()],
+        },
+    )?;
+
+    store_config(
+        deps.storage,
+        &Config {
+            owner: deps.api.addr_canonicalize(_info.sender.as_str())?, 
+            staking_token: msg.staking_token,
+            reward_streams: msg.reward_streams,
+            lock_tiers: msg.lock_tiers,
+            early_exit: msg.early_exit,
+            unbonding_period: msg.unbonding_period.unwrap_or_default(),
         },
     )?;
 
@@ -55,7 +97,10 @@ pub fn instantiate(
 pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
-        ExecuteMsg::Bond { amount } => {
+        ExecuteMsg::Bond {
+            amount,
+            lock_duration,
+        } => {
             // Load the configuration so we can get the staking token information.
             let config: Config = read_config(deps.storage)?;
             // For native tokens, check that the funds sent match the staking token denom.
@@ -70,20 +115,40 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
                 if found.is_none() {
                     return Err(StdError::generic_err("Insufficient funds for bonding"));
                 }
-                bond(deps, env, info.sender.clone(), amount)
+                bond(deps, env, info.sender.clone(), amount, lock_duration)
             }
             else {
-                return Err(StdError::generic_err("Cannot call bond directly with non native tokens"));
+                Err(StdError::generic_err("Cannot call bond directly with non native tokens"))
             }
         },
         ExecuteMsg::Unbond { amount } => unbond(deps, env, info, amount),
+        ExecuteMsg::EarlyUnbond { amount } => early_unbond(deps, env, info, amount),
+        ExecuteMsg::Claim {} => claim(deps, env, info),
         ExecuteMsg::Withdraw {} => withdraw(deps, env, info),
+        ExecuteMsg::ReleaseExpiredLocks { staker } => release_expired_locks(deps, env, staker),
         ExecuteMsg::MigrateStaking {
             new_staking_contract,
         } => migrate_staking(deps, env, info, new_staking_contract),
         ExecuteMsg::UpdateConfig {
+            reward_token,
             distribution_schedule,
-        } => update_config(deps, env, info, distribution_schedule),
+        } => update_config(deps, env, info, reward_token, distribution_schedule),
+        ExecuteMsg::AddRewards { schedule } => {
+            let reward = match info.funds.as_slice() {
+                [coin] => Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: coin.denom.clone(),
+                    },
+                    amount: coin.amount,
+                },
+                _ => {
+                    return Err(StdError::generic_err(
+                        "funds mismatch; must send exactly one reward token",
+                    ))
+                }
+            };
+            add_rewards(deps, env, info.sender, reward, schedule)
+        }
     }
 }
 
@@ -96,7 +161,7 @@ pub fn receive_cw20(
     let config: Config = read_config(deps.storage)?;
 
     match from_json(&cw20_msg.msg) {
-        Ok(Cw20HookMsg::Bond {}) => {
+        Ok(Cw20HookMsg::Bond { lock_duration }) => {
             // only staking token contract can execute this message
             
             match config.staking_token {
@@ -107,18 +172,34 @@ pub fn receive_cw20(
                     }
                 },
                 AssetInfo::NativeToken { ref denom } => {
-                    return Err(StdError::generic_err(&format!("staking token is native: {}", denom)));
+                    return Err(StdError::generic_err(format!("staking token is native: {}", denom)));
                 },
             }
 
             let cw20_sender = deps.api.addr_validate(&cw20_msg.sender)?;
-            bond(deps, env, cw20_sender, cw20_msg.amount)
+            bond(deps, env, cw20_sender, cw20_msg.amount, lock_duration)
+        }
+        Ok(Cw20HookMsg::AddRewards { schedule }) => {
+            let cw20_sender = deps.api.addr_validate(&cw20_msg.sender)?;
+            let reward = Asset {
+                info: AssetInfo::Token {
+                    contract_addr: info.sender.to_string(),
+                },
+                amount: cw20_msg.amount,
+            };
+            add_rewards(deps, env, cw20_sender, reward, schedule)
         }
         Err(_) => Err(StdError::generic_err("data should be given")),
     }
 }
 
-pub fn bond(deps: DepsMut, env: Env, sender_addr: Addr, amount: Uint128) -> StdResult<Response> {
+pub fn bond(
+    deps: DepsMut,
+    env: Env,
+    sender_addr: Addr,
+    amount: Uint128,
+    lock_duration: Option<u64>,
+) -> StdResult<Response> {
     let sender_addr_raw: CanonicalAddr = deps.api.addr_canonicalize(sender_addr.as_str())?;
 
     let config: Config = read_config(deps.storage)?;
@@ -126,11 +207,29 @@ pub fn bond(deps: DepsMut, env: Env, sender_addr: Addr, amount: Uint128) -> StdR
     let mut staker_info: StakerInfo = read_staker_info(deps.storage, &sender_addr_raw)?;
 
     // Compute global reward & staker reward
-    compute_reward(&config, &mut state, env.block.time.seconds());
-    compute_staker_reward(&state, &mut staker_info)?;
+    let block_time = env.block.time.seconds();
+    settle_staker(&config, &mut state, &mut staker_info, block_time)?;
+
+    let lock_duration = lock_duration.unwrap_or_default();
+    if lock_duration > 0 {
+        let tier = config
+            .lock_tiers
+            .iter()
+            .find(|tier| tier.duration == lock_duration)
+            .ok_or_else(|| StdError::generic_err("invalid lock duration"))?;
+        add_lock(
+            &mut staker_info,
+            LockPosition {
+                amount,
+                multiplier: tier.multiplier,
+                unlock_time: block_time + lock_duration,
+            },
+        );
+    }
 
     // Increase bond_amount
     increase_bond_amount(&mut state, &mut staker_info, amount);
+    update_bond_weight(&mut state, &mut staker_info)?;
 
     // Store updated state with staker's staker_info
     store_staker_info(deps.storage, &sender_addr_raw, &staker_info)?;
@@ -140,6 +239,7 @@ pub fn bond(deps: DepsMut, env: Env, sender_addr: Addr, amount: Uint128) -> StdR
         ("action", "bond"),
         ("owner", sender_addr.as_str()),
         ("amount", amount.to_string().as_str()),
+        ("lock_duration", lock_duration.to_string().as_str()),
     ]))
 }
 
@@ -155,40 +255,42 @@ pub fn unbond(deps: DepsMut, env: Env, info: MessageInfo, amount: Uint128) -> St
     }
 
     // Compute global reward & staker reward
-    compute_reward(&config, &mut state, env.block.time.seconds());
-    compute_staker_reward(&state, &mut staker_info)?;
+    let block_time = env.block.time.seconds();
+    settle_staker(&config, &mut state, &mut staker_info, block_time)?;
+
+    if unlocked_amount(&staker_info) < amount {
+        return Err(StdError::generic_err("Cannot unbond more than unlocked amount"));
+    }
 
     // Decrease bond_amount
     decrease_bond_amount(&mut state, &mut staker_info, amount)?;
+    update_bond_weight(&mut state, &mut staker_info)?;
 
     // Store or remove updated rewards info
     // depends on the left pending reward and bond amount
-    if staker_info.pending_reward.is_zero() && staker_info.bond_amount.is_zero() {
+    if staker_info.rewards.iter().all(|reward| reward.pending_reward.is_zero())
+        && staker_info.bond_amount.is_zero()
+    {
         remove_staker_info(deps.storage, &sender_addr_raw);
     } else {
         store_staker_info(deps.storage, &sender_addr_raw, &staker_info)?;
     }
 
+    let unbond_msgs = release_bond(
+        deps.storage,
+        &config,
+        &mut state,
+        &sender_addr_raw,
+        &info.sender,
+        amount,
+        block_time,
+    )?;
+
     // Store updated state
     store_state(deps.storage, &state)?;
 
-    let unbond_msg = match config.staking_token {
-        AssetInfo::Token { ref contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: contract_addr.clone(),
-            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: info.sender.to_string(),
-                amount,
-            })?,
-            funds: vec![],
-        }),
-        AssetInfo::NativeToken { ref denom } => CosmosMsg::Bank(BankMsg::Send {
-            to_address: info.sender.to_string(),
-            amount: coins(amount.u128(), denom),
-        }),
-    };
-    
     Ok(Response::new()
-        .add_messages(vec![unbond_msg])
+        .add_messages(unbond_msgs)
         .add_attributes(vec![
             ("action", "unbond"),
             ("owner", info.sender.as_str()),
@@ -196,6 +298,158 @@ pub fn unbond(deps: DepsMut, env: Env, info: MessageInfo, amount: Uint128) -> St
         ]))
 }
 
+/// Unbond past the unlocked amount, the locked part pays the
+/// early exit penalty to the auction contract
+pub fn early_unbond(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> StdResult<Response> {
+    let config: Config = read_config(deps.storage)?;
+    let early_exit: EarlyExit = config
+        .early_exit
+        .clone()
+        .ok_or_else(|| StdError::generic_err("early exit is disabled"))?;
+    let sender_addr_raw: CanonicalAddr = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    let mut state: State = read_state(deps.storage)?;
+    let mut staker_info: StakerInfo = read_staker_info(deps.storage, &sender_addr_raw)?;
+
+    if staker_info.bond_amount < amount {
+        return Err(StdError::generic_err("Cannot unbond more than bond amount"));
+    }
+
+    // Compute global reward & staker reward
+    let block_time = env.block.time.seconds();
+    settle_staker(&config, &mut state, &mut staker_info, block_time)?;
+
+    // unlocked amount first, then locks by soonest expiry
+    let mut locked_amount = amount.saturating_sub(unlocked_amount(&staker_info));
+    let mut penalty_amount = Uint128::zero();
+    for lock in staker_info.locks.iter_mut() {
+        if locked_amount.is_zero() {
+            break;
+        }
+
+        let released = std::cmp::min(lock.amount, locked_amount);
+        lock.amount -= released;
+        locked_amount -= released;
+        penalty_amount += released.mul_floor(early_exit.penalty_rate);
+    }
+    staker_info.locks.retain(|lock| !lock.amount.is_zero());
+
+    // Decrease bond_amount
+    decrease_bond_amount(&mut state, &mut staker_info, amount)?;
+    update_bond_weight(&mut state, &mut staker_info)?;
+
+    if staker_info.rewards.iter().all(|reward| reward.pending_reward.is_zero())
+        && staker_info.bond_amount.is_zero()
+    {
+        remove_staker_info(deps.storage, &sender_addr_raw);
+    } else {
+        store_staker_info(deps.storage, &sender_addr_raw, &staker_info)?;
+    }
+
+    let return_amount = amount.checked_sub(penalty_amount)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !return_amount.is_zero() {
+        messages = release_bond(
+            deps.storage,
+            &config,
+            &mut state,
+            &sender_addr_raw,
+            &info.sender,
+            return_amount,
+            block_time,
+        )?;
+    }
+
+    // Store updated state
+    store_state(deps.storage, &state)?;
+
+    if !penalty_amount.is_zero() {
+        messages.push(auction_msg(
+            &early_exit.auction_contract,
+            Asset {
+                info: config.staking_token,
+                amount: penalty_amount,
+            },
+        )?);
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        ("action", "early_unbond"),
+        ("owner", info.sender.as_str()),
+        ("amount", amount.to_string().as_str()),
+        ("penalty_amount", penalty_amount.to_string().as_str()),
+    ]))
+}
+
+// pay unbonded tokens out at once, or queue a claim during the unbonding period
+fn release_bond(
+    storage: &mut dyn Storage,
+    config: &Config,
+    state: &mut State,
+    staker: &CanonicalAddr,
+    recipient: &Addr,
+    amount: Uint128,
+    block_time: u64,
+) -> StdResult<Vec<CosmosMsg>> {
+    if config.unbonding_period == 0 {
+        return Ok(vec![transfer_msg(
+            &config.staking_token,
+            recipient.to_string(),
+            amount,
+        )?]);
+    }
+
+    // the period is fixed, so new claims release last
+    let release_time = block_time + config.unbonding_period;
+    let mut claims = read_unbonding_claims(storage, staker)?;
+    match claims.last_mut() {
+        Some(claim) if claim.release_time == release_time => claim.amount += amount,
+        _ => claims.push(UnbondClaim {
+            amount,
+            release_time,
+        }),
+    }
+    store_unbonding_claims(storage, staker, &claims)?;
+
+    state.total_unbonding_amount += amount;
+    Ok(vec![])
+}
+
+/// Pay out the unbond claims past their release time
+pub fn claim(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let sender_addr_raw: CanonicalAddr = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    let block_time = env.block.time.seconds();
+    let (matured, pending): (Vec<UnbondClaim>, Vec<UnbondClaim>) =
+        read_unbonding_claims(deps.storage, &sender_addr_raw)?
+            .into_iter()
+            .partition(|claim| claim.release_time <= block_time);
+
+    let amount: Uint128 = matured.iter().map(|claim| claim.amount).sum();
+    if amount.is_zero() {
+        return Err(StdError::generic_err("no unbonded tokens to claim"));
+    }
+
+    store_unbonding_claims(deps.storage, &sender_addr_raw, &pending)?;
+    state.total_unbonding_amount = state.total_unbonding_amount.checked_sub(amount)?;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_message(transfer_msg(&config.staking_token, info.sender.to_string(), amount)?)
+        .add_attributes(vec![
+            ("action", "claim"),
+            ("owner", info.sender.as_str()),
+            ("amount", amount.to_string().as_str()),
+        ]))
+}
+
 // withdraw rewards to executor
 pub fn withdraw(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Response> {
     let sender_addr_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
@@ -205,11 +459,17 @@ pub fn withdraw(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Respons
     let mut staker_info = read_staker_info(deps.storage, &sender_addr_raw)?;
 
     // Compute global reward & staker reward
-    compute_reward(&config, &mut state, env.block.time.seconds());
-    compute_staker_reward(&state, &mut staker_info)?;
-
-    let amount = staker_info.pending_reward;
-    staker_info.pending_reward = Uint128::zero();
+    settle_staker(&config, &mut state, &mut staker_info, env.block.time.seconds())?;
+
+    let rewards: Vec<Asset> = config
+        .reward_streams
+        .iter()
+        .zip(staker_info.rewards.iter_mut())
+        .map(|(stream, reward)| Asset {
+            info: stream.reward_token.clone(),
+            amount: std::mem::take(&mut reward.pending_reward),
+        })
+        .collect();
 
     // Store or remove updated rewards info
     // depends on the left pending reward and bond amount
@@ -219,31 +479,25 @@ pub fn withdraw(deps: DepsMut, env: Env, info: MessageInfo) -> StdResult<Respons
         store_staker_info(deps.storage, &sender_addr_raw, &staker_info)?;
     }
 
+    for (unclaimed, reward) in state.unclaimed_rewards.iter_mut().zip(rewards.iter()) {
+        *unclaimed = unclaimed.checked_sub(reward.amount)?;
+    }
+
     // Store updated state
     store_state(deps.storage, &state)?;
 
-    // Build the reward send message based on the asset type
-    let reward_msg: CosmosMsg = match config.reward_token {
-        AssetInfo::Token { ref contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: contract_addr.clone(),
-            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: info.sender.to_string(),
-                amount,
-            })?,
-            funds: vec![],
-        }),
-        AssetInfo::NativeToken { ref denom } => CosmosMsg::Bank(BankMsg::Send {
-            to_address: info.sender.to_string(),
-            amount: coins(amount.u128(), denom),
-        }),
-    };
+    // one send per reward token with something to claim
+    let mut reward_msgs: Vec<CosmosMsg> = vec![];
+    for reward in rewards.iter().filter(|reward| !reward.amount.is_zero()) {
+        reward_msgs.push(transfer_msg(&reward.info, info.sender.to_string(), reward.amount)?);
+    }
 
     Ok(Response::new()
-        .add_messages(vec![reward_msg])
+        .add_messages(reward_msgs)
         .add_attributes(vec![
             ("action", "withdraw"),
             ("owner", info.sender.as_str()),
-            ("amount", amount.to_string().as_str()),
+            ("rewards", &format_assets(&rewards)),
         ]))
 }
 
@@ -251,10 +505,11 @@ pub fn update_config(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
+    reward_token: AssetInfo,
     distribution_schedule: Vec<(u64, u64, Uint128)>,
 ) -> StdResult<Response> {
     // get gov address by querying anc token minter
-    let config: Config = read_config(deps.storage)?;
+    let mut config: Config = read_config(deps.storage)?;
     let state: State = read_state(deps.storage)?;
 
     // Get the sender's canonical address
@@ -265,19 +520,100 @@ pub fn update_config(
         return Err(StdError::generic_err("unauthorized"));
     }
 
-    assert_new_schedules(&config, &state, distribution_schedule.clone())?;
+    match config
+        .reward_streams
+        .iter_mut()
+        .find(|stream| stream.reward_token.equal(&reward_token))
+    {
+        Some(stream) => {
+            assert_new_schedules(
+                &stream.distribution_schedule,
+                &state,
+                distribution_schedule.clone(),
+            )?;
+            stream.distribution_schedule = distribution_schedule;
+        }
+        None => {
+            if config.reward_streams.len() >= MAX_REWARD_STREAMS {
+                return Err(StdError::generic_err("too many reward streams"));
+            }
 
-    let new_config = Config {
-        owner: config.owner,
-        reward_token: config.reward_token,
-        staking_token: config.staking_token,
-        distribution_schedule,
-    };
-    store_config(deps.storage, &new_config)?;
+            // a new stream may only schedule future distributions
+            assert_new_schedules(&[], &state, distribution_schedule.clone())?;
+            config.reward_streams.push(RewardStream {
+                reward_token,
+                distribution_schedule,
+            });
+        }
+    }
+    store_config(deps.storage, &config)?;
 
     Ok(Response::new().add_attributes(vec![("action", "update_config")]))
 }
 
+/// Anyone can fund future distributions of a reward token the owner
+/// registered, the funds must match the schedule and keep the token solvent
+pub fn add_rewards(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    reward: Asset,
+    schedule: Vec<(u64, u64, Uint128)>,
+) -> StdResult<Response> {
+    let block_time = env.block.time.seconds();
+    if schedule.is_empty() {
+        return Err(StdError::generic_err("empty reward schedule"));
+    }
+
+    for s in schedule.iter() {
+        if s.0 <= block_time {
+            return Err(StdError::generic_err("reward schedule must start in the future"));
+        }
+
+        if s.1 <= s.0 || s.2.is_zero() {
+            return Err(StdError::generic_err("invalid reward schedule slot"));
+        }
+    }
+
+    let scheduled_amount: Uint128 = schedule.iter().map(|s| s.2).sum();
+    if scheduled_amount != reward.amount {
+        return Err(StdError::generic_err(
+            "funds mismatch; must send exactly the scheduled rewards",
+        ));
+    }
+
+    let mut config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    // a stream of an unknown token would let any cw20 contract into the accrual
+    let stream = config
+        .reward_streams
+        .iter_mut()
+        .find(|stream| stream.reward_token.equal(&reward.info))
+        .ok_or_else(|| StdError::generic_err("reward token not registered"))?;
+    stream.distribution_schedule.extend(schedule);
+
+    // the new slots start in the future, so past distributions are unchanged
+    compute_reward(&config, &mut state, block_time);
+
+    let solvency = reward_balances(deps.as_ref(), &env, &config, &state)?;
+    if solvency
+        .iter()
+        .any(|balance| balance.reward_token.equal(&reward.info) && balance.held < balance.promised)
+    {
+        return Err(StdError::generic_err("insufficient reward balance"));
+    }
+
+    store_config(deps.storage, &config)?;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "add_rewards"),
+        ("sender", sender.as_str()),
+        ("rewards", &reward.to_string()),
+    ]))
+}
+
 pub fn migrate_staking(
     deps: DepsMut,
     env: Env,
@@ -296,33 +632,46 @@ pub fn migrate_staking(
     // compute global reward, sets last_distributed_seconds to env.block.time.seconds
     compute_reward(&config, &mut state, env.block.time.seconds());
 
-    let total_distribution_amount: Uint128 =
-        config.distribution_schedule.iter().map(|item| item.2).sum();
-
     let block_time = env.block.time.seconds();
-    // eliminate distribution slots that have not started
-    config
-        .distribution_schedule
-        .retain(|slot| slot.0 < block_time);
-
-    let mut distributed_amount = Uint128::zero();
-    for s in config.distribution_schedule.iter_mut() {
-        if s.1 < block_time {
-            // all distributed
-            distributed_amount += s.2;
-        } else {
-            // partially distributed slot
-            let whole_time = s.1 - s.0;
-            let distribution_amount_per_second: Decimal = Decimal::from_ratio(s.2, whole_time);
-
-            let passed_time = block_time - s.0;
-            let distributed_amount_on_slot = Uint128::from(passed_time as u128).mul_floor(distribution_amount_per_second);
-            distributed_amount += distributed_amount_on_slot;
-
-            // modify distribution slot
-            s.1 = block_time;
-            s.2 = distributed_amount_on_slot;
+    let mut distributed_assets: Vec<Asset> = vec![];
+    let mut remaining_assets: Vec<Asset> = vec![];
+    for stream in config.reward_streams.iter_mut() {
+        let total_distribution_amount: Uint128 =
+            stream.distribution_schedule.iter().map(|item| item.2).sum();
+
+        // eliminate distribution slots that have not started
+        stream
+            .distribution_schedule
+            .retain(|slot| slot.0 < block_time);
+
+        let mut distributed_amount = Uint128::zero();
+        for s in stream.distribution_schedule.iter_mut() {
+            if s.1 < block_time {
+                // all distributed
+                distributed_amount += s.2;
+            } else {
+                // partially distributed slot
+                let whole_time = s.1 - s.0;
+                let distribution_amount_per_second: Decimal = Decimal::from_ratio(s.2, whole_time);
+
+                let passed_time = block_time - s.0;
+                let distributed_amount_on_slot = Uint128::from(passed_time as u128).mul_floor(distribution_amount_per_second);
+                distributed_amount += distributed_amount_on_slot;
+
+                // modify distribution slot
+                s.1 = block_time;
+                s.2 = distributed_amount_on_slot;
+            }
         }
+
+        distributed_assets.push(Asset {
+            info: stream.reward_token.clone(),
+            amount: distributed_amount,
+        });
+        remaining_assets.push(Asset {
+            info: stream.reward_token.clone(),
+            amount: total_distribution_amount.checked_sub(distributed_amount)?,
+        });
     }
 
     // update config
@@ -330,33 +679,21 @@ pub fn migrate_staking(
     // update state
     store_state(deps.storage, &state)?;
 
-    let remaining_tokens = total_distribution_amount.checked_sub(distributed_amount)?;
-
-    let reward_token_msg = match config.reward_token {
-        AssetInfo::Token { ref contract_addr } => {
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: contract_addr.clone(),
-                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
-                    recipient: new_staking_contract,
-                    amount: remaining_tokens,
-                })?,
-                funds: vec![],
-            })
-        },
-        AssetInfo::NativeToken { ref denom } => {
-            CosmosMsg::Bank(BankMsg::Send {
-                to_address: new_staking_contract,
-                amount: coins(remaining_tokens.u128(), denom),
-            })
-        },
-    };
+    let mut reward_token_msgs: Vec<CosmosMsg> = vec![];
+    for remaining in remaining_assets.iter().filter(|asset| !asset.amount.is_zero()) {
+        reward_token_msgs.push(transfer_msg(
+            &remaining.info,
+            new_staking_contract.clone(),
+            remaining.amount,
+        )?);
+    }
 
     Ok(Response::new()
-        .add_messages(vec![reward_token_msg])
+        .add_messages(reward_token_msgs)
         .add_attributes(vec![
             ("action", "migrate_staking"),
-            ("distributed_amount", &distributed_amount.to_string()),
-            ("remaining_amount", &remaining_tokens.to_string()),
+            ("distributed_amount", &format_assets(&distributed_assets)),
+            ("remaining_amount", &format_assets(&remaining_assets)),
         ]))
 }
 
@@ -375,43 +712,244 @@ fn decrease_bond_amount(
     Ok(())
 }
 
-// compute distributed rewards and update global reward index
+// recompute the boosted weight of the staker after its bond or locks changed
+fn update_bond_weight(state: &mut State, staker_info: &mut StakerInfo) -> StdResult<()> {
+    let mut bond_weight = unlocked_amount(staker_info);
+    for lock in staker_info.locks.iter() {
+        bond_weight += lock.amount.mul_floor(lock.multiplier);
+    }
+
+    state.total_bond_weight = state
+        .total_bond_weight
+        .checked_sub(staker_info.bond_weight)?
+        .checked_add(bond_weight)?;
+    staker_info.bond_weight = bond_weight;
+    Ok(())
+}
+
+fn unlocked_amount(staker_info: &StakerInfo) -> Uint128 {
+    let locked_amount: Uint128 = staker_info.locks.iter().map(|lock| lock.amount).sum();
+    staker_info.bond_amount.saturating_sub(locked_amount)
+}
+
+// accrue rewards up to `block_time`, expired locks become unlocked bond and
+// their boost ends at the unlock time, or at the last distribution when
+// rewards were already distributed past it
+fn settle_staker(
+    config: &Config,
+    state: &mut State,
+    staker_info: &mut StakerInfo,
+    block_time: u64,
+) -> StdResult<()> {
+    // locks are ordered by expiry
+    while let Some(lock) = staker_info.locks.first() {
+        if lock.unlock_time > block_time {
+            break;
+        }
+
+        let unlock_time = std::cmp::max(lock.unlock_time, state.last_distributed);
+        compute_reward(config, state, unlock_time);
+        compute_staker_reward(state, staker_info)?;
+        staker_info.locks.remove(0);
+        update_bond_weight(state, staker_info)?;
+    }
+
+    compute_reward(config, state, block_time);
+    compute_staker_reward(state, staker_info)
+}
+
+/// Anyone can end the boost of the expired locks of `staker`, so an idle
+/// staker does not keep earning on it
+pub fn release_expired_locks(deps: DepsMut, env: Env, staker: String) -> StdResult<Response> {
+    let staker_raw: CanonicalAddr = deps.api.addr_canonicalize(&staker)?;
+
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let mut staker_info: StakerInfo = read_staker_info(deps.storage, &staker_raw)?;
+
+    let block_time = env.block.time.seconds();
+    let locks_len = staker_info.locks.len();
+    settle_staker(&config, &mut state, &mut staker_info, block_time)?;
+    if staker_info.locks.len() == locks_len {
+        return Err(StdError::generic_err("no expired locks"));
+    }
+
+    store_staker_info(deps.storage, &staker_raw, &staker_info)?;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "release_expired_locks"),
+        ("staker", staker.as_str()),
+        ("released_locks", (locks_len - staker_info.locks.len()).to_string().as_str()),
+    ]))
+}
+
+// keep locks ordered by expiry, merging locks of the same tier and expiry
+fn add_lock(staker_info: &mut StakerInfo, position: LockPosition) {
+    if let Some(lock) = staker_info.locks.iter_mut().find(|lock| {
+        lock.unlock_time == position.unlock_time && lock.multiplier == position.multiplier
+    }) {
+        lock.amount += position.amount;
+        return;
+    }
+
+    let index = staker_info
+        .locks
+        .iter()
+        .position(|lock| lock.unlock_time > position.unlock_time)
+        .unwrap_or(staker_info.locks.len());
+    staker_info.locks.insert(index, position);
+}
+
+// compute distributed rewards and update the global reward index of each stream
 fn compute_reward(config: &Config, state: &mut State, block_time: u64) {
-    if state.total_bond_amount.is_zero() {
+    // streams added since the last distribution start from a zero index
+    state
+        .global_reward_indexes
+        .resize(config.reward_streams.len(), Decimal::zero());
+    state
+        .unclaimed_rewards
+        .resize(config.reward_streams.len(), Uint128::zero());
+
+    if state.total_bond_weight.is_zero() {
         state.last_distributed = block_time;
         return;
     }
 
-    let mut distributed_amount: Uint128 = Uint128::zero();
-    for s in config.distribution_schedule.iter() {
-        if s.0 > block_time || s.1 < state.last_distributed {
+    for ((stream, global_reward_index), unclaimed) in config
+        .reward_streams
+        .iter()
+        .zip(state.global_reward_indexes.iter_mut())
+        .zip(state.unclaimed_rewards.iter_mut())
+    {
+        let distributed_amount =
+            scheduled_amount(&stream.distribution_schedule, state.last_distributed, block_time);
+
+        *global_reward_index += Decimal::from_ratio(distributed_amount, state.total_bond_weight);
+        *unclaimed += distributed_amount;
+    }
+
+    state.last_distributed = block_time;
+}
+
+// amount of the schedule distributed between `from` and `to`
+fn scheduled_amount(schedule: &[(u64, u64, Uint128)], from: u64, to: u64) -> Uint128 {
+    let mut amount: Uint128 = Uint128::zero();
+    for s in schedule.iter() {
+        if s.0 > to || s.1 < from {
             continue;
         }
 
-        // min(s.1, block_time) - max(s.0, last_distributed)
-        let passed_time =
-            std::cmp::min(s.1, block_time) - std::cmp::max(s.0, state.last_distributed);
+        // min(s.1, to) - max(s.0, from)
+        let passed_time = std::cmp::min(s.1, to) - std::cmp::max(s.0, from);
 
         let time = s.1 - s.0;
         let distribution_amount_per_second: Decimal = Decimal::from_ratio(s.2, time);
-        distributed_amount += Uint128::from(passed_time as u128).mul_floor(distribution_amount_per_second);
+        amount += Uint128::from(passed_time as u128).mul_floor(distribution_amount_per_second);
     }
+    amount
+}
 
-    state.last_distributed = block_time;
-    state.global_reward_index = state.global_reward_index
-        + Decimal::from_ratio(distributed_amount, state.total_bond_amount);
+// rewards owed to stakers of each stream and the balance held for them
+fn reward_balances(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    state: &State,
+) -> StdResult<Vec<RewardBalanceResponse>> {
+    let mut balances: Vec<RewardBalanceResponse> = vec![];
+    for (i, stream) in config.reward_streams.iter().enumerate() {
+        let promised = state.unclaimed_rewards.get(i).copied().unwrap_or_default()
+            + scheduled_amount(&stream.distribution_schedule, state.last_distributed, u64::MAX);
+
+        let mut held = stream.reward_token.query_pool(
+            &deps.querier,
+            deps.api,
+            env.contract.address.clone(),
+        )?;
+        // bonded and unbonding tokens are not rewards
+        if stream.reward_token.equal(&config.staking_token) {
+            held = held.saturating_sub(state.total_bond_amount + state.total_unbonding_amount);
+        }
+
+        balances.push(RewardBalanceResponse {
+            reward_token: stream.reward_token.clone(),
+            promised,
+            held,
+        });
+    }
+    Ok(balances)
 }
 
 // withdraw reward to pending reward
 fn compute_staker_reward(state: &State, staker_info: &mut StakerInfo) -> StdResult<()> {
-    let pending_reward = (staker_info.bond_amount.mul_floor(state.global_reward_index))
-        .checked_sub(staker_info.bond_amount.mul_floor(staker_info.reward_index))?;
-
-    staker_info.reward_index = state.global_reward_index;
-    staker_info.pending_reward += pending_reward;
+    staker_info
+        .rewards
+        .resize(state.global_reward_indexes.len(), StakerReward::default());
+
+    for (global_reward_index, reward) in state
+        .global_reward_indexes
+        .iter()
+        .zip(staker_info.rewards.iter_mut())
+    {
+        let pending_reward = (staker_info.bond_weight.mul_floor(*global_reward_index))
+            .checked_sub(staker_info.bond_weight.mul_floor(reward.reward_index))?;
+
+        reward.reward_index = *global_reward_index;
+        reward.pending_reward += pending_reward;
+    }
     Ok(())
 }
 
+fn transfer_msg(asset_info: &AssetInfo, recipient: String, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(match asset_info {
+        AssetInfo::Token { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.clone(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer { recipient, amount })?,
+            funds: vec![],
+        }),
+        AssetInfo::NativeToken { denom } => CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient,
+            amount: coins(amount.u128(), denom),
+        }),
+    })
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum BurnManagerMsg {
+    SendNative { asset: Asset },
+}
+
+fn auction_msg(auction_contract: &str, asset: Asset) -> StdResult<CosmosMsg> {
+    Ok(match &asset.info {
+        AssetInfo::NativeToken { denom } => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: auction_contract.to_string(),
+            msg: to_json_binary(&BurnManagerMsg::SendNative {
+                asset: asset.clone(),
+            })?,
+            funds: coins(asset.amount.u128(), denom),
+        }),
+        AssetInfo::Token { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.clone(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                contract: auction_contract.to_string(),
+                amount: asset.amount,
+                msg: Binary::default(),
+            })?,
+            funds: vec![],
+        }),
+    })
+}
+
+fn format_assets(assets: &[Asset]) -> String {
+    assets
+        .iter()
+        .map(|asset| asset.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -420,16 +958,34 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::StakerInfo { staker, block_time } => {
             to_json_binary(&query_staker_info(deps, staker, block_time)?)
         }
+        QueryMsg::RewardSolvency {} => to_json_binary(&query_reward_solvency(deps, _env)?),
+        QueryMsg::PendingUnbonds { staker } => {
+            to_json_binary(&query_pending_unbonds(deps, staker)?)
+        }
     }
 }
 
+pub fn query_reward_solvency(deps: Deps, env: Env) -> StdResult<RewardSolvencyResponse> {
+    let config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    compute_reward(&config, &mut state, env.block.time.seconds());
+
+    Ok(RewardSolvencyResponse {
+        rewards: reward_balances(deps, &env, &config, &state)?,
+    })
+}
+
+pub fn query_pending_unbonds(deps: Deps, staker: String) -> StdResult<PendingUnbondsResponse> {
+    let staker_raw = deps.api.addr_canonicalize(&staker)?;
+
+    Ok(PendingUnbondsResponse {
+        claims: read_unbonding_claims(deps.storage, &staker_raw)?,
+        staker,
+    })
+}
+
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config = read_config(deps.storage)?;
-    
-    let reward_token_str = match config.reward_token {
-        AssetInfo::Token { ref contract_addr } => contract_addr.clone(),
-        AssetInfo::NativeToken { ref denom } => denom.clone(),
-    };
 
     let staking_token_str = match config.staking_token {
         AssetInfo::Token { ref contract_addr } => contract_addr.clone(),
@@ -437,25 +993,42 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     };
 
     let resp = ConfigResponse {
-        reward_token: reward_token_str,
         staking_token: staking_token_str,
-        distribution_schedule: config.distribution_schedule,
+        reward_streams: config.reward_streams,
+        lock_tiers: config.lock_tiers,
+        early_exit: config.early_exit,
+        unbonding_period: config.unbonding_period,
     };
 
     Ok(resp)
 }
 
 pub fn query_state(deps: Deps, block_time: Option<u64>) -> StdResult<StateResponse> {
+    let config = read_config(deps.storage)?;
     let mut state: State = read_state(deps.storage)?;
     if let Some(block_time) = block_time {
-        let config = read_config(deps.storage)?;
         compute_reward(&config, &mut state, block_time);
     }
 
+    let rewards = config
+        .reward_streams
+        .into_iter()
+        .enumerate()
+        .map(|(i, stream)| RewardStateResponse {
+            reward_token: stream.reward_token,
+            global_reward_index: state
+                .global_reward_indexes
+                .get(i)
+                .copied()
+                .unwrap_or_default(),
+        })
+        .collect();
+
     Ok(StateResponse {
         last_distributed: state.last_distributed,
         total_bond_amount: state.total_bond_amount,
-        global_reward_index: state.global_reward_index,
+        total_bond_weight: state.total_bond_weight,
+        rewards,
     })
 }
 
@@ -466,36 +1039,50 @@ pub fn query_staker_info(
 ) -> StdResult<StakerInfoResponse> {
     let staker_raw = deps.api.addr_canonicalize(&staker)?;
 
+    let config = read_config(deps.storage)?;
     let mut staker_info: StakerInfo = read_staker_info(deps.storage, &staker_raw)?;
     if let Some(block_time) = block_time {
-        let config = read_config(deps.storage)?;
         let mut state = read_state(deps.storage)?;
 
-        compute_reward(&config, &mut state, block_time);
-        compute_staker_reward(&state, &mut staker_info)?;
+        settle_staker(&config, &mut state, &mut staker_info, block_time)?;
     }
 
+    let rewards = config
+        .reward_streams
+        .into_iter()
+        .enumerate()
+        .map(|(i, stream)| {
+            let reward = staker_info.rewards.get(i).cloned().unwrap_or_default();
+            StakerRewardResponse {
+                reward_token: stream.reward_token,
+                reward_index: reward.reward_index,
+                pending_reward: reward.pending_reward,
+            }
+        })
+        .collect();
+
     Ok(StakerInfoResponse {
         staker,
-        reward_index: staker_info.reward_index,
         bond_amount: staker_info.bond_amount,
-        pending_reward: staker_info.pending_reward,
+        bond_weight: staker_info.bond_weight,
+        locks: staker_info.locks,
+        rewards,
     })
 }
 
 pub fn assert_new_schedules(
-    config: &Config,
+    current_schedule: &[(u64, u64, Uint128)],
     state: &State,
     distribution_schedule: Vec<(u64, u64, Uint128)>,
 ) -> StdResult<()> {
-    if distribution_schedule.len() < config.distribution_schedule.len() {
+    if distribution_schedule.len() < current_schedule.len() {
         return Err(StdError::generic_err(
             "cannot update; the new schedule must support all of the previous schedule",
         ));
     }
 
     let mut existing_counts: BTreeMap<(u64, u64, Uint128), u32> = BTreeMap::new();
-    for schedule in config.distribution_schedule.clone() {
+    for schedule in current_schedule.iter().cloned() {
         let counter = existing_counts.entry(schedule).or_insert(0);
         *counter += 1;
     }
@@ -530,6 +1117,67 @@ pub fn assert_new_schedules(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    // farms paying a single reward token move it to the first stream
+    if let Ok(legacy_config) = LEGACY_CONFIG.load(deps.storage) {
+        let legacy_state = LEGACY_STATE.load(deps.storage)?;
+        store_config(
+            deps.storage,
+            &Config {
+                owner: legacy_config.owner,
+                staking_token: legacy_config.staking_token,
+                reward_streams: vec![RewardStream {
+                    reward_token: legacy_config.reward_token,
+                    distribution_schedule: legacy_config.distribution_schedule,
+                }],
+                lock_tiers: vec![],
+                early_exit: None,
+                unbonding_period: 0,
+            },
+        )?;
+        let staker_infos = LEGACY_STAKER_INFO
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        // rewards distributed to stakers but not withdrawn yet
+        let mut unclaimed = Uint128::zero();
+        for (staker, legacy_staker_info) in staker_infos {
+            unclaimed += legacy_staker_info.pending_reward
+                + legacy_staker_info
+                    .bond_amount
+                    .mul_floor(legacy_state.global_reward_index)
+                    .checked_sub(
+                        legacy_staker_info
+                            .bond_amount
+                            .mul_floor(legacy_staker_info.reward_index),
+                    )?;
+            STAKER_INFO.save(
+                deps.storage,
+                &staker,
+                &StakerInfo {
+                    bond_amount: legacy_staker_info.bond_amount,
+                    bond_weight: legacy_staker_info.bond_amount,
+                    locks: vec![],
+                    rewards: vec![StakerReward {
+                        reward_index: legacy_staker_info.reward_index,
+                        pending_reward: legacy_staker_info.pending_reward,
+                    }],
+                },
+            )?;
+        }
+
+        store_state(
+            deps.storage,
+            &State {
+                last_distributed: legacy_state.last_distributed,
+                total_bond_amount: legacy_state.total_bond_amount,
+                total_bond_weight: legacy_state.total_bond_amount,
+                total_unbonding_amount: Uint128::zero(),
+                global_reward_indexes: vec![legacy_state.global_reward_index],
+                unclaimed_rewards: vec![unclaimed],
+            },
+        )?;
+    }
+
     Ok(Response::default())
 }
\ No newline at end of file
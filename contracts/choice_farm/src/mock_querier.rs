@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::marker::PhantomData;
 
 use schemars::JsonSchema;
@@ -6,9 +7,9 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
     from_json, to_json_binary, Coin, ContractResult, Empty, OwnedDeps, Querier,
-    QuerierResult, QueryRequest, SystemError, SystemResult, WasmQuery,
+    QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery,
 };
-use cw20::MinterResponse;
+use cw20::{BalanceResponse, MinterResponse};
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
 /// this uses our CustomQuerier.
@@ -22,13 +23,37 @@ pub fn mock_dependencies(
         api: MockApi::default(),
         storage: MockStorage::default(),
         querier: custom_querier,
-        custom_query_type: PhantomData::default(),
+        custom_query_type: PhantomData,
     }
 }
 
 pub struct WasmMockQuerier {
     base: MockQuerier<Empty>,
     minter_querier: MinterQuerier,
+    token_querier: TokenQuerier,
+}
+
+#[derive(Clone, Default)]
+pub struct TokenQuerier {
+    // token contract -> holder -> balance
+    balances: HashMap<String, HashMap<String, Uint128>>,
+}
+
+impl TokenQuerier {
+    pub fn new(balances: &[(&String, &[(&String, &Uint128)])]) -> Self {
+        let mut balances_map: HashMap<String, HashMap<String, Uint128>> = HashMap::new();
+        for (contract_addr, balances) in balances.iter() {
+            let mut contract_balances_map: HashMap<String, Uint128> = HashMap::new();
+            for (addr, balance) in balances.iter() {
+                contract_balances_map.insert(addr.to_string(), **balance);
+            }
+
+            balances_map.insert(contract_addr.to_string(), contract_balances_map);
+        }
+        TokenQuerier {
+            balances: balances_map,
+        }
+    }
 }
 
 #[derive(Clone, Default)]
@@ -48,6 +73,7 @@ impl MinterQuerier {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Minter {},
+    Balance { address: String },
 }
 
 impl Querier for WasmMockQuerier {
@@ -70,7 +96,7 @@ impl WasmMockQuerier {
     pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
         match &request {
             QueryRequest::Wasm(WasmQuery::Smart {
-                contract_addr: _,
+                contract_addr,
                 msg,
             }) => match from_json(msg) {
                 Ok(QueryMsg::Minter {}) => {
@@ -79,6 +105,18 @@ impl WasmMockQuerier {
                         cap: None,
                     })))
                 }
+                Ok(QueryMsg::Balance { address }) => {
+                    let balance = self
+                        .token_querier
+                        .balances
+                        .get(contract_addr)
+                        .and_then(|balances| balances.get(&address))
+                        .copied()
+                        .unwrap_or_default();
+                    SystemResult::Ok(ContractResult::from(to_json_binary(&BalanceResponse {
+                        balance,
+                    })))
+                }
                 _ => panic!("query not mocked"),
             },
             _ => self.base.handle_query(request),
@@ -91,10 +129,15 @@ impl WasmMockQuerier {
         WasmMockQuerier {
             base,
             minter_querier: MinterQuerier::default(),
+            token_querier: TokenQuerier::default(),
         }
     }
 
     pub fn with_anc_minter(&mut self, minter: String) {
         self.minter_querier = MinterQuerier::new(minter);
     }
+
+    pub fn with_token_balances(&mut self, balances: &[(&String, &[(&String, &Uint128)])]) {
+        self.token_querier = TokenQuerier::new(balances);
+    }
 }
\ No newline at end of file
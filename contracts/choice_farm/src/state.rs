@@ -5,6 +5,7 @@ use cosmwasm_std::{CanonicalAddr, Decimal, StdResult, Storage, Uint128};
 use cw_storage_plus::{Item, Map};
 
 use choice::asset::AssetInfo;
+use choice::staking::{EarlyExit, LockPosition, LockTier, RewardStream, UnbondClaim};
 
 
 /// Store the configuration under the key "config"
@@ -16,12 +17,17 @@ pub const STATE: Item<State> = Item::new("state");
 /// Store staker info using the prefix "reward"
 pub const STAKER_INFO: Map<&[u8], StakerInfo> = Map::new("reward");
 
+/// Store pending unbond claims of each staker under the prefix "unbonding_claims"
+pub const UNBONDING_CLAIMS: Map<&[u8], Vec<UnbondClaim>> = Map::new("unbonding_claims");
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub owner: CanonicalAddr,
-    pub reward_token: AssetInfo,
     pub staking_token: AssetInfo,
-    pub distribution_schedule: Vec<(u64, u64, Uint128)>,
+    pub reward_streams: Vec<RewardStream>,
+    pub lock_tiers: Vec<LockTier>,
+    pub early_exit: Option<EarlyExit>,
+    pub unbonding_period: u64,
 }
 
 /// Save the configuration into storage.
@@ -38,7 +44,14 @@ pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
 pub struct State {
     pub last_distributed: u64,
     pub total_bond_amount: Uint128,
-    pub global_reward_index: Decimal,
+    /// Sum of the boosted bond weights, rewards are split by weight
+    pub total_bond_weight: Uint128,
+    /// Unbonded tokens waiting to be claimed
+    pub total_unbonding_amount: Uint128,
+    /// Global index of each reward stream, in the order of `Config::reward_streams`
+    pub global_reward_indexes: Vec<Decimal>,
+    /// Distributed rewards of each stream not withdrawn yet
+    pub unclaimed_rewards: Vec<Uint128>,
 }
 
 /// Save the state into storage.
@@ -53,8 +66,17 @@ pub fn read_state(storage: &dyn Storage) -> StdResult<State> {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct StakerInfo {
-    pub reward_index: Decimal,
     pub bond_amount: Uint128,
+    pub bond_weight: Uint128,
+    /// Locked part of `bond_amount`, the rest is unlocked
+    pub locks: Vec<LockPosition>,
+    /// Rewards of each reward stream, in the order of `Config::reward_streams`
+    pub rewards: Vec<StakerReward>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct StakerReward {
+    pub reward_index: Decimal,
     pub pending_reward: Uint128,
 }
 
@@ -81,9 +103,61 @@ pub fn read_staker_info(storage: &dyn Storage, owner: &CanonicalAddr) -> StdResu
     match STAKER_INFO.may_load(storage, owner.as_slice())? {
         Some(info) => Ok(info),
         None => Ok(StakerInfo {
-            reward_index: Decimal::zero(),
             bond_amount: Uint128::zero(),
-            pending_reward: Uint128::zero(),
+            bond_weight: Uint128::zero(),
+            locks: vec![],
+            rewards: vec![],
         }),
     }
 }
+
+/// Read the pending unbond claims of a staker, ordered by release time.
+pub fn read_unbonding_claims(
+    storage: &dyn Storage,
+    owner: &CanonicalAddr,
+) -> StdResult<Vec<UnbondClaim>> {
+    Ok(UNBONDING_CLAIMS
+        .may_load(storage, owner.as_slice())?
+        .unwrap_or_default())
+}
+
+/// Save the pending unbond claims of a staker, removing them once all are paid.
+pub fn store_unbonding_claims(
+    storage: &mut dyn Storage,
+    owner: &CanonicalAddr,
+    claims: &[UnbondClaim],
+) -> StdResult<()> {
+    if claims.is_empty() {
+        UNBONDING_CLAIMS.remove(storage, owner.as_slice());
+        Ok(())
+    } else {
+        UNBONDING_CLAIMS.save(storage, owner.as_slice(), &claims.to_vec())
+    }
+}
+
+/// Config of farms paying a single reward token
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyConfig {
+    pub owner: CanonicalAddr,
+    pub reward_token: AssetInfo,
+    pub staking_token: AssetInfo,
+    pub distribution_schedule: Vec<(u64, u64, Uint128)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyState {
+    pub last_distributed: u64,
+    pub total_bond_amount: Uint128,
+    pub global_reward_index: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyStakerInfo {
+    pub reward_index: Decimal,
+    pub bond_amount: Uint128,
+    pub pending_reward: Uint128,
+}
+
+pub const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("config");
+pub const LEGACY_STATE: Item<LegacyState> = Item::new("state");
+pub const LEGACY_STAKER_INFO: Map<&[u8], LegacyStakerInfo> = Map::new("reward");
@@ -1,29 +1,45 @@
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, migrate, query};
 use crate::mock_querier::mock_dependencies;
+use crate::state::{
+    LegacyConfig, LegacyStakerInfo, LegacyState, LEGACY_CONFIG, LEGACY_STAKER_INFO, LEGACY_STATE,
+};
 use choice::staking::ExecuteMsg::UpdateConfig;
 use choice::staking::{
-    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg, StakerInfoResponse,
-    StateResponse,
+    ConfigResponse, Cw20HookMsg, EarlyExit, ExecuteMsg, InstantiateMsg, LockPosition, LockTier,
+    MigrateMsg, PendingUnbondsResponse, QueryMsg, RewardBalanceResponse, RewardSolvencyResponse,
+    RewardStateResponse, RewardStream, StakerInfoResponse, StakerRewardResponse, StateResponse,
+    UnbondClaim,
 };
 use choice::asset::AssetInfo;
-use cosmwasm_std::testing::{mock_env, message_info};
+use cosmwasm_std::testing::{mock_env, message_info, MockApi, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, from_json, to_json_binary, coins, CosmosMsg, Coin, Decimal, StdError, SubMsg, Uint128, WasmMsg, BankMsg
+    attr, from_json, Api, to_json_binary, Binary, coins, CosmosMsg, Coin, Decimal, Deps, StdError, SubMsg, Uint128, WasmMsg, BankMsg
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 
+fn reward_token(api: &MockApi) -> AssetInfo {
+    AssetInfo::Token {
+        contract_addr: api.addr_make("reward0000").to_string(),
+    }
+}
+
 #[test]
 fn proper_initialization() {
     let mut deps = mock_dependencies(&[]);
 
     let msg = InstantiateMsg {
-        reward_token: AssetInfo::Token {
-            contract_addr: deps.api.addr_make("reward0000").to_string(),
-        },
         staking_token: AssetInfo::Token {
             contract_addr: deps.api.addr_make("staking0000").to_string(),
         },
-        distribution_schedule: vec![(100, 200, Uint128::from(1000000u128))],
+        reward_streams: vec![RewardStream {
+                reward_token: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("reward0000").to_string(),
+                },
+                distribution_schedule: vec![(100, 200, Uint128::from(1000000u128))],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: None,
     };
 
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -37,9 +53,14 @@ fn proper_initialization() {
     assert_eq!(
         config,
         ConfigResponse {
-            reward_token: deps.api.addr_make("reward0000").to_string(),
             staking_token: deps.api.addr_make("staking0000").to_string(),
-            distribution_schedule: vec![(100, 200, Uint128::from(1000000u128))],
+            reward_streams: vec![RewardStream {
+                reward_token: reward_token(&deps.api),
+                distribution_schedule: vec![(100, 200, Uint128::from(1000000u128))],
+            }],
+            lock_tiers: vec![],
+            early_exit: None,
+            unbonding_period: 0,
         }
     );
 
@@ -55,7 +76,11 @@ fn proper_initialization() {
         StateResponse {
             last_distributed: mock_env().block.time.seconds(),
             total_bond_amount: Uint128::zero(),
-            global_reward_index: Decimal::zero(),
+            total_bond_weight: Uint128::zero(),
+            rewards: vec![RewardStateResponse {
+                reward_token: reward_token(&deps.api),
+                global_reward_index: Decimal::zero(),
+            }],
         }
     );
 }
@@ -65,24 +90,29 @@ fn test_bond_tokens() {
     let mut deps = mock_dependencies(&[]);
 
     let msg = InstantiateMsg {
-        reward_token: AssetInfo::Token {
-            contract_addr: deps.api.addr_make("reward0000").to_string(),
-        },
         staking_token: AssetInfo::Token {
             contract_addr: deps.api.addr_make("staking0000").to_string(),
         },
-        distribution_schedule: vec![
-            (
-                mock_env().block.time.seconds(),
-                mock_env().block.time.seconds() + 100,
-                Uint128::from(1000000u128),
-            ),
-            (
-                mock_env().block.time.seconds() + 100,
-                mock_env().block.time.seconds() + 200,
-                Uint128::from(10000000u128),
-            ),
-        ],
+        reward_streams: vec![RewardStream {
+                reward_token: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("reward0000").to_string(),
+                },
+                distribution_schedule: vec![
+                    (
+                        mock_env().block.time.seconds(),
+                        mock_env().block.time.seconds() + 100,
+                        Uint128::from(1000000u128),
+                    ),
+                    (
+                        mock_env().block.time.seconds() + 100,
+                        mock_env().block.time.seconds() + 200,
+                        Uint128::from(10000000u128),
+                    ),
+                ],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: None,
     };
 
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -91,7 +121,7 @@ fn test_bond_tokens() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: deps.api.addr_make("addr0000").to_string(),
         amount: Uint128::from(100u128),
-        msg: to_json_binary(&Cw20HookMsg::Bond {}).unwrap(),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
     });
 
     let info = message_info(&deps.api.addr_make("staking0000"), &[]);
@@ -113,9 +143,14 @@ fn test_bond_tokens() {
         .unwrap(),
         StakerInfoResponse {
             staker: deps.api.addr_make("addr0000").to_string(),
-            reward_index: Decimal::zero(),
-            pending_reward: Uint128::zero(),
+            rewards: vec![StakerRewardResponse {
+                reward_token: reward_token(&deps.api),
+                reward_index: Decimal::zero(),
+                pending_reward: Uint128::zero(),
+            }],
             bond_amount: Uint128::from(100u128),
+            bond_weight: Uint128::from(100u128),
+            locks: vec![],
         }
     );
 
@@ -131,7 +166,11 @@ fn test_bond_tokens() {
         .unwrap(),
         StateResponse {
             total_bond_amount: Uint128::from(100u128),
-            global_reward_index: Decimal::zero(),
+            total_bond_weight: Uint128::from(100u128),
+            rewards: vec![RewardStateResponse {
+                reward_token: reward_token(&deps.api),
+                global_reward_index: Decimal::zero(),
+            }],
             last_distributed: mock_env().block.time.seconds(),
         }
     );
@@ -140,7 +179,7 @@ fn test_bond_tokens() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: deps.api.addr_make("addr0000").to_string(),
         amount: Uint128::from(100u128),
-        msg: to_json_binary(&Cw20HookMsg::Bond {}).unwrap(),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
     });
     env.block.time = env.block.time.plus_seconds(10);
 
@@ -161,9 +200,14 @@ fn test_bond_tokens() {
         .unwrap(),
         StakerInfoResponse {
             staker: deps.api.addr_make("addr0000").to_string(),
-            reward_index: Decimal::from_ratio(1000u128, 1u128),
-            pending_reward: Uint128::from(100000u128),
+            rewards: vec![StakerRewardResponse {
+                reward_token: reward_token(&deps.api),
+                reward_index: Decimal::from_ratio(1000u128, 1u128),
+                pending_reward: Uint128::from(100000u128),
+            }],
             bond_amount: Uint128::from(200u128),
+            bond_weight: Uint128::from(200u128),
+            locks: vec![],
         }
     );
 
@@ -179,7 +223,11 @@ fn test_bond_tokens() {
         .unwrap(),
         StateResponse {
             total_bond_amount: Uint128::from(200u128),
-            global_reward_index: Decimal::from_ratio(1000u128, 1u128),
+            total_bond_weight: Uint128::from(200u128),
+            rewards: vec![RewardStateResponse {
+                reward_token: reward_token(&deps.api),
+                global_reward_index: Decimal::from_ratio(1000u128, 1u128),
+            }],
             last_distributed: mock_env().block.time.seconds() + 10,
         }
     );
@@ -188,7 +236,7 @@ fn test_bond_tokens() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: deps.api.addr_make("addr0000").to_string(),
         amount: Uint128::from(100u128),
-        msg: to_json_binary(&Cw20HookMsg::Bond {}).unwrap(),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
     });
 
     let info = message_info(&deps.api.addr_make("staking0001"), &[]);
@@ -204,16 +252,21 @@ fn test_unbond() {
     let mut deps = mock_dependencies(&[]);
 
     let msg = InstantiateMsg {
-        reward_token: AssetInfo::Token {
-            contract_addr: deps.api.addr_make("reward0000").to_string(),
-        },
         staking_token: AssetInfo::Token {
             contract_addr: deps.api.addr_make("staking0000").to_string(),
         },
-        distribution_schedule: vec![
-            (12345, 12345 + 100, Uint128::from(1000000u128)),
-            (12345 + 100, 12345 + 200, Uint128::from(10000000u128)),
-        ],
+        reward_streams: vec![RewardStream {
+                reward_token: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("reward0000").to_string(),
+                },
+                distribution_schedule: vec![
+                    (12345, 12345 + 100, Uint128::from(1000000u128)),
+                    (12345 + 100, 12345 + 200, Uint128::from(10000000u128)),
+                ],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: None,
     };
 
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -223,7 +276,7 @@ fn test_unbond() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: deps.api.addr_make("addr0000").to_string(),
         amount: Uint128::from(100u128),
-        msg: to_json_binary(&Cw20HookMsg::Bond {}).unwrap(),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
     });
     let info = message_info(&deps.api.addr_make("staking0000"), &[]);
     let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -268,24 +321,29 @@ fn test_compute_reward() {
     let mut deps = mock_dependencies(&[]);
 
     let msg = InstantiateMsg {
-        reward_token: AssetInfo::Token {
-            contract_addr: deps.api.addr_make("reward0000").to_string(),
-        },
         staking_token: AssetInfo::Token {
             contract_addr: deps.api.addr_make("staking0000").to_string(),
         },
-        distribution_schedule: vec![
-            (
-                mock_env().block.time.seconds(),
-                mock_env().block.time.seconds() + 100,
-                Uint128::from(1000000u128),
-            ),
-            (
-                mock_env().block.time.seconds() + 100,
-                mock_env().block.time.seconds() + 200,
-                Uint128::from(10000000u128),
-            ),
-        ],
+        reward_streams: vec![RewardStream {
+                reward_token: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("reward0000").to_string(),
+                },
+                distribution_schedule: vec![
+                    (
+                        mock_env().block.time.seconds(),
+                        mock_env().block.time.seconds() + 100,
+                        Uint128::from(1000000u128),
+                    ),
+                    (
+                        mock_env().block.time.seconds() + 100,
+                        mock_env().block.time.seconds() + 200,
+                        Uint128::from(10000000u128),
+                    ),
+                ],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: None,
     };
 
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -295,7 +353,7 @@ fn test_compute_reward() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: deps.api.addr_make("addr0000").to_string(),
         amount: Uint128::from(100u128),
-        msg: to_json_binary(&Cw20HookMsg::Bond {}).unwrap(),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
     });
     let info = message_info(&deps.api.addr_make("staking0000"), &[]);
     let mut env = mock_env();
@@ -309,7 +367,7 @@ fn test_compute_reward() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: deps.api.addr_make("addr0000").to_string(),
         amount: Uint128::from(100u128),
-        msg: to_json_binary(&Cw20HookMsg::Bond {}).unwrap(),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
     });
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
@@ -328,9 +386,14 @@ fn test_compute_reward() {
         .unwrap(),
         StakerInfoResponse {
             staker: deps.api.addr_make("addr0000").to_string(),
-            reward_index: Decimal::from_ratio(10000u128, 1u128),
-            pending_reward: Uint128::from(1000000u128),
+            rewards: vec![StakerRewardResponse {
+                reward_token: reward_token(&deps.api),
+                reward_index: Decimal::from_ratio(10000u128, 1u128),
+                pending_reward: Uint128::from(1000000u128),
+            }],
             bond_amount: Uint128::from(200u128),
+            bond_weight: Uint128::from(200u128),
+            locks: vec![],
         }
     );
 
@@ -359,9 +422,14 @@ fn test_compute_reward() {
         .unwrap(),
         StakerInfoResponse {
             staker: deps.api.addr_make("addr0000").to_string(),
-            reward_index: Decimal::from_ratio(15000u64, 1u64),
-            pending_reward: Uint128::from(2000000u128),
+            rewards: vec![StakerRewardResponse {
+                reward_token: reward_token(&deps.api),
+                reward_index: Decimal::from_ratio(15000u64, 1u64),
+                pending_reward: Uint128::from(2000000u128),
+            }],
             bond_amount: Uint128::from(100u128),
+            bond_weight: Uint128::from(100u128),
+            locks: vec![],
         }
     );
 
@@ -381,9 +449,14 @@ fn test_compute_reward() {
         .unwrap(),
         StakerInfoResponse {
             staker: deps.api.addr_make("addr0000").to_string(),
-            reward_index: Decimal::from_ratio(25000u64, 1u64),
-            pending_reward: Uint128::from(3000000u128),
+            rewards: vec![StakerRewardResponse {
+                reward_token: reward_token(&deps.api),
+                reward_index: Decimal::from_ratio(25000u64, 1u64),
+                pending_reward: Uint128::from(3000000u128),
+            }],
             bond_amount: Uint128::from(100u128),
+            bond_weight: Uint128::from(100u128),
+            locks: vec![],
         }
     );
 }
@@ -393,24 +466,29 @@ fn test_withdraw() {
     let mut deps = mock_dependencies(&[]);
 
     let msg = InstantiateMsg {
-        reward_token: AssetInfo::Token {
-            contract_addr: deps.api.addr_make("reward0000").to_string(),
-        },
         staking_token: AssetInfo::Token {
             contract_addr: deps.api.addr_make("staking0000").to_string(),
         },
-        distribution_schedule: vec![
-            (
-                mock_env().block.time.seconds(),
-                mock_env().block.time.seconds() + 100,
-                Uint128::from(1000000u128),
-            ),
-            (
-                mock_env().block.time.seconds() + 100,
-                mock_env().block.time.seconds() + 200,
-                Uint128::from(10000000u128),
-            ),
-        ],
+        reward_streams: vec![RewardStream {
+                reward_token: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("reward0000").to_string(),
+                },
+                distribution_schedule: vec![
+                    (
+                        mock_env().block.time.seconds(),
+                        mock_env().block.time.seconds() + 100,
+                        Uint128::from(1000000u128),
+                    ),
+                    (
+                        mock_env().block.time.seconds() + 100,
+                        mock_env().block.time.seconds() + 200,
+                        Uint128::from(10000000u128),
+                    ),
+                ],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: None,
     };
 
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -420,7 +498,7 @@ fn test_withdraw() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: deps.api.addr_make("addr0000").to_string(),
         amount: Uint128::from(100u128),
-        msg: to_json_binary(&Cw20HookMsg::Bond {}).unwrap(),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
     });
     let info = message_info(&deps.api.addr_make("staking0000"), &[]);
     let mut env = mock_env();
@@ -454,24 +532,29 @@ fn test_migrate_staking() {
     let mut deps = mock_dependencies(&[]);
 
     let msg = InstantiateMsg {
-        reward_token: AssetInfo::Token {
-            contract_addr: deps.api.addr_make("reward0000").to_string(),
-        },
         staking_token: AssetInfo::Token {
             contract_addr: deps.api.addr_make("staking0000").to_string(),
         },
-        distribution_schedule: vec![
-            (
-                mock_env().block.time.seconds(),
-                mock_env().block.time.seconds() + 100,
-                Uint128::from(1000000u128),
-            ),
-            (
-                mock_env().block.time.seconds() + 100,
-                mock_env().block.time.seconds() + 200,
-                Uint128::from(10000000u128),
-            ),
-        ],
+        reward_streams: vec![RewardStream {
+                reward_token: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("reward0000").to_string(),
+                },
+                distribution_schedule: vec![
+                    (
+                        mock_env().block.time.seconds(),
+                        mock_env().block.time.seconds() + 100,
+                        Uint128::from(1000000u128),
+                    ),
+                    (
+                        mock_env().block.time.seconds() + 100,
+                        mock_env().block.time.seconds() + 200,
+                        Uint128::from(10000000u128),
+                    ),
+                ],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: None,
     };
 
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -481,7 +564,7 @@ fn test_migrate_staking() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: deps.api.addr_make("addr0000").to_string(),
         amount: Uint128::from(100u128),
-        msg: to_json_binary(&Cw20HookMsg::Bond {}).unwrap(),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
     });
     let info = message_info(&deps.api.addr_make("staking0000"), &[]);
     let mut env = mock_env();
@@ -532,8 +615,16 @@ fn test_migrate_staking() {
         res.attributes,
         vec![
             attr("action", "migrate_staking"),
-            attr("distributed_amount", "6000000"), // 1000000 + (10000000 / 2)
-            attr("remaining_amount", "5000000")    // 11,000,000 - 6000000
+            // 1000000 + (10000000 / 2)
+            attr(
+                "distributed_amount",
+                format!("6000000{}", deps.api.addr_make("reward0000"))
+            ),
+            // 11,000,000 - 6000000
+            attr(
+                "remaining_amount",
+                format!("5000000{}", deps.api.addr_make("reward0000"))
+            )
         ]
     );
 
@@ -556,20 +647,25 @@ fn test_migrate_staking() {
     assert_eq!(
         config,
         ConfigResponse {
-            reward_token: deps.api.addr_make("reward0000").to_string(),
             staking_token: deps.api.addr_make("staking0000").to_string(),
-            distribution_schedule: vec![
-                (
-                    mock_env().block.time.seconds(),
-                    mock_env().block.time.seconds() + 100,
-                    Uint128::from(1000000u128)
-                ),
-                (
-                    mock_env().block.time.seconds() + 100,
-                    mock_env().block.time.seconds() + 150,
-                    Uint128::from(5000000u128)
-                ), // slot was modified
-            ]
+            reward_streams: vec![RewardStream {
+                reward_token: reward_token(&deps.api),
+                distribution_schedule: vec![
+                    (
+                        mock_env().block.time.seconds(),
+                        mock_env().block.time.seconds() + 100,
+                        Uint128::from(1000000u128)
+                    ),
+                    (
+                        mock_env().block.time.seconds() + 100,
+                        mock_env().block.time.seconds() + 150,
+                        Uint128::from(5000000u128)
+                    ), // slot was modified
+                ]
+            }],
+            lock_tiers: vec![],
+            early_exit: None,
+            unbonding_period: 0,
         }
     );
 }
@@ -579,45 +675,51 @@ fn test_update_config() {
     let mut deps = mock_dependencies(&[]);
 
     let msg = InstantiateMsg {
-        reward_token: AssetInfo::Token {
-            contract_addr: deps.api.addr_make("reward0000").to_string(),
-        },
         staking_token: AssetInfo::Token {
             contract_addr: deps.api.addr_make("staking0000").to_string(),
         },
-        distribution_schedule: vec![
-            (
-                mock_env().block.time.seconds(),
-                mock_env().block.time.seconds() + 100,
-                Uint128::from(1000000u128),
-            ),
-            (
-                mock_env().block.time.seconds() + 100,
-                mock_env().block.time.seconds() + 200,
-                Uint128::from(10000000u128),
-            ),
-            (
-                mock_env().block.time.seconds() + 200,
-                mock_env().block.time.seconds() + 300,
-                Uint128::from(10000000u128),
-            ),
-            (
-                mock_env().block.time.seconds() + 300,
-                mock_env().block.time.seconds() + 400,
-                Uint128::from(10000000u128),
-            ),
-            (
-                mock_env().block.time.seconds() + 400,
-                mock_env().block.time.seconds() + 500,
-                Uint128::from(10000000u128),
-            ),
-        ],
+        reward_streams: vec![RewardStream {
+                reward_token: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("reward0000").to_string(),
+                },
+                distribution_schedule: vec![
+                    (
+                        mock_env().block.time.seconds(),
+                        mock_env().block.time.seconds() + 100,
+                        Uint128::from(1000000u128),
+                    ),
+                    (
+                        mock_env().block.time.seconds() + 100,
+                        mock_env().block.time.seconds() + 200,
+                        Uint128::from(10000000u128),
+                    ),
+                    (
+                        mock_env().block.time.seconds() + 200,
+                        mock_env().block.time.seconds() + 300,
+                        Uint128::from(10000000u128),
+                    ),
+                    (
+                        mock_env().block.time.seconds() + 300,
+                        mock_env().block.time.seconds() + 400,
+                        Uint128::from(10000000u128),
+                    ),
+                    (
+                        mock_env().block.time.seconds() + 400,
+                        mock_env().block.time.seconds() + 500,
+                        Uint128::from(10000000u128),
+                    ),
+                ],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: None,
     };
 
     let info = message_info(&deps.api.addr_make("gov0000"), &[]);
     let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let update_config = UpdateConfig {
+        reward_token: reward_token(&deps.api),
         distribution_schedule: vec![(
             mock_env().block.time.seconds() + 300,
             mock_env().block.time.seconds() + 400,
@@ -637,7 +739,7 @@ fn test_update_config() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: deps.api.addr_make("addr0000").to_string(),
         amount: Uint128::from(100u128),
-        msg: to_json_binary(&Cw20HookMsg::Bond {}).unwrap(),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
     });
     let info = message_info(&deps.api.addr_make("staking0000"), &[]);
     let mut env = mock_env();
@@ -664,6 +766,7 @@ fn test_update_config() {
     );
 
     let update_config = UpdateConfig {
+        reward_token: reward_token(&deps.api),
         distribution_schedule: vec![
             (
                 mock_env().block.time.seconds(),
@@ -708,7 +811,7 @@ fn test_update_config() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: deps.api.addr_make("addr0000").to_string(),
         amount: Uint128::from(100u128),
-        msg: to_json_binary(&Cw20HookMsg::Bond {}).unwrap(),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
     });
     let info = message_info(&deps.api.addr_make("staking0000"), &[]);
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
@@ -724,6 +827,7 @@ fn test_update_config() {
 
     //cannot update previous schedule
     let update_config = UpdateConfig {
+        reward_token: reward_token(&deps.api),
         distribution_schedule: vec![
             (
                 mock_env().block.time.seconds(),
@@ -766,6 +870,7 @@ fn test_update_config() {
 
     //successful one
     let update_config = UpdateConfig {
+        reward_token: reward_token(&deps.api),
         distribution_schedule: vec![
             (
                 mock_env().block.time.seconds(),
@@ -806,7 +911,7 @@ fn test_update_config() {
     let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
     let config: ConfigResponse = from_json(&res).unwrap();
     assert_eq!(
-        config.distribution_schedule,
+        config.reward_streams[0].distribution_schedule,
         vec![
             (
                 mock_env().block.time.seconds(),
@@ -838,6 +943,7 @@ fn test_update_config() {
 
     //successful one
     let update_config = UpdateConfig {
+        reward_token: reward_token(&deps.api),
         distribution_schedule: vec![
             (
                 mock_env().block.time.seconds(),
@@ -878,7 +984,7 @@ fn test_update_config() {
     let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
     let config: ConfigResponse = from_json(&res).unwrap();
     assert_eq!(
-        config.distribution_schedule,
+        config.reward_streams[0].distribution_schedule,
         vec![
             (
                 mock_env().block.time.seconds(),
@@ -909,6 +1015,7 @@ fn test_update_config() {
     );
 
     let update_config = UpdateConfig {
+        reward_token: reward_token(&deps.api),
         distribution_schedule: vec![
             (
                 mock_env().block.time.seconds(),
@@ -949,7 +1056,7 @@ fn test_update_config() {
     let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
     let config: ConfigResponse = from_json(&res).unwrap();
     assert_eq!(
-        config.distribution_schedule,
+        config.reward_streams[0].distribution_schedule,
         vec![
             (
                 mock_env().block.time.seconds(),
@@ -980,6 +1087,7 @@ fn test_update_config() {
     );
 
     let update_config = UpdateConfig {
+        reward_token: reward_token(&deps.api),
         distribution_schedule: vec![
             (
                 mock_env().block.time.seconds(),
@@ -1025,7 +1133,7 @@ fn test_update_config() {
     let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
     let config: ConfigResponse = from_json(&res).unwrap();
     assert_eq!(
-        config.distribution_schedule,
+        config.reward_streams[0].distribution_schedule,
         vec![
             (
                 mock_env().block.time.seconds(),
@@ -1071,14 +1179,19 @@ fn test_instantiate_and_query_native_reward_token() {
 
     // Instantiate the contract with a native reward token (e.g., "inj")
     let msg = InstantiateMsg {
-        reward_token: AssetInfo::NativeToken { denom: "inj".to_string() },
         staking_token: AssetInfo::Token {
             contract_addr: deps.api.addr_make("staking0000").to_string(),
         },
-        distribution_schedule: vec![
-            (current_time, current_time + 100, Uint128::from(1000000u128)),
-            (current_time + 100, current_time + 200, Uint128::from(10000000u128)),
-        ],
+        reward_streams: vec![RewardStream {
+                reward_token: AssetInfo::NativeToken { denom: "inj".to_string() },
+                distribution_schedule: vec![
+                    (current_time, current_time + 100, Uint128::from(1000000u128)),
+                    (current_time + 100, current_time + 200, Uint128::from(10000000u128)),
+                ],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: None,
     };
 
     // Use "addr0000" as the instantiator (owner)
@@ -1088,7 +1201,10 @@ fn test_instantiate_and_query_native_reward_token() {
     // Query config and verify that the reward token returns the native denom.
     let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
     let config: ConfigResponse = from_json(&res).unwrap();
-    assert_eq!(config.reward_token, "inj".to_string());
+    assert_eq!(
+        config.reward_streams[0].reward_token,
+        AssetInfo::NativeToken { denom: "inj".to_string() }
+    );
     assert_eq!(config.staking_token, deps.api.addr_make("staking0000").to_string());
 }
 
@@ -1100,14 +1216,19 @@ fn test_withdraw_native_reward_token() {
 
     // Instantiate with a native reward token ("inj")
     let msg = InstantiateMsg {
-        reward_token: AssetInfo::NativeToken { denom: "inj".to_string() },
         staking_token: AssetInfo::Token {
             contract_addr: deps.api.addr_make("staking0000").to_string(),
         },
-        distribution_schedule: vec![
-            (current_time, current_time + 100, Uint128::from(1000000u128)),
-            (current_time + 100, current_time + 200, Uint128::from(10000000u128)),
-        ],
+        reward_streams: vec![RewardStream {
+                reward_token: AssetInfo::NativeToken { denom: "inj".to_string() },
+                distribution_schedule: vec![
+                    (current_time, current_time + 100, Uint128::from(1000000u128)),
+                    (current_time + 100, current_time + 200, Uint128::from(10000000u128)),
+                ],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: None,
     };
 
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -1117,7 +1238,7 @@ fn test_withdraw_native_reward_token() {
     let bond_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: deps.api.addr_make("addr0000").to_string(),
         amount: Uint128::from(100u128),
-        msg: to_json_binary(&Cw20HookMsg::Bond {}).unwrap(),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
     });
     // The staking token is expected to be CW20, so we use "staking0000" as sender.
     let bond_info = message_info(&deps.api.addr_make("staking0000"), &[]);
@@ -1147,24 +1268,29 @@ fn test_bond_native()  {
     let mut deps = mock_dependencies(&[]);
 
     let instantiate_msg = InstantiateMsg {
-        reward_token: AssetInfo::Token {
-            contract_addr: deps.api.addr_make("reward0000").to_string(),
-        },
         staking_token: AssetInfo::NativeToken {
             denom: "uusd".to_string(),
         },
-        distribution_schedule: vec![
-            (
-                mock_env().block.time.seconds(),
-                mock_env().block.time.seconds() + 100,
-                Uint128::from(1_000_000u128),
-            ),
-            (
-                mock_env().block.time.seconds() + 100,
-                mock_env().block.time.seconds() + 200,
-                Uint128::from(10_000_000u128),
-            ),
-        ],
+        reward_streams: vec![RewardStream {
+                reward_token: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("reward0000").to_string(),
+                },
+                distribution_schedule: vec![
+                    (
+                        mock_env().block.time.seconds(),
+                        mock_env().block.time.seconds() + 100,
+                        Uint128::from(1_000_000u128),
+                    ),
+                    (
+                        mock_env().block.time.seconds() + 100,
+                        mock_env().block.time.seconds() + 200,
+                        Uint128::from(10_000_000u128),
+                    ),
+                ],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: None,
     };
 
     let env = mock_env();
@@ -1182,7 +1308,10 @@ fn test_bond_native()  {
     );
 
     // Use the new Bond message variant (for native bonding).
-    let bond_msg = ExecuteMsg::Bond { amount: bond_amount };
+    let bond_msg = ExecuteMsg::Bond {
+        amount: bond_amount,
+        lock_duration: None,
+    };
 
     let res = execute(deps.as_mut(), env.clone(), bond_info, bond_msg).unwrap();
     println!("Bond response: {:?}", res);
@@ -1215,16 +1344,21 @@ fn test_unbond_native() {
 
     // Instantiate contract with a native staking token.
     let instantiate_msg = InstantiateMsg {
-        reward_token: AssetInfo::Token {
-            contract_addr: deps.api.addr_make("reward0000").to_string(),
-        },
         staking_token: AssetInfo::NativeToken {
             denom: "uusd".to_string(),
         },
-        distribution_schedule: vec![
-            (12345, 12345 + 100, Uint128::from(1_000_000u128)),
-            (12345 + 100, 12345 + 200, Uint128::from(10_000_000u128)),
-        ],
+        reward_streams: vec![RewardStream {
+                reward_token: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("reward0000").to_string(),
+                },
+                distribution_schedule: vec![
+                    (12345, 12345 + 100, Uint128::from(1_000_000u128)),
+                    (12345 + 100, 12345 + 200, Uint128::from(10_000_000u128)),
+                ],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: None,
     };
 
     // Create an environment with contract address equal to MOCK_CONTRACT_ADDR.
@@ -1235,7 +1369,10 @@ fn test_unbond_native() {
     // Simulate bonding native tokens.
     let bond_amount = Uint128::from(100u128);
     // For native bonding, assume we have an ExecuteMsg::Bond variant.
-    let bond_msg = ExecuteMsg::Bond { amount: bond_amount };
+    let bond_msg = ExecuteMsg::Bond {
+        amount: bond_amount,
+        lock_duration: None,
+    };
     // The user sends the native tokens in funds.
     let bond_info = message_info(
         &deps.api.addr_make("addr0000"),
@@ -1275,4 +1412,1179 @@ fn test_unbond_native() {
 
     assert_eq!(res_unbond.messages, vec![expected_msg]);
 
-}
\ No newline at end of file
+}
+#[test]
+fn test_withdraw_multiple_reward_tokens() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        staking_token: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("staking0000").to_string(),
+        },
+        reward_streams: vec![RewardStream {
+            reward_token: reward_token(&deps.api),
+            distribution_schedule: vec![(
+                mock_env().block.time.seconds(),
+                mock_env().block.time.seconds() + 100,
+                Uint128::from(1000000u128),
+            )],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // bond 100 tokens
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: Uint128::from(100u128),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
+    });
+    let info = message_info(&deps.api.addr_make("staking0000"), &[]);
+    let mut env = mock_env();
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // a partner stream cannot start in the past
+    let partner_token = AssetInfo::NativeToken {
+        denom: "inj".to_string(),
+    };
+    env.block.time = env.block.time.plus_seconds(50);
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        UpdateConfig {
+            reward_token: partner_token.clone(),
+            distribution_schedule: vec![(
+                mock_env().block.time.seconds(),
+                mock_env().block.time.seconds() + 150,
+                Uint128::from(300000u128),
+            )],
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "new schedule adds an already started distribution")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    // add the partner stream for the next 50 seconds
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        UpdateConfig {
+            reward_token: partner_token.clone(),
+            distribution_schedule: vec![(
+                mock_env().block.time.seconds() + 50,
+                mock_env().block.time.seconds() + 100,
+                Uint128::from(200000u128),
+            )],
+        },
+    )
+    .unwrap();
+
+    // 50 more seconds passed
+    // 1,000,000 reward0000 and 200,000 inj distributed
+    env.block.time = env.block.time.plus_seconds(50);
+    assert_eq!(
+        from_json::<StakerInfoResponse>(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::StakerInfo {
+                    staker: deps.api.addr_make("addr0000").to_string(),
+                    block_time: Some(env.block.time.seconds()),
+                },
+            )
+            .unwrap()
+        )
+        .unwrap(),
+        StakerInfoResponse {
+            staker: deps.api.addr_make("addr0000").to_string(),
+            bond_amount: Uint128::from(100u128),
+            bond_weight: Uint128::from(100u128),
+            locks: vec![],
+            rewards: vec![
+                StakerRewardResponse {
+                    reward_token: reward_token(&deps.api),
+                    reward_index: Decimal::from_ratio(10000u128, 1u128),
+                    pending_reward: Uint128::from(1000000u128),
+                },
+                StakerRewardResponse {
+                    reward_token: partner_token.clone(),
+                    reward_index: Decimal::from_ratio(2000u128, 1u128),
+                    pending_reward: Uint128::from(200000u128),
+                },
+            ],
+        }
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Withdraw {}).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("reward0000").to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: deps.api.addr_make("addr0000").to_string(),
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: deps.api.addr_make("addr0000").to_string(),
+                amount: coins(200000, "inj"),
+            })),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "withdraw"),
+            attr("owner", deps.api.addr_make("addr0000").to_string()),
+            attr(
+                "rewards",
+                format!("1000000{}, 200000inj", deps.api.addr_make("reward0000"))
+            ),
+        ]
+    );
+
+    assert_eq!(
+        from_json::<StateResponse>(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::State { block_time: None }
+            )
+            .unwrap()
+        )
+        .unwrap(),
+        StateResponse {
+            last_distributed: mock_env().block.time.seconds() + 100,
+            total_bond_amount: Uint128::from(100u128),
+            total_bond_weight: Uint128::from(100u128),
+            rewards: vec![
+                RewardStateResponse {
+                    reward_token: reward_token(&deps.api),
+                    global_reward_index: Decimal::from_ratio(10000u128, 1u128),
+                },
+                RewardStateResponse {
+                    reward_token: partner_token,
+                    global_reward_index: Decimal::from_ratio(2000u128, 1u128),
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn test_migrate_single_reward_token_farm() {
+    let mut deps = mock_dependencies(&[]);
+
+    let owner = deps.api.addr_canonicalize(deps.api.addr_make("addr0000").as_str()).unwrap();
+    let staker = deps.api.addr_canonicalize(deps.api.addr_make("addr0001").as_str()).unwrap();
+    LEGACY_CONFIG
+        .save(
+            &mut deps.storage,
+            &LegacyConfig {
+                owner,
+                reward_token: reward_token(&deps.api),
+                staking_token: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("staking0000").to_string(),
+                },
+                distribution_schedule: vec![(100, 200, Uint128::from(1000000u128))],
+            },
+        )
+        .unwrap();
+    LEGACY_STATE
+        .save(
+            &mut deps.storage,
+            &LegacyState {
+                last_distributed: 150,
+                total_bond_amount: Uint128::from(100u128),
+                global_reward_index: Decimal::from_ratio(5000u128, 1u128),
+            },
+        )
+        .unwrap();
+    LEGACY_STAKER_INFO
+        .save(
+            &mut deps.storage,
+            staker.as_slice(),
+            &LegacyStakerInfo {
+                reward_index: Decimal::from_ratio(1000u128, 1u128),
+                bond_amount: Uint128::from(100u128),
+                pending_reward: Uint128::from(7u128),
+            },
+        )
+        .unwrap();
+
+    let _res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    let config: ConfigResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config.reward_streams,
+        vec![RewardStream {
+            reward_token: reward_token(&deps.api),
+            distribution_schedule: vec![(100, 200, Uint128::from(1000000u128))],
+        }]
+    );
+
+    assert_eq!(
+        from_json::<StakerInfoResponse>(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::StakerInfo {
+                    staker: deps.api.addr_make("addr0001").to_string(),
+                    block_time: None,
+                },
+            )
+            .unwrap()
+        )
+        .unwrap(),
+        StakerInfoResponse {
+            staker: deps.api.addr_make("addr0001").to_string(),
+            bond_amount: Uint128::from(100u128),
+            bond_weight: Uint128::from(100u128),
+            locks: vec![],
+            rewards: vec![StakerRewardResponse {
+                reward_token: reward_token(&deps.api),
+                reward_index: Decimal::from_ratio(1000u128, 1u128),
+                pending_reward: Uint128::from(7u128),
+            }],
+        }
+    );
+
+    let state: StateResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::State { block_time: None },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(state.last_distributed, 150);
+    assert_eq!(
+        state.rewards,
+        vec![RewardStateResponse {
+            reward_token: reward_token(&deps.api),
+            global_reward_index: Decimal::from_ratio(5000u128, 1u128),
+        }]
+    );
+
+    // 7 pending + 100 * (5000 - 1000) owed to the staker
+    // and the last 500,000 of the schedule
+    assert_eq!(
+        from_json::<RewardSolvencyResponse>(
+            query(deps.as_ref(), mock_env(), QueryMsg::RewardSolvency {}).unwrap()
+        )
+        .unwrap()
+        .rewards,
+        vec![RewardBalanceResponse {
+            reward_token: reward_token(&deps.api),
+            promised: Uint128::from(900007u128),
+            held: Uint128::zero(),
+        }]
+    );
+}
+
+#[test]
+fn test_add_native_rewards() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "inj".to_string(),
+        amount: Uint128::from(300000u128),
+    }]);
+
+    let msg = InstantiateMsg {
+        staking_token: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("staking0000").to_string(),
+        },
+        reward_streams: vec![RewardStream {
+            reward_token: reward_token(&deps.api),
+            distribution_schedule: vec![(
+                mock_env().block.time.seconds(),
+                mock_env().block.time.seconds() + 100,
+                Uint128::from(1000000u128),
+            )],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let schedule = vec![(
+        mock_env().block.time.seconds() + 100,
+        mock_env().block.time.seconds() + 200,
+        Uint128::from(300000u128),
+    )];
+
+    // no funds attached
+    let info = message_info(&deps.api.addr_make("partner0000"), &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::AddRewards {
+            schedule: schedule.clone(),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "funds mismatch; must send exactly one reward token")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    // funds do not match the schedule
+    let info = message_info(&deps.api.addr_make("partner0000"), &coins(200000, "inj"));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::AddRewards {
+            schedule: schedule.clone(),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "funds mismatch; must send exactly the scheduled rewards")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    // schedule already started
+    let info = message_info(&deps.api.addr_make("partner0000"), &coins(300000, "inj"));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::AddRewards {
+            schedule: vec![(
+                mock_env().block.time.seconds(),
+                mock_env().block.time.seconds() + 100,
+                Uint128::from(300000u128),
+            )],
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "reward schedule must start in the future")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    // only the owner starts new streams
+    let info = message_info(&deps.api.addr_make("partner0000"), &coins(300000, "inj"));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::AddRewards {
+            schedule: schedule.clone(),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "reward token not registered")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    let owner = message_info(&deps.api.addr_make("addr0000"), &[]);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        owner.clone(),
+        ExecuteMsg::UpdateConfig {
+            reward_token: AssetInfo::NativeToken {
+                denom: "inj".to_string(),
+            },
+            distribution_schedule: vec![],
+        },
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::AddRewards {
+            schedule: schedule.clone(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "add_rewards"),
+            attr("sender", deps.api.addr_make("partner0000").to_string()),
+            attr("rewards", "300000inj"),
+        ]
+    );
+
+    // the attached token funds the registered stream
+    let config: ConfigResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config.reward_streams[1],
+        RewardStream {
+            reward_token: AssetInfo::NativeToken {
+                denom: "inj".to_string(),
+            },
+            distribution_schedule: schedule,
+        }
+    );
+
+    // the farm holds none of the cw20 rewards it promises
+    assert_eq!(
+        from_json::<RewardSolvencyResponse>(
+            query(deps.as_ref(), mock_env(), QueryMsg::RewardSolvency {}).unwrap()
+        )
+        .unwrap(),
+        RewardSolvencyResponse {
+            rewards: vec![
+                RewardBalanceResponse {
+                    reward_token: reward_token(&deps.api),
+                    promised: Uint128::from(1000000u128),
+                    held: Uint128::zero(),
+                },
+                RewardBalanceResponse {
+                    reward_token: AssetInfo::NativeToken {
+                        denom: "inj".to_string(),
+                    },
+                    promised: Uint128::from(300000u128),
+                    held: Uint128::from(300000u128),
+                },
+            ],
+        }
+    );
+
+    // the number of streams is capped
+    for i in 2..10 {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            owner.clone(),
+            ExecuteMsg::UpdateConfig {
+                reward_token: AssetInfo::NativeToken {
+                    denom: format!("reward{}", i),
+                },
+                distribution_schedule: vec![],
+            },
+        )
+        .unwrap();
+    }
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        owner,
+        ExecuteMsg::UpdateConfig {
+            reward_token: AssetInfo::NativeToken {
+                denom: "reward10".to_string(),
+            },
+            distribution_schedule: vec![],
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "too many reward streams"),
+        _ => panic!("Must return generic error"),
+    }
+}
+
+#[test]
+fn test_add_cw20_rewards() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        staking_token: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("staking0000").to_string(),
+        },
+        reward_streams: vec![RewardStream {
+            reward_token: reward_token(&deps.api),
+            distribution_schedule: vec![(
+                mock_env().block.time.seconds(),
+                mock_env().block.time.seconds() + 100,
+                Uint128::from(1000000u128),
+            )],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("partner0000").to_string(),
+        amount: Uint128::from(500000u128),
+        msg: to_json_binary(&Cw20HookMsg::AddRewards {
+            schedule: vec![(
+                mock_env().block.time.seconds() + 100,
+                mock_env().block.time.seconds() + 200,
+                Uint128::from(500000u128),
+            )],
+        })
+        .unwrap(),
+    });
+
+    // a token without a stream cannot add one
+    let info = message_info(&deps.api.addr_make("fake0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "reward token not registered")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    // the farm only holds the top-up, the first schedule was never funded
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("reward0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(500000u128))],
+    )]);
+    let info = message_info(&deps.api.addr_make("reward0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "insufficient reward balance")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("reward0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1500000u128))],
+    )]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "add_rewards"),
+            attr("sender", deps.api.addr_make("partner0000").to_string()),
+            attr(
+                "rewards",
+                format!("500000{}", deps.api.addr_make("reward0000"))
+            ),
+        ]
+    );
+
+    let config: ConfigResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.reward_streams.len(), 1);
+    assert_eq!(
+        config.reward_streams[0].distribution_schedule,
+        vec![
+            (
+                mock_env().block.time.seconds(),
+                mock_env().block.time.seconds() + 100,
+                Uint128::from(1000000u128),
+            ),
+            (
+                mock_env().block.time.seconds() + 100,
+                mock_env().block.time.seconds() + 200,
+                Uint128::from(500000u128),
+            ),
+        ]
+    );
+
+    // bond 100 tokens
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: Uint128::from(100u128),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
+    });
+    let info = message_info(&deps.api.addr_make("staking0000"), &[]);
+    let mut env = mock_env();
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // 100 seconds passed
+    // 1,000,000 rewards distributed and still owed to the staker
+    env.block.time = env.block.time.plus_seconds(100);
+    assert_eq!(
+        from_json::<RewardSolvencyResponse>(
+            query(deps.as_ref(), env.clone(), QueryMsg::RewardSolvency {}).unwrap()
+        )
+        .unwrap()
+        .rewards,
+        vec![RewardBalanceResponse {
+            reward_token: reward_token(&deps.api),
+            promised: Uint128::from(1500000u128),
+            held: Uint128::from(1500000u128),
+        }]
+    );
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Withdraw {}).unwrap();
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("reward0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(500000u128))],
+    )]);
+    assert_eq!(
+        from_json::<RewardSolvencyResponse>(
+            query(deps.as_ref(), env, QueryMsg::RewardSolvency {}).unwrap()
+        )
+        .unwrap()
+        .rewards,
+        vec![RewardBalanceResponse {
+            reward_token: reward_token(&deps.api),
+            promised: Uint128::from(500000u128),
+            held: Uint128::from(500000u128),
+        }]
+    );
+}
+
+#[test]
+fn test_bond_with_lock() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        staking_token: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("staking0000").to_string(),
+        },
+        reward_streams: vec![RewardStream {
+            reward_token: reward_token(&deps.api),
+            distribution_schedule: vec![(
+                mock_env().block.time.seconds(),
+                mock_env().block.time.seconds() + 100,
+                Uint128::from(1000000u128),
+            )],
+        }],
+        lock_tiers: vec![
+            LockTier {
+                duration: 30 * 86400,
+                multiplier: Decimal::percent(150),
+            },
+            LockTier {
+                duration: 90 * 86400,
+                multiplier: Decimal::percent(200),
+            },
+        ],
+        early_exit: None,
+        unbonding_period: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = message_info(&deps.api.addr_make("staking0000"), &[]);
+    let mut env = mock_env();
+
+    // only configured durations can be locked
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0001").to_string(),
+        amount: Uint128::from(100u128),
+        msg: to_json_binary(&Cw20HookMsg::Bond {
+            lock_duration: Some(10),
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "invalid lock duration"),
+        _ => panic!("Must return generic error"),
+    }
+
+    // bond 100 unlocked tokens and 100 tokens locked for 90 days
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: Uint128::from(100u128),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
+    });
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0001").to_string(),
+        amount: Uint128::from(100u128),
+        msg: to_json_binary(&Cw20HookMsg::Bond {
+            lock_duration: Some(90 * 86400),
+        })
+        .unwrap(),
+    });
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // 100 seconds passed
+    // 1,000,000 rewards distributed by weight, 100 : 200
+    env.block.time = env.block.time.plus_seconds(100);
+    assert_eq!(
+        from_json::<StakerInfoResponse>(
+            query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::StakerInfo {
+                    staker: deps.api.addr_make("addr0001").to_string(),
+                    block_time: Some(env.block.time.seconds()),
+                },
+            )
+            .unwrap()
+        )
+        .unwrap(),
+        StakerInfoResponse {
+            staker: deps.api.addr_make("addr0001").to_string(),
+            bond_amount: Uint128::from(100u128),
+            bond_weight: Uint128::from(200u128),
+            locks: vec![LockPosition {
+                amount: Uint128::from(100u128),
+                multiplier: Decimal::percent(200),
+                unlock_time: mock_env().block.time.seconds() + 90 * 86400,
+            }],
+            rewards: vec![StakerRewardResponse {
+                reward_token: reward_token(&deps.api),
+                reward_index: Decimal::from_ratio(1000000u128, 300u128),
+                pending_reward: Uint128::from(666666u128),
+            }],
+        }
+    );
+    let staker_info: StakerInfoResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::StakerInfo {
+                staker: deps.api.addr_make("addr0000").to_string(),
+                block_time: Some(env.block.time.seconds()),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(staker_info.rewards[0].pending_reward, Uint128::from(333333u128));
+
+    // locked tokens cannot be unbonded
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    let msg = ExecuteMsg::Unbond {
+        amount: Uint128::from(50u128),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "Cannot unbond more than unlocked amount")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    // early exit is not configured
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::EarlyUnbond {
+            amount: Uint128::from(50u128),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "early exit is disabled"),
+        _ => panic!("Must return generic error"),
+    }
+
+    // the lock expired
+    env.block.time = env.block.time.plus_seconds(90 * 86400);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_make("staking0000").to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: deps.api.addr_make("addr0001").to_string(),
+                amount: Uint128::from(50u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        }))]
+    );
+
+    let state: StateResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::State { block_time: None },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(state.total_bond_amount, Uint128::from(150u128));
+    assert_eq!(state.total_bond_weight, Uint128::from(150u128));
+}
+
+#[test]
+fn test_release_expired_locks() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        staking_token: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("staking0000").to_string(),
+        },
+        reward_streams: vec![RewardStream {
+            reward_token: reward_token(&deps.api),
+            distribution_schedule: vec![(
+                mock_env().block.time.seconds(),
+                mock_env().block.time.seconds() + 200,
+                Uint128::from(2000000u128),
+            )],
+        }],
+        lock_tiers: vec![LockTier {
+            duration: 100,
+            multiplier: Decimal::percent(300),
+        }],
+        early_exit: None,
+        unbonding_period: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // bond 100 unlocked tokens and 100 tokens locked for 100 seconds
+    let info = message_info(&deps.api.addr_make("staking0000"), &[]);
+    let mut env = mock_env();
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: Uint128::from(100u128),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
+    });
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0001").to_string(),
+        amount: Uint128::from(100u128),
+        msg: to_json_binary(&Cw20HookMsg::Bond {
+            lock_duration: Some(100),
+        })
+        .unwrap(),
+    });
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::ReleaseExpiredLocks {
+        staker: deps.api.addr_make("addr0001").to_string(),
+    };
+    let keeper = message_info(&deps.api.addr_make("addr0002"), &[]);
+    env.block.time = env.block.time.plus_seconds(50);
+    let res = execute(deps.as_mut(), env.clone(), keeper.clone(), msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "no expired locks"),
+        _ => panic!("Must return generic error"),
+    }
+
+    // the staker stays idle 50 seconds past the expiry
+    // 1,000,000 rewards distributed 100 : 300 while locked, 500,000 split 100 : 100 after
+    env.block.time = env.block.time.plus_seconds(100);
+    let pending_reward = |deps: Deps, staker: String, block_time: u64| {
+        from_json::<StakerInfoResponse>(
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::StakerInfo {
+                    staker,
+                    block_time: Some(block_time),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap()
+        .rewards[0]
+            .pending_reward
+    };
+    assert_eq!(
+        pending_reward(
+            deps.as_ref(),
+            deps.api.addr_make("addr0001").to_string(),
+            env.block.time.seconds(),
+        ),
+        Uint128::from(1000000u128)
+    );
+
+    let res = execute(deps.as_mut(), env.clone(), keeper.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "release_expired_locks"),
+            attr("staker", deps.api.addr_make("addr0001").to_string()),
+            attr("released_locks", "1"),
+        ]
+    );
+    assert_eq!(
+        pending_reward(
+            deps.as_ref(),
+            deps.api.addr_make("addr0001").to_string(),
+            env.block.time.seconds(),
+        ),
+        Uint128::from(1000000u128)
+    );
+
+    let state: StateResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::State { block_time: None },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(state.total_bond_weight, Uint128::from(200u128));
+
+    // the rest is split evenly
+    env.block.time = env.block.time.plus_seconds(50);
+    assert_eq!(
+        pending_reward(
+            deps.as_ref(),
+            deps.api.addr_make("addr0001").to_string(),
+            env.block.time.seconds(),
+        ),
+        Uint128::from(1250000u128)
+    );
+    assert_eq!(
+        pending_reward(
+            deps.as_ref(),
+            deps.api.addr_make("addr0000").to_string(),
+            env.block.time.seconds(),
+        ),
+        Uint128::from(750000u128)
+    );
+
+    let res = execute(deps.as_mut(), env, keeper, msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "no expired locks"),
+        _ => panic!("Must return generic error"),
+    }
+}
+
+#[test]
+fn test_early_unbond() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        staking_token: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("staking0000").to_string(),
+        },
+        reward_streams: vec![RewardStream {
+            reward_token: reward_token(&deps.api),
+            distribution_schedule: vec![(
+                mock_env().block.time.seconds(),
+                mock_env().block.time.seconds() + 100,
+                Uint128::from(1000000u128),
+            )],
+        }],
+        lock_tiers: vec![LockTier {
+            duration: 30 * 86400,
+            multiplier: Decimal::percent(150),
+        }],
+        early_exit: Some(EarlyExit {
+            penalty_rate: Decimal::percent(10),
+            auction_contract: deps.api.addr_make("auction0000").to_string(),
+        }),
+        unbonding_period: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // bond 100 unlocked tokens and 200 tokens locked for 30 days
+    let info = message_info(&deps.api.addr_make("staking0000"), &[]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: Uint128::from(100u128),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
+    });
+    let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: Uint128::from(200u128),
+        msg: to_json_binary(&Cw20HookMsg::Bond {
+            lock_duration: Some(30 * 86400),
+        })
+        .unwrap(),
+    });
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // the unlocked 100 leave first, 50 locked tokens pay 10%
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::EarlyUnbond {
+            amount: Uint128::from(150u128),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("staking0000").to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: deps.api.addr_make("addr0000").to_string(),
+                    amount: Uint128::from(145u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("staking0000").to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: deps.api.addr_make("auction0000").to_string(),
+                    amount: Uint128::from(5u128),
+                    msg: Binary::default(),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "early_unbond"),
+            attr("owner", deps.api.addr_make("addr0000").to_string()),
+            attr("amount", "150"),
+            attr("penalty_amount", "5"),
+        ]
+    );
+
+    let staker_info: StakerInfoResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::StakerInfo {
+                staker: deps.api.addr_make("addr0000").to_string(),
+                block_time: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(staker_info.bond_amount, Uint128::from(150u128));
+    assert_eq!(staker_info.bond_weight, Uint128::from(225u128));
+    assert_eq!(
+        staker_info.locks,
+        vec![LockPosition {
+            amount: Uint128::from(150u128),
+            multiplier: Decimal::percent(150),
+            unlock_time: mock_env().block.time.seconds() + 30 * 86400,
+        }]
+    );
+}
+
+#[test]
+fn test_unbonding_period() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        staking_token: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("staking0000").to_string(),
+        },
+        reward_streams: vec![RewardStream {
+            reward_token: reward_token(&deps.api),
+            distribution_schedule: vec![(
+                mock_env().block.time.seconds(),
+                mock_env().block.time.seconds() + 100,
+                Uint128::from(1000000u128),
+            )],
+        }],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: Some(7 * 86400),
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // bond 100 tokens
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: Uint128::from(100u128),
+        msg: to_json_binary(&Cw20HookMsg::Bond { lock_duration: None }).unwrap(),
+    });
+    let info = message_info(&deps.api.addr_make("staking0000"), &[]);
+    let mut env = mock_env();
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // unbond 100 tokens after 50 seconds, nothing is sent yet
+    env.block.time = env.block.time.plus_seconds(50);
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let msg = ExecuteMsg::Unbond {
+        amount: Uint128::from(100u128),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(res.messages, vec![]);
+
+    assert_eq!(
+        from_json::<PendingUnbondsResponse>(
+            query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::PendingUnbonds {
+                    staker: deps.api.addr_make("addr0000").to_string(),
+                },
+            )
+            .unwrap()
+        )
+        .unwrap(),
+        PendingUnbondsResponse {
+            staker: deps.api.addr_make("addr0000").to_string(),
+            claims: vec![UnbondClaim {
+                amount: Uint128::from(100u128),
+                release_time: env.block.time.seconds() + 7 * 86400,
+            }],
+        }
+    );
+
+    // unbonding tokens stop earning right away
+    let staker_info: StakerInfoResponse = from_json(
+        query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::StakerInfo {
+                staker: deps.api.addr_make("addr0000").to_string(),
+                block_time: Some(mock_env().block.time.seconds() + 100),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(staker_info.bond_amount, Uint128::zero());
+    assert_eq!(staker_info.rewards[0].pending_reward, Uint128::from(500000u128));
+
+    // nothing matured yet
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), ExecuteMsg::Claim {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "no unbonded tokens to claim"),
+        _ => panic!("Must return generic error"),
+    }
+
+    env.block.time = env.block.time.plus_seconds(7 * 86400);
+    let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Claim {}).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_make("staking0000").to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: deps.api.addr_make("addr0000").to_string(),
+                amount: Uint128::from(100u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        }))]
+    );
+
+    let pending: PendingUnbondsResponse = from_json(
+        query(
+            deps.as_ref(),
+            env,
+            QueryMsg::PendingUnbonds {
+                staker: deps.api.addr_make("addr0000").to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(pending.claims, vec![]);
+}
@@ -1,13 +1,26 @@
 use crate::error::ContractError;
-use crate::state::PAIR_INFO;
+use crate::oracle::{
+    accumulate_prices, cumulative_prices_at, observation_before, record_reserves,
+};
+use crate::stats::{record_swap, volume_history};
+use crate::stableswap::{
+    compute_pool_d, compute_stable_offer_amount, compute_stable_swap, MAX_AMP, MAX_AMP_CHANGE,
+    MIN_RAMP_TIME,
+};
+use crate::state::{
+    AmpConfig, Cw20Deposit, FlashSwap, PriceAccumulator, AMP_CONFIG, CW20_DEPOSIT, CW20_REFUND,
+    FACTORY,
+    FLASH_SWAP, PAIR_INFO, PRICE_ACCUMULATOR, PROTOCOL_FEE_MODE, PROTOCOL_SHARES, RESERVES_K_LAST,
+    ROOT_K_LAST, SWAP_STATS,
+};
 
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
 use cosmwasm_std::{
-    coins, from_json, to_json_binary, Addr, BankMsg, Binary, CosmosMsg, Decimal,
-    Decimal256, DepsMut, Deps, Env, MessageInfo, Response, StdResult,
-    Uint128, Uint256, WasmMsg, Coin
+    coins, from_json, to_json_binary, Addr, Api, BankMsg, Binary, CosmosMsg, Decimal,
+    Decimal256, DecimalRangeExceeded, DepsMut, Deps, Env, Isqrt, MessageInfo, QuerierWrapper,
+    Reply, Response, StdError, StdResult, Storage, SubMsg, Uint128, Uint256, Uint512, WasmMsg, Coin
 };
 
 use cw2::set_contract_version;
@@ -16,12 +29,18 @@ use std::cmp::Ordering;
 use std::convert::TryInto;
 use std::ops::Mul;
 use std::str::FromStr;
-use choice::asset::{Asset, AssetInfo, PairInfo, PairInfoRaw};
+use choice::asset::{Asset, AssetInfo, PairInfo, PairInfoRaw, PairType};
+use choice::factory::FeeSplit;
 use choice::pair::{
-    Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PoolResponse, QueryMsg,
-    ReverseSimulationResponse, SimulationResponse,
+    AmpResponse, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, FlashCallbackMsg,
+    InstantiateMsg, MigrateMsg,
+    PoolResponse, ProtocolFeeMode, ProtocolFeesResponse, QueryMsg, ReverseSimulationResponse,
+    SimulateProvideLiquidityResponse, SimulateProvideResponse, SimulateWithdrawLiquidityResponse,
+    SimulationResponse, StatsResponse, TwapResponse, VolumeBucketResponse, VolumeHistoryResponse,
+};
+use choice::querier::{
+    query_factory_paused, query_fee_split, query_token_factory_denom_total_supply,
 };
-use choice::querier::query_token_factory_denom_total_supply;
 use choice::util::migrate_version;
 
 use serde::{Deserialize, Serialize};
@@ -35,20 +54,34 @@ use injective_cosmwasm::query::InjectiveQueryWrapper;
 const CONTRACT_NAME: &str = "crates.io:choice-pair";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// Commission rate == 0.3%
-const COMMISSION_RATE: u64 = 3;
-
 const MINIMUM_LIQUIDITY_AMOUNT: u128 = 1_000;
 
+const FLASH_SWAP_REPLY_ID: u64 = 1;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut<InjectiveQueryWrapper>,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> StdResult<Response<InjectiveMsgWrapper>>  {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    
+
+    if msg.commission_rate >= Decimal::one() {
+        return Err(StdError::generic_err("commission rate must be less than 1"));
+    }
+
+    if let PairType::Stable { amp } = msg.pair_type {
+        if amp == 0 || amp > MAX_AMP {
+            return Err(StdError::generic_err(format!(
+                "amp must be between 1 and {}",
+                MAX_AMP
+            )));
+        }
+
+        AMP_CONFIG.save(deps.storage, &AmpConfig::new(amp, env.block.time.seconds()))?;
+    }
+
     let subdenom = "lp".to_string();
     let lp_denom = format!("factory/{}/{}", env.contract.address, subdenom);
 
@@ -62,9 +95,13 @@ pub fn instantiate(
         asset_decimals: msg.asset_decimals,
         burn_address: deps.api.addr_canonicalize(&msg.burn_address)?,
         fee_wallet_address: deps.api.addr_canonicalize(&msg.fee_wallet_address)?,
+        commission_rate: msg.commission_rate,
+        pair_type: msg.pair_type,
+        paused: false,
     };
 
     PAIR_INFO.save(deps.storage, pair_info)?;
+    FACTORY.save(deps.storage, &info.sender)?;
 
     let create_msg = create_new_denom_msg(env.contract.address.to_string(), subdenom.clone());
 
@@ -83,20 +120,38 @@ pub fn instantiate(
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut<InjectiveQueryWrapper>,
+    mut deps: DepsMut<InjectiveQueryWrapper>,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
-    match msg {
-        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+    // the reserves are out of the pool until the flash swap reply
+    if FLASH_SWAP.exists(deps.storage) {
+        return Err(ContractError::FlashSwapInProgress {});
+    }
+
+    // a cw20 deposit only waits for the provide of its own transaction,
+    // a stale one is kept out of the pool and refunded to its sender
+    let mut refund: Option<Cw20Deposit> = None;
+    if let Some(deposit) = CW20_DEPOSIT.may_load(deps.storage)? {
+        if !is_same_transaction(&env, &deposit) {
+            CW20_DEPOSIT.remove(deps.storage);
+            CW20_REFUND.save(deps.storage, &deposit)?;
+            refund = Some(deposit);
+        } else if !matches!(msg, ExecuteMsg::ProvideLiquidity { .. }) || info.sender != deposit.sender {
+            return Err(ContractError::DepositPending {});
+        }
+    }
+
+    let res = match msg {
+        ExecuteMsg::Receive(msg) => receive_cw20(deps.branch(), env, info, msg),
         ExecuteMsg::ProvideLiquidity {
             assets,
             receiver,
             deadline,
             slippage_tolerance,
         } => provide_liquidity(
-            deps,
+            deps.branch(),
             env,
             info,
             assets,
@@ -104,14 +159,30 @@ pub fn execute(
             deadline,
             slippage_tolerance,
         ),
+        ExecuteMsg::ProvideSingleSided {
+            offer_asset,
+            min_share,
+            receiver,
+            deadline,
+        } => provide_single_sided(deps.branch(), env, info, offer_asset, min_share, receiver, deadline),
 
         ExecuteMsg::WithdrawLiquidity { 
             amount, 
             min_assets, 
-            deadline 
+            deadline,
+            ask_asset,
         } => {
             let sender_addr = info.sender.clone();
-            withdraw_liquidity(deps, env, info, sender_addr, amount, min_assets, deadline)
+            withdraw_liquidity(
+                deps.branch(),
+                env,
+                info,
+                sender_addr,
+                amount,
+                min_assets,
+                deadline,
+                ask_asset,
+            )
         },
 
         ExecuteMsg::Swap {
@@ -132,7 +203,7 @@ pub fn execute(
             };
 
             swap(
-                deps,
+                deps.branch(),
                 env,
                 info.clone(),
                 info.sender,
@@ -143,6 +214,37 @@ pub fn execute(
                 deadline,
             )
         }
+        ExecuteMsg::RampAmp {
+            future_amp,
+            future_time,
+        } => ramp_amp(deps.branch(), env, info, future_amp, future_time),
+        ExecuteMsg::StopRampAmp {} => stop_ramp_amp(deps.branch(), env, info),
+        ExecuteMsg::UpdateProtocolFeeMode { mode } => {
+            update_protocol_fee_mode(deps.branch(), env, info, mode)
+        }
+        ExecuteMsg::CollectProtocolFees {} => collect_protocol_fees(deps.branch(), env),
+        ExecuteMsg::FlashSwap {
+            ask_asset,
+            contract,
+            msg,
+        } => flash_swap(deps.branch(), env, info, ask_asset, contract, msg),
+        ExecuteMsg::Pause {} => set_paused(deps.branch(), info, true),
+        ExecuteMsg::Unpause {} => set_paused(deps.branch(), info, false),
+    }?;
+
+    match refund {
+        Some(deposit) => {
+            CW20_REFUND.remove(deps.storage);
+            // refunded first, the balances match the pool the handler saw
+            let mut res = res;
+            res.messages
+                .insert(0, SubMsg::new(deposit.asset.clone().into_msg(deposit.sender.clone())?));
+            Ok(res.add_attributes(vec![
+                ("refund_deposit", deposit.asset.to_string()),
+                ("refund_receiver", deposit.sender.to_string()),
+            ]))
+        }
+        None => Ok(res),
     }
 }
 
@@ -154,6 +256,31 @@ pub fn receive_cw20(
 ) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
     let contract_addr = info.sender.clone();
 
+    // only asset contract can execute this message
+    let mut authorized: bool = false;
+    let config: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] =
+        config.query_pools(&deps.querier, deps.api, env.contract.address.clone())?;
+
+    for pool in pools.iter() {
+        if let AssetInfo::Token { contract_addr, .. } = &pool.info {
+            if contract_addr == &info.sender.to_string() {
+                authorized = true;
+            }
+        }
+    }
+
+    if !authorized {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let deposit = Asset {
+        info: AssetInfo::Token {
+            contract_addr: contract_addr.to_string(),
+        },
+        amount: cw20_msg.amount,
+    };
+
     match from_json(&cw20_msg.msg) {
         Ok(Cw20HookMsg::Swap {
             belief_price,
@@ -161,24 +288,6 @@ pub fn receive_cw20(
             to,
             deadline,
         }) => {
-            // only asset contract can execute this message
-            let mut authorized: bool = false;
-            let config: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
-            let pools: [Asset; 2] =
-                config.query_pools(&deps.querier, deps.api, env.contract.address.clone())?;
-            
-            for pool in pools.iter() {
-                if let AssetInfo::Token { contract_addr, .. } = &pool.info {
-                    if contract_addr == &info.sender.to_string() {
-                        authorized = true;
-                    }
-                }
-            }
-
-            if !authorized {
-                return Err(ContractError::Unauthorized {});
-            }
-
             let to_addr = if let Some(to_addr) = to {
                 Some(deps.api.addr_validate(to_addr.as_str())?)
             } else {
@@ -202,10 +311,124 @@ pub fn receive_cw20(
                 deadline,
             )
         }
+        Ok(Cw20HookMsg::ProvideLiquidity {
+            assets,
+            receiver,
+            deadline,
+            slippage_tolerance,
+        }) => {
+            if !assets.contains(&deposit) {
+                return Err(ContractError::AssetMismatch {});
+            }
+
+            let sender = deps.api.addr_validate(&cw20_msg.sender)?;
+            save_cw20_deposit(deps.storage, &env, &sender, &deposit)?;
+
+            // the native leg comes with a ProvideLiquidity of the same transaction
+            if assets
+                .iter()
+                .any(|asset| asset.is_native_token() && !asset.amount.is_zero())
+            {
+                return Ok(Response::new().add_attributes(vec![
+                    ("action", "deposit_liquidity"),
+                    ("sender", sender.as_str()),
+                    ("deposit", &deposit.to_string()),
+                ]));
+            }
+
+            provide_liquidity(
+                deps,
+                env,
+                MessageInfo {
+                    sender,
+                    funds: vec![],
+                },
+                assets,
+                receiver,
+                deadline,
+                slippage_tolerance,
+            )
+        }
+        Ok(Cw20HookMsg::ProvideSingleSided {
+            min_share,
+            receiver,
+            deadline,
+        }) => {
+            let sender = deps.api.addr_validate(&cw20_msg.sender)?;
+            save_cw20_deposit(deps.storage, &env, &sender, &deposit)?;
+
+            provide_single_sided(
+                deps,
+                env,
+                MessageInfo {
+                    sender,
+                    funds: vec![],
+                },
+                deposit,
+                min_share,
+                receiver,
+                deadline,
+            )
+        }
         Err(err) => Err(ContractError::Std(err)),
     }
 }
 
+fn save_cw20_deposit(
+    storage: &mut dyn Storage,
+    env: &Env,
+    sender: &Addr,
+    asset: &Asset,
+) -> StdResult<()> {
+    CW20_DEPOSIT.save(
+        storage,
+        &Cw20Deposit {
+            sender: sender.clone(),
+            asset: asset.clone(),
+            block_height: env.block.height,
+            tx_index: env.transaction.as_ref().map(|tx| tx.index),
+        },
+    )
+}
+
+fn is_same_transaction(env: &Env, deposit: &Cw20Deposit) -> bool {
+    deposit.block_height == env.block.height
+        && deposit.tx_index == env.transaction.as_ref().map(|tx| tx.index)
+}
+
+/// Pool balances without the cw20 deposits which are not part of the pool,
+/// a stale one being refunded or one still waiting in another transaction
+fn query_pools(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: &Env,
+    pair_info: &PairInfoRaw,
+) -> StdResult<[Asset; 2]> {
+    let mut pools = pair_info.query_pools(&deps.querier, deps.api, env.contract.address.clone())?;
+
+    let pending = CW20_DEPOSIT
+        .may_load(deps.storage)?
+        .filter(|deposit| !is_same_transaction(env, deposit));
+    for deposit in CW20_REFUND.may_load(deps.storage)?.into_iter().chain(pending) {
+        for pool in pools.iter_mut() {
+            if pool.info.equal(&deposit.asset.info) {
+                pool.amount = pool.amount.checked_sub(deposit.asset.amount)?;
+            }
+        }
+    }
+
+    Ok(pools)
+}
+
+/// Cw20 deposit of `sender` to provide instead of pulling it with TransferFrom
+fn take_cw20_deposit(storage: &mut dyn Storage, sender: &Addr) -> StdResult<Option<Asset>> {
+    let deposit = CW20_DEPOSIT.may_load(storage)?;
+    CW20_DEPOSIT.remove(storage);
+
+    Ok(deposit
+        .filter(|deposit| deposit.sender == *sender)
+        .map(|deposit| deposit.asset))
+}
+
 
 /// CONTRACT - should approve contract to use the amount of token
 pub fn provide_liquidity(
@@ -224,55 +447,62 @@ pub fn provide_liquidity(
     }
 
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    assert_not_paused(deps.as_ref(), &pair_info)?;
 
-    let mut pools: [Asset; 2] =
-        pair_info.query_pools(&deps.querier, deps.api, env.contract.address.clone())?;
+    let mut pools: [Asset; 2] = query_pools(deps.as_ref(), &env, &pair_info)?;
 
     // println!("pools: {:?}", pools);
 
-    let deposits: [Uint128; 2] = [
-        assets
-            .iter()
-            .find(|a| a.info.equal(&pools[0].info))
-            .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
-        assets
-            .iter()
-            .find(|a| a.info.equal(&pools[1].info))
-            .map(|a| a.amount)
-            .expect("Wrong asset info is given"),
-    ];
+    let deposits: [Uint128; 2] = deposits_in_pool_order(&assets, &pools)?;
 
     // println!("deposits: {:?}", deposits);
 
+    let cw20_deposit = take_cw20_deposit(deps.storage, &info.sender)?;
+    let is_deposited = |pool: &Asset| {
+        cw20_deposit
+            .as_ref()
+            .is_some_and(|deposit| deposit.info.equal(&pool.info))
+    };
+
     let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
     for (i, pool) in pools.iter_mut().enumerate() {
-        if pool.is_native_token() {
-            // If the asset is native token, balance is already increased
+        if is_deposited(pool) && cw20_deposit.as_ref().map(|d| d.amount) != Some(deposits[i]) {
+            return Err(ContractError::AssetMismatch {});
+        }
+
+        if pool.is_native_token() || is_deposited(pool) {
+            // If the asset is native token or a cw20 deposit, balance is already increased
             // To calculated properly we should subtract user deposit from the pool
             pool.amount = pool.amount.checked_sub(deposits[i])?;
         }
     }
 
+    accumulate_prices(
+        deps.storage,
+        env.block.time.seconds(),
+        [pools[0].amount, pools[1].amount],
+    )?;
+
     let total_share: Uint128 = query_token_factory_denom_total_supply(
         &deps.querier,
         pair_info.liquidity_token.clone(),
     ).unwrap();
 
-    let share: Uint128 = if total_share.is_zero() {
-        // Initial share = collateral amount
-        let deposit0: Uint256 = deposits[0].into();
-        let deposit1: Uint256 = deposits[1].into();
-    
-        // Compute the square root of the product.
-        let computed = Decimal256::from_ratio(deposit0.mul(deposit1), 1u8).sqrt();
-        // Assume Decimal256 uses 18 decimals. Its internal representation of 1 is 1e18.
-        // To get the integer value 1, we divide by 10^18.
-        let scaling_factor = Uint256::from(1_000_000_000_000_000_000u128);
-        let share: Uint128 = (computed.atomics() / scaling_factor)
-            .try_into()
-            .map_err(|e| ContractError::ConversionOverflowError(e))?;
-    
+    let (protocol_shares, mint_fee_msg) = mint_protocol_fee(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &pair_info,
+        [pools[0].amount, pools[1].amount],
+        total_share,
+    )?;
+    messages.extend(mint_fee_msg);
+    let total_share = total_share + protocol_shares;
+
+    let (share, desired_amounts) =
+        compute_provide([pools[0].amount, pools[1].amount], deposits, total_share)?;
+
+    if total_share.is_zero() {
         // Mint the minimum liquidity tokens to lock forever (to protect the pair)
         messages.push(create_mint_tokens_msg(
             env.contract.address.clone(),
@@ -282,42 +512,15 @@ pub fn provide_liquidity(
             },
             env.contract.address.to_string(),
         ));
-    
-        // Deduct the minimum liquidity amount and return the result.
-        share
-            .checked_sub(MINIMUM_LIQUIDITY_AMOUNT.into())
-            .map_err(|_| ContractError::MinimumLiquidityAmountError {
-                min_lp_token: MINIMUM_LIQUIDITY_AMOUNT.to_string(),
-                given_lp: share.to_string(),
-            })?
-    } else {
-        std::cmp::min(
-            deposits[0].multiply_ratio(total_share, pools[0].amount),
-            deposits[1].multiply_ratio(total_share, pools[1].amount),
-        )
-    };
-
-    // prevent providing free token
-    if share.is_zero() {
-        return Err(ContractError::InvalidZeroAmount {});
     }
 
     // refund of remaining native token & desired of token
     let mut refund_assets: Vec<Asset> = vec![];
+    let mut reserves_after = [pools[0].amount, pools[1].amount];
     for (i, pool) in pools.iter().enumerate() {
-        let desired_amount = match total_share.is_zero() {
-            true => deposits[i],
-            false => {
-                let mut desired_amount = pool.amount.multiply_ratio(share, total_share);
-                if desired_amount.multiply_ratio(total_share, share) != pool.amount {
-                    desired_amount += Uint128::from(1u8);
-                }
-
-                desired_amount
-            }
-        };
-
+        let desired_amount = desired_amounts[i];
         let remain_amount = deposits[i] - desired_amount;
+        reserves_after[i] += desired_amount;
         if let Some(slippage_tolerance) = slippage_tolerance {
             if remain_amount > deposits[i].mul_floor(slippage_tolerance) {
                 return Err(ContractError::MaxSlippageAssertion {});
@@ -336,6 +539,16 @@ pub fn provide_liquidity(
                     amount: coins(remain_amount.u128(), denom),
                 }))
             }
+        } else if is_deposited(pool) {
+            if !remain_amount.is_zero() {
+                messages.push(
+                    Asset {
+                        info: pool.info.clone(),
+                        amount: remain_amount,
+                    }
+                    .into_msg(info.sender.clone())?,
+                );
+            }
         } else if let AssetInfo::Token { contract_addr, .. } = &pool.info {
             messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: contract_addr.to_string(),
@@ -349,6 +562,9 @@ pub fn provide_liquidity(
         }
     }
 
+    update_root_k_last(deps.storage, &env, &pair_info, reserves_after)?;
+    record_reserves(deps.storage, reserves_after)?;
+
     // mint LP token to sender
     let receiver = receiver.unwrap_or_else(|| info.sender.to_string());
         messages.push(create_mint_tokens_msg(
@@ -373,81 +589,97 @@ pub fn provide_liquidity(
     ]))
 }
 
-pub fn withdraw_liquidity(
-    deps: DepsMut<InjectiveQueryWrapper>,
-    env: Env,
-    _info: MessageInfo,
-    sender: Addr,
-    amount: Uint128,
-    min_assets: Option<[Asset; 2]>,
-    deadline: Option<u64>,
-) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
-    assert_deadline(env.block.time.seconds(), deadline)?;
+/// Amounts of `assets` in the order of `pools`
+fn deposits_in_pool_order(
+    assets: &[Asset; 2],
+    pools: &[Asset; 2],
+) -> Result<[Uint128; 2], ContractError> {
+    let deposit = |pool: &Asset| {
+        assets
+            .iter()
+            .find(|a| a.info.equal(&pool.info))
+            .map(|a| a.amount)
+            .ok_or(ContractError::AssetMismatch {})
+    };
 
-    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    Ok([deposit(&pools[0])?, deposit(&pools[1])?])
+}
 
-    let contract_addr = env.contract.address.clone();
+/// Share minted for `deposits` and the part of each deposit the pool takes,
+/// the rest is refunded
+fn compute_provide(
+    reserves: [Uint128; 2],
+    deposits: [Uint128; 2],
+    total_share: Uint128,
+) -> Result<(Uint128, [Uint128; 2]), ContractError> {
+    let share: Uint128 = if total_share.is_zero() {
+        // Initial share = collateral amount
+        let deposit0: Uint256 = deposits[0].into();
+        let deposit1: Uint256 = deposits[1].into();
+    
+        // Compute the square root of the product.
+        let computed = Decimal256::from_ratio(deposit0.mul(deposit1), 1u8).sqrt();
+        // Assume Decimal256 uses 18 decimals. Its internal representation of 1 is 1e18.
+        // To get the integer value 1, we divide by 10^18.
+        let scaling_factor = Uint256::from(1_000_000_000_000_000_000u128);
+        let share: Uint128 = (computed.atomics() / scaling_factor)
+            .try_into()
+            .map_err(ContractError::ConversionOverflowError)?;
+    
+        // Deduct the minimum liquidity amount locked in the pair and return the result.
+        share
+            .checked_sub(MINIMUM_LIQUIDITY_AMOUNT.into())
+            .map_err(|_| ContractError::MinimumLiquidityAmountError {
+                min_lp_token: MINIMUM_LIQUIDITY_AMOUNT.to_string(),
+                given_lp: share.to_string(),
+            })?
+    } else {
+        std::cmp::min(
+            deposits[0].multiply_ratio(total_share, reserves[0]),
+            deposits[1].multiply_ratio(total_share, reserves[1]),
+        )
+    };
 
-    let pools: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, contract_addr.clone())?;
+    // prevent providing free token
+    if share.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
 
-    let total_share: Uint128 = query_token_factory_denom_total_supply(
-        &deps.querier,
-        pair_info.liquidity_token.clone(),
-    ).unwrap();
+    let mut desired_amounts = deposits;
+    if !total_share.is_zero() {
+        for (i, reserve) in reserves.iter().enumerate() {
+            let mut desired_amount = reserve.multiply_ratio(share, total_share);
+            if desired_amount.multiply_ratio(total_share, share) != *reserve {
+                desired_amount += Uint128::from(1u8);
+            }
+
+            desired_amounts[i] = desired_amount;
+        }
+    }
+
+    Ok((share, desired_amounts))
+}
 
+/// Assets returned for burning `amount` of `total_share`
+fn compute_withdraw(pools: &[Asset; 2], amount: Uint128, total_share: Uint128) -> Vec<Asset> {
     let share_ratio: Decimal = Decimal::from_ratio(amount, total_share);
-    let refund_assets: Vec<Asset> = pools
+    pools
         .iter()
         .map(|a| Asset {
             info: a.info.clone(),
             amount: a.amount.mul_floor(share_ratio),
         })
-        .collect();
-
-    assert_minimum_assets(refund_assets.to_vec(), min_assets)?;
-
-    // update pool info
-    Ok(Response::new()
-        .add_messages(vec![
-            refund_assets[0].clone().into_msg(sender.clone())?,
-            refund_assets[1].clone().into_msg(sender.clone())?,
-            // burn liquidity token
-            create_burn_tokens_msg(
-                contract_addr.clone(), // sender: contract address as the minter/burner
-                Coin {
-                    denom: pair_info.liquidity_token.clone(), // our LP denom string
-                    amount, // amount to burn
-                },
-            ),
-        ])
-        .add_attributes(vec![
-            ("action", "withdraw_liquidity"),
-            ("sender", sender.as_str()),
-            ("withdrawn_share", &amount.to_string()),
-            (
-                "refund_assets",
-                &format!("{}, {}", refund_assets[0], refund_assets[1]),
-            ),
-        ]))
+        .collect()
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
-#[serde(rename_all = "snake_case")]
-enum BurnManagerMsg {
-    SendNative { asset: Asset }
-}
-
-// CONTRACT - a user must do token approval
-#[allow(clippy::too_many_arguments)]
-pub fn swap(
+/// CONTRACT - should approve contract to use the amount of token
+pub fn provide_single_sided(
     deps: DepsMut<InjectiveQueryWrapper>,
     env: Env,
     info: MessageInfo,
-    sender: Addr,
     offer_asset: Asset,
-    belief_price: Option<Decimal>,
-    max_spread: Option<Decimal>,
-    to: Option<Addr>,
+    min_share: Option<Uint128>,
+    receiver: Option<String>,
     deadline: Option<u64>,
 ) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
     assert_deadline(env.block.time.seconds(), deadline)?;
@@ -455,24 +687,354 @@ pub fn swap(
     offer_asset.assert_sent_native_token_balance(&info)?;
 
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    assert_not_paused(deps.as_ref(), &pair_info)?;
 
-    let pools: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, env.contract.address)?;
+    let mut pools: [Asset; 2] = query_pools(deps.as_ref(), &env, &pair_info)?;
 
-    let offer_pool: Asset;
-    let ask_pool: Asset;
+    let is_deposited = match take_cw20_deposit(deps.storage, &info.sender)? {
+        Some(deposit) if deposit != offer_asset => return Err(ContractError::AssetMismatch {}),
+        deposit => deposit.is_some(),
+    };
 
-    let offer_decimal: u8;
-    let ask_decimal: u8;
-    // If the asset balance is already increased
-    // To calculated properly we should subtract user deposit from the pool
-    if offer_asset.info.equal(&pools[0].info) {
-        offer_pool = Asset {
-            amount: pools[0].amount.checked_sub(offer_asset.amount)?,
-            info: pools[0].info.clone(),
-        };
-        ask_pool = pools[1].clone();
+    for pool in pools.iter_mut() {
+        if (pool.is_native_token() || is_deposited) && pool.info.equal(&offer_asset.info) {
+            // If the asset is native token or a cw20 deposit, balance is already increased
+            // To calculated properly we should subtract user deposit from the pool
+            pool.amount = pool.amount.checked_sub(offer_asset.amount)?;
+        }
+    }
 
-        offer_decimal = pair_info.asset_decimals[0];
+    accumulate_prices(
+        deps.storage,
+        env.block.time.seconds(),
+        [pools[0].amount, pools[1].amount],
+    )?;
+
+    let total_share: Uint128 = query_token_factory_denom_total_supply(
+        &deps.querier,
+        pair_info.liquidity_token.clone(),
+    )?;
+
+    let provide = compute_single_sided_provide(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &pair_info,
+        &pools,
+        &offer_asset,
+        total_share,
+    )?;
+
+    if let Some(min_share) = min_share {
+        if provide.share < min_share {
+            return Err(ContractError::MinShareAssertion {
+                min_share: min_share.to_string(),
+                share: provide.share.to_string(),
+            });
+        }
+    }
+
+    let offer_index = provide.offer_index;
+    let mut volume = [Uint128::zero(); 2];
+    volume[offer_index] = provide.swap_amount;
+    volume[1 - offer_index] = provide.return_amount;
+    record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        volume,
+        1 - offer_index,
+        provide.commission_amount,
+        provide.fee_wallet_amount,
+        provide.burn_amount,
+    )?;
+
+    let refund_assets: [Asset; 2] = [
+        Asset {
+            info: pools[0].info.clone(),
+            amount: provide.refunds[0],
+        },
+        Asset {
+            info: pools[1].info.clone(),
+            amount: provide.refunds[1],
+        },
+    ];
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    if let (AssetInfo::Token { contract_addr, .. }, false) = (&offer_asset.info, is_deposited) {
+        // only pull what the pool takes
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: info.sender.to_string(),
+                recipient: env.contract.address.to_string(),
+                amount: offer_asset.amount - provide.refunds[offer_index],
+            })?,
+            funds: vec![],
+        }));
+    }
+
+    messages.extend(protocol_fee_msgs(
+        deps.api,
+        &pair_info,
+        &pools[1 - offer_index].info,
+        provide.fee_wallet_amount,
+        provide.burn_amount,
+    )?);
+
+    for (i, refund_asset) in refund_assets.iter().enumerate() {
+        if refund_asset.amount.is_zero()
+            || (i == offer_index && !refund_asset.is_native_token() && !is_deposited)
+        {
+            continue;
+        }
+
+        messages.push(refund_asset.clone().into_msg(info.sender.clone())?);
+    }
+
+    messages.extend(accrue_protocol_shares(
+        deps.storage,
+        &env,
+        &pair_info,
+        provide.protocol_shares,
+    )?);
+    update_root_k_last(deps.storage, &env, &pair_info, provide.reserves_after)?;
+    record_reserves(deps.storage, provide.reserves_after)?;
+
+    // mint LP token to receiver
+    let receiver = receiver.unwrap_or_else(|| info.sender.to_string());
+    messages.push(create_mint_tokens_msg(
+        env.contract.address.clone(),
+        Coin {
+            denom: pair_info.liquidity_token.clone(),
+            amount: provide.share,
+        },
+        receiver.to_string(),
+    ));
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        ("action", "provide_single_sided"),
+        ("sender", info.sender.as_str()),
+        ("receiver", receiver.as_str()),
+        ("offer_asset", &offer_asset.to_string()),
+        ("swap_amount", &provide.swap_amount.to_string()),
+        ("return_amount", &provide.return_amount.to_string()),
+        ("commission_amount", &provide.commission_amount.to_string()),
+        ("share", &provide.share.to_string()),
+        (
+            "refund_assets",
+            &format!("{}, {}", refund_assets[0], refund_assets[1]),
+        ),
+    ]))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_liquidity(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    amount: Uint128,
+    min_assets: Option<[Asset; 2]>,
+    deadline: Option<u64>,
+    ask_asset: Option<AssetInfo>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    // the burned shares come with the call, the pair holds the accrued protocol shares
+    if info.funds != coins(amount.u128(), &pair_info.liquidity_token) {
+        return Err(ContractError::LiquidityNotSent {
+            amount: amount.to_string(),
+        });
+    }
+    // withdrawing to a single asset swaps, a plain withdraw always works
+    if ask_asset.is_some() {
+        assert_not_paused(deps.as_ref(), &pair_info)?;
+    }
+
+    let contract_addr = env.contract.address.clone();
+
+    let pools: [Asset; 2] = query_pools(deps.as_ref(), &env, &pair_info)?;
+
+    accumulate_prices(
+        deps.storage,
+        env.block.time.seconds(),
+        [pools[0].amount, pools[1].amount],
+    )?;
+
+    let total_share: Uint128 = query_token_factory_denom_total_supply(
+        &deps.querier,
+        pair_info.liquidity_token.clone(),
+    ).unwrap();
+
+    let (protocol_shares, mint_fee_msg) = mint_protocol_fee(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &pair_info,
+        [pools[0].amount, pools[1].amount],
+        total_share,
+    )?;
+    let total_share = total_share + protocol_shares;
+
+    let mut refund_assets: Vec<Asset> = compute_withdraw(&pools, amount, total_share);
+    let mut reserves_after = [
+        pools[0].amount - refund_assets[0].amount,
+        pools[1].amount - refund_assets[1].amount,
+    ];
+
+    let mut fee_msgs: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    let mut swap_attributes: Vec<(&str, String)> = vec![];
+    if let Some(ask_asset) = ask_asset {
+        let ask_index = if ask_asset.equal(&pools[0].info) {
+            0
+        } else if ask_asset.equal(&pools[1].info) {
+            1
+        } else {
+            return Err(ContractError::AssetMismatch {});
+        };
+        let offer_index = 1 - ask_index;
+
+        // the other leg never leaves the pool, it is swapped against
+        // the reserves left after the withdrawal
+        let offer_amount = refund_assets[offer_index].amount;
+        let (return_amount, spread_amount, commission_amount) = compute_pair_swap(
+            deps.storage,
+            &env,
+            &pair_info,
+            reserves_after[offer_index],
+            reserves_after[ask_index],
+            offer_amount,
+            pair_info.asset_decimals[offer_index],
+            pair_info.asset_decimals[ask_index],
+        )?;
+
+        let fee_split = query_fee_split(&deps.querier, FACTORY.load(deps.storage)?)?;
+        let (fee_wallet_amount, burn_amount) =
+            protocol_fee_amounts(deps.storage, &fee_split, commission_amount)?;
+        let lp_amount = commission_amount.checked_sub(fee_wallet_amount + burn_amount)?;
+
+        let mut volume = [Uint128::zero(); 2];
+        volume[offer_index] = offer_amount;
+        volume[ask_index] = return_amount;
+        record_swap(
+            deps.storage,
+            env.block.time.seconds(),
+            volume,
+            ask_index,
+            commission_amount,
+            fee_wallet_amount,
+            burn_amount,
+        )?;
+
+        reserves_after[offer_index] += offer_amount;
+        reserves_after[ask_index] =
+            reserves_after[ask_index].checked_sub(return_amount + fee_wallet_amount + burn_amount)?;
+        refund_assets[offer_index].amount = Uint128::zero();
+        refund_assets[ask_index].amount += return_amount;
+
+        fee_msgs = protocol_fee_msgs(
+            deps.api,
+            &pair_info,
+            &ask_asset,
+            fee_wallet_amount,
+            burn_amount,
+        )?;
+
+        swap_attributes = vec![
+            ("ask_asset", ask_asset.to_string()),
+            ("swap_amount", offer_amount.to_string()),
+            ("return_amount", return_amount.to_string()),
+            ("spread_amount", spread_amount.to_string()),
+            ("commission_amount", commission_amount.to_string()),
+            ("burn_amount", burn_amount.to_string()),
+            ("fee_wallet_amount", fee_wallet_amount.to_string()),
+            ("pool_amount", lp_amount.to_string()),
+        ];
+    }
+
+    assert_minimum_assets(refund_assets.to_vec(), min_assets)?;
+
+    update_root_k_last(deps.storage, &env, &pair_info, reserves_after)?;
+    record_reserves(deps.storage, reserves_after)?;
+
+    let mut refund_msgs: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    for refund_asset in refund_assets.iter() {
+        if swap_attributes.is_empty() || !refund_asset.amount.is_zero() {
+            refund_msgs.push(refund_asset.clone().into_msg(sender.clone())?);
+        }
+    }
+
+    // update pool info
+    Ok(Response::new()
+        .add_messages(mint_fee_msg)
+        .add_messages(refund_msgs)
+        .add_messages(fee_msgs)
+        .add_messages(vec![
+            // burn liquidity token
+            create_burn_tokens_msg(
+                contract_addr.clone(), // sender: contract address as the minter/burner
+                Coin {
+                    denom: pair_info.liquidity_token.clone(), // our LP denom string
+                    amount, // amount to burn
+                },
+            ),
+        ])
+        .add_attributes(vec![
+            ("action", "withdraw_liquidity"),
+            ("sender", sender.as_str()),
+            ("withdrawn_share", &amount.to_string()),
+            (
+                "refund_assets",
+                &format!("{}, {}", refund_assets[0], refund_assets[1]),
+            ),
+        ])
+        .add_attributes(swap_attributes))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum BurnManagerMsg {
+    SendNative { asset: Asset }
+}
+
+// CONTRACT - a user must do token approval
+#[allow(clippy::too_many_arguments)]
+pub fn swap(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    offer_asset: Asset,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to: Option<Addr>,
+    deadline: Option<u64>,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    assert_deadline(env.block.time.seconds(), deadline)?;
+
+    offer_asset.assert_sent_native_token_balance(&info)?;
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    assert_not_paused(deps.as_ref(), &pair_info)?;
+
+    let pools: [Asset; 2] = query_pools(deps.as_ref(), &env, &pair_info)?;
+
+    let offer_pool: Asset;
+    let ask_pool: Asset;
+
+    let offer_decimal: u8;
+    let ask_decimal: u8;
+    // If the asset balance is already increased
+    // To calculated properly we should subtract user deposit from the pool
+    if offer_asset.info.equal(&pools[0].info) {
+        offer_pool = Asset {
+            amount: pools[0].amount.checked_sub(offer_asset.amount)?,
+            info: pools[0].info.clone(),
+        };
+        ask_pool = pools[1].clone();
+
+        offer_decimal = pair_info.asset_decimals[0];
         ask_decimal = pair_info.asset_decimals[1];
     } else if offer_asset.info.equal(&pools[1].info) {
         offer_pool = Asset {
@@ -487,9 +1049,24 @@ pub fn swap(
         return Err(ContractError::AssetMismatch {});
     }
 
+    let reserves = if offer_asset.info.equal(&pools[0].info) {
+        [offer_pool.amount, ask_pool.amount]
+    } else {
+        [ask_pool.amount, offer_pool.amount]
+    };
+    accumulate_prices(deps.storage, env.block.time.seconds(), reserves)?;
+
     let offer_amount = offer_asset.amount;
-    let (return_amount, spread_amount, commission_amount) =
-        compute_swap(offer_pool.amount, ask_pool.amount, offer_amount)?;
+    let (return_amount, spread_amount, commission_amount) = compute_pair_swap(
+        deps.storage,
+        &env,
+        &pair_info,
+        offer_pool.amount,
+        ask_pool.amount,
+        offer_amount,
+        offer_decimal,
+        ask_decimal,
+    )?;
 
     let return_asset = Asset {
         info: ask_pool.info.clone(),
@@ -505,30 +1082,400 @@ pub fn swap(
         spread_amount,
         offer_decimal,
         ask_decimal,
+        pair_info.commission_rate,
     )?;
 
     let receiver = to.unwrap_or_else(|| sender.clone());
 
-    let total_fee = commission_amount; // Total fee, assumed to be 0.3% of the transaction
-    let lp_amount = total_fee.multiply_ratio(2u128, 3u128); // 0.2% (2/3 of the total fee)
-    let fee_wallet_amount = total_fee.multiply_ratio(1u128, 6u128); // 0.05% (1/6 of the total fee)
-    let burn_amount = total_fee.multiply_ratio(1u128, 6u128); // 0.05% (1/6 of the total fee)
+    // split the commission as configured on the factory,
+    // the LP share is whatever is left after rounding down the others
+    let fee_split = query_fee_split(&deps.querier, FACTORY.load(deps.storage)?)?;
+    let (fee_wallet_amount, burn_amount) =
+        protocol_fee_amounts(deps.storage, &fee_split, commission_amount)?;
+    let lp_amount = commission_amount.checked_sub(fee_wallet_amount + burn_amount)?;
+
+    let offer_index = if offer_asset.info.equal(&pools[0].info) { 0 } else { 1 };
+    let mut volume = [Uint128::zero(); 2];
+    volume[offer_index] = offer_amount;
+    volume[1 - offer_index] = return_amount;
+    record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        volume,
+        1 - offer_index,
+        commission_amount,
+        fee_wallet_amount,
+        burn_amount,
+    )?;
+
+    let mut reserves_after = reserves;
+    reserves_after[offer_index] += offer_amount;
+    reserves_after[1 - offer_index] = reserves_after[1 - offer_index]
+        .checked_sub(return_amount + fee_wallet_amount + burn_amount)?;
+    record_reserves(deps.storage, reserves_after)?;
 
     let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
     if !return_amount.is_zero() {
         messages.push(return_asset.into_msg(receiver.clone())?);
     }
 
+    messages.extend(protocol_fee_msgs(
+        deps.api,
+        &pair_info,
+        &ask_pool.info,
+        fee_wallet_amount,
+        burn_amount,
+    )?);
+
+    // 1. send collateral token from the contract to a user
+    // 2. send inactive commission to collector
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        ("action", "swap"),
+        ("sender", sender.as_str()),
+        ("receiver", receiver.as_str()),
+        ("offer_asset", &offer_asset.info.to_string()),
+        ("ask_asset", &ask_pool.info.to_string()),
+        ("offer_amount", &offer_amount.to_string()),
+        ("return_amount", &return_amount.to_string()),
+        ("spread_amount", &spread_amount.to_string()),
+        ("commission_amount", &commission_amount.to_string()),
+        ("burn_amount", &burn_amount.to_string()),
+        ("fee_wallet_amount", &fee_wallet_amount.to_string()),
+        ("pool_amount", &lp_amount.to_string()),
+        ("lp_share", &fee_split.lp.to_string()),
+        ("fee_wallet_share", &fee_split.fee_wallet.to_string()),
+        ("burn_share", &fee_split.burn.to_string()),
+    ]))
+}
+
+pub fn flash_swap(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    ask_asset: Asset,
+    contract: String,
+    msg: Binary,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let contract = deps.api.addr_validate(&contract)?;
+
+    if ask_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    // funds would count as repayment in the reply
+    if !info.funds.is_empty() {
+        return Err(ContractError::FlashSwapFunds {});
+    }
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    assert_not_paused(deps.as_ref(), &pair_info)?;
+    let pools: [Asset; 2] = query_pools(deps.as_ref(), &env, &pair_info)?;
+
+    let ask_pool = pools
+        .iter()
+        .find(|pool| pool.info.equal(&ask_asset.info))
+        .ok_or(ContractError::AssetMismatch {})?;
+    if ask_asset.amount >= ask_pool.amount {
+        return Err(StdError::generic_err("ask amount exceeds the pool").into());
+    }
+
+    let reserves = [pools[0].amount, pools[1].amount];
+    accumulate_prices(deps.storage, env.block.time.seconds(), reserves)?;
+
+    let commission_amount = ask_asset.amount.mul_ceil(pair_info.commission_rate);
+    FLASH_SWAP.save(
+        deps.storage,
+        &FlashSwap {
+            sender: info.sender.clone(),
+            ask_asset: ask_asset.clone(),
+            commission_amount,
+            reserves,
+        },
+    )?;
+
+    let callback = SubMsg::reply_on_success(
+        WasmMsg::Execute {
+            contract_addr: contract.to_string(),
+            msg: to_json_binary(&FlashCallbackMsg::FlashCallback {
+                sender: info.sender.to_string(),
+                ask_asset: ask_asset.clone(),
+                commission_amount,
+                msg,
+            })?,
+            funds: vec![],
+        },
+        FLASH_SWAP_REPLY_ID,
+    );
+
+    Ok(Response::new()
+        .add_message(ask_asset.clone().into_msg(contract.clone())?)
+        .add_submessage(callback)
+        .add_attributes(vec![
+            ("action", "flash_swap"),
+            ("sender", info.sender.as_str()),
+            ("receiver", contract.as_str()),
+            ("ask_asset", &ask_asset.to_string()),
+            ("commission_amount", &commission_amount.to_string()),
+        ]))
+}
+
+/// Checks the flash swap callback restored the pool and splits the commission
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    msg: Reply,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    if msg.id != FLASH_SWAP_REPLY_ID {
+        return Err(StdError::generic_err("invalid reply msg").into());
+    }
+
+    let flash_swap = FLASH_SWAP.load(deps.storage)?;
+    FLASH_SWAP.remove(deps.storage);
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = query_pools(deps.as_ref(), &env, &pair_info)?;
+
+    // the whole commission is held back from the invariant
+    let ask_index = if flash_swap.ask_asset.info.equal(&pools[0].info) { 0 } else { 1 };
+    let mut reserves = [pools[0].amount, pools[1].amount];
+    if reserves[ask_index] < flash_swap.commission_amount {
+        return Err(ContractError::FlashSwapBalanceBelowLoan {});
+    }
+    reserves[ask_index] -= flash_swap.commission_amount;
+
+    let restored = match pair_info.pair_type {
+        PairType::Xyk {} => {
+            Uint256::from(reserves[0]) * Uint256::from(reserves[1])
+                >= Uint256::from(flash_swap.reserves[0]) * Uint256::from(flash_swap.reserves[1])
+        }
+        PairType::Stable { .. } => {
+            pool_liquidity(deps.storage, &env, &pair_info, reserves)?
+                >= pool_liquidity(deps.storage, &env, &pair_info, flash_swap.reserves)?
+        }
+    };
+    if !restored {
+        return Err(ContractError::FlashSwapNotRepaid {});
+    }
+
+    let fee_split = query_fee_split(&deps.querier, FACTORY.load(deps.storage)?)?;
+    let commission_amount = flash_swap.commission_amount;
+    let (fee_wallet_amount, burn_amount) =
+        protocol_fee_amounts(deps.storage, &fee_split, commission_amount)?;
+    let lp_amount = commission_amount.checked_sub(fee_wallet_amount + burn_amount)?;
+
+    let mut reserves_after = [pools[0].amount, pools[1].amount];
+    reserves_after[ask_index] =
+        reserves_after[ask_index].checked_sub(fee_wallet_amount + burn_amount)?;
+    record_reserves(deps.storage, reserves_after)?;
+
+    // only the borrowed side is known, the repayment may come in either asset
+    let mut volume = [Uint128::zero(); 2];
+    volume[ask_index] = flash_swap.ask_asset.amount;
+    record_swap(
+        deps.storage,
+        env.block.time.seconds(),
+        volume,
+        ask_index,
+        commission_amount,
+        fee_wallet_amount,
+        burn_amount,
+    )?;
+
+    let messages = protocol_fee_msgs(
+        deps.api,
+        &pair_info,
+        &flash_swap.ask_asset.info,
+        fee_wallet_amount,
+        burn_amount,
+    )?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        ("action", "flash_swap_repaid"),
+        ("sender", flash_swap.sender.as_str()),
+        ("ask_asset", &flash_swap.ask_asset.to_string()),
+        ("commission_amount", &commission_amount.to_string()),
+        ("burn_amount", &burn_amount.to_string()),
+        ("fee_wallet_amount", &fee_wallet_amount.to_string()),
+        ("pool_amount", &lp_amount.to_string()),
+        ("lp_share", &fee_split.lp.to_string()),
+        ("fee_wallet_share", &fee_split.fee_wallet.to_string()),
+        ("burn_share", &fee_split.burn.to_string()),
+    ]))
+}
+
+pub fn ramp_amp(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    future_amp: u64,
+    future_time: u64,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    if info.sender != FACTORY.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    if !matches!(pair_info.pair_type, PairType::Stable { .. }) {
+        return Err(ContractError::NotStablePair {});
+    }
+
+    let block_time = env.block.time.seconds();
+    let amp_config = AMP_CONFIG.load(deps.storage)?;
+    let current_amp = amp_config.current_amp(block_time);
+
+    // ramps cannot be chained to move the amp faster than one ramp allows
+    if block_time < amp_config.init_amp_time + MIN_RAMP_TIME {
+        return Err(ContractError::InvalidAmpRamp {
+            reason: format!("amp can change only once every {} seconds", MIN_RAMP_TIME),
+        });
+    }
+
+    if future_amp == 0 || future_amp > MAX_AMP {
+        return Err(ContractError::InvalidAmpRamp {
+            reason: format!("amp must be between 1 and {}", MAX_AMP),
+        });
+    }
+
+    if future_time < block_time + MIN_RAMP_TIME {
+        return Err(ContractError::InvalidAmpRamp {
+            reason: format!("ramp must last at least {} seconds", MIN_RAMP_TIME),
+        });
+    }
+
+    if future_amp > current_amp * MAX_AMP_CHANGE || future_amp * MAX_AMP_CHANGE < current_amp {
+        return Err(ContractError::InvalidAmpRamp {
+            reason: format!("amp can change at most {}x per ramp", MAX_AMP_CHANGE),
+        });
+    }
+
+    AMP_CONFIG.save(
+        deps.storage,
+        &AmpConfig {
+            init_amp: current_amp,
+            init_amp_time: block_time,
+            next_amp: future_amp,
+            next_amp_time: future_time,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "ramp_amp"),
+        ("init_amp", &current_amp.to_string()),
+        ("future_amp", &future_amp.to_string()),
+        ("future_time", &future_time.to_string()),
+    ]))
+}
+
+pub fn stop_ramp_amp(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    if info.sender != FACTORY.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    if !matches!(pair_info.pair_type, PairType::Stable { .. }) {
+        return Err(ContractError::NotStablePair {});
+    }
+
+    let block_time = env.block.time.seconds();
+    let current_amp = AMP_CONFIG.load(deps.storage)?.current_amp(block_time);
+    AMP_CONFIG.save(deps.storage, &AmpConfig::new(current_amp, block_time))?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "stop_ramp_amp"),
+        ("amp", &current_amp.to_string()),
+    ]))
+}
+
+pub fn set_paused(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    if info.sender != FACTORY.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    pair_info.paused = paused;
+    PAIR_INFO.save(deps.storage, &pair_info)?;
+
+    Ok(Response::new().add_attribute("action", if paused { "pause" } else { "unpause" }))
+}
+
+/// The pair is paused on its own or with every pair of the factory
+fn assert_not_paused(
+    deps: Deps<InjectiveQueryWrapper>,
+    pair_info: &PairInfoRaw,
+) -> Result<(), ContractError> {
+    if pair_info.paused || query_factory_paused(&deps.querier, FACTORY.load(deps.storage)?)? {
+        return Err(ContractError::Paused {});
+    }
+
+    Ok(())
+}
+
+/// Fee wallet and burn amounts of a commission to send out of the pool
+fn protocol_fee_amounts(
+    storage: &dyn Storage,
+    fee_split: &FeeSplit,
+    commission_amount: Uint128,
+) -> StdResult<(Uint128, Uint128)> {
+    match PROTOCOL_FEE_MODE.may_load(storage)?.unwrap_or_default() {
+        // 1/6 is not exact as a Decimal, keep the legacy rounding of the default split
+        ProtocolFeeMode::Transfer {} if *fee_split == FeeSplit::default() => Ok((
+            commission_amount.multiply_ratio(1u128, 6u128),
+            commission_amount.multiply_ratio(1u128, 6u128),
+        )),
+        ProtocolFeeMode::Transfer {} => Ok((
+            commission_amount.mul_floor(fee_split.fee_wallet),
+            commission_amount.mul_floor(fee_split.burn),
+        )),
+        // the protocol share stays in the pool until the next provide or withdraw
+        ProtocolFeeMode::Accrue {} => Ok((Uint128::zero(), Uint128::zero())),
+    }
+}
+
+#[test]
+fn test_protocol_fee_amounts_with_default_split() {
+    let storage = cosmwasm_std::testing::MockStorage::new();
+
+    // the default split pays what the fixed 1/6 shares paid before
+    for amount in [6u128, 7u128, 600u128, 3_000_000u128, 2_857_143u128] {
+        let commission_amount = Uint128::from(amount);
+        assert_eq!(
+            protocol_fee_amounts(&storage, &FeeSplit::default(), commission_amount).unwrap(),
+            (
+                commission_amount.multiply_ratio(1u128, 6u128),
+                commission_amount.multiply_ratio(1u128, 6u128),
+            )
+        );
+    }
+}
+
+/// Messages paying the fee wallet and burn shares of a commission in `asset_info`
+fn protocol_fee_msgs(
+    api: &dyn Api,
+    pair_info: &PairInfoRaw,
+    asset_info: &AssetInfo,
+    fee_wallet_amount: Uint128,
+    burn_amount: Uint128,
+) -> StdResult<Vec<CosmosMsg<InjectiveMsgWrapper>>> {
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+
     // Handle the burn amount
     if !burn_amount.is_zero() {
         let burn_asset = Asset {
-            info: ask_pool.info.clone(),
+            info: asset_info.clone(),
             amount: burn_amount,
         };
 
+        let burn_handler_address = api.addr_humanize(&pair_info.burn_address)?;
         if let AssetInfo::NativeToken { denom } = &burn_asset.info {
             // Call send_native for native tokens
-            let burn_handler_address = deps.api.addr_humanize(&pair_info.burn_address)?;
             messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: burn_handler_address.to_string(),
                 msg: to_json_binary(&BurnManagerMsg::SendNative {
@@ -541,7 +1488,6 @@ pub fn swap(
             }));
         } else if let AssetInfo::Token { contract_addr } = &burn_asset.info {
             // Send CW20 tokens directly to the burn address
-            let burn_handler_address = deps.api.addr_humanize(&pair_info.burn_address)?;
             messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: contract_addr.clone(),
                 msg: to_json_binary(&Cw20ExecuteMsg::Send {
@@ -554,60 +1500,334 @@ pub fn swap(
         }
     }
 
-    // Handle the fee wallet amount
-    if !fee_wallet_amount.is_zero() {
-        let fee_wallet_asset = Asset {
-            info: ask_pool.info.clone(),
-            amount: fee_wallet_amount,
+    // Handle the fee wallet amount
+    if !fee_wallet_amount.is_zero() {
+        let fee_wallet_asset = Asset {
+            info: asset_info.clone(),
+            amount: fee_wallet_amount,
+        };
+        messages.push(fee_wallet_asset.into_msg(api.addr_humanize(&pair_info.fee_wallet_address)?)?);
+    }
+
+    Ok(messages)
+}
+
+/// sqrt(k) for constant product pairs and D for stable pairs,
+/// both grow linearly with the liquidity
+fn pool_liquidity(
+    storage: &dyn Storage,
+    env: &Env,
+    pair_info: &PairInfoRaw,
+    reserves: [Uint128; 2],
+) -> StdResult<Uint256> {
+    match pair_info.pair_type {
+        PairType::Xyk {} => Ok((Uint256::from(reserves[0]) * Uint256::from(reserves[1])).isqrt()),
+        PairType::Stable { .. } => compute_pool_d(
+            AMP_CONFIG.load(storage)?.current_amp(env.block.time.seconds()),
+            reserves,
+            pair_info.asset_decimals,
+        ),
+    }
+}
+
+/// Remembers the liquidity left by a provide or withdraw while accruing
+fn update_root_k_last(
+    storage: &mut dyn Storage,
+    env: &Env,
+    pair_info: &PairInfoRaw,
+    reserves: [Uint128; 2],
+) -> StdResult<()> {
+    if let ProtocolFeeMode::Accrue {} = PROTOCOL_FEE_MODE.may_load(storage)?.unwrap_or_default() {
+        save_root_k_last(storage, env, pair_info, reserves)?;
+    }
+
+    Ok(())
+}
+
+fn save_root_k_last(
+    storage: &mut dyn Storage,
+    env: &Env,
+    pair_info: &PairInfoRaw,
+    reserves: [Uint128; 2],
+) -> StdResult<()> {
+    let root_k = pool_liquidity(storage, env, pair_info, reserves)?;
+    ROOT_K_LAST.save(storage, &root_k)?;
+    RESERVES_K_LAST.save(storage, &reserves)
+}
+
+/// Protocol share of the liquidity grown since `ROOT_K_LAST`
+fn compute_protocol_fee_shares(
+    storage: &dyn Storage,
+    querier: &QuerierWrapper<InjectiveQueryWrapper>,
+    env: &Env,
+    pair_info: &PairInfoRaw,
+    reserves: [Uint128; 2],
+    total_share: Uint128,
+) -> Result<Uint128, ContractError> {
+    let root_k_last = match ROOT_K_LAST.may_load(storage)? {
+        Some(root_k_last) if !root_k_last.is_zero() && !total_share.is_zero() => root_k_last,
+        _ => return Ok(Uint128::zero()),
+    };
+    // D also moves with the amp, only the growth at the current amp is fees
+    let root_k_last = match (&pair_info.pair_type, RESERVES_K_LAST.may_load(storage)?) {
+        (PairType::Stable { .. }, Some(reserves_last)) => {
+            pool_liquidity(storage, env, pair_info, reserves_last)?
+        }
+        _ => root_k_last,
+    };
+
+    let root_k = pool_liquidity(storage, env, pair_info, reserves)?;
+    if root_k <= root_k_last {
+        return Ok(Uint128::zero());
+    }
+
+    let fee_split = query_fee_split(querier, FACTORY.load(storage)?)?;
+    let protocol_share = Decimal256::from(fee_split.fee_wallet + fee_split.burn);
+
+    // shares = total_share * share * (root_k - root_k_last) / ((1 - share) * root_k + share * root_k_last)
+    let numerator = (Uint256::from(total_share) * (root_k - root_k_last)).mul_floor(protocol_share);
+    let denominator = root_k.mul_floor(Decimal256::one() - protocol_share)
+        + root_k_last.mul_floor(protocol_share);
+
+    Ok((numerator / denominator).try_into()?)
+}
+
+/// Records `shares` as accrued and mints them to the pair itself
+fn accrue_protocol_shares(
+    storage: &mut dyn Storage,
+    env: &Env,
+    pair_info: &PairInfoRaw,
+    shares: Uint128,
+) -> StdResult<Option<CosmosMsg<InjectiveMsgWrapper>>> {
+    if shares.is_zero() {
+        return Ok(None);
+    }
+
+    let accrued = PROTOCOL_SHARES.may_load(storage)?.unwrap_or_default();
+    PROTOCOL_SHARES.save(storage, &(accrued + shares))?;
+
+    Ok(Some(create_mint_tokens_msg(
+        env.contract.address.clone(),
+        Coin {
+            denom: pair_info.liquidity_token.clone(),
+            amount: shares,
+        },
+        env.contract.address.to_string(),
+    )))
+}
+
+/// Mints the protocol share of the liquidity grown since `ROOT_K_LAST`
+/// to the pair itself, returns the minted shares
+fn mint_protocol_fee(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper<InjectiveQueryWrapper>,
+    env: &Env,
+    pair_info: &PairInfoRaw,
+    reserves: [Uint128; 2],
+    total_share: Uint128,
+) -> Result<(Uint128, Option<CosmosMsg<InjectiveMsgWrapper>>), ContractError> {
+    let shares =
+        compute_protocol_fee_shares(storage, querier, env, pair_info, reserves, total_share)?;
+    let msg = accrue_protocol_shares(storage, env, pair_info, shares)?;
+
+    Ok((shares, msg))
+}
+
+pub fn update_protocol_fee_mode(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    mode: ProtocolFeeMode,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    if info.sender != FACTORY.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = query_pools(deps.as_ref(), &env, &pair_info)?;
+    let reserves = [pools[0].amount, pools[1].amount];
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    match (PROTOCOL_FEE_MODE.may_load(deps.storage)?.unwrap_or_default(), &mode) {
+        (ProtocolFeeMode::Transfer {}, ProtocolFeeMode::Accrue {}) => {
+            save_root_k_last(deps.storage, &env, &pair_info, reserves)?;
+        }
+        (ProtocolFeeMode::Accrue {}, ProtocolFeeMode::Transfer {}) => {
+            // settle what accrued so far, the shares stay collectable
+            let total_share = query_token_factory_denom_total_supply(
+                &deps.querier,
+                pair_info.liquidity_token.clone(),
+            )?;
+            let (_, mint_fee_msg) = mint_protocol_fee(
+                deps.storage,
+                &deps.querier,
+                &env,
+                &pair_info,
+                reserves,
+                total_share,
+            )?;
+            messages.extend(mint_fee_msg);
+            ROOT_K_LAST.remove(deps.storage);
+            RESERVES_K_LAST.remove(deps.storage);
+        }
+        _ => {}
+    }
+
+    PROTOCOL_FEE_MODE.save(deps.storage, &mode)?;
+
+    let mode = match mode {
+        ProtocolFeeMode::Transfer {} => "transfer",
+        ProtocolFeeMode::Accrue {} => "accrue",
+    };
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        ("action", "update_protocol_fee_mode"),
+        ("mode", mode),
+    ]))
+}
+
+/// Anyone can execute it
+pub fn collect_protocol_fees(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+) -> Result<Response<InjectiveMsgWrapper>, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = query_pools(deps.as_ref(), &env, &pair_info)?;
+    let reserves = [pools[0].amount, pools[1].amount];
+
+    accumulate_prices(deps.storage, env.block.time.seconds(), reserves)?;
+
+    let total_share: Uint128 = query_token_factory_denom_total_supply(
+        &deps.querier,
+        pair_info.liquidity_token.clone(),
+    )?;
+    let (protocol_shares, mint_fee_msg) = mint_protocol_fee(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &pair_info,
+        reserves,
+        total_share,
+    )?;
+    let total_share = total_share + protocol_shares;
+
+    let shares = PROTOCOL_SHARES.may_load(deps.storage)?.unwrap_or_default();
+    if shares.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    PROTOCOL_SHARES.save(deps.storage, &Uint128::zero())?;
+
+    let fee_split = query_fee_split(&deps.querier, FACTORY.load(deps.storage)?)?;
+    let protocol_share = fee_split.fee_wallet + fee_split.burn;
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    messages.extend(mint_fee_msg);
+    messages.push(create_burn_tokens_msg(
+        env.contract.address.clone(),
+        Coin {
+            denom: pair_info.liquidity_token.clone(),
+            amount: shares,
+        },
+    ));
+
+    let share_ratio = Decimal::from_ratio(shares, total_share);
+    let mut collected: Vec<Asset> = vec![];
+    for pool in pools.iter() {
+        let amount = pool.amount.mul_floor(share_ratio);
+        // shares accrued before the burn share was set to zero go to the fee wallet
+        let fee_wallet_amount = if protocol_share.is_zero() {
+            amount
+        } else {
+            amount.multiply_ratio(fee_split.fee_wallet.atomics(), protocol_share.atomics())
         };
-        messages.push(fee_wallet_asset.into_msg(
-            deps.api.addr_humanize(&pair_info.fee_wallet_address)?,
+
+        messages.extend(protocol_fee_msgs(
+            deps.api,
+            &pair_info,
+            &pool.info,
+            fee_wallet_amount,
+            amount - fee_wallet_amount,
         )?);
+        collected.push(Asset {
+            info: pool.info.clone(),
+            amount,
+        });
     }
 
-    // 1. send collateral token from the contract to a user
-    // 2. send inactive commission to collector
+    let reserves_after = [
+        reserves[0] - collected[0].amount,
+        reserves[1] - collected[1].amount,
+    ];
+    update_root_k_last(deps.storage, &env, &pair_info, reserves_after)?;
+    record_reserves(deps.storage, reserves_after)?;
+
     Ok(Response::new().add_messages(messages).add_attributes(vec![
-        ("action", "swap"),
-        ("sender", sender.as_str()),
-        ("receiver", receiver.as_str()),
-        ("offer_asset", &offer_asset.info.to_string()),
-        ("ask_asset", &ask_pool.info.to_string()),
-        ("offer_amount", &offer_amount.to_string()),
-        ("return_amount", &return_amount.to_string()),
-        ("spread_amount", &spread_amount.to_string()),
-        ("commission_amount", &commission_amount.to_string()),
-        ("burn_amount", &burn_amount.to_string()),
-        ("fee_wallet_amount", &fee_wallet_amount.to_string()),
-        ("pool_amount", &lp_amount.to_string()),
+        ("action", "collect_protocol_fees"),
+        ("shares", &shares.to_string()),
+        ("assets", &format!("{}, {}", collected[0], collected[1])),
     ]))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps<InjectiveQueryWrapper>, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+pub fn query(deps: Deps<InjectiveQueryWrapper>, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     match msg {
-        QueryMsg::Pair {} => Ok(to_json_binary(&query_pair_info(deps)?)?),
-        QueryMsg::Pool {} => Ok(to_json_binary(&query_pool(deps)?)?),
+        QueryMsg::Pair {} => Ok(to_json_binary(&query_pair_info(deps, env)?)?),
+        QueryMsg::Pool {} => Ok(to_json_binary(&query_pool(deps, env)?)?),
         QueryMsg::Simulation { offer_asset } => {
-            Ok(to_json_binary(&query_simulation(deps, offer_asset)?)?)
+            Ok(to_json_binary(&query_simulation(deps, env, offer_asset)?)?)
         }
         QueryMsg::ReverseSimulation { ask_asset } => {
-            Ok(to_json_binary(&query_reverse_simulation(deps, ask_asset)?)?)
+            Ok(to_json_binary(&query_reverse_simulation(deps, env, ask_asset)?)?)
+        }
+        QueryMsg::SimulateProvide { offer_asset } => {
+            Ok(to_json_binary(&query_simulate_provide(deps, env, offer_asset)?)?)
+        }
+        QueryMsg::SimulateProvideLiquidity { assets } => Ok(to_json_binary(
+            &query_simulate_provide_liquidity(deps, env, assets)?,
+        )?),
+        QueryMsg::SimulateWithdrawLiquidity { amount } => Ok(to_json_binary(
+            &query_simulate_withdraw_liquidity(deps, env, amount)?,
+        )?),
+        QueryMsg::Amp {} => Ok(to_json_binary(&query_amp(deps, env)?)?),
+        QueryMsg::CumulativePrices {} => Ok(to_json_binary(&query_cumulative_prices(deps, env)?)?),
+        QueryMsg::Twap {
+            asset_info,
+            window_seconds,
+        } => Ok(to_json_binary(&query_twap(deps, env, asset_info, window_seconds)?)?),
+        QueryMsg::ProtocolFees {} => Ok(to_json_binary(&query_protocol_fees(deps)?)?),
+        QueryMsg::Stats {} => Ok(to_json_binary(&query_stats(deps)?)?),
+        QueryMsg::VolumeHistory { buckets } => {
+            Ok(to_json_binary(&query_volume_history(deps, env, buckets)?)?)
         }
     }
 }
 
-pub fn query_pair_info(deps: Deps<InjectiveQueryWrapper>) -> Result<PairInfo, ContractError> {
+pub fn query_pair_info(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+) -> Result<PairInfo, ContractError> {
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
-    let pair_info = pair_info.to_normal(deps.api)?;
+    let mut pair_info = pair_info.to_normal(deps.api)?;
+
+    // the stored amp is the one the pair was created with
+    if let PairType::Stable { ref mut amp } = pair_info.pair_type {
+        *amp = AMP_CONFIG
+            .load(deps.storage)?
+            .current_amp(env.block.time.seconds());
+    }
+
+    // the factory pauses every pair at once with its global flag
+    pair_info.paused |= query_factory_paused(&deps.querier, FACTORY.load(deps.storage)?)?;
 
     Ok(pair_info)
 }
 
-pub fn query_pool(deps: Deps<InjectiveQueryWrapper>) -> Result<PoolResponse, ContractError> {
+pub fn query_pool(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+) -> Result<PoolResponse, ContractError> {
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
-    let contract_addr = deps.api.addr_humanize(&pair_info.contract_addr)?;
-    let assets: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, contract_addr)?;
+    let assets: [Asset; 2] = query_pools(deps, &env, &pair_info)?;
     
     let total_share: Uint128 = query_token_factory_denom_total_supply(
         &deps.querier,
@@ -624,27 +1844,41 @@ pub fn query_pool(deps: Deps<InjectiveQueryWrapper>) -> Result<PoolResponse, Con
 
 pub fn query_simulation(
     deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
     offer_asset: Asset,
 ) -> Result<SimulationResponse, ContractError> {
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
 
-    let contract_addr = deps.api.addr_humanize(&pair_info.contract_addr)?;
-    let pools: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, contract_addr)?;
+    let pools: [Asset; 2] = query_pools(deps, &env, &pair_info)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
+    let offer_decimal: u8;
+    let ask_decimal: u8;
     if offer_asset.info.equal(&pools[0].info) {
         offer_pool = pools[0].clone();
         ask_pool = pools[1].clone();
+        offer_decimal = pair_info.asset_decimals[0];
+        ask_decimal = pair_info.asset_decimals[1];
     } else if offer_asset.info.equal(&pools[1].info) {
         offer_pool = pools[1].clone();
         ask_pool = pools[0].clone();
+        offer_decimal = pair_info.asset_decimals[1];
+        ask_decimal = pair_info.asset_decimals[0];
     } else {
         return Err(ContractError::AssetMismatch {});
     }
 
-    let (return_amount, spread_amount, commission_amount) =
-        compute_swap(offer_pool.amount, ask_pool.amount, offer_asset.amount)?;
+    let (return_amount, spread_amount, commission_amount) = compute_pair_swap(
+        deps.storage,
+        &env,
+        &pair_info,
+        offer_pool.amount,
+        ask_pool.amount,
+        offer_asset.amount,
+        offer_decimal,
+        ask_decimal,
+    )?;
 
     Ok(SimulationResponse {
         return_amount,
@@ -655,27 +1889,41 @@ pub fn query_simulation(
 
 pub fn query_reverse_simulation(
     deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
     ask_asset: Asset,
 ) -> Result<ReverseSimulationResponse, ContractError> {
     let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
 
-    let contract_addr = deps.api.addr_humanize(&pair_info.contract_addr)?;
-    let pools: [Asset; 2] = pair_info.query_pools(&deps.querier, deps.api, contract_addr)?;
+    let pools: [Asset; 2] = query_pools(deps, &env, &pair_info)?;
 
     let offer_pool: Asset;
     let ask_pool: Asset;
+    let offer_decimal: u8;
+    let ask_decimal: u8;
     if ask_asset.info.equal(&pools[0].info) {
         ask_pool = pools[0].clone();
         offer_pool = pools[1].clone();
+        ask_decimal = pair_info.asset_decimals[0];
+        offer_decimal = pair_info.asset_decimals[1];
     } else if ask_asset.info.equal(&pools[1].info) {
         ask_pool = pools[1].clone();
         offer_pool = pools[0].clone();
+        ask_decimal = pair_info.asset_decimals[1];
+        offer_decimal = pair_info.asset_decimals[0];
     } else {
         return Err(ContractError::AssetMismatch {});
     }
 
-    let (offer_amount, spread_amount, commission_amount) =
-        compute_offer_amount(offer_pool.amount, ask_pool.amount, ask_asset.amount)?;
+    let (offer_amount, spread_amount, commission_amount) = compute_pair_offer_amount(
+        deps.storage,
+        &env,
+        &pair_info,
+        offer_pool.amount,
+        ask_pool.amount,
+        ask_asset.amount,
+        offer_decimal,
+        ask_decimal,
+    )?;
 
     Ok(ReverseSimulationResponse {
         offer_amount,
@@ -684,16 +1932,527 @@ pub fn query_reverse_simulation(
     })
 }
 
+pub fn query_simulate_provide(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+    offer_asset: Asset,
+) -> Result<SimulateProvideResponse, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+
+    let pools: [Asset; 2] = query_pools(deps, &env, &pair_info)?;
+    let total_share: Uint128 =
+        query_token_factory_denom_total_supply(&deps.querier, pair_info.liquidity_token.clone())?;
+
+    let provide = compute_single_sided_provide(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &pair_info,
+        &pools,
+        &offer_asset,
+        total_share,
+    )?;
+
+    Ok(SimulateProvideResponse {
+        share: provide.share,
+        swap_amount: provide.swap_amount,
+        return_amount: provide.return_amount,
+        spread_amount: provide.spread_amount,
+        commission_amount: provide.commission_amount,
+        refund_assets: [
+            Asset {
+                info: pools[0].info.clone(),
+                amount: provide.refunds[0],
+            },
+            Asset {
+                info: pools[1].info.clone(),
+                amount: provide.refunds[1],
+            },
+        ],
+    })
+}
+
+pub fn query_simulate_provide_liquidity(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+    assets: [Asset; 2],
+) -> Result<SimulateProvideLiquidityResponse, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+
+    let pools: [Asset; 2] = query_pools(deps, &env, &pair_info)?;
+    let reserves = [pools[0].amount, pools[1].amount];
+    let deposits = deposits_in_pool_order(&assets, &pools)?;
+
+    let total_share: Uint128 =
+        query_token_factory_denom_total_supply(&deps.querier, pair_info.liquidity_token.clone())?;
+    let total_share = total_share
+        + compute_protocol_fee_shares(
+            deps.storage,
+            &deps.querier,
+            &env,
+            &pair_info,
+            reserves,
+            total_share,
+        )?;
+
+    let (share, desired_amounts) = compute_provide(reserves, deposits, total_share)?;
+
+    Ok(SimulateProvideLiquidityResponse {
+        share,
+        refund_assets: [
+            Asset {
+                info: pools[0].info.clone(),
+                amount: deposits[0] - desired_amounts[0],
+            },
+            Asset {
+                info: pools[1].info.clone(),
+                amount: deposits[1] - desired_amounts[1],
+            },
+        ],
+    })
+}
+
+pub fn query_simulate_withdraw_liquidity(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+    amount: Uint128,
+) -> Result<SimulateWithdrawLiquidityResponse, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+
+    let pools: [Asset; 2] = query_pools(deps, &env, &pair_info)?;
+
+    let total_share: Uint128 =
+        query_token_factory_denom_total_supply(&deps.querier, pair_info.liquidity_token.clone())?;
+    if total_share.is_zero() {
+        return Err(ContractError::NoLiquidity {});
+    }
+
+    let total_share = total_share
+        + compute_protocol_fee_shares(
+            deps.storage,
+            &deps.querier,
+            &env,
+            &pair_info,
+            [pools[0].amount, pools[1].amount],
+            total_share,
+        )?;
+
+    let assets = compute_withdraw(&pools, amount, total_share);
+
+    Ok(SimulateWithdrawLiquidityResponse {
+        assets: [assets[0].clone(), assets[1].clone()],
+    })
+}
+
+pub fn query_amp(deps: Deps<InjectiveQueryWrapper>, env: Env) -> Result<AmpResponse, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    if !matches!(pair_info.pair_type, PairType::Stable { .. }) {
+        return Err(ContractError::NotStablePair {});
+    }
+
+    let amp_config = AMP_CONFIG.load(deps.storage)?;
+
+    Ok(AmpResponse {
+        amp: amp_config.current_amp(env.block.time.seconds()),
+        future_amp: amp_config.next_amp,
+        future_time: amp_config.next_amp_time,
+    })
+}
+
+pub fn query_protocol_fees(
+    deps: Deps<InjectiveQueryWrapper>,
+) -> Result<ProtocolFeesResponse, ContractError> {
+    Ok(ProtocolFeesResponse {
+        mode: PROTOCOL_FEE_MODE.may_load(deps.storage)?.unwrap_or_default(),
+        shares: PROTOCOL_SHARES.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+pub fn query_stats(deps: Deps<InjectiveQueryWrapper>) -> Result<StatsResponse, ContractError> {
+    let pair_info = PAIR_INFO.load(deps.storage)?.to_normal(deps.api)?;
+    let stats = SWAP_STATS.may_load(deps.storage)?.unwrap_or_default();
+
+    Ok(StatsResponse {
+        volume: pool_amounts(&pair_info.asset_infos, stats.volume),
+        commission: pool_amounts(&pair_info.asset_infos, stats.commission),
+        burn: pool_amounts(&pair_info.asset_infos, stats.burn),
+        fee_wallet: pool_amounts(&pair_info.asset_infos, stats.fee_wallet),
+        swap_count: stats.swap_count,
+    })
+}
+
+pub fn query_volume_history(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+    buckets: u32,
+) -> Result<VolumeHistoryResponse, ContractError> {
+    if buckets == 0 {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let pair_info = PAIR_INFO.load(deps.storage)?.to_normal(deps.api)?;
+    let buckets = volume_history(deps.storage, env.block.time.seconds(), buckets)?
+        .into_iter()
+        .map(|bucket| VolumeBucketResponse {
+            start: bucket.start,
+            volume: pool_amounts(&pair_info.asset_infos, bucket.volume),
+            swap_count: bucket.swap_count,
+        })
+        .collect();
+
+    Ok(VolumeHistoryResponse { buckets })
+}
+
+fn pool_amounts(asset_infos: &[AssetInfo; 2], amounts: [Uint128; 2]) -> [Asset; 2] {
+    [
+        Asset {
+            info: asset_infos[0].clone(),
+            amount: amounts[0],
+        },
+        Asset {
+            info: asset_infos[1].clone(),
+            amount: amounts[1],
+        },
+    ]
+}
+
+pub fn query_cumulative_prices(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+) -> Result<CumulativePricesResponse, ContractError> {
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let assets: [Asset; 2] = query_pools(deps, &env, &pair_info)?;
+
+    let block_time = env.block.time.seconds();
+    let (price0_cumulative, price1_cumulative) = match PRICE_ACCUMULATOR.may_load(deps.storage)? {
+        Some(accumulator) => cumulative_prices_at(deps.storage, &accumulator, block_time)?,
+        None => (Uint256::zero(), Uint256::zero()),
+    };
+
+    Ok(CumulativePricesResponse {
+        assets,
+        price0_cumulative,
+        price1_cumulative,
+        block_time,
+    })
+}
+
+pub fn query_twap(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+    asset_info: AssetInfo,
+    window_seconds: u64,
+) -> Result<TwapResponse, ContractError> {
+    if window_seconds == 0 {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let pair_info: PairInfoRaw = PAIR_INFO.load(deps.storage)?;
+    let pools: [Asset; 2] = query_pools(deps, &env, &pair_info)?;
+
+    let is_asset0 = if asset_info.equal(&pools[0].info) {
+        true
+    } else if asset_info.equal(&pools[1].info) {
+        false
+    } else {
+        return Err(ContractError::AssetMismatch {});
+    };
+
+    let block_time = env.block.time.seconds();
+    let accumulator: PriceAccumulator = PRICE_ACCUMULATOR
+        .may_load(deps.storage)?
+        .ok_or_else(|| StdError::generic_err("no price history"))?;
+    let (price0_cumulative, price1_cumulative) =
+        cumulative_prices_at(deps.storage, &accumulator, block_time)?;
+
+    let observation = block_time
+        .checked_sub(window_seconds)
+        .map(|start| observation_before(deps.storage, start))
+        .transpose()?
+        .flatten()
+        .ok_or_else(|| StdError::generic_err("not enough price history for the window"))?;
+
+    let (cumulative_now, cumulative_then) = if is_asset0 {
+        (price0_cumulative, observation.price0_cumulative)
+    } else {
+        (price1_cumulative, observation.price1_cumulative)
+    };
+
+    let elapsed = block_time - observation.timestamp;
+    let price = Decimal256::new(cumulative_now.wrapping_sub(cumulative_then) / Uint256::from(elapsed));
+
+    Ok(TwapResponse {
+        price: price
+            .try_into()
+            .map_err(|e: DecimalRangeExceeded| StdError::generic_err(e.to_string()))?,
+        window_seconds: elapsed,
+    })
+}
+
+/// Prices a swap with the invariant of the pair
+#[allow(clippy::too_many_arguments)]
+fn compute_pair_swap(
+    storage: &dyn Storage,
+    env: &Env,
+    pair_info: &PairInfoRaw,
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    offer_amount: Uint128,
+    offer_decimal: u8,
+    ask_decimal: u8,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    match pair_info.pair_type {
+        PairType::Xyk {} => compute_swap(
+            offer_pool,
+            ask_pool,
+            offer_amount,
+            pair_info.commission_rate,
+        ),
+        PairType::Stable { .. } => compute_stable_swap(
+            offer_pool,
+            ask_pool,
+            offer_amount,
+            offer_decimal,
+            ask_decimal,
+            AMP_CONFIG.load(storage)?.current_amp(env.block.time.seconds()),
+            pair_info.commission_rate,
+        ),
+    }
+}
+
+/// Outcome of a single sided provide, the pool amounts follow the pool order
+struct SingleSidedProvide {
+    offer_index: usize,
+    swap_amount: Uint128,
+    return_amount: Uint128,
+    spread_amount: Uint128,
+    commission_amount: Uint128,
+    fee_wallet_amount: Uint128,
+    burn_amount: Uint128,
+    protocol_shares: Uint128,
+    share: Uint128,
+    refunds: [Uint128; 2],
+    reserves_after: [Uint128; 2],
+}
+
+/// Swaps part of `offer_asset` against the pool and provides the rest
+/// together with the swap return at the new pool ratio
+fn compute_single_sided_provide(
+    storage: &dyn Storage,
+    querier: &QuerierWrapper<InjectiveQueryWrapper>,
+    env: &Env,
+    pair_info: &PairInfoRaw,
+    pools: &[Asset; 2],
+    offer_asset: &Asset,
+    total_share: Uint128,
+) -> Result<SingleSidedProvide, ContractError> {
+    let offer_index = if offer_asset.info.equal(&pools[0].info) {
+        0
+    } else if offer_asset.info.equal(&pools[1].info) {
+        1
+    } else {
+        return Err(ContractError::AssetMismatch {});
+    };
+    let ask_index = 1 - offer_index;
+
+    if offer_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    if total_share.is_zero() || pools[0].amount.is_zero() || pools[1].amount.is_zero() {
+        return Err(ContractError::NoLiquidity {});
+    }
+
+    let offer_pool = pools[offer_index].amount;
+    let ask_pool = pools[ask_index].amount;
+    let offer_decimal = pair_info.asset_decimals[offer_index];
+    let ask_decimal = pair_info.asset_decimals[ask_index];
+
+    let swap_amount = compute_single_sided_swap_amount(
+        storage,
+        env,
+        pair_info,
+        offer_pool,
+        ask_pool,
+        offer_asset.amount,
+        offer_decimal,
+        ask_decimal,
+    )?;
+    let (return_amount, spread_amount, commission_amount) = compute_pair_swap(
+        storage,
+        env,
+        pair_info,
+        offer_pool,
+        ask_pool,
+        swap_amount,
+        offer_decimal,
+        ask_decimal,
+    )?;
+
+    let fee_split = query_fee_split(querier, FACTORY.load(storage)?)?;
+    let (fee_wallet_amount, burn_amount) =
+        protocol_fee_amounts(storage, &fee_split, commission_amount)?;
+
+    let mut reserves = [Uint128::zero(); 2];
+    reserves[offer_index] = offer_pool + swap_amount;
+    reserves[ask_index] = ask_pool.checked_sub(return_amount + fee_wallet_amount + burn_amount)?;
+
+    let mut deposits = [Uint128::zero(); 2];
+    deposits[offer_index] = offer_asset.amount - swap_amount;
+    deposits[ask_index] = return_amount;
+
+    // the swap commission is already part of the grown liquidity
+    let protocol_shares =
+        compute_protocol_fee_shares(storage, querier, env, pair_info, reserves, total_share)?;
+    let total_share = total_share + protocol_shares;
+
+    let (share, desired_amounts) = compute_provide(reserves, deposits, total_share)?;
+
+    let refunds = [
+        deposits[0] - desired_amounts[0],
+        deposits[1] - desired_amounts[1],
+    ];
+    let reserves_after = [
+        reserves[0] + desired_amounts[0],
+        reserves[1] + desired_amounts[1],
+    ];
+
+    Ok(SingleSidedProvide {
+        offer_index,
+        swap_amount,
+        return_amount,
+        spread_amount,
+        commission_amount,
+        fee_wallet_amount,
+        burn_amount,
+        protocol_shares,
+        share,
+        refunds,
+        reserves_after,
+    })
+}
+
+/// Part of `offer_amount` to swap so that the rest and the swap return
+/// match the pool ratio after the swap
+#[allow(clippy::too_many_arguments)]
+fn compute_single_sided_swap_amount(
+    storage: &dyn Storage,
+    env: &Env,
+    pair_info: &PairInfoRaw,
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    offer_amount: Uint128,
+    offer_decimal: u8,
+    ask_decimal: u8,
+) -> StdResult<Uint128> {
+    match pair_info.pair_type {
+        PairType::Xyk {} => {
+            compute_xyk_swap_amount(offer_pool, offer_amount, pair_info.commission_rate)
+        }
+        PairType::Stable { .. } => {
+            // the remaining offer per swap return shrinks as the swap grows,
+            // bisect for the swap amount where it meets the pool ratio
+            let mut low = Uint128::zero();
+            let mut high = offer_amount;
+            while high - low > Uint128::one() {
+                let mid = low + (high - low) / Uint128::from(2u8);
+                let (return_amount, _, _) = compute_pair_swap(
+                    storage,
+                    env,
+                    pair_info,
+                    offer_pool,
+                    ask_pool,
+                    mid,
+                    offer_decimal,
+                    ask_decimal,
+                )?;
+
+                let remain = Uint256::from(offer_amount - mid)
+                    * Uint256::from(ask_pool.checked_sub(return_amount)?);
+                let needed = Uint256::from(return_amount) * Uint256::from(offer_pool + mid);
+                if remain > needed {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+
+            Ok(low)
+        }
+    }
+}
+
+/// Solves `(a - s)(R + f s) = (1 - f) s (R + s)` for the swap amount `s`,
+/// with `a` the offer amount, `R` the offer pool and `f` the commission rate
+/// taken from the swap return, i.e. `s^2 + (R (2 - f) - a f) s - a R = 0`
+fn compute_xyk_swap_amount(
+    offer_pool: Uint128,
+    offer_amount: Uint128,
+    commission_rate: Decimal,
+) -> StdResult<Uint128> {
+    // scaled by the decimal fractional, so the rate stays an integer
+    let one = Uint512::from(Decimal::one().atomics());
+    let rate = Uint512::from(commission_rate.atomics());
+    let offer_pool = Uint512::from(offer_pool);
+    let offer_amount = Uint512::from(offer_amount);
+
+    let b_pos = offer_pool * (one + one - rate);
+    let b_neg = offer_amount * rate;
+    let discriminant_rest = Uint512::from(4u8) * offer_amount * offer_pool * one * one;
+
+    let swap_amount = if b_pos >= b_neg {
+        let b = b_pos - b_neg;
+        ((b * b + discriminant_rest).isqrt() - b) / (one + one)
+    } else {
+        let b = b_neg - b_pos;
+        ((b * b + discriminant_rest).isqrt() + b) / (one + one)
+    };
+
+    Ok(swap_amount.min(offer_amount).try_into()?)
+}
+
+/// Prices a reverse swap with the invariant of the pair
+#[allow(clippy::too_many_arguments)]
+fn compute_pair_offer_amount(
+    storage: &dyn Storage,
+    env: &Env,
+    pair_info: &PairInfoRaw,
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    ask_amount: Uint128,
+    offer_decimal: u8,
+    ask_decimal: u8,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    match pair_info.pair_type {
+        PairType::Xyk {} => compute_offer_amount(
+            offer_pool,
+            ask_pool,
+            ask_amount,
+            pair_info.commission_rate,
+        ),
+        PairType::Stable { .. } => compute_stable_offer_amount(
+            offer_pool,
+            ask_pool,
+            ask_amount,
+            offer_decimal,
+            ask_decimal,
+            AMP_CONFIG.load(storage)?.current_amp(env.block.time.seconds()),
+            pair_info.commission_rate,
+        ),
+    }
+}
+
 fn compute_swap(
     offer_pool: Uint128,
     ask_pool: Uint128,
     offer_amount: Uint128,
+    commission_rate: Decimal,
 ) -> StdResult<(Uint128, Uint128, Uint128)> {
     let offer_pool: Uint256 = offer_pool.into();
     let ask_pool: Uint256 = ask_pool.into();
     let offer_amount: Uint256 = offer_amount.into();
 
-    let commission_rate = Decimal256::permille(COMMISSION_RATE);
+    let commission_rate = Decimal256::from(commission_rate);
 
     // offer => ask
     // ask_amount = (ask_pool - cp / (offer_pool + offer_amount)) * (1 - commission_rate)
@@ -702,10 +2461,8 @@ fn compute_swap(
     // calculate spread & commission
     let spread_amount: Uint256 =
         (offer_amount.mul_floor(Decimal256::from_ratio(ask_pool, offer_pool))) - return_amount;
-    let mut commission_amount: Uint256 = return_amount.mul_floor(commission_rate);
-    if return_amount != (commission_amount.mul_floor(Decimal256::one() / commission_rate)) {
-        commission_amount += Uint256::from(1u128);
-    }
+    // round the commission up
+    let commission_amount: Uint256 = return_amount.mul_ceil(commission_rate);
     // commission will be absorbed to pool
     let return_amount: Uint256 = return_amount - commission_amount;
     Ok((
@@ -721,7 +2478,7 @@ fn test_compute_swap_with_huge_pool_variance() {
     let ask_pool = Uint128::from(317u128);
 
     assert_eq!(
-        compute_swap(offer_pool, ask_pool, Uint128::from(1u128))
+        compute_swap(offer_pool, ask_pool, Uint128::from(1u128), Decimal::permille(3))
             .unwrap()
             .0,
         Uint128::zero()
@@ -732,12 +2489,13 @@ fn compute_offer_amount(
     offer_pool: Uint128,
     ask_pool: Uint128,
     ask_amount: Uint128,
+    commission_rate: Decimal,
 ) -> StdResult<(Uint128, Uint128, Uint128)> {
     let offer_pool: Uint256 = offer_pool.into();
     let ask_pool: Uint256 = ask_pool.into();
     let ask_amount: Uint256 = ask_amount.into();
 
-    let commission_rate = Decimal256::permille(COMMISSION_RATE);
+    let commission_rate = Decimal256::from(commission_rate);
 
     // ask => offer
     // offer_amount = cp / (ask_pool - ask_amount / (1 - commission_rate)) - offer_pool
@@ -778,7 +2536,10 @@ fn compute_offer_amount(
 
 /// If `belief_price` and `max_spread` both are given,
 /// we compute new spread else we just use choice
-/// spread to check `max_spread`
+/// spread to check `max_spread`.
+/// The expected return at `belief_price` is net of the pair
+/// `commission_rate`, so the fee tier itself never counts as spread
+#[allow(clippy::too_many_arguments)]
 pub fn assert_max_spread(
     belief_price: Option<Decimal>,
     max_spread: Option<Decimal>,
@@ -787,6 +2548,7 @@ pub fn assert_max_spread(
     spread_amount: Uint128,
     offer_decimal: u8,
     return_decimal: u8,
+    commission_rate: Decimal,
 ) -> Result<(), ContractError> {
     let (offer_amount, return_amount, spread_amount): (Uint256, Uint256, Uint256) =
         match offer_decimal.cmp(&return_decimal) {
@@ -827,7 +2589,11 @@ pub fn assert_max_spread(
         let belief_price: Decimal256 = Decimal256::from_str(&belief_price.to_string())?;
         let max_spread: Decimal256 = Decimal256::from_str(&max_spread.to_string())?;
 
-        let expected_return = offer_amount.mul_floor(Decimal256::one() / belief_price);
+        let commission_rate = Decimal256::from(commission_rate);
+
+        let expected_return = offer_amount
+            .mul_floor(Decimal256::one() / belief_price)
+            .mul_floor(Decimal256::one() - commission_rate);
         let spread_amount = if expected_return > return_amount {
             expected_return - return_amount
         } else {
@@ -895,7 +2661,17 @@ pub fn assert_deadline(blocktime: u64, deadline: Option<u64>) -> Result<(), Cont
 
 const TARGET_CONTRACT_VERSION: &str = "0.1.1";
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut<InjectiveQueryWrapper>, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+pub fn migrate(deps: DepsMut<InjectiveQueryWrapper>, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // pairs created before the fee split was configurable do not know their
+    // factory, which is always the admin migrating them
+    if FACTORY.may_load(deps.storage)?.is_none() {
+        let contract_info = deps.querier.query_wasm_contract_info(env.contract.address)?;
+        let factory = contract_info
+            .admin
+            .ok_or_else(|| StdError::generic_err("pair has no admin"))?;
+        FACTORY.save(deps.storage, &factory)?;
+    }
+
     migrate_version(
         deps,
         TARGET_CONTRACT_VERSION,
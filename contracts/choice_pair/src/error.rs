@@ -33,6 +33,39 @@ pub enum ContractError {
     #[error("Max slippage assertion")]
     MaxSlippageAssertion {},
 
+    #[error("Min share assertion ({min_share} > {share})")]
+    MinShareAssertion { min_share: String, share: String },
+
+    #[error("Pool has no liquidity")]
+    NoLiquidity {},
+
+    #[error("Pair is paused")]
+    Paused {},
+
+    #[error("Flash swap in progress")]
+    FlashSwapInProgress {},
+
+    #[error("Flash swap not repaid")]
+    FlashSwapNotRepaid {},
+
+    #[error("Flash swap balance below the loan")]
+    FlashSwapBalanceBelowLoan {},
+
+    #[error("Flash swap takes no funds")]
+    FlashSwapFunds {},
+
+    #[error("Cw20 deposit waiting for its provide")]
+    DepositPending {},
+
+    #[error("Must send exactly {amount} of the liquidity token")]
+    LiquidityNotSent { amount: String },
+
+    #[error("Not a stable pair")]
+    NotStablePair {},
+
+    #[error("Invalid amp ramp: {reason}")]
+    InvalidAmpRamp { reason: String },
+
     #[error("More initial liquidity needed ({min_lp_token} > {given_lp})")]
     MinimumLiquidityAmountError {
         min_lp_token: String,
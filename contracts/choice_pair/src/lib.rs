@@ -2,7 +2,10 @@ pub mod contract;
 pub mod state;
 
 mod error;
+mod oracle;
 mod response;
+mod stableswap;
+mod stats;
 
 #[cfg(test)]
 mod testing;
@@ -0,0 +1,148 @@
+use choice::asset::PairType;
+use cosmwasm_std::{Decimal256, StdResult, Storage, Uint128, Uint256};
+
+use crate::stableswap::compute_stable_prices;
+use crate::state::{
+    Observation, ObservationIndex, PriceAccumulator, AMP_CONFIG, OBSERVATIONS, OBSERVATION_INDEX,
+    PAIR_INFO, PRICE_ACCUMULATOR,
+};
+
+/// Size of the observation ring buffer
+pub const MAX_OBSERVATIONS: u32 = 240;
+/// Minimum seconds between two observations, the buffer covers at least 4 hours
+pub const OBSERVATION_INTERVAL: u64 = 60;
+
+/// Cumulative prices at `block_time`, assuming the recorded reserves held since the last update
+pub fn cumulative_prices_at(
+    storage: &dyn Storage,
+    accumulator: &PriceAccumulator,
+    block_time: u64,
+) -> StdResult<(Uint256, Uint256)> {
+    let reserves = accumulator.reserves;
+    let elapsed = block_time.saturating_sub(accumulator.block_time_last);
+    if elapsed == 0 || reserves[0].is_zero() || reserves[1].is_zero() {
+        return Ok((accumulator.price0_cumulative, accumulator.price1_cumulative));
+    }
+
+    let elapsed = Uint256::from(elapsed);
+    let (price0, price1) = spot_prices(storage, reserves, accumulator.block_time_last)?;
+
+    Ok((
+        accumulator
+            .price0_cumulative
+            .wrapping_add(price0.atomics().wrapping_mul(elapsed)),
+        accumulator
+            .price1_cumulative
+            .wrapping_add(price1.atomics().wrapping_mul(elapsed)),
+    ))
+}
+
+/// Trading prices of the pool at `reserves`, the reserve ratio for constant
+/// product pairs and the invariant slope at the amp of `block_time` for stable pairs
+fn spot_prices(
+    storage: &dyn Storage,
+    reserves: [Uint128; 2],
+    block_time: u64,
+) -> StdResult<(Decimal256, Decimal256)> {
+    let pair_info = PAIR_INFO.load(storage)?;
+    match pair_info.pair_type {
+        PairType::Xyk {} => Ok((
+            Decimal256::from_ratio(reserves[1], reserves[0]),
+            Decimal256::from_ratio(reserves[0], reserves[1]),
+        )),
+        PairType::Stable { .. } => compute_stable_prices(
+            AMP_CONFIG.load(storage)?.current_amp(block_time),
+            reserves,
+            pair_info.asset_decimals,
+        ),
+    }
+}
+
+/// Must run before the reserves change. The time since the last update is
+/// priced with the recorded reserves, then `reserves` (the current balances,
+/// donations included) are recorded until the operation calls `record_reserves`
+pub fn accumulate_prices(
+    storage: &mut dyn Storage,
+    block_time: u64,
+    reserves: [Uint128; 2],
+) -> StdResult<()> {
+    let accumulator = match PRICE_ACCUMULATOR.may_load(storage)? {
+        Some(mut accumulator) => {
+            // accumulators saved before the reserves were recorded
+            if accumulator.reserves == [Uint128::zero(); 2] {
+                accumulator.reserves = reserves;
+            }
+            let (price0_cumulative, price1_cumulative) =
+                cumulative_prices_at(storage, &accumulator, block_time)?;
+            PriceAccumulator {
+                price0_cumulative,
+                price1_cumulative,
+                block_time_last: block_time,
+                reserves,
+            }
+        }
+        None => PriceAccumulator {
+            price0_cumulative: Uint256::zero(),
+            price1_cumulative: Uint256::zero(),
+            block_time_last: block_time,
+            reserves,
+        },
+    };
+    PRICE_ACCUMULATOR.save(storage, &accumulator)?;
+
+    let mut index = OBSERVATION_INDEX.may_load(storage)?.unwrap_or_default();
+    if let Some(last) = last_observation(storage, &index)? {
+        if block_time < last.timestamp + OBSERVATION_INTERVAL {
+            return Ok(());
+        }
+    }
+
+    OBSERVATIONS.save(
+        storage,
+        index.next,
+        &Observation {
+            timestamp: block_time,
+            price0_cumulative: accumulator.price0_cumulative,
+            price1_cumulative: accumulator.price1_cumulative,
+        },
+    )?;
+    index.next = (index.next + 1) % MAX_OBSERVATIONS;
+    index.len = std::cmp::min(index.len + 1, MAX_OBSERVATIONS);
+    OBSERVATION_INDEX.save(storage, &index)
+}
+
+/// Records the reserves left by an operation, the next update prices with them
+pub fn record_reserves(storage: &mut dyn Storage, reserves: [Uint128; 2]) -> StdResult<()> {
+    PRICE_ACCUMULATOR.update(storage, |mut accumulator| -> StdResult<_> {
+        accumulator.reserves = reserves;
+        Ok(accumulator)
+    })?;
+
+    Ok(())
+}
+
+fn last_observation(
+    storage: &dyn Storage,
+    index: &ObservationIndex,
+) -> StdResult<Option<Observation>> {
+    if index.len == 0 {
+        return Ok(None);
+    }
+
+    OBSERVATIONS.may_load(storage, (index.next + MAX_OBSERVATIONS - 1) % MAX_OBSERVATIONS)
+}
+
+/// Newest observation taken at or before `timestamp`
+pub fn observation_before(storage: &dyn Storage, timestamp: u64) -> StdResult<Option<Observation>> {
+    let index = OBSERVATION_INDEX.may_load(storage)?.unwrap_or_default();
+
+    for i in 1..=index.len {
+        let slot = (index.next + MAX_OBSERVATIONS - i) % MAX_OBSERVATIONS;
+        let observation = OBSERVATIONS.load(storage, slot)?;
+        if observation.timestamp <= timestamp {
+            return Ok(Some(observation));
+        }
+    }
+
+    Ok(None)
+}
@@ -6,6 +6,8 @@
 #![allow(clippy::all)]
 
 #![allow(unused_attributes)]
+#![allow(unused_parens)]
+#![allow(mismatched_lifetime_syntaxes)]
 #![cfg_attr(rustfmt, rustfmt::skip)]
 
 #![allow(dead_code)]
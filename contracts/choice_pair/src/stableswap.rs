@@ -0,0 +1,238 @@
+use cosmwasm_std::{Decimal, Decimal256, StdError, StdResult, Uint128, Uint256};
+use std::cmp::max;
+use std::convert::TryInto;
+
+use crate::state::AmpConfig;
+
+pub const MAX_AMP: u64 = 1_000_000;
+/// Amplification can move at most 10x in one ramp
+pub const MAX_AMP_CHANGE: u64 = 10;
+/// A ramp must last at least one day, and the amp changes at most once a day
+pub const MIN_RAMP_TIME: u64 = 86_400;
+
+const N_COINS: u8 = 2;
+const ITERATIONS: u8 = 64;
+
+impl AmpConfig {
+    pub fn new(amp: u64, block_time: u64) -> Self {
+        AmpConfig {
+            init_amp: amp,
+            init_amp_time: block_time,
+            next_amp: amp,
+            next_amp_time: block_time,
+        }
+    }
+
+    /// Linear interpolation between the ramp start and target
+    pub fn current_amp(&self, block_time: u64) -> u64 {
+        if block_time >= self.next_amp_time {
+            return self.next_amp;
+        }
+
+        let elapsed = (block_time - self.init_amp_time) as u128;
+        let duration = (self.next_amp_time - self.init_amp_time) as u128;
+        if self.next_amp > self.init_amp {
+            let diff = (self.next_amp - self.init_amp) as u128;
+            self.init_amp + (diff * elapsed / duration) as u64
+        } else {
+            let diff = (self.init_amp - self.next_amp) as u128;
+            self.init_amp - (diff * elapsed / duration) as u64
+        }
+    }
+}
+
+/// Computes the invariant D of `4A(x + y) + D = 4AD + D^3 / (4xy)`
+/// with Newton's method
+pub fn compute_d(amp: u64, x: Uint256, y: Uint256) -> StdResult<Uint256> {
+    if x.is_zero() || y.is_zero() {
+        return Err(StdError::generic_err("stable pool is empty"));
+    }
+
+    let n = Uint256::from(N_COINS);
+    let ann = Uint256::from(amp) * n * n;
+    let sum = x + y;
+
+    let mut d = sum;
+    for _ in 0..ITERATIONS {
+        let d_p = d.checked_mul(d)? / (x * n);
+        let d_p = d_p.checked_mul(d)? / (y * n);
+        let d_prev = d;
+
+        d = (ann.checked_mul(sum)? + d_p * n).checked_mul(d)?
+            / ((ann - Uint256::one()).checked_mul(d)? + (n + Uint256::one()).checked_mul(d_p)?);
+
+        if d.abs_diff(d_prev) <= Uint256::one() {
+            return Ok(d);
+        }
+    }
+
+    Err(StdError::generic_err("stableswap invariant did not converge"))
+}
+
+/// Computes the balance of the other side of the pool which keeps `d`
+/// once this side holds `x`
+pub fn compute_y(amp: u64, x: Uint256, d: Uint256) -> StdResult<Uint256> {
+    if x.is_zero() {
+        return Err(StdError::generic_err("stable pool is empty"));
+    }
+
+    let n = Uint256::from(N_COINS);
+    let ann = Uint256::from(amp) * n * n;
+
+    let c = d.checked_mul(d)? / (x * n);
+    let c = c.checked_mul(d)? / (ann * n);
+    let b = x + d / ann;
+
+    let mut y = d;
+    for _ in 0..ITERATIONS {
+        let y_prev = y;
+        y = (y.checked_mul(y)? + c) / (y * n + b).checked_sub(d)?;
+
+        if y.abs_diff(y_prev) <= Uint256::one() {
+            return Ok(y);
+        }
+    }
+
+    Err(StdError::generic_err("stableswap invariant did not converge"))
+}
+
+/// D of a pool, zero while either side is empty
+pub fn compute_pool_d(amp: u64, pools: [Uint128; 2], decimals: [u8; 2]) -> StdResult<Uint256> {
+    if pools[0].is_zero() || pools[1].is_zero() {
+        return Ok(Uint256::zero());
+    }
+
+    let precision = max(decimals[0], decimals[1]);
+    compute_d(
+        amp,
+        upscale(pools[0], decimals[0], precision),
+        upscale(pools[1], decimals[1], precision),
+    )
+}
+
+/// Spot prices of a stable pool, asset 0 in units of asset 1 and the
+/// reverse, from the slope of the invariant at the pool amounts
+pub fn compute_stable_prices(
+    amp: u64,
+    pools: [Uint128; 2],
+    decimals: [u8; 2],
+) -> StdResult<(Decimal256, Decimal256)> {
+    let precision = max(decimals[0], decimals[1]);
+    let x = upscale(pools[0], decimals[0], precision);
+    let y = upscale(pools[1], decimals[1], precision);
+    let d = compute_d(amp, x, y)?;
+
+    // -dy/dx = y/x * (4·Ann·x²y + D³) / (4·Ann·xy² + D³), with x and y
+    // taken relative to D to stay in range
+    let n = Uint256::from(N_COINS);
+    let ann4 = Decimal256::from_ratio(Uint256::from(amp) * n * n * n * n, 1u8);
+    let x = Decimal256::from_ratio(x, d);
+    let y = Decimal256::from_ratio(y, d);
+    let slope = (ann4 * x * x * y + Decimal256::one()) / (ann4 * x * y * y + Decimal256::one());
+
+    Ok((
+        Decimal256::from_ratio(pools[1], pools[0]) * slope,
+        Decimal256::from_ratio(pools[0], pools[1]) / slope,
+    ))
+}
+
+/// Both sides are priced at the precision of the more precise asset
+fn upscale(amount: Uint128, decimals: u8, precision: u8) -> Uint256 {
+    Uint256::from(amount) * Uint256::from(10u8).pow((precision - decimals) as u32)
+}
+
+fn downscale(amount: Uint256, decimals: u8, precision: u8) -> Uint256 {
+    amount / Uint256::from(10u8).pow((precision - decimals) as u32)
+}
+
+fn downscale_ceil(amount: Uint256, decimals: u8, precision: u8) -> Uint256 {
+    let factor = Uint256::from(10u8).pow((precision - decimals) as u32);
+    (amount + factor - Uint256::one()) / factor
+}
+
+/// Stable counterpart of `compute_swap`, the spread is measured
+/// against a 1:1 peg of the two assets
+#[allow(clippy::too_many_arguments)]
+pub fn compute_stable_swap(
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    offer_amount: Uint128,
+    offer_decimal: u8,
+    ask_decimal: u8,
+    amp: u64,
+    commission_rate: Decimal,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let precision = max(offer_decimal, ask_decimal);
+    let offer_pool = upscale(offer_pool, offer_decimal, precision);
+    let ask_pool = upscale(ask_pool, ask_decimal, precision);
+    let offer_amount = upscale(offer_amount, offer_decimal, precision);
+
+    let d = compute_d(amp, offer_pool, ask_pool)?;
+    let new_ask_pool = compute_y(amp, offer_pool + offer_amount, d)?;
+
+    // round against the trader
+    let return_amount = ask_pool
+        .saturating_sub(new_ask_pool)
+        .saturating_sub(Uint256::one());
+    let spread_amount = offer_amount.saturating_sub(return_amount);
+
+    let return_amount = downscale(return_amount, ask_decimal, precision);
+    let spread_amount = downscale(spread_amount, ask_decimal, precision);
+
+    // round the commission up
+    let commission_amount = return_amount.mul_ceil(Decimal256::from(commission_rate));
+    let return_amount = return_amount - commission_amount;
+
+    Ok((
+        return_amount.try_into()?,
+        spread_amount.try_into()?,
+        commission_amount.try_into()?,
+    ))
+}
+
+/// Stable counterpart of `compute_offer_amount`
+#[allow(clippy::too_many_arguments)]
+pub fn compute_stable_offer_amount(
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    ask_amount: Uint128,
+    offer_decimal: u8,
+    ask_decimal: u8,
+    amp: u64,
+    commission_rate: Decimal,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let one_minus_commission = Decimal256::one() - Decimal256::from(commission_rate);
+    let ask_amount = Uint256::from(ask_amount);
+    let mut before_commission_deduction =
+        ask_amount.mul_floor(Decimal256::one() / one_minus_commission);
+    if before_commission_deduction.mul_floor(one_minus_commission) != ask_amount {
+        before_commission_deduction += Uint256::one();
+    }
+    let commission_amount = before_commission_deduction - ask_amount;
+
+    let precision = max(offer_decimal, ask_decimal);
+    let offer_pool = upscale(offer_pool, offer_decimal, precision);
+    let ask_pool = upscale(ask_pool, ask_decimal, precision);
+    let before_commission_deduction =
+        upscale(before_commission_deduction.try_into()?, ask_decimal, precision);
+
+    if before_commission_deduction >= ask_pool {
+        return Err(StdError::generic_err("ask amount exceeds the pool"));
+    }
+
+    let d = compute_d(amp, offer_pool, ask_pool)?;
+    let new_offer_pool = compute_y(amp, ask_pool - before_commission_deduction, d)?;
+
+    // round against the trader
+    let offer_amount = new_offer_pool.saturating_sub(offer_pool) + Uint256::one();
+    let spread_amount = offer_amount.saturating_sub(before_commission_deduction);
+
+    let offer_amount = downscale_ceil(offer_amount, offer_decimal, precision);
+    let spread_amount = downscale(spread_amount, ask_decimal, precision);
+
+    Ok((
+        offer_amount.try_into()?,
+        spread_amount.try_into()?,
+        commission_amount.try_into()?,
+    ))
+}
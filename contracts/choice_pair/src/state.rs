@@ -1,4 +1,116 @@
-use cw_storage_plus::Item;
-use choice::asset::PairInfoRaw;
+use cosmwasm_std::{Addr, Uint128, Uint256};
+use serde::{Deserialize, Serialize};
+use cw_storage_plus::{Item, Map};
+use choice::asset::{Asset, PairInfoRaw};
+use choice::pair::ProtocolFeeMode;
 
 pub const PAIR_INFO: Item<PairInfoRaw> = Item::new("pair_info");
+
+/// Factory which created the pair, the fee split is read from it
+pub const FACTORY: Item<Addr> = Item::new("factory");
+
+/// Amplification ramp of a stable pair, times are in seconds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AmpConfig {
+    pub init_amp: u64,
+    pub init_amp_time: u64,
+    pub next_amp: u64,
+    pub next_amp_time: u64,
+}
+
+pub const AMP_CONFIG: Item<AmpConfig> = Item::new("amp_config");
+
+/// Flash swap waiting for its callback to return, `reserves` are
+/// the pool amounts before the ask asset was sent out
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FlashSwap {
+    pub sender: Addr,
+    pub ask_asset: Asset,
+    pub commission_amount: Uint128,
+    pub reserves: [Uint128; 2],
+}
+
+pub const FLASH_SWAP: Item<FlashSwap> = Item::new("flash_swap");
+
+/// Cw20 leg sent with `Cw20HookMsg::ProvideLiquidity`, waiting for the native
+/// leg of the same transaction
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Cw20Deposit {
+    pub sender: Addr,
+    pub asset: Asset,
+    pub block_height: u64,
+    pub tx_index: Option<u32>,
+}
+
+pub const CW20_DEPOSIT: Item<Cw20Deposit> = Item::new("cw20_deposit");
+/// Stale deposit refunded by the running execute, kept out of the pool until then
+pub const CW20_REFUND: Item<Cw20Deposit> = Item::new("cw20_refund");
+
+/// Time weighted sums of both pool prices, scaled by 1e18 and wrapping on overflow
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PriceAccumulator {
+    /// Price of asset 0 in units of asset 1
+    pub price0_cumulative: Uint256,
+    /// Price of asset 1 in units of asset 0
+    pub price1_cumulative: Uint256,
+    pub block_time_last: u64,
+    /// Reserves left by the last operation, donations are only picked up by the next one
+    #[serde(default)]
+    pub reserves: [Uint128; 2],
+}
+
+pub const PRICE_ACCUMULATOR: Item<PriceAccumulator> = Item::new("price_accumulator");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Observation {
+    pub timestamp: u64,
+    pub price0_cumulative: Uint256,
+    pub price1_cumulative: Uint256,
+}
+
+/// Ring buffer of accumulator snapshots
+pub const OBSERVATIONS: Map<u32, Observation> = Map::new("observations");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+pub struct ObservationIndex {
+    /// Slot the next observation is written to
+    pub next: u32,
+    /// Number of filled slots
+    pub len: u32,
+}
+
+pub const OBSERVATION_INDEX: Item<ObservationIndex> = Item::new("observation_index");
+
+/// Missing on pairs created before accrual existed, they transfer
+pub const PROTOCOL_FEE_MODE: Item<ProtocolFeeMode> = Item::new("protocol_fee_mode");
+/// Pool liquidity (sqrt(k) or D) after the last provide or withdraw, only set while accruing
+pub const ROOT_K_LAST: Item<Uint256> = Item::new("root_k_last");
+/// Reserves behind `ROOT_K_LAST`, D of a stable pair is recomputed from them
+/// with the current amp so that an amp ramp does not count as growth
+pub const RESERVES_K_LAST: Item<[Uint128; 2]> = Item::new("reserves_k_last");
+/// LP shares minted to the pair as protocol fee and not collected yet
+pub const PROTOCOL_SHARES: Item<Uint128> = Item::new("protocol_shares");
+
+/// Running totals of the swaps, amounts are indexed like the pool assets
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Default)]
+pub struct SwapStats {
+    /// Amounts offered to and returned by the pair
+    pub volume: [Uint128; 2],
+    pub commission: [Uint128; 2],
+    pub burn: [Uint128; 2],
+    pub fee_wallet: [Uint128; 2],
+    pub swap_count: u64,
+}
+
+pub const SWAP_STATS: Item<SwapStats> = Item::new("swap_stats");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct VolumeBucket {
+    /// Start of the day the bucket covers
+    pub start: u64,
+    pub volume: [Uint128; 2],
+    pub swap_count: u64,
+}
+
+/// Ring buffer of daily volume, slotted by day number
+pub const VOLUME_BUCKETS: Map<u32, VolumeBucket> = Map::new("volume_buckets");
@@ -0,0 +1,84 @@
+use cosmwasm_std::{StdResult, Storage, Uint128};
+
+use crate::state::{VolumeBucket, SWAP_STATS, VOLUME_BUCKETS};
+
+/// Size of the volume ring buffer, in days
+pub const MAX_VOLUME_BUCKETS: u32 = 30;
+pub const VOLUME_BUCKET_SECONDS: u64 = 86_400;
+
+/// Adds a swap to the totals and to the bucket of the current day,
+/// the fees are taken in the asset at `ask_index`
+#[allow(clippy::too_many_arguments)]
+pub fn record_swap(
+    storage: &mut dyn Storage,
+    block_time: u64,
+    volume: [Uint128; 2],
+    ask_index: usize,
+    commission_amount: Uint128,
+    fee_wallet_amount: Uint128,
+    burn_amount: Uint128,
+) -> StdResult<()> {
+    let mut stats = SWAP_STATS.may_load(storage)?.unwrap_or_default();
+    for (total, amount) in stats.volume.iter_mut().zip(volume) {
+        *total = total.checked_add(amount)?;
+    }
+    stats.commission[ask_index] = stats.commission[ask_index].checked_add(commission_amount)?;
+    stats.fee_wallet[ask_index] = stats.fee_wallet[ask_index].checked_add(fee_wallet_amount)?;
+    stats.burn[ask_index] = stats.burn[ask_index].checked_add(burn_amount)?;
+    stats.swap_count += 1;
+    SWAP_STATS.save(storage, &stats)?;
+
+    let start = bucket_start(block_time);
+    let slot = bucket_slot(start);
+    let mut bucket = match VOLUME_BUCKETS.may_load(storage, slot)? {
+        // the slot is reused once the ring wraps around
+        Some(bucket) if bucket.start == start => bucket,
+        _ => empty_bucket(start),
+    };
+    for (total, amount) in bucket.volume.iter_mut().zip(volume) {
+        *total = total.checked_add(amount)?;
+    }
+    bucket.swap_count += 1;
+    VOLUME_BUCKETS.save(storage, slot, &bucket)
+}
+
+/// Daily buckets of the last `buckets` days, newest first, days
+/// without swaps are empty
+pub fn volume_history(
+    storage: &dyn Storage,
+    block_time: u64,
+    buckets: u32,
+) -> StdResult<Vec<VolumeBucket>> {
+    let current = bucket_start(block_time);
+
+    let mut history = vec![];
+    for i in 0..buckets.min(MAX_VOLUME_BUCKETS) {
+        let start = match current.checked_sub(u64::from(i) * VOLUME_BUCKET_SECONDS) {
+            Some(start) => start,
+            None => break,
+        };
+
+        history.push(match VOLUME_BUCKETS.may_load(storage, bucket_slot(start))? {
+            Some(bucket) if bucket.start == start => bucket,
+            _ => empty_bucket(start),
+        });
+    }
+
+    Ok(history)
+}
+
+fn bucket_start(block_time: u64) -> u64 {
+    block_time - block_time % VOLUME_BUCKET_SECONDS
+}
+
+fn bucket_slot(start: u64) -> u32 {
+    ((start / VOLUME_BUCKET_SECONDS) % u64::from(MAX_VOLUME_BUCKETS)) as u32
+}
+
+fn empty_bucket(start: u64) -> VolumeBucket {
+    VolumeBucket {
+        start,
+        volume: [Uint128::zero(), Uint128::zero()],
+        swap_count: 0,
+    }
+}
@@ -1,24 +1,30 @@
 use crate::contract::{
-    assert_deadline, assert_max_spread, assert_minimum_assets, execute, instantiate,
-    query_pair_info, query_pool, query_reverse_simulation, query_simulation
+    assert_deadline, assert_max_spread, assert_minimum_assets, execute, instantiate, reply,
+    query_amp, query_cumulative_prices, query_pair_info, query_pool, query_protocol_fees,
+    query_reverse_simulation, query_simulate_provide, query_simulate_provide_liquidity,
+    query_simulate_withdraw_liquidity, query_simulation, query_stats, query_twap,
+    query_volume_history,
 };
 use crate::error::ContractError;
 use std::str::FromStr;
 
-use choice::mock_querier::mock_dependencies;
-use cosmwasm_std::testing::{mock_env, message_info, MOCK_CONTRACT_ADDR};
+use choice::mock_querier::{mock_dependencies, WasmMockQuerier};
+use cosmwasm_std::testing::{mock_env, message_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, to_json_binary, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal, ReplyOn, Response, StdError, SubMsg, Uint128, WasmMsg, Decimal256
+    attr, coins, to_json_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Decimal, Env, OwnedDeps, Reply, ReplyOn, Response, StdError, SubMsg, SubMsgResponse, SubMsgResult, Uint128, Uint256, WasmMsg, Decimal256
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
-use choice::asset::{Asset, AssetInfo, PairInfo};
+use choice::asset::{Asset, AssetInfo, PairInfo, PairType};
+use choice::factory::FeeSplit;
 use choice::pair::{
-    Cw20HookMsg, ExecuteMsg, InstantiateMsg, PoolResponse, ReverseSimulationResponse,
-    SimulationResponse,
+    AmpResponse, Cw20HookMsg, ExecuteMsg, FlashCallbackMsg, InstantiateMsg, PoolResponse,
+    ProtocolFeeMode,
+    ReverseSimulationResponse, SimulateProvideResponse, SimulationResponse, StatsResponse,
+    TwapResponse,
 };
 use injective_cosmwasm::msg::{create_new_denom_msg, create_set_token_metadata_msg};
 use injective_cosmwasm::{create_burn_tokens_msg, create_mint_tokens_msg};
-use injective_cosmwasm::InjectiveMsgWrapper;
+use injective_cosmwasm::{InjectiveMsgWrapper, InjectiveQueryWrapper};
 use std::convert::TryInto;
 
 #[test]
@@ -38,6 +44,8 @@ fn proper_initialization() {
         asset_decimals: [6u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
     };
 
     // we can just call .unwrap() to assert this was a success
@@ -74,7 +82,7 @@ fn proper_initialization() {
     );
 
     // it worked, let's query the state
-    let pair_info: PairInfo = query_pair_info(deps.as_ref()).unwrap();
+    let pair_info: PairInfo = query_pair_info(deps.as_ref(), mock_env()).unwrap();
 
     // Compute the expected LP denom.
     let expected_lp_denom = format!("factory/{}/{}", env.contract.address, "lp");
@@ -131,6 +139,8 @@ fn provide_liquidity() {
         asset_decimals: [6u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
     };
 
     let env = mock_env();
@@ -212,7 +222,7 @@ fn provide_liquidity() {
         }],
     );
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
-    let liquidity_to_contract_msg = res.messages.get(0).expect("no message");
+    let liquidity_to_contract_msg = res.messages.first().expect("no message");
     let transfer_from_msg = res.messages.get(1).expect("no message");
     let mint_msg = res.messages.get(2).expect("no message");
 
@@ -221,7 +231,7 @@ fn provide_liquidity() {
         deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), // sender
         Coin {
             // amount minted is 1_000 with the LP denom as defined in your state.
-            denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR.to_string(), "lp"),
+            denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR, "lp"),
             amount: Uint128::from(1_000u128),
         },
         MOCK_CONTRACT_ADDR.to_string(), // mint_to
@@ -244,7 +254,7 @@ fn provide_liquidity() {
     let expected_mint_msg = SubMsg::new(create_mint_tokens_msg(
         deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), // sender for minting
         Coin {
-            denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR.to_string(), "lp"),
+            denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR, "lp"),
             amount: Uint128::from(100u128),
         },
         deps.api.addr_make("addr0000").to_string(), // mint_to recipient
@@ -263,7 +273,7 @@ fn provide_liquidity() {
                 amount: Uint128::from(200u128 + 200u128),
             }, 
             Coin {
-                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR.to_string(), "lp"),
+                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR, "lp"),
                 amount: Uint128::from(1_100u128),
             }
         ],
@@ -326,7 +336,7 @@ fn provide_liquidity() {
                 ),
             },
             Coin {
-                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR.to_string(), "lp"),
+                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR, "lp"),
                 amount: Uint128::from(100u128),
             }
         ],
@@ -370,7 +380,7 @@ fn provide_liquidity() {
     );
 
     let res: Response<InjectiveMsgWrapper> = execute(deps.as_mut(), env, info, msg).unwrap();
-    let transfer_from_msg = res.messages.get(0).expect("no message");
+    let transfer_from_msg = res.messages.first().expect("no message");
     let mint_msg = res.messages.get(1).expect("no message");
 
 
@@ -390,7 +400,7 @@ fn provide_liquidity() {
     let expected_mint_msg = SubMsg::new(create_mint_tokens_msg(
         deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), // sender for minting
         Coin {
-            denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR.to_string(), "lp"),
+            denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR, "lp"),
             amount: Uint128::from(50u128),
         },
         deps.api.addr_make("staking0000").to_string(), // mint_to recipient
@@ -444,7 +454,7 @@ fn provide_liquidity() {
                 amount: Uint128::from(100u128 + 98u128 /* user deposit must be pre-applied */),
             },
             Coin {
-                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR.to_string(), "lp"),
+                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR, "lp"),
                 amount: Uint128::from(100u128),
             }
         ],
@@ -489,7 +499,7 @@ fn provide_liquidity() {
     );
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
 
-    let transfer_from_msg = res.messages.get(0).expect("no message");
+    let transfer_from_msg = res.messages.first().expect("no message");
     let mint_msg = res.messages.get(1).expect("no message");
 
     let expected_transfer_msg = SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -508,7 +518,7 @@ fn provide_liquidity() {
     let expected_mint_msg = SubMsg::new(create_mint_tokens_msg(
         deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), // sender for minting
         Coin {
-            denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR.to_string(), "lp"),
+            denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR, "lp"),
             amount: Uint128::from(98u128),
         },
         deps.api.addr_make("addr0001").to_string(), // mint_to recipient
@@ -527,7 +537,7 @@ fn withdraw_liquidity() {
             &deps.api.addr_make("addr0000").to_string(),
             vec![
                 Coin {
-                    denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR.to_string(), "lp"),
+                    denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR, "lp"),
                     amount: Uint128::from(100u128),
                 }
             ],
@@ -565,6 +575,8 @@ fn withdraw_liquidity() {
         asset_decimals: [6u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
     };
 
     let env = mock_env();
@@ -577,16 +589,23 @@ fn withdraw_liquidity() {
         min_assets: None,
         deadline: None,
         amount: Uint128::from(100u128),
+        ask_asset: None,
     };
 
     let env = mock_env();
-    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+            amount: Uint128::from(100u128),
+        }],
+    );
 
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
 
     let log_withdrawn_share = res.attributes.get(2).expect("no log");
     let log_refund_assets = res.attributes.get(3).expect("no log");
-    let msg_refund_0 = res.messages.get(0).expect("no message");
+    let msg_refund_0 = res.messages.first().expect("no message");
     let msg_refund_1 = res.messages.get(1).expect("no message");
     let msg_burn_liquidity = res.messages.get(2).expect("no message");
     
@@ -617,7 +636,7 @@ fn withdraw_liquidity() {
     let expected_burn_msg = SubMsg::new(create_burn_tokens_msg(
         deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), // sender for burning
         Coin {
-            denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR.to_string(), "lp"),
+            denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR, "lp"),
             amount: Uint128::from(100u128),
         },
     ));
@@ -630,7 +649,7 @@ fn withdraw_liquidity() {
     );
     assert_eq!(
         log_refund_assets,
-        &attr("refund_assets", format!("100uusd, 100{}", deps.api.addr_make("asset0000").to_string()))
+        &attr("refund_assets", format!("100uusd, 100{}", deps.api.addr_make("asset0000")))
     );
 
     // withdraw liquidity with assert min_assets
@@ -651,10 +670,17 @@ fn withdraw_liquidity() {
         ]),
         deadline: None,
         amount: Uint128::from(100u128),
+        ask_asset: None,
     };
 
     let env = mock_env();
-    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+            amount: Uint128::from(100u128),
+        }],
+    );
     let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
 
     assert_eq!(
@@ -670,15 +696,280 @@ fn withdraw_liquidity() {
         min_assets: None,
         deadline: Some(100u64),
         amount: Uint128::from(100u128),
+        ask_asset: None,
     };
 
     let env = mock_env();
-    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+            amount: Uint128::from(100u128),
+        }],
+    );
     let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
     assert_eq!(err, ContractError::ExpiredDeadline {})
 }
 
 
+#[test]
+fn withdraw_liquidity_to_single_asset() {
+    let total_share = Uint128::from(24494897427u128);
+    let asset_pool_amount = Uint128::from(20000000000u128);
+    let collateral_pool_amount = Uint128::from(30000000000u128);
+
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount,
+        }],
+    )]);
+
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+    )]);
+
+    deps.querier.with_token_factory_denom_supply(&[(&format!("factory/{}/lp", MOCK_CONTRACT_ADDR), total_share)]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 8u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
+    };
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // the asset leg can not be kept
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        amount: Uint128::from(2449489742u128),
+        min_assets: Some([
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0000").to_string(),
+                },
+                amount: Uint128::one(),
+            },
+        ]),
+        deadline: None,
+        ask_asset: Some(AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        }),
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+            amount: Uint128::from(2449489742u128),
+        }],
+    );
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MinAmountAssertion {
+            min_asset: format!("1{}", deps.api.addr_make("asset0000")),
+            asset: format!("0{}", deps.api.addr_make("asset0000")),
+        }
+    );
+
+    // withdraw 10% of the pool as uusd
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        amount: Uint128::from(2449489742u128),
+        min_assets: None,
+        deadline: None,
+        ask_asset: Some(AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        }),
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+            amount: Uint128::from(2449489742u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // 2999.999999 uusd and 19.99999999 asset are withdrawn, the asset is swapped
+    // against 27000.000001 uusd and 180.00000001 asset
+    // 2699.999998 = 27000.000001 - 27000.000001 * 180.00000001 / (180.00000001 + 19.99999999)
+    let expected_pro_rata = Uint128::from(2999999999u128);
+    let expected_ret_amount = Uint128::from(2699999998u128);
+    let expected_commission_amount =
+        expected_ret_amount.multiply_ratio(3u128, 1000u128) + Uint128::from(1u8); // 0.3%, round up
+    let expected_return = expected_pro_rata + expected_ret_amount - expected_commission_amount;
+
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: deps.api.addr_make("addr0000").to_string(),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: expected_return,
+            }],
+        })),
+    );
+    // burn and fee wallet shares of the commission, then the LP burn
+    assert_eq!(res.messages.len(), 4);
+    assert!(res.attributes.contains(&attr(
+        "refund_assets",
+        format!("{}uusd, 0{}", expected_return, deps.api.addr_make("asset0000")),
+    )));
+    assert!(res.attributes.contains(&attr(
+        "commission_amount",
+        expected_commission_amount.to_string(),
+    )));
+}
+
+#[test]
+fn simulate_provide_and_withdraw_liquidity() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::zero())],
+    )]);
+
+    deps.querier.with_token_factory_denom_supply(&[(
+        &format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+        Uint128::zero(),
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 8u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
+    };
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let assets = |uusd: u128, asset: u128| -> [Asset; 2] {
+        [
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: MockApi::default().addr_make("asset0000").to_string(),
+                },
+                amount: Uint128::from(asset),
+            },
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::from(uusd),
+            },
+        ]
+    };
+
+    // the first provide locks the minimum liquidity
+    let err = query_simulate_provide_liquidity(deps.as_ref(), mock_env(), assets(100, 400))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MinimumLiquidityAmountError {
+            min_lp_token: "1000".to_string(),
+            given_lp: "200".to_string(),
+        }
+    );
+
+    // 3000 = sqrt(2000 * 8000) - 1000
+    let res = query_simulate_provide_liquidity(deps.as_ref(), mock_env(), assets(2000, 8000))
+        .unwrap();
+    assert_eq!(res.share, Uint128::from(3000u128));
+    assert_eq!(res.refund_assets[0].amount, Uint128::zero());
+    assert_eq!(res.refund_assets[1].amount, Uint128::zero());
+
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1_000_000u128),
+        }],
+    )]);
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(2_000_000u128))],
+    )]);
+    deps.querier.with_token_factory_denom_supply(&[(
+        &format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+        Uint128::from(1_000_000u128),
+    )]);
+
+    // the extra asset is refunded
+    let res = query_simulate_provide_liquidity(deps.as_ref(), mock_env(), assets(100, 300))
+        .unwrap();
+    assert_eq!(res.share, Uint128::from(100u128));
+    assert_eq!(
+        res.refund_assets,
+        [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0000").to_string(),
+                },
+                amount: Uint128::from(100u128),
+            },
+        ]
+    );
+
+    let res = query_simulate_withdraw_liquidity(deps.as_ref(), mock_env(), Uint128::from(100u128))
+        .unwrap();
+    assert_eq!(
+        res.assets,
+        [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::from(100u128),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0000").to_string(),
+                },
+                amount: Uint128::from(200u128),
+            },
+        ]
+    );
+}
+
 #[test]
 fn try_native_to_token() {
     let total_share = Uint128::from(30000000000u128);
@@ -697,7 +988,7 @@ fn try_native_to_token() {
                 amount: collateral_pool_amount + offer_amount
             },
             Coin {
-                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR.to_string(), "lp"),
+                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR, "lp"),
                 amount: total_share,
             }
         ],
@@ -725,6 +1016,8 @@ fn try_native_to_token() {
         asset_decimals: [6u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
     };
 
     let env = mock_env();
@@ -753,7 +1046,7 @@ fn try_native_to_token() {
         }],
     );
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
-    let msg_transfer = res.messages.get(0).expect("no message");
+    let msg_transfer = res.messages.first().expect("no message");
 
     // current price is 1.5, so expected return without spread is 1000
     // 952.380952 = 20000 - 20000 * 30000 / (30000 + 1500)
@@ -764,9 +1057,9 @@ fn try_native_to_token() {
     let expected_commission_amount =
         expected_ret_amount.multiply_ratio(3u128, 1000u128) + Uint128::from(1u8); // 0.3%, round up
 
-    let expected_lp_amount = expected_commission_amount.multiply_ratio(2u128, 3u128); // 0.2% (2/3 of the total fee)
     let expected_fee_wallet_amount = expected_commission_amount.multiply_ratio(1u128, 6u128); // 0.05% (1/6 of the total fee)
     let expected_burn_amount = expected_commission_amount.multiply_ratio(1u128, 6u128); // 0.05% (1/6 of the total fee)
+    let expected_lp_amount = expected_commission_amount - expected_fee_wallet_amount - expected_burn_amount; // the rest stays in the pool
     
 
     let expected_return_amount = expected_ret_amount
@@ -783,6 +1076,7 @@ fn try_native_to_token() {
 
     let simulation_res: SimulationResponse = query_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             info: AssetInfo::NativeToken {
                 denom: "uusd".to_string(),
@@ -798,6 +1092,7 @@ fn try_native_to_token() {
     // check reverse simulation res
     let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             info: AssetInfo::Token {
                 contract_addr: deps.api.addr_make("asset0000").to_string(),
@@ -840,6 +1135,9 @@ fn try_native_to_token() {
             attr("burn_amount", expected_burn_amount.to_string()),
             attr("fee_wallet_amount", expected_fee_wallet_amount.to_string()),
             attr("pool_amount", expected_lp_amount.to_string()),
+            attr("lp_share", "0.666666666666666668"),
+            attr("fee_wallet_share", "0.166666666666666666"),
+            attr("burn_share", "0.166666666666666666"),
         ]
     );
 
@@ -875,7 +1173,7 @@ fn try_token_to_native() {
                 amount: collateral_pool_amount
             },
             Coin {
-                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR.to_string(), "lp"),
+                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR, "lp"),
                 amount: total_share,
             }
         ],
@@ -907,6 +1205,8 @@ fn try_token_to_native() {
         asset_decimals: [8u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
     };
 
     let env = mock_env();
@@ -952,7 +1252,7 @@ fn try_token_to_native() {
     let info = message_info(&deps.api.addr_make("asset0000"), &[]);
 
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
-    let msg_transfer = res.messages.get(0).expect("no message");
+    let msg_transfer = res.messages.first().expect("no message");
 
     // current price is 1.5, so expected return without spread is 1000
     // 952.380952 = 20000 - 20000 * 30000 / (30000 + 1500)
@@ -967,9 +1267,9 @@ fn try_token_to_native() {
         .unwrap();
 
 
-    let expected_lp_amount = expected_commission_amount.multiply_ratio(2u128, 3u128); // 0.2% (2/3 of the total fee)
     let expected_fee_wallet_amount = expected_commission_amount.multiply_ratio(1u128, 6u128); // 0.05% (1/6 of the total fee)
     let expected_burn_amount = expected_commission_amount.multiply_ratio(1u128, 6u128); // 0.05% (1/6 of the total fee)
+    let expected_lp_amount = expected_commission_amount - expected_fee_wallet_amount - expected_burn_amount; // the rest stays in the pool
 
     // check simulation res
     // return asset token balance as normal
@@ -982,7 +1282,7 @@ fn try_token_to_native() {
                 amount: collateral_pool_amount
             },
             Coin {
-                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR.to_string(), "lp"),
+                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR, "lp"),
                 amount: total_share,
             }
         ],
@@ -1002,6 +1302,7 @@ fn try_token_to_native() {
 
     let simulation_res: SimulationResponse = query_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             amount: offer_amount,
             info: AssetInfo::Token {
@@ -1017,6 +1318,7 @@ fn try_token_to_native() {
     // check reverse simulation res
     let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
         deps.as_ref(),
+        mock_env(),
         Asset {
             amount: expected_return_amount,
             info: AssetInfo::NativeToken {
@@ -1058,6 +1360,9 @@ fn try_token_to_native() {
             attr("burn_amount", expected_burn_amount.to_string()),
             attr("fee_wallet_amount", expected_fee_wallet_amount.to_string()),
             attr("pool_amount", expected_lp_amount.to_string()),
+            attr("lp_share", "0.666666666666666668"),
+            attr("fee_wallet_share", "0.166666666666666666"),
+            attr("burn_share", "0.166666666666666666"),
         ]
     );
 
@@ -1094,51 +1399,1899 @@ fn try_token_to_native() {
 }
 
 #[test]
-fn test_max_spread() {
-    let offer_asset_info = AssetInfo::NativeToken {
-        denom: "offer_asset".to_string(),
-    };
-    let ask_asset_info = AssetInfo::NativeToken {
-        denom: "ask_asset_info".to_string(),
-    };
-
-    assert_max_spread(
-        Some(Decimal::from_ratio(1200u128, 1u128)),
-        Some(Decimal::percent(1)),
-        Asset {
-            info: offer_asset_info.clone(),
-            amount: Uint128::from(1200000000u128),
-        },
-        Asset {
-            info: ask_asset_info.clone(),
-            amount: Uint128::from(989999u128),
-        },
-        Uint128::zero(),
-        6u8,
-        6u8,
-    )
-    .unwrap_err();
+fn swap_with_custom_fee_split() {
+    let total_share = Uint128::from(30000000000u128);
+    let asset_pool_amount = Uint128::from(20000000000u128);
+    let collateral_pool_amount = Uint128::from(30000000000u128);
+    let offer_amount = Uint128::from(1500000000u128);
 
-    assert_max_spread(
-        Some(Decimal::from_ratio(1200u128, 1u128)),
-        Some(Decimal::percent(1)),
-        Asset {
-            info: offer_asset_info.clone(),
-            amount: Uint128::from(1200000000u128),
-        },
-        Asset {
-            info: ask_asset_info.clone(),
-            amount: Uint128::from(990000u128),
-        },
-        Uint128::zero(),
-        6u8,
-        6u8,
-    )
-    .unwrap();
+    let mut deps = mock_dependencies(&[]);
 
-    assert_max_spread(
-        None,
-        Some(Decimal::percent(1)),
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + offer_amount,
+        }],
+    )]);
+
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+    )]);
+
+    deps.querier.with_token_factory_denom_supply(&[(&format!("factory/{}/lp", MOCK_CONTRACT_ADDR), total_share)]);
+    deps.querier.with_fee_split(FeeSplit {
+        lp: Decimal::percent(50),
+        fee_wallet: Decimal::percent(30),
+        burn: Decimal::percent(20),
+    });
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 8u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
+    };
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // 952.380952 = 20000 - 20000 * 30000 / (30000 + 1500)
+    let expected_ret_amount = Uint128::from(952_380_952u128);
+    let expected_commission_amount =
+        expected_ret_amount.multiply_ratio(3u128, 1000u128) + Uint128::from(1u8); // 0.3%, round up
+    let expected_fee_wallet_amount = expected_commission_amount.multiply_ratio(3u128, 10u128);
+    let expected_burn_amount = expected_commission_amount.multiply_ratio(2u128, 10u128);
+    let expected_lp_amount = expected_commission_amount - expected_fee_wallet_amount - expected_burn_amount;
+
+    let attrs = &res.attributes[res.attributes.len() - 6..];
+    assert_eq!(
+        attrs,
+        vec![
+            attr("burn_amount", expected_burn_amount.to_string()),
+            attr("fee_wallet_amount", expected_fee_wallet_amount.to_string()),
+            attr("pool_amount", expected_lp_amount.to_string()),
+            attr("lp_share", "0.5"),
+            attr("fee_wallet_share", "0.3"),
+            attr("burn_share", "0.2"),
+        ]
+    );
+
+    assert_eq!(
+        res.messages[2],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: deps.api.addr_make("feeaddr0000").to_string(),
+                amount: expected_fee_wallet_amount,
+            })
+            .unwrap(),
+            funds: vec![],
+        })),
+    );
+}
+
+#[test]
+fn accrue_protocol_fee_as_shares() {
+    let total_share = Uint128::from(30000000000u128);
+    let asset_pool_amount = Uint128::from(20000000000u128);
+    let collateral_pool_amount = Uint128::from(30000000000u128);
+    let offer_amount = Uint128::from(1500000000u128);
+
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount,
+        }],
+    )]);
+
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+    )]);
+
+    deps.querier.with_token_factory_denom_supply(&[(&format!("factory/{}/lp", MOCK_CONTRACT_ADDR), total_share)]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 8u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
+    };
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // only the factory can switch the mode
+    let msg = ExecuteMsg::UpdateProtocolFeeMode {
+        mode: ProtocolFeeMode::Accrue {},
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(res, ContractError::Unauthorized {});
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query_protocol_fees(deps.as_ref()).unwrap();
+    assert_eq!(res.mode, ProtocolFeeMode::Accrue {});
+    assert_eq!(res.shares, Uint128::zero());
+
+    // nothing accrued yet
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::CollectProtocolFees {}).unwrap_err();
+    assert_eq!(res, ContractError::InvalidZeroAmount {});
+
+    // swaps keep the whole commission in the pool
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + offer_amount,
+        }],
+    )]);
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert!(res.attributes.contains(&attr("burn_amount", "0")));
+    assert!(res.attributes.contains(&attr("fee_wallet_amount", "0")));
+
+    // the pool grew by 10% on both sides, sqrt(k) by 10%
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(33000000000u128),
+        }],
+    )]);
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(22000000000u128))],
+    )]);
+
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        min_assets: None,
+        deadline: None,
+        amount: Uint128::from(100u128),
+        ask_asset: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+            amount: Uint128::from(100u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // 1/3 of the growth goes to the protocol,
+    // 30000 * (1/3 * 0.1) / (2/3 * 1.1 + 1/3) = 937.5
+    let expected_shares = Uint128::from(937500000u128);
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(create_mint_tokens_msg(
+            Addr::unchecked(MOCK_CONTRACT_ADDR),
+            Coin {
+                denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+                amount: expected_shares,
+            },
+            MOCK_CONTRACT_ADDR.to_string(),
+        )),
+    );
+
+    let res = query_protocol_fees(deps.as_ref()).unwrap();
+    assert_eq!(res.shares, expected_shares);
+
+    // the accrued shares held by the pair can not be withdrawn without sending them
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        min_assets: None,
+        deadline: None,
+        amount: expected_shares,
+        ask_asset: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0001"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::LiquidityNotSent {
+            amount: expected_shares.to_string(),
+        }
+    );
+    let info = message_info(
+        &deps.api.addr_make("addr0001"),
+        &[Coin {
+            denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+            amount: Uint128::from(100u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(
+        res,
+        ContractError::LiquidityNotSent {
+            amount: expected_shares.to_string(),
+        }
+    );
+
+    // reflect the withdrawal, the minted shares are part of the supply now
+    let collateral_pool_amount = Uint128::from(33000000000u128 - 106u128);
+    let asset_pool_amount = Uint128::from(22000000000u128 - 71u128);
+    let total_share = total_share + expected_shares - Uint128::from(100u128);
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount,
+        }],
+    )]);
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+    )]);
+    deps.querier.with_token_factory_denom_supply(&[(
+        &format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+        total_share,
+    )]);
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::CollectProtocolFees {}).unwrap();
+
+    // the default split sends half to the fee wallet and half to the burn address
+    let share_ratio = Decimal::from_ratio(expected_shares, total_share);
+    let expected_collateral = collateral_pool_amount.mul_floor(share_ratio);
+    let expected_asset = asset_pool_amount.mul_floor(share_ratio);
+
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(create_burn_tokens_msg(
+            Addr::unchecked(MOCK_CONTRACT_ADDR),
+            Coin {
+                denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+                amount: expected_shares,
+            },
+        )),
+    );
+    assert_eq!(
+        res.messages[2],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: deps.api.addr_make("feeaddr0000").to_string(),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: expected_collateral.multiply_ratio(1u128, 2u128),
+            }],
+        })),
+    );
+    assert_eq!(
+        res.messages[4],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: deps.api.addr_make("feeaddr0000").to_string(),
+                amount: expected_asset.multiply_ratio(1u128, 2u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })),
+    );
+
+    let res = query_protocol_fees(deps.as_ref()).unwrap();
+    assert_eq!(res.shares, Uint128::zero());
+}
+
+#[test]
+fn provide_single_sided() {
+    let total_share = Uint128::from(24494897427u128);
+    let asset_pool_amount = Uint128::from(20000000000u128);
+    let collateral_pool_amount = Uint128::from(30000000000u128);
+    let offer_amount = Uint128::from(1000000000u128);
+
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount,
+        }],
+    )]);
+
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+    )]);
+
+    deps.querier.with_token_factory_denom_supply(&[(&format!("factory/{}/lp", MOCK_CONTRACT_ADDR), total_share)]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 8u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
+    };
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: offer_amount,
+    };
+
+    let res: SimulateProvideResponse =
+        query_simulate_provide(deps.as_ref(), mock_env(), offer_asset.clone()).unwrap();
+    assert_eq!(res.swap_amount, Uint128::from(496658657u128));
+    assert_eq!(res.return_amount, Uint128::from(324736349u128));
+    assert_eq!(res.commission_amount, Uint128::from(977141u128));
+    assert_eq!(res.share, Uint128::from(404283456u128));
+    // only dust from the protocol fee leaving the pool is refunded
+    assert_eq!(res.refund_assets[0].amount, Uint128::zero());
+    assert_eq!(res.refund_assets[1].amount, Uint128::from(5374u128));
+
+    // the offer is already in the pool balance on execution
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + offer_amount,
+        }],
+    )]);
+
+    let msg = ExecuteMsg::ProvideSingleSided {
+        offer_asset: offer_asset.clone(),
+        min_share: Some(res.share + Uint128::one()),
+        receiver: None,
+        deadline: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MinShareAssertion {
+            min_share: (res.share + Uint128::one()).to_string(),
+            share: res.share.to_string(),
+        }
+    );
+
+    let msg = ExecuteMsg::ProvideSingleSided {
+        offer_asset,
+        min_share: Some(res.share),
+        receiver: None,
+        deadline: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // fee wallet and burn shares of the commission, the refund and the LP mint
+    assert_eq!(res.messages.len(), 4);
+    assert_eq!(
+        res.messages[2],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: deps.api.addr_make("addr0000").to_string(),
+                amount: Uint128::from(5374u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })),
+    );
+    assert_eq!(
+        res.messages[3],
+        SubMsg::new(create_mint_tokens_msg(
+            Addr::unchecked(MOCK_CONTRACT_ADDR),
+            Coin {
+                denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+                amount: Uint128::from(404283456u128),
+            },
+            deps.api.addr_make("addr0000").to_string(),
+        )),
+    );
+    assert!(res.attributes.contains(&attr("swap_amount", "496658657")));
+    assert!(res.attributes.contains(&attr("share", "404283456")));
+
+    // no liquidity to provide against
+    deps.querier.with_token_factory_denom_supply(&[(&format!("factory/{}/lp", MOCK_CONTRACT_ADDR), Uint128::zero())]);
+    let err = query_simulate_provide(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::NoLiquidity {});
+}
+
+#[test]
+fn flash_swap() {
+    let total_share = Uint128::from(30000000000u128);
+    let asset_pool_amount = Uint128::from(20000000000u128);
+    let collateral_pool_amount = Uint128::from(30000000000u128);
+    let ask_amount = Uint128::from(1000000000u128);
+
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount,
+        }],
+    )]);
+
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+    )]);
+
+    deps.querier.with_token_factory_denom_supply(&[(&format!("factory/{}/lp", MOCK_CONTRACT_ADDR), total_share)]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 8u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
+    };
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let ask_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: ask_amount,
+    };
+    let msg = ExecuteMsg::FlashSwap {
+        ask_asset: ask_asset.clone(),
+        contract: deps.api.addr_make("arb0000").to_string(),
+        msg: Binary::default(),
+    };
+    // funds sent along would be taken as repayment
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: ask_amount,
+        }],
+    );
+    let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+    assert_eq!(err, ContractError::FlashSwapFunds {});
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+
+    // 0.3% of the ask amount, round up
+    let expected_commission_amount = Uint128::from(3000000u128);
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: deps.api.addr_make("arb0000").to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: ask_amount,
+                }],
+            })),
+            SubMsg::reply_on_success(
+                WasmMsg::Execute {
+                    contract_addr: deps.api.addr_make("arb0000").to_string(),
+                    msg: to_json_binary(&FlashCallbackMsg::FlashCallback {
+                        sender: deps.api.addr_make("addr0000").to_string(),
+                        ask_asset: ask_asset.clone(),
+                        commission_amount: expected_commission_amount,
+                        msg: Binary::default(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                },
+                1,
+            ),
+        ]
+    );
+
+    // the pair is locked until the reply
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::CollectProtocolFees {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::FlashSwapInProgress {});
+
+    #[allow(deprecated)]
+    let flash_reply = Reply {
+        id: 1,
+        payload: Binary::default(),
+        gas_used: 0,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None, // deprecated, so leave it as None
+            msg_responses: vec![],
+        }),
+    };
+
+    // repaid without the commission
+    let err = reply(deps.as_mut(), mock_env(), flash_reply.clone()).unwrap_err();
+    assert_eq!(err, ContractError::FlashSwapNotRepaid {});
+
+    // the loan was kept and the pool drained below the commission
+    execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    )]);
+    let err = reply(deps.as_mut(), mock_env(), flash_reply.clone()).unwrap_err();
+    assert_eq!(err, ContractError::FlashSwapBalanceBelowLoan {});
+
+    // a failed reply reverts the whole transaction, start over
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount,
+        }],
+    )]);
+    execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + expected_commission_amount,
+        }],
+    )]);
+
+    let res = reply(deps.as_mut(), mock_env(), flash_reply).unwrap();
+
+    // the default split sends a sixth to the fee wallet and a sixth to the burn address
+    assert_eq!(res.messages.len(), 2);
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: deps.api.addr_make("feeaddr0000").to_string(),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(500000u128),
+            }],
+        })),
+    );
+    assert!(res.attributes.contains(&attr("burn_amount", "500000")));
+    assert!(res.attributes.contains(&attr("pool_amount", "2000000")));
+
+    // unlocked again
+    let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::CollectProtocolFees {})
+        .unwrap_err();
+    assert_eq!(err, ContractError::InvalidZeroAmount {});
+}
+
+#[test]
+fn pause() {
+    let total_share = Uint128::from(30000000000u128);
+    let asset_pool_amount = Uint128::from(20000000000u128);
+    let collateral_pool_amount = Uint128::from(30000000000u128);
+
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount,
+        }],
+    )]);
+
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+    )]);
+
+    deps.querier.with_token_factory_denom_supply(&[(&format!("factory/{}/lp", MOCK_CONTRACT_ADDR), total_share)]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 8u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
+    };
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // only the factory can pause
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Pause {});
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("Must return unauthorized error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Pause {}).unwrap();
+    assert_eq!(res.attributes, vec![attr("action", "pause")]);
+
+    let pair_info: PairInfo = query_pair_info(deps.as_ref(), mock_env()).unwrap();
+    assert!(pair_info.paused);
+
+    // swaps and provides are halted
+    let offer_amount = Uint128::from(1500000000u128);
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::Paused {}) => (),
+        _ => panic!("Must return paused error"),
+    }
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "uusd".to_string(),
+                },
+                amount: Uint128::zero(),
+            },
+            Asset {
+                info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0000").to_string(),
+                },
+                amount: Uint128::from(100u128),
+            },
+        ],
+        receiver: None,
+        deadline: None,
+        slippage_tolerance: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::Paused {}) => (),
+        _ => panic!("Must return paused error"),
+    }
+
+    // withdrawals still work
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        amount: Uint128::from(100u128),
+        min_assets: None,
+        deadline: None,
+        ask_asset: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+            amount: Uint128::from(100u128),
+        }],
+    );
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Unpause {}).unwrap();
+    assert_eq!(res.attributes, vec![attr("action", "unpause")]);
+
+    let pair_info: PairInfo = query_pair_info(deps.as_ref(), mock_env()).unwrap();
+    assert!(!pair_info.paused);
+
+    // the global pause of the factory halts the pair as well
+    deps.querier.with_factory_paused(true);
+    let pair_info: PairInfo = query_pair_info(deps.as_ref(), mock_env()).unwrap();
+    assert!(pair_info.paused);
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::Paused {}) => (),
+        _ => panic!("Must return paused error"),
+    }
+}
+
+#[test]
+fn provide_liquidity_cw20_hook() {
+    let total_share = Uint128::from(100000000000u128);
+    let pool_amount = Uint128::from(100000000000u128);
+    let deposit_amount = Uint128::from(1000000000u128);
+
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount,
+        }],
+    )]);
+
+    // the cw20 leg is already sent when the hook runs
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &(pool_amount + deposit_amount))],
+    )]);
+
+    deps.querier.with_token_factory_denom_supply(&[(&format!("factory/{}/lp", MOCK_CONTRACT_ADDR), total_share)]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 8u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
+    };
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let assets = [
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: deposit_amount,
+        },
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+            amount: deposit_amount,
+        },
+    ];
+    let hook_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: deposit_amount,
+        msg: to_json_binary(&Cw20HookMsg::ProvideLiquidity {
+            assets: assets.clone(),
+            receiver: None,
+            deadline: None,
+            slippage_tolerance: None,
+        })
+        .unwrap(),
+    });
+
+    // the sent amount must match its leg
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: Uint128::from(1u128),
+        msg: to_json_binary(&Cw20HookMsg::ProvideLiquidity {
+            assets: assets.clone(),
+            receiver: None,
+            deadline: None,
+            slippage_tolerance: None,
+        })
+        .unwrap(),
+    });
+    let info = message_info(&deps.api.addr_make("asset0000"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        ContractError::AssetMismatch {} => (),
+        _ => panic!("Must return asset mismatch error"),
+    }
+
+    let info = message_info(&deps.api.addr_make("asset0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, hook_msg.clone()).unwrap();
+    assert_eq!(res.messages.len(), 0);
+    assert_eq!(res.attributes[0], attr("action", "deposit_liquidity"));
+
+    // nothing else runs before the native leg
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: deposit_amount,
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0001"), &coins(deposit_amount.u128(), "uusd"));
+    match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        ContractError::DepositPending {} => (),
+        _ => panic!("Must return deposit pending error"),
+    }
+
+    // the native leg is attached to the follow-up
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: pool_amount + deposit_amount,
+        }],
+    )]);
+    let provide_msg = ExecuteMsg::ProvideLiquidity {
+        assets: assets.clone(),
+        receiver: None,
+        deadline: None,
+        slippage_tolerance: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &coins(deposit_amount.u128(), "uusd"));
+    let res = execute(deps.as_mut(), mock_env(), info, provide_msg.clone()).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(create_mint_tokens_msg(
+            deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(),
+            Coin {
+                denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+                amount: Uint128::from(1000000000u128),
+            },
+            deps.api.addr_make("addr0000").to_string(),
+        ))]
+    );
+
+    // a deposit not followed in its transaction is refunded by the next execute
+    let info = message_info(&deps.api.addr_make("asset0000"), &[]);
+    execute(deps.as_mut(), mock_env(), info, hook_msg).unwrap();
+
+    // it is not part of the pool meanwhile
+    let mut env = mock_env();
+    env.block.height += 1;
+    let res = query_pool(deps.as_ref(), env.clone()).unwrap();
+    assert_eq!(res.assets[1].amount, pool_amount);
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &coins(deposit_amount.u128(), "uusd"));
+    let res = execute(deps.as_mut(), env, info, provide_msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: deps.api.addr_make("addr0000").to_string(),
+                    amount: deposit_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            // the provide ignores the refunded deposit and pulls the token leg
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: deps.api.addr_make("addr0000").to_string(),
+                    recipient: MOCK_CONTRACT_ADDR.to_string(),
+                    amount: deposit_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(create_mint_tokens_msg(
+                deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(),
+                Coin {
+                    denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+                    amount: Uint128::from(1000000000u128),
+                },
+                deps.api.addr_make("addr0000").to_string(),
+            )),
+        ]
+    );
+    let refund = format!("{}{}", deposit_amount, deps.api.addr_make("asset0000"));
+    assert!(res.attributes.contains(&attr("refund_deposit", refund)));
+
+    // single sided provide of the sent token
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: deposit_amount,
+        msg: to_json_binary(&Cw20HookMsg::ProvideSingleSided {
+            min_share: None,
+            receiver: None,
+            deadline: None,
+        })
+        .unwrap(),
+    });
+    let info = message_info(&deps.api.addr_make("asset0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(res.attributes[0], attr("action", "provide_single_sided"));
+    assert!(res.messages.iter().all(|msg| !matches!(
+        &msg.msg,
+        CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. })
+            if contract_addr == &deps.api.addr_make("asset0000").to_string()
+    )));
+}
+
+#[test]
+fn simulation_with_custom_commission_rate() {
+    let total_share = Uint128::from(30000000000u128);
+    let asset_pool_amount = Uint128::from(20000000000u128);
+    let collateral_pool_amount = Uint128::from(30000000000u128);
+    let offer_amount = Uint128::from(1500000000u128);
+
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount,
+        }],
+    )]);
+
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &asset_pool_amount)],
+    )]);
+
+    deps.querier.with_token_factory_denom_supply(&[(&format!("factory/{}/lp", MOCK_CONTRACT_ADDR), total_share)]);
+
+    let mut msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 8u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::one(),
+        pair_type: PairType::Xyk {},
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    match instantiate(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+        StdError::GenericErr { msg, .. } => {
+            assert_eq!(msg, "commission rate must be less than 1")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    // 0.05% stable tier
+    msg.commission_rate = Decimal::bps(5);
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let pair_info: PairInfo = query_pair_info(deps.as_ref(), mock_env()).unwrap();
+    assert_eq!(pair_info.commission_rate, Decimal::bps(5));
+
+    // 952.380952 = 20000 - 20000 * 30000 / (30000 + 1500)
+    let expected_ret_amount = Uint128::from(952_380_952u128);
+    let expected_commission_amount =
+        expected_ret_amount.multiply_ratio(5u128, 10000u128) + Uint128::from(1u8); // 0.05%, round up
+    let expected_return_amount = expected_ret_amount
+        .checked_sub(expected_commission_amount)
+        .unwrap();
+
+    let simulation_res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            amount: offer_amount,
+        },
+    )
+    .unwrap();
+    assert_eq!(expected_return_amount, simulation_res.return_amount);
+    assert_eq!(expected_commission_amount, simulation_res.commission_amount);
+
+    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+            amount: expected_return_amount,
+        },
+    )
+    .unwrap();
+    assert!(
+        (offer_amount.u128() as i128 - reverse_simulation_res.offer_amount.u128() as i128).abs()
+            < 3i128
+    );
+    assert!(
+        (expected_commission_amount.u128() as i128
+            - reverse_simulation_res.commission_amount.u128() as i128)
+            .abs()
+            < 3i128
+    );
+}
+
+fn stable_pair_deps(
+    pool_amounts: [Uint128; 2],
+    asset_decimals: [u8; 2],
+    amp: u64,
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper> {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![
+            Coin {
+                denom: "uusdt".to_string(),
+                amount: pool_amounts[0],
+            },
+            Coin {
+                denom: "uusdc".to_string(),
+                amount: pool_amounts[1],
+            },
+        ],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusdt".to_string(),
+            },
+            AssetInfo::NativeToken {
+                denom: "uusdc".to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals,
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::bps(5),
+        pair_type: PairType::Stable { amp },
+    };
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    deps
+}
+
+#[test]
+fn stable_pair_simulation() {
+    let pool_amount = Uint128::from(1_000_000_000_000u128);
+    let offer_amount = Uint128::from(10_000_000_000u128);
+    let deps = stable_pair_deps([pool_amount, pool_amount], [6u8, 6u8], 100);
+
+    let pair_info: PairInfo = query_pair_info(deps.as_ref(), mock_env()).unwrap();
+    assert_eq!(pair_info.pair_type, PairType::Stable { amp: 100 });
+
+    let simulation_res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusdt".to_string(),
+            },
+            amount: offer_amount,
+        },
+    )
+    .unwrap();
+
+    // 1% of a balanced pool trades close to the peg,
+    // the same trade on a constant product pool would lose ~1%
+    let before_commission = simulation_res.return_amount + simulation_res.commission_amount;
+    assert!(before_commission > offer_amount.multiply_ratio(9999u128, 10000u128));
+    assert!(before_commission < offer_amount);
+    assert_eq!(offer_amount - before_commission, simulation_res.spread_amount);
+    assert_eq!(
+        simulation_res.commission_amount,
+        before_commission.mul_ceil(Decimal::bps(5))
+    );
+
+    // reverse simulation asks for at least what the forward one offered
+    let reverse_simulation_res: ReverseSimulationResponse = query_reverse_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusdc".to_string(),
+            },
+            amount: simulation_res.return_amount,
+        },
+    )
+    .unwrap();
+    assert!(reverse_simulation_res.offer_amount <= offer_amount);
+    assert!(offer_amount - reverse_simulation_res.offer_amount < Uint128::from(3u8));
+}
+
+#[test]
+fn stable_pair_simulation_with_diff_decimal() {
+    // 1,000,000 of each side, uusdc has 8 decimals
+    let deps = stable_pair_deps(
+        [
+            Uint128::from(1_000_000_000_000u128),
+            Uint128::from(100_000_000_000_000u128),
+        ],
+        [6u8, 8u8],
+        100,
+    );
+
+    let simulation_res: SimulationResponse = query_simulation(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusdt".to_string(),
+            },
+            amount: Uint128::from(1_000_000u128),
+        },
+    )
+    .unwrap();
+
+    // 1 uusdt buys almost 1 uusdc
+    let before_commission = simulation_res.return_amount + simulation_res.commission_amount;
+    assert!(before_commission > Uint128::from(99_990_000u128));
+    assert!(before_commission < Uint128::from(100_000_000u128));
+}
+
+#[test]
+fn stable_pair_amp_ramp() {
+    let pool_amount = Uint128::from(1_000_000_000_000u128);
+    let mut deps = stable_pair_deps([pool_amount, pool_amount], [6u8, 6u8], 100);
+
+    // the amp was set when the pair was created
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::RampAmp {
+            future_amp: 200,
+            future_time: mock_env().block.time.seconds() + 86_400,
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidAmpRamp {
+            reason: "amp can change only once every 86400 seconds".to_string()
+        }
+    );
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(86_400);
+    let start = env.block.time.seconds();
+
+    // only the factory can ramp
+    let msg = ExecuteMsg::RampAmp {
+        future_amp: 200,
+        future_time: start + 86_400,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    assert_eq!(
+        execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err(),
+        ContractError::Unauthorized {}
+    );
+
+    // too fast
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::RampAmp {
+            future_amp: 200,
+            future_time: start + 3_600,
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidAmpRamp {
+            reason: "ramp must last at least 86400 seconds".to_string()
+        }
+    );
+
+    // too far
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::RampAmp {
+            future_amp: 1001,
+            future_time: start + 86_400,
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidAmpRamp {
+            reason: "amp can change at most 10x per ramp".to_string()
+        }
+    );
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let mut halfway = env.clone();
+    halfway.block.time = halfway.block.time.plus_seconds(43_200);
+    assert_eq!(
+        query_amp(deps.as_ref(), halfway.clone()).unwrap(),
+        AmpResponse {
+            amp: 150,
+            future_amp: 200,
+            future_time: start + 86_400,
+        }
+    );
+
+    let mut after = env.clone();
+    after.block.time = after.block.time.plus_seconds(100_000);
+    assert_eq!(query_amp(deps.as_ref(), after).unwrap().amp, 200);
+
+    // freeze halfway
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    execute(deps.as_mut(), halfway.clone(), info, ExecuteMsg::StopRampAmp {}).unwrap();
+
+    let mut after = env;
+    after.block.time = after.block.time.plus_seconds(100_000);
+    assert_eq!(
+        query_amp(deps.as_ref(), after.clone()).unwrap(),
+        AmpResponse {
+            amp: 150,
+            future_amp: 150,
+            future_time: halfway.block.time.seconds(),
+        }
+    );
+
+    // the pair info reports the current amp
+    let pair_info: PairInfo = query_pair_info(deps.as_ref(), after.clone()).unwrap();
+    assert_eq!(pair_info.pair_type, PairType::Stable { amp: 150 });
+
+    // stopping counts as a change
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    let res = execute(
+        deps.as_mut(),
+        after.clone(),
+        info,
+        ExecuteMsg::RampAmp {
+            future_amp: 1000,
+            future_time: after.block.time.seconds() + 86_400,
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::InvalidAmpRamp {
+            reason: "amp can change only once every 86400 seconds".to_string()
+        }
+    );
+}
+
+#[test]
+fn amp_ramp_does_not_accrue_protocol_fee() {
+    // D of an unbalanced pool grows with the amp
+    let mut deps = stable_pair_deps(
+        [Uint128::from(1_000_000_000u128), Uint128::from(3_000_000_000u128)],
+        [6u8, 6u8],
+        100,
+    );
+    let lp_denom = format!("factory/{}/lp", MOCK_CONTRACT_ADDR);
+    deps.querier
+        .with_token_factory_denom_supply(&[(&lp_denom, Uint128::from(4_000_000_000u128))]);
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    let msg = ExecuteMsg::UpdateProtocolFeeMode {
+        mode: ProtocolFeeMode::Accrue {},
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(86_400);
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    let msg = ExecuteMsg::RampAmp {
+        future_amp: 1000,
+        future_time: env.block.time.seconds() + 86_400,
+    };
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // no swap happened during the ramp, nothing is minted to the protocol
+    env.block.time = env.block.time.plus_seconds(86_400);
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        amount: Uint128::from(100u128),
+        min_assets: None,
+        deadline: None,
+        ask_asset: None,
+    };
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[Coin {
+            denom: lp_denom.clone(),
+            amount: Uint128::from(100u128),
+        }],
+    );
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    // two refunds and the burn, no protocol mint
+    assert_eq!(res.messages.len(), 3);
+    assert_eq!(query_protocol_fees(deps.as_ref()).unwrap().shares, Uint128::zero());
+}
+
+#[test]
+fn stable_pair_provide_single_sided() {
+    let pool_amount = Uint128::from(1_000_000_000_000u128);
+    let mut deps = stable_pair_deps([pool_amount, pool_amount * Uint128::from(2u8)], [6u8, 6u8], 100);
+    deps.querier.with_token_factory_denom_supply(&[(
+        &format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
+        Uint128::from(3_000_000_000_000u128),
+    )]);
+
+    let res: SimulateProvideResponse = query_simulate_provide(
+        deps.as_ref(),
+        mock_env(),
+        Asset {
+            info: AssetInfo::NativeToken {
+                denom: "uusdt".to_string(),
+            },
+            amount: Uint128::from(100_000_000_000u128),
+        },
+    )
+    .unwrap();
+
+    // the pool holds more uusdc, so uusdt buys it above par
+    assert_eq!(res.swap_amount, Uint128::from(64440447121u128));
+    assert_eq!(res.return_amount, Uint128::from(64653732505u128));
+    assert_eq!(res.share, Uint128::from(100220410569u128));
+    assert_eq!(res.refund_assets[0].amount, Uint128::zero());
+    assert_eq!(res.refund_assets[1].amount, Uint128::from(360156u128));
+}
+
+#[test]
+fn xyk_pair_has_no_amp() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::NativeToken {
+                denom: "inj".to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 18u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
+    };
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    assert_eq!(
+        query_amp(deps.as_ref(), mock_env()).unwrap_err(),
+        ContractError::NotStablePair {}
+    );
+
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::StopRampAmp {});
+    assert_eq!(res.unwrap_err(), ContractError::NotStablePair {});
+}
+
+type PairDeps = OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper>;
+
+fn instantiate_twap_pair(deps: &mut PairDeps) {
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::NativeToken {
+                denom: "inj".to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 6u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        // without a commission the swaps land on round reserves
+        commission_rate: Decimal::zero(),
+        pair_type: PairType::Xyk {},
+    };
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+}
+
+fn set_twap_reserves(deps: &mut PairDeps, uusd: u128, inj: u128) {
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(uusd),
+            },
+            Coin {
+                denom: "inj".to_string(),
+                amount: Uint128::from(inj),
+            },
+        ],
+    )]);
+}
+
+/// Swaps `amount` uusd, the balance must already include it
+fn twap_swap(deps: &mut PairDeps, env: Env, amount: u128) {
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: Uint128::from(amount),
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::from(amount),
+    }]);
+    let msg = ExecuteMsg::Swap {
+        offer_asset,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    execute(deps.as_mut(), env, info, msg).unwrap();
+}
+
+#[test]
+fn cumulative_prices_and_twap() {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_twap_pair(&mut deps);
+
+    // first swap starts the history and leaves 1 uusd = 2 inj
+    let mut env = mock_env();
+    let start = env.block.time.seconds();
+    set_twap_reserves(&mut deps, 500_000 + 500_000, 4_000_000);
+    twap_swap(&mut deps, env.clone(), 500_000);
+    set_twap_reserves(&mut deps, 1_000_000, 2_000_000);
+    env.block.time = env.block.time.plus_seconds(100);
+
+    let res = query_cumulative_prices(deps.as_ref(), env.clone()).unwrap();
+    assert_eq!(res.price0_cumulative, Uint256::from(200_000_000_000_000_000_000u128));
+    assert_eq!(res.price1_cumulative, Uint256::from(50_000_000_000_000_000_000u128));
+    assert_eq!(res.block_time, start + 100);
+
+    let twap = query_twap(
+        deps.as_ref(),
+        env.clone(),
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        100,
+    )
+    .unwrap();
+    assert_eq!(
+        twap,
+        TwapResponse {
+            price: Decimal::from_ratio(2u128, 1u128),
+            window_seconds: 100,
+        }
+    );
+
+    match query_twap(
+        deps.as_ref(),
+        env.clone(),
+        AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        101,
+    )
+    .unwrap_err()
+    {
+        ContractError::Std(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "not enough price history for the window")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    // the price flips for the next 100 seconds
+    set_twap_reserves(&mut deps, 1_000_000 + 1_000_000, 2_000_000);
+    twap_swap(&mut deps, env.clone(), 1_000_000);
+    set_twap_reserves(&mut deps, 2_000_000, 1_000_000);
+    env.block.time = env.block.time.plus_seconds(100);
+
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let twap = query_twap(deps.as_ref(), env.clone(), uusd.clone(), 200).unwrap();
+    assert_eq!(twap.price, Decimal::from_ratio(125u128, 100u128));
+
+    let twap = query_twap(deps.as_ref(), env.clone(), uusd, 100).unwrap();
+    assert_eq!(twap.price, Decimal::from_ratio(1u128, 2u128));
+
+    let twap = query_twap(
+        deps.as_ref(),
+        env,
+        AssetInfo::NativeToken {
+            denom: "inj".to_string(),
+        },
+        100,
+    )
+    .unwrap();
+    assert_eq!(twap.price, Decimal::from_ratio(2u128, 1u128));
+}
+
+#[test]
+fn twap_ignores_donations() {
+    let mut deps = mock_dependencies(&[]);
+    instantiate_twap_pair(&mut deps);
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+
+    let mut env = mock_env();
+    set_twap_reserves(&mut deps, 500_000 + 500_000, 4_000_000);
+    twap_swap(&mut deps, env.clone(), 500_000);
+
+    // a donation right after the swap quadruples the uusd balance
+    set_twap_reserves(&mut deps, 4_000_000, 2_000_000);
+    env.block.time = env.block.time.plus_seconds(100);
+
+    let res = query_cumulative_prices(deps.as_ref(), env.clone()).unwrap();
+    assert_eq!(res.price0_cumulative, Uint256::from(200_000_000_000_000_000_000u128));
+    let twap = query_twap(deps.as_ref(), env.clone(), uusd.clone(), 100).unwrap();
+    assert_eq!(twap.price, Decimal::from_ratio(2u128, 1u128));
+
+    // the next swap picks the donation up, but only from then on
+    set_twap_reserves(&mut deps, 4_000_000 + 1_000, 2_000_000);
+    twap_swap(&mut deps, env.clone(), 1_000);
+    let twap = query_twap(deps.as_ref(), env, uusd, 100).unwrap();
+    assert_eq!(twap.price, Decimal::from_ratio(2u128, 1u128));
+}
+
+#[test]
+fn stable_pair_twap() {
+    // 100:200 stable pool, the swap leaves it about as imbalanced
+    let offer_amount = Uint128::from(1_000u128);
+    let mut deps = stable_pair_deps(
+        [
+            Uint128::from(100_000_000u128) + offer_amount,
+            Uint128::from(200_000_000u128),
+        ],
+        [6u8, 6u8],
+        100,
+    );
+    let uusdt = AssetInfo::NativeToken {
+        denom: "uusdt".to_string(),
+    };
+    let uusdc = AssetInfo::NativeToken {
+        denom: "uusdc".to_string(),
+    };
+
+    let mut env = mock_env();
+    let info = message_info(&deps.api.addr_make("addr0000"), &[Coin {
+        denom: "uusdt".to_string(),
+        amount: offer_amount,
+    }]);
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: uusdt.clone(),
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    env.block.time = env.block.time.plus_seconds(100);
+
+    // the pool trades near the peg, not at the 1:2 reserve ratio
+    let twap = query_twap(deps.as_ref(), env.clone(), uusdt, 100).unwrap();
+    assert!(twap.price > Decimal::one());
+    assert!(twap.price < Decimal::permille(1010));
+    let twap = query_twap(deps.as_ref(), env, uusdc, 100).unwrap();
+    assert!(twap.price < Decimal::one());
+    assert!(twap.price > Decimal::permille(990));
+}
+
+#[test]
+fn swap_stats_and_volume_history() {
+    let offer_amount = Uint128::from(1_000u128);
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1_000_000u128) + offer_amount,
+            },
+            Coin {
+                denom: "inj".to_string(),
+                amount: Uint128::from(1_000_000u128),
+            },
+        ],
+    )]);
+
+    let msg = InstantiateMsg {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "uusd".to_string(),
+            },
+            AssetInfo::NativeToken {
+                denom: "inj".to_string(),
+            },
+        ],
+        token_code_id: 10u64,
+        asset_decimals: [6u8, 6u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
+    };
+    let info = message_info(&deps.api.addr_make("factory0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let inj = AssetInfo::NativeToken {
+        denom: "inj".to_string(),
+    };
+    let amounts = |uusd_amount: u128, inj_amount: u128| {
+        [
+            Asset {
+                info: uusd.clone(),
+                amount: Uint128::from(uusd_amount),
+            },
+            Asset {
+                info: inj.clone(),
+                amount: Uint128::from(inj_amount),
+            },
+        ]
+    };
+
+    let stats = query_stats(deps.as_ref()).unwrap();
+    assert_eq!(
+        stats,
+        StatsResponse {
+            volume: amounts(0, 0),
+            commission: amounts(0, 0),
+            burn: amounts(0, 0),
+            fee_wallet: amounts(0, 0),
+            swap_count: 0,
+        }
+    );
+
+    let swap_msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: uusd.clone(),
+            amount: offer_amount,
+        },
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        deadline: None,
+    };
+    let swap_info = message_info(&deps.api.addr_make("addr0000"), &[Coin {
+        denom: "uusd".to_string(),
+        amount: offer_amount,
+    }]);
+
+    let mut env = mock_env();
+    let res = execute(deps.as_mut(), env.clone(), swap_info.clone(), swap_msg.clone()).unwrap();
+    let attribute = |name: &str| -> u128 {
+        res.attributes
+            .iter()
+            .find(|attr| attr.key == name)
+            .unwrap()
+            .value
+            .parse()
+            .unwrap()
+    };
+    let return_amount = attribute("return_amount");
+    let commission_amount = attribute("commission_amount");
+    let burn_amount = attribute("burn_amount");
+    let fee_wallet_amount = attribute("fee_wallet_amount");
+
+    // the second swap lands in the next day
+    env.block.time = env.block.time.plus_seconds(86_400);
+    execute(deps.as_mut(), env.clone(), swap_info, swap_msg).unwrap();
+
+    let stats = query_stats(deps.as_ref()).unwrap();
+    assert_eq!(
+        stats,
+        StatsResponse {
+            volume: amounts(2 * offer_amount.u128(), 2 * return_amount),
+            commission: amounts(0, 2 * commission_amount),
+            burn: amounts(0, 2 * burn_amount),
+            fee_wallet: amounts(0, 2 * fee_wallet_amount),
+            swap_count: 2,
+        }
+    );
+
+    let history = query_volume_history(deps.as_ref(), env.clone(), 3).unwrap();
+    let day = env.block.time.seconds() - env.block.time.seconds() % 86_400;
+    assert_eq!(history.buckets.len(), 3);
+    assert_eq!(history.buckets[0].start, day);
+    assert_eq!(history.buckets[0].volume, amounts(offer_amount.u128(), return_amount));
+    assert_eq!(history.buckets[0].swap_count, 1);
+    assert_eq!(history.buckets[1].start, day - 86_400);
+    assert_eq!(history.buckets[1].swap_count, 1);
+    assert_eq!(history.buckets[2].volume, amounts(0, 0));
+    assert_eq!(history.buckets[2].swap_count, 0);
+
+    // the ring only keeps 30 days, older slots are reused
+    env.block.time = env.block.time.plus_seconds(30 * 86_400);
+    let history = query_volume_history(deps.as_ref(), env.clone(), 100).unwrap();
+    assert_eq!(history.buckets.len(), 30);
+    assert!(history.buckets.iter().all(|bucket| bucket.swap_count == 0));
+
+    match query_volume_history(deps.as_ref(), env, 0).unwrap_err() {
+        ContractError::InvalidZeroAmount {} => {}
+        _ => panic!("Must return invalid zero amount error"),
+    }
+}
+
+#[test]
+fn test_max_spread() {
+    let offer_asset_info = AssetInfo::NativeToken {
+        denom: "offer_asset".to_string(),
+    };
+    let ask_asset_info = AssetInfo::NativeToken {
+        denom: "ask_asset_info".to_string(),
+    };
+
+    assert_max_spread(
+        Some(Decimal::from_ratio(1200u128, 1u128)),
+        Some(Decimal::percent(1)),
+        Asset {
+            info: offer_asset_info.clone(),
+            amount: Uint128::from(1200000000u128),
+        },
+        Asset {
+            info: ask_asset_info.clone(),
+            amount: Uint128::from(989999u128),
+        },
+        Uint128::zero(),
+        6u8,
+        6u8,
+        Decimal::zero(),
+    )
+    .unwrap_err();
+
+    assert_max_spread(
+        Some(Decimal::from_ratio(1200u128, 1u128)),
+        Some(Decimal::percent(1)),
+        Asset {
+            info: offer_asset_info.clone(),
+            amount: Uint128::from(1200000000u128),
+        },
+        Asset {
+            info: ask_asset_info.clone(),
+            amount: Uint128::from(990000u128),
+        },
+        Uint128::zero(),
+        6u8,
+        6u8,
+        Decimal::zero(),
+    )
+    .unwrap();
+
+    assert_max_spread(
+        None,
+        Some(Decimal::percent(1)),
         Asset {
             info: offer_asset_info.clone(),
             amount: Uint128::zero(),
@@ -1150,6 +3303,7 @@ fn test_max_spread() {
         Uint128::from(10001u128),
         6u8,
         6u8,
+        Decimal::zero(),
     )
     .unwrap_err();
 
@@ -1167,8 +3321,75 @@ fn test_max_spread() {
         Uint128::from(10000u128),
         6u8,
         6u8,
+        Decimal::zero(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_max_spread_with_commission() {
+    let offer_asset_info = AssetInfo::NativeToken {
+        denom: "offer_asset".to_string(),
+    };
+    let ask_asset_info = AssetInfo::NativeToken {
+        denom: "ask_asset_info".to_string(),
+    };
+
+    // 1_000_000 at the belief price, 997_000 net of the 0.3% commission
+    assert_max_spread(
+        Some(Decimal::from_ratio(1200u128, 1u128)),
+        Some(Decimal::percent(1)),
+        Asset {
+            info: offer_asset_info.clone(),
+            amount: Uint128::from(1200000000u128),
+        },
+        Asset {
+            info: ask_asset_info.clone(),
+            amount: Uint128::from(987030u128),
+        },
+        Uint128::zero(),
+        6u8,
+        6u8,
+        Decimal::permille(3),
     )
     .unwrap();
+
+    assert_max_spread(
+        Some(Decimal::from_ratio(1200u128, 1u128)),
+        Some(Decimal::percent(1)),
+        Asset {
+            info: offer_asset_info.clone(),
+            amount: Uint128::from(1200000000u128),
+        },
+        Asset {
+            info: ask_asset_info.clone(),
+            amount: Uint128::from(987029u128),
+        },
+        Uint128::zero(),
+        6u8,
+        6u8,
+        Decimal::permille(3),
+    )
+    .unwrap_err();
+
+    // without the commission the same return is over the max spread
+    assert_max_spread(
+        Some(Decimal::from_ratio(1200u128, 1u128)),
+        Some(Decimal::percent(1)),
+        Asset {
+            info: offer_asset_info,
+            amount: Uint128::from(1200000000u128),
+        },
+        Asset {
+            info: ask_asset_info,
+            amount: Uint128::from(987030u128),
+        },
+        Uint128::zero(),
+        6u8,
+        6u8,
+        Decimal::zero(),
+    )
+    .unwrap_err();
 }
 
 #[test]
@@ -1204,6 +3425,7 @@ fn test_max_spread_with_diff_decimal() {
         Uint128::zero(),
         6u8,
         8u8,
+        Decimal::zero(),
     )
     .unwrap();
 
@@ -1221,6 +3443,7 @@ fn test_max_spread_with_diff_decimal() {
         Uint128::zero(),
         6u8,
         8u8,
+        Decimal::zero(),
     )
     .unwrap_err();
 
@@ -1245,6 +3468,7 @@ fn test_max_spread_with_diff_decimal() {
         Uint128::zero(),
         8u8,
         6u8,
+        Decimal::zero(),
     )
     .unwrap();
 
@@ -1262,6 +3486,7 @@ fn test_max_spread_with_diff_decimal() {
         Uint128::zero(),
         8u8,
         6u8,
+        Decimal::zero(),
     )
     .unwrap_err();
 }
@@ -1282,7 +3507,7 @@ fn test_query_pool() {
                 amount: asset_0_amount
             },
             Coin {
-                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR.to_string(), "lp"),
+                denom: format!("factory/{}/{}", MOCK_CONTRACT_ADDR, "lp"),
                 amount: total_share_amount,
             }
         ],
@@ -1313,6 +3538,8 @@ fn test_query_pool() {
         asset_decimals: [6u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
     };
 
     let env = mock_env();
@@ -1320,7 +3547,7 @@ fn test_query_pool() {
     // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
 
-    let res: PoolResponse = query_pool(deps.as_ref()).unwrap();
+    let res: PoolResponse = query_pool(deps.as_ref(), mock_env()).unwrap();
 
     assert_eq!(
         res.assets,
@@ -1685,6 +3912,8 @@ fn test_initial_liquidity_provide() {
         asset_decimals: [6u8, 8u8],
         burn_address: deps.api.addr_make("burnaddr0000").to_string(),
         fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
     };
     let env = mock_env();
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -1757,7 +3986,7 @@ fn test_initial_liquidity_provide() {
     let expected_mint_msg = SubMsg::new(create_mint_tokens_msg(
         deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), // sender (contract address)
         Coin {
-            denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR.to_string()),
+            denom: format!("factory/{}/lp", MOCK_CONTRACT_ADDR),
             amount: expected_provider_lp,
         },
         deps.api.addr_make("addr0000").to_string(), // mint_to (user)
@@ -2,43 +2,60 @@
 use cosmwasm_std::entry_point;
 
 use cosmwasm_std::{
-    from_json, to_json_binary, Addr, Api, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
-    Response, StdError, StdResult, Uint128, WasmMsg,
+    from_json, to_json_binary, Addr, Api, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo,
+    Order, Reply, Response, StdError, StdResult, SubMsgResult, Uint128, WasmMsg,
 };
 use cw2::set_contract_version;
 
-use crate::operations::execute_swap_operation;
-use crate::state::{Config, CONFIG};
+use crate::helix::load_market;
+use crate::operations::{
+    execute_swap_operation, settle_helix_spot_swap, HelixSpotSwap, HELIX_SPOT_REPLY_ID,
+    SWAP_OPERATION_REPLY_ID,
+};
+use crate::state::{Config, ReferralStats, CONFIG, REFERRAL_STATS};
 
 use cw20::Cw20ReceiveMsg;
 use std::collections::HashMap;
 use choice::asset::{Asset, AssetInfo, PairInfo};
 use choice::pair::SimulationResponse;
-use choice::querier::{query_pair_info, reverse_simulate, simulate};
+use choice::querier::{
+    query_factory_paused, query_pair_info, query_pairs_by_asset, reverse_simulate, simulate,
+};
 use choice::router::{
-    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
-    SimulateSwapOperationsResponse, SwapOperation,
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, FindBestRouteResponse, InstantiateMsg, MigrateMsg,
+    QueryMsg, Referral, ReferralAssetStats, ReferralStatsResponse, RouteShare,
+    SimulateSwapOperationsResponse, SplitRoute, SwapOperation,
 };
 use choice::util::migrate_version;
-use injective_cosmwasm::query::InjectiveQueryWrapper;
+use injective_cosmwasm::{InjectiveMsgWrapper, InjectiveQueryWrapper};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:choice-router";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+const DEFAULT_MAX_HOPS: u32 = 3;
+const MAX_HOPS: u32 = 4;
+/// Pairs of an asset expanded per hop when searching routes
+const MAX_HOP_CANDIDATES: u32 = 10;
+const BPS_DENOMINATOR: u16 = 10_000;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut<InjectiveQueryWrapper>,
     _env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> StdResult<Response<InjectiveMsgWrapper>> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     CONFIG.save(
         deps.storage,
         &Config {
             choice_factory: deps.api.addr_canonicalize(&msg.choice_factory)?,
+            max_spread: msg.max_spread.map(assert_max_spread).transpose()?,
+            max_referral_commission_bps: assert_max_referral_commission(
+                msg.max_referral_commission_bps.unwrap_or_default(),
+            )?,
         },
     )?;
 
@@ -46,30 +63,100 @@ pub fn instantiate(
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn execute(deps: DepsMut<InjectiveQueryWrapper>, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+pub fn execute(deps: DepsMut<InjectiveQueryWrapper>, env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response<InjectiveMsgWrapper>> {
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::UpdateConfig {
+            max_spread,
+            clear_max_spread,
+            max_referral_commission_bps,
+        } => update_config(
+            deps,
+            env,
+            info,
+            max_spread,
+            clear_max_spread.unwrap_or(false),
+            max_referral_commission_bps,
+        ),
         ExecuteMsg::ExecuteSwapOperations {
             operations,
             minimum_receive,
             to,
             deadline,
+            referral,
         } => {
+            let offer_asset_info = operations
+                .first()
+                .ok_or_else(|| StdError::generic_err("must provide operations"))?
+                .get_offer_asset_info();
+            let offer_asset = offer_asset_from_funds(&info, offer_asset_info)?;
+
             let api = deps.api;
             execute_swap_operations(
                 deps,
                 env,
                 info.sender,
+                offer_asset,
                 operations,
                 minimum_receive,
                 optional_addr_validate(api, to)?,
                 deadline,
+                referral,
+            )
+        }
+        ExecuteMsg::ExecuteSplitSwap {
+            routes,
+            minimum_receive,
+            to,
+            deadline,
+        } => {
+            let offer_asset = offer_asset_from_funds(&info, assert_split_routes(&routes)?)?;
+
+            let api = deps.api;
+            execute_split_swap(
+                deps,
+                env,
+                info.sender,
+                offer_asset,
+                routes,
+                minimum_receive,
+                optional_addr_validate(api, to)?,
+                deadline,
+            )
+        }
+        ExecuteMsg::ExecuteSwapOperationsExactOut {
+            operations,
+            ask_amount,
+            max_offer,
+            to,
+            deadline,
+        } => {
+            let offer_asset_info = operations
+                .first()
+                .ok_or_else(|| StdError::generic_err("must provide operations"))?
+                .get_offer_asset_info();
+            let offer_asset = offer_asset_from_funds(&info, offer_asset_info)?;
+
+            let api = deps.api;
+            execute_swap_operations_exact_out(
+                deps,
+                env,
+                info.sender,
+                offer_asset,
+                operations,
+                ask_amount,
+                max_offer,
+                optional_addr_validate(api, to)?,
+                deadline,
             )
         }
         ExecuteMsg::ExecuteSwapOperation {
             operation,
             to,
             deadline,
+            amount,
+            prev_balance,
+            refund_to,
         } => {
             let api = deps.api;
             execute_swap_operation(
@@ -79,6 +166,9 @@ pub fn execute(deps: DepsMut<InjectiveQueryWrapper>, env: Env, info: MessageInfo
                 operation,
                 optional_addr_validate(api, to)?.map(|v| v.to_string()),
                 deadline,
+                amount,
+                prev_balance,
+                optional_addr_validate(api, refund_to)?.map(|v| v.to_string()),
             )
         }
         ExecuteMsg::AssertMinimumReceive {
@@ -96,6 +186,130 @@ pub fn execute(deps: DepsMut<InjectiveQueryWrapper>, env: Env, info: MessageInfo
     }
 }
 
+pub fn update_config(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    max_spread: Option<Decimal>,
+    clear_max_spread: bool,
+    max_referral_commission_bps: Option<u16>,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    // the router has no owner, the contract admin manages it
+    let contract_info = deps
+        .querier
+        .query_wasm_contract_info(env.contract.address)?;
+    if contract_info.admin != Some(info.sender) {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    if clear_max_spread {
+        if max_spread.is_some() {
+            return Err(StdError::generic_err(
+                "max spread cannot be set and cleared at once",
+            ));
+        }
+        config.max_spread = None;
+    } else if let Some(max_spread) = max_spread {
+        config.max_spread = Some(assert_max_spread(max_spread)?);
+    }
+
+    if let Some(max_referral_commission_bps) = max_referral_commission_bps {
+        config.max_referral_commission_bps =
+            assert_max_referral_commission(max_referral_commission_bps)?;
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attribute("action", "update_config"))
+}
+
+fn assert_max_spread(max_spread: Decimal) -> StdResult<Decimal> {
+    if max_spread >= Decimal::one() {
+        return Err(StdError::generic_err("max spread must be below 1"));
+    }
+
+    Ok(max_spread)
+}
+
+fn assert_max_referral_commission(max_referral_commission_bps: u16) -> StdResult<u16> {
+    if max_referral_commission_bps > BPS_DENOMINATOR {
+        return Err(StdError::generic_err(format!(
+            "max referral commission must not exceed {} bps",
+            BPS_DENOMINATOR
+        )));
+    }
+
+    Ok(max_referral_commission_bps)
+}
+
+/// Records the referral of `offer_asset` and returns the commission
+/// paid to the referrer
+fn take_referral_commission(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    referral: Referral,
+    offer_asset: &Asset,
+) -> StdResult<(Addr, Asset)> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    if referral.commission_bps > config.max_referral_commission_bps {
+        return Err(StdError::generic_err(format!(
+            "referral commission exceeds the max of {} bps",
+            config.max_referral_commission_bps
+        )));
+    }
+
+    let referrer = deps.api.addr_validate(&referral.address)?;
+    let commission = Asset {
+        info: offer_asset.info.clone(),
+        amount: offer_asset
+            .amount
+            .multiply_ratio(referral.commission_bps, BPS_DENOMINATOR),
+    };
+
+    let asset_key = offer_asset.info.to_string();
+    REFERRAL_STATS.update(
+        deps.storage,
+        (&referrer, asset_key.as_str()),
+        |stats| -> StdResult<ReferralStats> {
+            let mut stats = stats.unwrap_or(ReferralStats {
+                asset_info: offer_asset.info.clone(),
+                volume: Uint128::zero(),
+                commission: Uint128::zero(),
+            });
+            stats.volume = stats.volume.checked_add(offer_asset.amount)?;
+            stats.commission = stats.commission.checked_add(commission.amount)?;
+            Ok(stats)
+        },
+    )?;
+
+    Ok((referrer, commission))
+}
+
+/// Offer asset sent as native funds, tokens come through the receive hook
+fn offer_asset_from_funds(info: &MessageInfo, offer_asset_info: AssetInfo) -> StdResult<Asset> {
+    let amount = match &offer_asset_info {
+        AssetInfo::NativeToken { denom } => match info.funds.as_slice() {
+            [coin] if &coin.denom == denom && !coin.amount.is_zero() => coin.amount,
+            _ => {
+                return Err(StdError::generic_err(format!(
+                    "funds mismatch; must send only the offer asset {}",
+                    denom
+                )))
+            }
+        },
+        AssetInfo::Token { .. } => {
+            return Err(StdError::generic_err(
+                "token offer must be sent with the receive hook",
+            ))
+        }
+    };
+
+    Ok(Asset {
+        info: offer_asset_info,
+        amount,
+    })
+}
+
 fn optional_addr_validate(api: &dyn Api, addr: Option<String>) -> StdResult<Option<Addr>> {
     let addr = if let Some(addr) = addr {
         Some(api.addr_validate(&addr)?)
@@ -109,9 +323,9 @@ fn optional_addr_validate(api: &dyn Api, addr: Option<String>) -> StdResult<Opti
 pub fn receive_cw20(
     deps: DepsMut<InjectiveQueryWrapper>,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     cw20_msg: Cw20ReceiveMsg,
-) -> StdResult<Response> {
+) -> StdResult<Response<InjectiveMsgWrapper>> {
     let sender = deps.api.addr_validate(&cw20_msg.sender)?;
     match from_json(&cw20_msg.msg)? {
         Cw20HookMsg::ExecuteSwapOperations {
@@ -119,61 +333,131 @@ pub fn receive_cw20(
             minimum_receive,
             to,
             deadline,
+            referral,
         } => {
+            let offer_asset = Asset {
+                info: AssetInfo::Token {
+                    contract_addr: info.sender.to_string(),
+                },
+                amount: cw20_msg.amount,
+            };
+
             let api = deps.api;
             execute_swap_operations(
                 deps,
                 env,
                 sender,
+                offer_asset,
                 operations,
                 minimum_receive,
                 optional_addr_validate(api, to)?,
                 deadline,
+                referral,
+            )
+        }
+        Cw20HookMsg::ExecuteSplitSwap {
+            routes,
+            minimum_receive,
+            to,
+            deadline,
+        } => {
+            let offer_asset = Asset {
+                info: AssetInfo::Token {
+                    contract_addr: info.sender.to_string(),
+                },
+                amount: cw20_msg.amount,
+            };
+
+            let api = deps.api;
+            execute_split_swap(
+                deps,
+                env,
+                sender,
+                offer_asset,
+                routes,
+                minimum_receive,
+                optional_addr_validate(api, to)?,
+                deadline,
+            )
+        }
+        Cw20HookMsg::ExecuteSwapOperationsExactOut {
+            operations,
+            ask_amount,
+            max_offer,
+            to,
+            deadline,
+        } => {
+            let offer_asset = Asset {
+                info: AssetInfo::Token {
+                    contract_addr: info.sender.to_string(),
+                },
+                amount: cw20_msg.amount,
+            };
+
+            let api = deps.api;
+            execute_swap_operations_exact_out(
+                deps,
+                env,
+                sender,
+                offer_asset,
+                operations,
+                ask_amount,
+                max_offer,
+                optional_addr_validate(api, to)?,
+                deadline,
             )
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn execute_swap_operations(
-    deps: DepsMut<InjectiveQueryWrapper>,
+    mut deps: DepsMut<InjectiveQueryWrapper>,
     env: Env,
     sender: Addr,
+    offer_asset: Asset,
     operations: Vec<SwapOperation>,
     minimum_receive: Option<Uint128>,
     to: Option<Addr>,
     deadline: Option<u64>,
-) -> StdResult<Response> {
-    let operations_len = operations.len();
-    if operations_len == 0 {
+    referral: Option<Referral>,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    if operations.is_empty() {
         return Err(StdError::generic_err("must provide operations"));
     }
 
     // Assert the operations are properly set
     assert_operations(&operations)?;
+    if !operations[0].get_offer_asset_info().equal(&offer_asset.info) {
+        return Err(StdError::generic_err("invalid operations; offer asset mismatch"));
+    }
 
-    let to = if let Some(to) = to { to } else { sender };
+    // the commission comes out of the offer before the first hop
+    let mut offer_amount = offer_asset.amount;
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    let mut attributes: Vec<(&str, String)> = vec![];
+    if let Some(referral) = referral {
+        let (referrer, commission) = take_referral_commission(deps.branch(), referral, &offer_asset)?;
+        attributes.push(("referrer", referrer.to_string()));
+        attributes.push(("referral_commission", commission.to_string()));
+        if !commission.amount.is_zero() {
+            offer_amount = offer_amount.checked_sub(commission.amount)?;
+            messages.push(commission.into_msg(referrer)?);
+        }
+    }
+
+    let to = if let Some(to) = to { to } else { sender.clone() };
     let target_asset_info = operations.last().unwrap().get_target_asset_info();
 
-    let mut operation_index = 0;
-    let mut messages: Vec<CosmosMsg> = operations
-        .into_iter()
-        .map(|op| {
-            operation_index += 1;
-            Ok(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: env.contract.address.to_string(),
-                funds: vec![],
-                msg: to_json_binary(&ExecuteMsg::ExecuteSwapOperation {
-                    operation: op,
-                    to: if operation_index == operations_len {
-                        Some(to.to_string())
-                    } else {
-                        None
-                    },
-                    deadline,
-                })?,
-            }))
-        })
-        .collect::<StdResult<Vec<CosmosMsg>>>()?;
+    messages.extend(route_messages(
+        deps.as_ref(),
+        &env,
+        operations,
+        offer_amount,
+        &to,
+        &sender,
+        deadline,
+    )?);
 
     // Execute minimum amount assertion
     if let Some(minimum_receive) = minimum_receive {
@@ -191,16 +475,271 @@ pub fn execute_swap_operations(
         }))
     }
 
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(attributes))
+}
+
+/// Hop messages of a route, the first hop swaps `offer_amount` and the
+/// next ones only what the previous hop returned, so balances the router
+/// held before are left out of the trade
+fn route_messages(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: &Env,
+    operations: Vec<SwapOperation>,
+    offer_amount: Uint128,
+    to: &Addr,
+    refund_to: &Addr,
+    deadline: Option<u64>,
+) -> StdResult<Vec<CosmosMsg<InjectiveMsgWrapper>>> {
+    let operations_len = operations.len();
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    for (i, operation) in operations.into_iter().enumerate() {
+        let (amount, prev_balance) = if i == 0 {
+            (Some(offer_amount), None)
+        } else {
+            let prev_balance = operation.get_offer_asset_info().query_pool(
+                &deps.querier,
+                deps.api,
+                env.contract.address.clone(),
+            )?;
+            (None, Some(prev_balance))
+        };
+
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_json_binary(&ExecuteMsg::ExecuteSwapOperation {
+                operation,
+                to: if i + 1 == operations_len {
+                    Some(to.to_string())
+                } else {
+                    None
+                },
+                deadline,
+                amount,
+                prev_balance,
+                refund_to: Some(refund_to.to_string()),
+            })?,
+        }));
+    }
+
+    Ok(messages)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn execute_split_swap(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    sender: Addr,
+    offer_asset: Asset,
+    routes: Vec<SplitRoute>,
+    minimum_receive: Option<Uint128>,
+    to: Option<Addr>,
+    deadline: Option<u64>,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    if !assert_split_routes(&routes)?.equal(&offer_asset.info) {
+        return Err(StdError::generic_err("invalid routes; offer asset mismatch"));
+    }
+
+    let amounts = split_offer_amount(offer_asset.amount, &routes)?;
+
+    let to = if let Some(to) = to { to } else { sender.clone() };
+    let target_asset_info = routes[0].operations.last().unwrap().get_target_asset_info();
+
+    // each route swaps its share on the first hop and what it received
+    // on the next ones, the last hop sends to the receiver
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    for (route, amount) in routes.into_iter().zip(amounts) {
+        messages.extend(route_messages(
+            deps.as_ref(),
+            &env,
+            route.operations,
+            amount,
+            &to,
+            &sender,
+            deadline,
+        )?);
+    }
+
+    // Execute minimum amount assertion on the merged return
+    if let Some(minimum_receive) = minimum_receive {
+        let receiver_balance = target_asset_info.query_pool(&deps.querier, deps.api, to.clone())?;
+
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_json_binary(&ExecuteMsg::AssertMinimumReceive {
+                asset_info: target_asset_info,
+                prev_balance: receiver_balance,
+                minimum_receive,
+                receiver: to.to_string(),
+            })?,
+        }))
+    }
+
     Ok(Response::new().add_messages(messages))
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn execute_swap_operations_exact_out(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    sender: Addr,
+    offer_asset: Asset,
+    operations: Vec<SwapOperation>,
+    ask_amount: Uint128,
+    max_offer: Option<Uint128>,
+    to: Option<Addr>,
+    deadline: Option<u64>,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    if operations.is_empty() {
+        return Err(StdError::generic_err("must provide operations"));
+    }
+
+    assert_operations(&operations)?;
+    if !operations[0].get_offer_asset_info().equal(&offer_asset.info) {
+        return Err(StdError::generic_err("invalid operations; offer asset mismatch"));
+    }
+
+    let offer_amount =
+        reverse_simulate_swap_operations(deps.as_ref(), ask_amount, operations.clone())?.amount;
+    if let Some(max_offer) = max_offer {
+        if offer_amount > max_offer {
+            return Err(StdError::generic_err(format!(
+                "assertion failed; max offer amount: {}, offer amount: {}",
+                max_offer, offer_amount
+            )));
+        }
+    }
+
+    let refund_amount = offer_asset.amount.checked_sub(offer_amount).map_err(|_| {
+        StdError::generic_err(format!(
+            "insufficient offer amount; sent: {}, required: {}",
+            offer_asset.amount, offer_amount
+        ))
+    })?;
+
+    let to = if let Some(to) = to { to } else { sender.clone() };
+    let target_asset_info = operations.last().unwrap().get_target_asset_info();
+
+    // the first hop swaps the required offer only
+    let mut messages =
+        route_messages(deps.as_ref(), &env, operations, offer_amount, &to, &sender, deadline)?;
+
+    let receiver_balance = target_asset_info.query_pool(&deps.querier, deps.api, to.clone())?;
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        funds: vec![],
+        msg: to_json_binary(&ExecuteMsg::AssertMinimumReceive {
+            asset_info: target_asset_info,
+            prev_balance: receiver_balance,
+            minimum_receive: ask_amount,
+            receiver: to.to_string(),
+        })?,
+    }));
+
+    if !refund_amount.is_zero() {
+        let refund_asset = Asset {
+            info: offer_asset.info,
+            amount: refund_amount,
+        };
+        messages.push(refund_asset.into_msg(sender)?);
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "swap_exact_out")
+        .add_attribute("offer_amount", offer_amount.to_string())
+        .add_attribute("refund_amount", refund_amount.to_string()))
+}
+
+/// Checks every route is a valid path from the same offer asset
+/// to the same ask asset and returns the offer asset
+fn assert_split_routes(routes: &[SplitRoute]) -> StdResult<AssetInfo> {
+    if routes.is_empty() {
+        return Err(StdError::generic_err("must provide routes"));
+    }
+
+    let mut ends: Option<(AssetInfo, AssetInfo)> = None;
+    for route in routes.iter() {
+        if route.operations.is_empty() {
+            return Err(StdError::generic_err("must provide operations"));
+        }
+
+        assert_operations(&route.operations)?;
+
+        let offer_asset_info = route.operations[0].get_offer_asset_info();
+        let ask_asset_info = route.operations.last().unwrap().get_target_asset_info();
+        match &ends {
+            None => ends = Some((offer_asset_info, ask_asset_info)),
+            Some((offer, ask)) => {
+                if !offer.equal(&offer_asset_info) || !ask.equal(&ask_asset_info) {
+                    return Err(StdError::generic_err(
+                        "invalid routes; routes must share the offer and ask assets",
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(ends.unwrap().0)
+}
+
+/// Offer amount swapped by each route, fixed amounts come first and
+/// the last weighted route takes the rounding dust
+fn split_offer_amount(offer_amount: Uint128, routes: &[SplitRoute]) -> StdResult<Vec<Uint128>> {
+    let mut fixed_amount = Uint128::zero();
+    let mut total_weight = Decimal::zero();
+    for route in routes.iter() {
+        match route.share {
+            RouteShare::Amount { amount } => fixed_amount = fixed_amount.checked_add(amount)?,
+            RouteShare::Weight { weight } => total_weight = total_weight.checked_add(weight)?,
+        }
+    }
+
+    let weighted_amount = offer_amount
+        .checked_sub(fixed_amount)
+        .map_err(|_| StdError::generic_err("route amounts exceed the offer amount"))?;
+    if total_weight.is_zero() && !weighted_amount.is_zero() {
+        return Err(StdError::generic_err(
+            "route amounts must add up to the offer amount",
+        ));
+    }
+
+    let last_weighted = routes
+        .iter()
+        .rposition(|route| matches!(route.share, RouteShare::Weight { .. }));
+    let mut amount_left = weighted_amount;
+    let mut amounts: Vec<Uint128> = vec![];
+    for (i, route) in routes.iter().enumerate() {
+        let amount = match route.share {
+            RouteShare::Amount { amount } => amount,
+            RouteShare::Weight { .. } if Some(i) == last_weighted => amount_left,
+            RouteShare::Weight { weight } => {
+                let amount = weighted_amount.multiply_ratio(weight.atomics(), total_weight.atomics());
+                amount_left -= amount;
+                amount
+            }
+        };
+
+        if amount.is_zero() {
+            return Err(StdError::generic_err("route amount must not be zero"));
+        }
+
+        amounts.push(amount);
+    }
+
+    Ok(amounts)
+}
+
 fn assert_minimum_receive(
     deps: Deps<InjectiveQueryWrapper>,
     asset_info: AssetInfo,
     prev_balance: Uint128,
     minium_receive: Uint128,
     receiver: Addr,
-) -> StdResult<Response> {
+) -> StdResult<Response<InjectiveMsgWrapper>> {
     let receiver_balance = asset_info.query_pool(&deps.querier, deps.api, receiver)?;
     let swap_amount = receiver_balance.checked_sub(prev_balance)?;
 
@@ -214,6 +753,33 @@ fn assert_minimum_receive(
     Ok(Response::default())
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut<InjectiveQueryWrapper>, env: Env, msg: Reply) -> StdResult<Response<InjectiveMsgWrapper>> {
+    let (operation, helix_spot_swap) = match msg.id {
+        SWAP_OPERATION_REPLY_ID => (from_json::<SwapOperation>(&msg.payload)?, None),
+        HELIX_SPOT_REPLY_ID => {
+            let swap: HelixSpotSwap = from_json(&msg.payload)?;
+            (swap.operation.clone(), Some(swap))
+        }
+        _ => return Err(StdError::generic_err("invalid reply msg")),
+    };
+
+    let err = match (msg.result, helix_spot_swap) {
+        (SubMsgResult::Err(err), _) => err,
+        (SubMsgResult::Ok(_), Some(swap)) => {
+            return settle_helix_spot_swap(deps.as_ref(), env, swap)
+        }
+        (SubMsgResult::Ok(_), None) => return Ok(Response::default()),
+    };
+
+    Err(StdError::generic_err(format!(
+        "swap operation {} -> {} failed: {}",
+        operation.get_offer_asset_info(),
+        operation.get_target_asset_info(),
+        err
+    )))
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps<InjectiveQueryWrapper>, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -228,6 +794,23 @@ pub fn query(deps: Deps<InjectiveQueryWrapper>, _env: Env, msg: QueryMsg) -> Std
         } => to_json_binary(&reverse_simulate_swap_operations(
             deps, ask_amount, operations,
         )?),
+        QueryMsg::SimulateSplitSwap {
+            offer_amount,
+            routes,
+        } => to_json_binary(&simulate_split_swap(deps, offer_amount, routes)?),
+        QueryMsg::FindBestRoute {
+            offer_asset,
+            ask_asset_info,
+            max_hops,
+        } => to_json_binary(&find_best_route(
+            deps,
+            offer_asset,
+            ask_asset_info,
+            max_hops,
+        )?),
+        QueryMsg::ReferralStats { referrer } => {
+            to_json_binary(&query_referral_stats(deps, referrer)?)
+        }
     }
 }
 
@@ -238,11 +821,37 @@ pub fn query_config(deps: Deps<InjectiveQueryWrapper>) -> StdResult<ConfigRespon
             .api
             .addr_humanize(&state.choice_factory)?
             .to_string(),
+        max_spread: state.max_spread,
+        max_referral_commission_bps: state.max_referral_commission_bps,
     };
 
     Ok(resp)
 }
 
+pub fn query_referral_stats(
+    deps: Deps<InjectiveQueryWrapper>,
+    referrer: String,
+) -> StdResult<ReferralStatsResponse> {
+    let referrer = deps.api.addr_validate(&referrer)?;
+    let stats = REFERRAL_STATS
+        .prefix(&referrer)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (_, stats) = item?;
+            Ok(ReferralAssetStats {
+                asset_info: stats.asset_info,
+                volume: stats.volume,
+                commission: stats.commission,
+            })
+        })
+        .collect::<StdResult<Vec<ReferralAssetStats>>>()?;
+
+    Ok(ReferralStatsResponse {
+        referrer: referrer.to_string(),
+        stats,
+    })
+}
+
 fn simulate_swap_operations(
     deps: Deps<InjectiveQueryWrapper>,
     offer_amount: Uint128,
@@ -262,6 +871,7 @@ fn simulate_swap_operations(
             SwapOperation::Choice {
                 offer_asset_info,
                 ask_asset_info,
+                ..
             } => {
                 let pair_info: PairInfo = query_pair_info(
                     &deps.querier,
@@ -280,6 +890,15 @@ fn simulate_swap_operations(
 
                 offer_amount = res.return_amount;
             }
+            SwapOperation::HelixSpot {
+                market_id,
+                offer_asset_info,
+                ask_asset_info,
+                worst_price,
+            } => {
+                let market = load_market(deps, &market_id, &offer_asset_info, &ask_asset_info)?;
+                offer_amount = market.simulate(deps, offer_amount, worst_price)?;
+            }
         }
     }
 
@@ -288,6 +907,24 @@ fn simulate_swap_operations(
     })
 }
 
+fn simulate_split_swap(
+    deps: Deps<InjectiveQueryWrapper>,
+    offer_amount: Uint128,
+    routes: Vec<SplitRoute>,
+) -> StdResult<SimulateSwapOperationsResponse> {
+    assert_split_routes(&routes)?;
+    let amounts = split_offer_amount(offer_amount, &routes)?;
+
+    let mut return_amount = Uint128::zero();
+    for (route, amount) in routes.into_iter().zip(amounts) {
+        return_amount += simulate_swap_operations(deps, amount, route.operations)?.amount;
+    }
+
+    Ok(SimulateSwapOperationsResponse {
+        amount: return_amount,
+    })
+}
+
 fn reverse_simulate_swap_operations(
     deps: Deps<InjectiveQueryWrapper>,
     ask_amount: Uint128,
@@ -306,6 +943,7 @@ fn reverse_simulate_swap_operations(
             SwapOperation::Choice {
                 offer_asset_info,
                 ask_asset_info,
+                ..
             } => {
                 let choice_factory = deps.api.addr_humanize(&config.choice_factory)?;
 
@@ -315,8 +953,16 @@ fn reverse_simulate_swap_operations(
                     ask_amount,
                     offer_asset_info,
                     ask_asset_info,
-                )
-                .unwrap()
+                )?
+            }
+            SwapOperation::HelixSpot {
+                market_id,
+                offer_asset_info,
+                ask_asset_info,
+                worst_price,
+            } => {
+                let market = load_market(deps, &market_id, &offer_asset_info, &ask_asset_info)?;
+                market.reverse_simulate(deps, ask_amount, worst_price)?
             }
         }
     }
@@ -349,15 +995,152 @@ fn reverse_simulate_return_amount(
     Ok(res.offer_amount)
 }
 
+fn find_best_route(
+    deps: Deps<InjectiveQueryWrapper>,
+    offer_asset: Asset,
+    ask_asset_info: AssetInfo,
+    max_hops: Option<u32>,
+) -> StdResult<FindBestRouteResponse> {
+    let max_hops = max_hops.unwrap_or(DEFAULT_MAX_HOPS);
+    if max_hops == 0 || max_hops > MAX_HOPS {
+        return Err(StdError::generic_err(format!(
+            "max_hops must be between 1 and {}",
+            MAX_HOPS
+        )));
+    }
+
+    if offer_asset.info.equal(&ask_asset_info) {
+        return Err(StdError::generic_err("offer and ask assets are the same"));
+    }
+
+    let config: Config = CONFIG.load(deps.storage)?;
+    let choice_factory = deps.api.addr_humanize(&config.choice_factory)?;
+
+    // the global pause of the factory halts every pair
+    if query_factory_paused(&deps.querier, choice_factory.clone())? {
+        return Err(StdError::generic_err("no route found"));
+    }
+
+    let mut best_route: Option<FindBestRouteResponse> = None;
+    search_routes(
+        deps,
+        &choice_factory,
+        &offer_asset,
+        &ask_asset_info,
+        max_hops,
+        &mut vec![],
+        &mut best_route,
+    )?;
+
+    best_route.ok_or_else(|| StdError::generic_err("no route found"))
+}
+
+/// Depth first search over the pairs, simulating each hop as the route grows.
+/// Each hop expands at most `MAX_HOP_CANDIDATES` pairs of the offer asset plus the
+/// direct pair to the ask asset. Routes never visit an asset twice, paused pairs
+/// and hops failing to simulate are skipped
+fn search_routes(
+    deps: Deps<InjectiveQueryWrapper>,
+    factory: &Addr,
+    offer_asset: &Asset,
+    ask_asset_info: &AssetInfo,
+    hops_left: u32,
+    operations: &mut Vec<SwapOperation>,
+    best_route: &mut Option<FindBestRouteResponse>,
+) -> StdResult<()> {
+    let mut pairs: Vec<PairInfo> = query_pairs_by_asset(
+        &deps.querier,
+        factory.clone(),
+        &offer_asset.info,
+        None,
+        Some(MAX_HOP_CANDIDATES),
+    )?
+    .pairs;
+
+    // the direct pair may be past the cap
+    let has_direct_pair = pairs
+        .iter()
+        .any(|pair| pair.asset_infos.iter().any(|info| info.equal(ask_asset_info)));
+    if !has_direct_pair {
+        if let Ok(pair) = query_pair_info(
+            &deps.querier,
+            factory.clone(),
+            &[offer_asset.info.clone(), ask_asset_info.clone()],
+        ) {
+            pairs.push(pair);
+        }
+    }
+
+    for pair in pairs.iter().filter(|pair| !pair.paused) {
+        let next_asset_info = if pair.asset_infos[0].equal(&offer_asset.info) {
+            &pair.asset_infos[1]
+        } else if pair.asset_infos[1].equal(&offer_asset.info) {
+            &pair.asset_infos[0]
+        } else {
+            continue;
+        };
+
+        let visited = operations
+            .iter()
+            .any(|operation| operation.get_offer_asset_info().equal(next_asset_info));
+        if visited {
+            continue;
+        }
+
+        let return_amount = match simulate(
+            &deps.querier,
+            Addr::unchecked(pair.contract_addr.clone()),
+            offer_asset,
+        ) {
+            Ok(res) if !res.return_amount.is_zero() => res.return_amount,
+            _ => continue,
+        };
+
+        operations.push(SwapOperation::Choice {
+            offer_asset_info: offer_asset.info.clone(),
+            ask_asset_info: next_asset_info.clone(),
+            max_spread: None,
+            belief_price: None,
+        });
+
+        if next_asset_info.equal(ask_asset_info) {
+            if best_route
+                .as_ref()
+                .is_none_or(|best| return_amount > best.amount)
+            {
+                *best_route = Some(FindBestRouteResponse {
+                    operations: operations.clone(),
+                    amount: return_amount,
+                });
+            }
+        } else if hops_left > 1 {
+            search_routes(
+                deps,
+                factory,
+                &Asset {
+                    info: next_asset_info.clone(),
+                    amount: return_amount,
+                },
+                ask_asset_info,
+                hops_left - 1,
+                operations,
+                best_route,
+            )?;
+        }
+
+        operations.pop();
+    }
+
+    Ok(())
+}
+
 fn assert_operations(operations: &[SwapOperation]) -> StdResult<()> {
     let mut ask_asset_map: HashMap<String, bool> = HashMap::new();
     for operation in operations.iter() {
-        let (offer_asset, ask_asset) = match operation {
-            SwapOperation::Choice {
-                offer_asset_info,
-                ask_asset_info,
-            } => (offer_asset_info.clone(), ask_asset_info.clone()),
-        };
+        let (offer_asset, ask_asset) = (
+            operation.get_offer_asset_info(),
+            operation.get_target_asset_info(),
+        );
 
         ask_asset_map.remove(&offer_asset.to_string());
         ask_asset_map.insert(ask_asset.to_string(), true);
@@ -386,6 +1169,8 @@ fn test_invalid_operations() {
             ask_asset_info: AssetInfo::Token {
                 contract_addr: "asset0001".to_string(),
             },
+            max_spread: None,
+            belief_price: None,
         },
         SwapOperation::Choice {
             offer_asset_info: AssetInfo::Token {
@@ -394,6 +1179,8 @@ fn test_invalid_operations() {
             ask_asset_info: AssetInfo::NativeToken {
                 denom: "inj".to_string(),
             },
+            max_spread: None,
+            belief_price: None,
         }
     ])
     .is_ok());
@@ -407,6 +1194,8 @@ fn test_invalid_operations() {
             ask_asset_info: AssetInfo::Token {
                 contract_addr: "asset0001".to_string(),
             },
+            max_spread: None,
+            belief_price: None,
         },
         SwapOperation::Choice {
             offer_asset_info: AssetInfo::Token {
@@ -415,6 +1204,8 @@ fn test_invalid_operations() {
             ask_asset_info: AssetInfo::NativeToken {
                 denom: "inj".to_string(),
             },
+            max_spread: None,
+            belief_price: None,
         },
         SwapOperation::Choice {
             offer_asset_info: AssetInfo::NativeToken {
@@ -423,6 +1214,8 @@ fn test_invalid_operations() {
             ask_asset_info: AssetInfo::Token {
                 contract_addr: "asset0002".to_string(),
             },
+            max_spread: None,
+            belief_price: None,
         },
     ])
     .is_ok());
@@ -430,7 +1223,7 @@ fn test_invalid_operations() {
 
 const TARGET_CONTRACT_VERSION: &str = "0.1.0";
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(deps: DepsMut<InjectiveQueryWrapper>, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut<InjectiveQueryWrapper>, _env: Env, _msg: MigrateMsg) -> StdResult<Response<InjectiveMsgWrapper>> {
     migrate_version(
         deps,
         TARGET_CONTRACT_VERSION,
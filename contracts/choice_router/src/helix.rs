@@ -0,0 +1,234 @@
+use cosmwasm_std::{Deps, StdError, StdResult, Uint128};
+
+use choice::asset::AssetInfo;
+use injective_cosmwasm::{
+    InjectiveQuerier, InjectiveQueryWrapper, MarketId, OrderSide, OrderType, PriceLevel, SpotMarket,
+};
+use injective_math::{round_to_min_tick, round_up_to_min_tick, FPDecimal};
+
+/// Spot market of a helix operation and the side its offer asset takes
+pub struct HelixMarket {
+    pub market: SpotMarket,
+    /// The offer asset is the quote denom
+    pub is_buy: bool,
+    /// Taker fee rate of atomic orders
+    pub fee_rate: FPDecimal,
+}
+
+pub fn load_market(
+    deps: Deps<InjectiveQueryWrapper>,
+    market_id: &str,
+    offer_asset_info: &AssetInfo,
+    ask_asset_info: &AssetInfo,
+) -> StdResult<HelixMarket> {
+    let market_id = MarketId::new(market_id)?;
+    let querier = InjectiveQuerier::new(&deps.querier);
+    let market = querier
+        .query_spot_market(&market_id)?
+        .market
+        .ok_or_else(|| StdError::generic_err("helix spot market not found"))?;
+
+    let (offer_denom, ask_denom) = match (offer_asset_info, ask_asset_info) {
+        (AssetInfo::NativeToken { denom: offer }, AssetInfo::NativeToken { denom: ask }) => {
+            (offer, ask)
+        }
+        _ => {
+            return Err(StdError::generic_err(
+                "helix spot operations only trade native tokens",
+            ))
+        }
+    };
+
+    let is_buy = if *offer_denom == market.quote_denom && *ask_denom == market.base_denom {
+        true
+    } else if *offer_denom == market.base_denom && *ask_denom == market.quote_denom {
+        false
+    } else {
+        return Err(StdError::generic_err(
+            "invalid helix spot operation; market denoms mismatch",
+        ));
+    };
+
+    let multiplier = querier
+        .query_market_atomic_execution_fee_multiplier(&market_id)?
+        .multiplier;
+
+    Ok(HelixMarket {
+        fee_rate: market.taker_fee_rate * multiplier,
+        market,
+        is_buy,
+    })
+}
+
+impl HelixMarket {
+    pub fn offer_denom(&self) -> &str {
+        if self.is_buy {
+            &self.market.quote_denom
+        } else {
+            &self.market.base_denom
+        }
+    }
+
+    pub fn ask_denom(&self) -> &str {
+        if self.is_buy {
+            &self.market.base_denom
+        } else {
+            &self.market.quote_denom
+        }
+    }
+
+    pub fn order_type(&self) -> OrderType {
+        if self.is_buy {
+            OrderType::BuyAtomic
+        } else {
+            OrderType::SellAtomic
+        }
+    }
+
+    /// Worst price on the price tick, never looser than asked
+    pub fn order_price(&self, worst_price: FPDecimal) -> FPDecimal {
+        if self.is_buy {
+            round_to_min_tick(worst_price, self.market.min_price_tick_size)
+        } else {
+            round_up_to_min_tick(worst_price, self.market.min_price_tick_size)
+        }
+    }
+
+    /// Base quantity ordered for `offer_amount`, buys reserve the
+    /// fee at the worst price
+    pub fn order_quantity(&self, offer_amount: Uint128, worst_price: FPDecimal) -> FPDecimal {
+        let offer_amount = FPDecimal::from(offer_amount);
+        let quantity = if self.is_buy {
+            offer_amount / (self.order_price(worst_price) * (FPDecimal::ONE + self.fee_rate))
+        } else {
+            offer_amount
+        };
+
+        round_to_min_tick(quantity, self.market.min_quantity_tick_size)
+    }
+
+    /// Return of the order for `offer_amount` filled against the orderbook
+    pub fn simulate(
+        &self,
+        deps: Deps<InjectiveQueryWrapper>,
+        offer_amount: Uint128,
+        worst_price: FPDecimal,
+    ) -> StdResult<Uint128> {
+        let quantity = self.order_quantity(offer_amount, worst_price);
+        if quantity.is_zero() {
+            return Err(StdError::generic_err(
+                "offer amount is below the market quantity tick",
+            ));
+        }
+
+        let mut quantity_left = quantity;
+        let mut notional = FPDecimal::ZERO;
+        for level in self.price_levels(deps, Some(quantity), worst_price)? {
+            let fill = quantity_left.minimum(&level.q);
+            notional += fill * level.p;
+            quantity_left -= fill;
+            if quantity_left.is_zero() {
+                break;
+            }
+        }
+
+        if !quantity_left.is_zero() {
+            return Err(StdError::generic_err(
+                "not enough liquidity in the helix spot orderbook",
+            ));
+        }
+
+        if self.is_buy {
+            Ok(quantity.into())
+        } else {
+            Ok((notional * (FPDecimal::ONE - self.fee_rate)).into())
+        }
+    }
+
+    /// Offer amount needed for a return of `ask_amount`
+    pub fn reverse_simulate(
+        &self,
+        deps: Deps<InjectiveQueryWrapper>,
+        ask_amount: Uint128,
+        worst_price: FPDecimal,
+    ) -> StdResult<Uint128> {
+        if self.is_buy {
+            let quantity =
+                round_up_to_min_tick(ask_amount.into(), self.market.min_quantity_tick_size);
+            let offer_amount = into_uint128_ceil(
+                quantity * self.order_price(worst_price) * (FPDecimal::ONE + self.fee_rate),
+            );
+
+            // fails when the orderbook cannot fill the quantity
+            self.simulate(deps, offer_amount, worst_price)?;
+            return Ok(offer_amount);
+        }
+
+        let mut notional_left = FPDecimal::from(ask_amount) / (FPDecimal::ONE - self.fee_rate);
+        let mut quantity = FPDecimal::ZERO;
+        for level in self.price_levels(deps, None, worst_price)? {
+            let level_notional = level.p * level.q;
+            if notional_left <= level_notional {
+                quantity += notional_left / level.p;
+                notional_left = FPDecimal::ZERO;
+                break;
+            }
+
+            quantity += level.q;
+            notional_left -= level_notional;
+        }
+
+        if !notional_left.is_zero() {
+            return Err(StdError::generic_err(
+                "not enough liquidity in the helix spot orderbook",
+            ));
+        }
+
+        Ok(into_uint128_ceil(round_up_to_min_tick(
+            quantity,
+            self.market.min_quantity_tick_size,
+        )))
+    }
+
+    /// Resting orders the order fills against, up to the worst price
+    fn price_levels(
+        &self,
+        deps: Deps<InjectiveQueryWrapper>,
+        limit_cumulative_quantity: Option<FPDecimal>,
+        worst_price: FPDecimal,
+    ) -> StdResult<Vec<PriceLevel>> {
+        let worst_price = self.order_price(worst_price);
+        let orderbook = InjectiveQuerier::new(&deps.querier).query_spot_market_orderbook(
+            &self.market.market_id,
+            if self.is_buy {
+                OrderSide::Sell
+            } else {
+                OrderSide::Buy
+            },
+            limit_cumulative_quantity,
+            None,
+        )?;
+
+        Ok(if self.is_buy {
+            orderbook
+                .sells_price_level
+                .into_iter()
+                .filter(|level| level.p <= worst_price)
+                .collect()
+        } else {
+            orderbook
+                .buys_price_level
+                .into_iter()
+                .filter(|level| level.p >= worst_price)
+                .collect()
+        })
+    }
+}
+
+fn into_uint128_ceil(value: FPDecimal) -> Uint128 {
+    if value.is_int() {
+        value.into()
+    } else {
+        (value.int() + FPDecimal::ONE).into()
+    }
+}
@@ -1,6 +1,7 @@
 pub mod contract;
 pub mod state;
 
+mod helix;
 mod operations;
 
 #[cfg(test)]
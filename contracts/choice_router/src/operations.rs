@@ -1,8 +1,11 @@
 use cosmwasm_std::{
-    to_json_binary, Addr, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError,
-    StdResult, WasmMsg,
+    to_json_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response,
+    StdError, StdResult, SubMsg, Uint128, WasmMsg,
 };
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
+use crate::helix::load_market;
 use crate::state::{Config, CONFIG};
 
 use cw20::Cw20ExecuteMsg;
@@ -10,10 +13,32 @@ use choice::asset::{Asset, AssetInfo, PairInfo};
 use choice::pair::ExecuteMsg as PairExecuteMsg;
 use choice::querier::{query_balance, query_pair_info, query_token_balance};
 use choice::router::SwapOperation;
-use injective_cosmwasm::query::InjectiveQueryWrapper;
+use injective_cosmwasm::{
+    create_spot_market_order_msg, get_default_subaccount_id_for_checked_address,
+    InjectiveMsgWrapper, InjectiveQueryWrapper, SpotOrder,
+};
+
+pub const SWAP_OPERATION_REPLY_ID: u64 = 1;
+pub const HELIX_SPOT_REPLY_ID: u64 = 2;
+
+/// Reply payload of a helix spot order, the ask balance before the
+/// order gives what it returned
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct HelixSpotSwap {
+    pub operation: SwapOperation,
+    pub ask_balance: Uint128,
+    /// Offer balance before the order less the offered amount,
+    /// anything above it after the order was not spent
+    #[serde(default)]
+    pub offer_balance: Uint128,
+    pub to: Option<String>,
+    #[serde(default)]
+    pub refund_to: Option<String>,
+}
 
 /// Execute swap operation
-/// swap all offer asset to ask asset
+/// swap `amount` or the offer asset above `prev_balance` to ask asset
+#[allow(clippy::too_many_arguments)]
 pub fn execute_swap_operation(
     deps: DepsMut<InjectiveQueryWrapper>,
     env: Env,
@@ -21,7 +46,10 @@ pub fn execute_swap_operation(
     operation: SwapOperation,
     to: Option<String>,
     deadline: Option<u64>,
-) -> StdResult<Response> {
+    amount: Option<Uint128>,
+    prev_balance: Option<Uint128>,
+    refund_to: Option<String>,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
 
     println!("env.contract.address: {:?}", env.contract.address);
     println!("info.sender: {:?}", info.sender);
@@ -30,10 +58,13 @@ pub fn execute_swap_operation(
         return Err(StdError::generic_err("unauthorized"));
     }
 
-    let messages: Vec<CosmosMsg> = match operation {
+    let payload = to_json_binary(&operation)?;
+    let message: CosmosMsg<InjectiveMsgWrapper> = match operation.clone() {
         SwapOperation::Choice {
             offer_asset_info,
             ask_asset_info,
+            max_spread,
+            belief_price,
         } => {
             let config: Config = CONFIG.load(deps.as_ref().storage)?;
             let choice_factory = deps.api.addr_humanize(&config.choice_factory)?;
@@ -43,33 +74,158 @@ pub fn execute_swap_operation(
                 &[offer_asset_info.clone(), ask_asset_info],
             )?;
 
-            let amount = match offer_asset_info.clone() {
-                AssetInfo::NativeToken { denom } => {
+            let amount = match (amount, offer_asset_info.clone()) {
+                (Some(amount), _) => amount,
+                (None, AssetInfo::NativeToken { denom }) => {
                     query_balance(&deps.querier, env.contract.address, denom)?
+                        .checked_sub(prev_balance.unwrap_or_default())?
                 }
-                AssetInfo::Token { contract_addr } => query_token_balance(
+                (None, AssetInfo::Token { contract_addr }) => query_token_balance(
                     &deps.querier,
                     deps.api.addr_validate(contract_addr.as_str())?,
                     env.contract.address,
-                )?,
+                )?
+                .checked_sub(prev_balance.unwrap_or_default())?,
             };
             let offer_asset: Asset = Asset {
                 info: offer_asset_info,
                 amount,
             };
 
-            vec![asset_into_swap_msg(
+            asset_into_swap_msg(
                 deps.as_ref(),
                 Addr::unchecked(pair_info.contract_addr),
                 offer_asset,
-                None,
+                max_spread.or(config.max_spread),
+                belief_price,
                 to,
                 deadline,
-            )?]
+            )?
+        }
+        SwapOperation::HelixSpot {
+            market_id,
+            offer_asset_info,
+            ask_asset_info,
+            worst_price,
+        } => {
+            if deadline.is_some_and(|deadline| env.block.time.seconds() >= deadline) {
+                return Err(StdError::generic_err("Expired deadline"));
+            }
+
+            let market = load_market(deps.as_ref(), &market_id, &offer_asset_info, &ask_asset_info)?;
+            let offer_balance = query_balance(
+                &deps.querier,
+                env.contract.address.clone(),
+                market.offer_denom().to_string(),
+            )?;
+            let amount = match amount {
+                Some(amount) => amount,
+                None => offer_balance.checked_sub(prev_balance.unwrap_or_default())?,
+            };
+
+            let quantity = market.order_quantity(amount, worst_price);
+            if quantity.is_zero() {
+                return Err(StdError::generic_err(
+                    "offer amount is below the market quantity tick",
+                ));
+            }
+
+            let order = SpotOrder::new(
+                market.order_price(worst_price),
+                quantity,
+                market.order_type(),
+                &market.market.market_id,
+                get_default_subaccount_id_for_checked_address(&env.contract.address),
+                None,
+                None,
+            );
+
+            // the order fills into the router, the reply forwards the return
+            let ask_balance = query_balance(
+                &deps.querier,
+                env.contract.address.clone(),
+                market.ask_denom().to_string(),
+            )?;
+            let payload = to_json_binary(&HelixSpotSwap {
+                operation,
+                ask_balance,
+                offer_balance: offer_balance.checked_sub(amount)?,
+                to,
+                refund_to,
+            })?;
+
+            return Ok(Response::new().add_submessage(
+                SubMsg::reply_always(
+                    create_spot_market_order_msg(env.contract.address, order),
+                    HELIX_SPOT_REPLY_ID,
+                )
+                .with_payload(payload),
+            ));
         }
     };
 
-    Ok(Response::new().add_messages(messages))
+    // a failing hop is reported with its assets instead of the bare pair error
+    Ok(Response::new().add_submessage(
+        SubMsg::reply_on_error(message, SWAP_OPERATION_REPLY_ID).with_payload(payload),
+    ))
+}
+
+/// Sends what a helix spot order returned to the hop receiver,
+/// intermediate hops leave it to the next operation. The offer the
+/// order did not spend is refunded
+pub fn settle_helix_spot_swap(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: Env,
+    swap: HelixSpotSwap,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    let (offer_denom, denom) = match (
+        swap.operation.get_offer_asset_info(),
+        swap.operation.get_target_asset_info(),
+    ) {
+        (AssetInfo::NativeToken { denom: offer_denom }, AssetInfo::NativeToken { denom }) => {
+            (offer_denom, denom)
+        }
+        _ => {
+            return Err(StdError::generic_err(
+                "helix spot operations only trade native tokens",
+            ))
+        }
+    };
+
+    let return_amount = query_balance(&deps.querier, env.contract.address.clone(), denom.clone())?
+        .checked_sub(swap.ask_balance)?;
+    let refund_amount = query_balance(&deps.querier, env.contract.address, offer_denom.clone())?
+        .checked_sub(swap.offer_balance)?;
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    if let Some(to) = swap.to {
+        if !return_amount.is_zero() {
+            messages.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: to,
+                amount: vec![Coin {
+                    denom,
+                    amount: return_amount,
+                }],
+            }));
+        }
+    }
+    if let Some(refund_to) = swap.refund_to {
+        if !refund_amount.is_zero() {
+            messages.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: refund_to,
+                amount: vec![Coin {
+                    denom: offer_denom,
+                    amount: refund_amount,
+                }],
+            }));
+        }
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "helix_spot_swap")
+        .add_attribute("return_amount", return_amount.to_string())
+        .add_attribute("refund_amount", refund_amount.to_string()))
 }
 
 pub fn asset_into_swap_msg(
@@ -77,9 +233,10 @@ pub fn asset_into_swap_msg(
     pair_contract: Addr,
     offer_asset: Asset,
     max_spread: Option<Decimal>,
+    belief_price: Option<Decimal>,
     to: Option<String>,
     deadline: Option<u64>,
-) -> StdResult<CosmosMsg> {
+) -> StdResult<CosmosMsg<InjectiveMsgWrapper>> {
     match offer_asset.info.clone() {
         AssetInfo::NativeToken { denom } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: pair_contract.to_string(),
@@ -89,7 +246,7 @@ pub fn asset_into_swap_msg(
             }],
             msg: to_json_binary(&PairExecuteMsg::Swap {
                 offer_asset,
-                belief_price: None,
+                belief_price,
                 max_spread,
                 to,
                 deadline,
@@ -103,7 +260,7 @@ pub fn asset_into_swap_msg(
                 amount: offer_asset.amount,
                 msg: to_json_binary(&PairExecuteMsg::Swap {
                     offer_asset,
-                    belief_price: None,
+                    belief_price,
                     max_spread,
                     to,
                     deadline,
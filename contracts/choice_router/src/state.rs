@@ -1,12 +1,29 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::CanonicalAddr;
-use cw_storage_plus::Item;
+use choice::asset::AssetInfo;
+use cosmwasm_std::{Addr, CanonicalAddr, Decimal, Uint128};
+use cw_storage_plus::{Item, Map};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Config {
     pub choice_factory: CanonicalAddr,
+    /// Routers stored before per hop slippage leave it to the pairs
+    #[serde(default)]
+    pub max_spread: Option<Decimal>,
+    /// Routers stored before referrals accept none
+    #[serde(default)]
+    pub max_referral_commission_bps: u16,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ReferralStats {
+    pub asset_info: AssetInfo,
+    pub volume: Uint128,
+    pub commission: Uint128,
+}
+
+/// Referral stats by referrer and offer asset
+pub const REFERRAL_STATS: Map<(&Addr, &str), ReferralStats> = Map::new("referral_stats");
@@ -1,18 +1,28 @@
 use cosmwasm_std::testing::{mock_env, message_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    coin, from_json, to_json_binary, Addr, Api, Coin, CosmosMsg, StdError, SubMsg, Uint128, WasmMsg
+    coin, from_json, to_json_binary, Addr, Api, BankMsg, Coin, CosmosMsg, Decimal, Reply, StdError, SubMsg,
+    SubMsgResponse, SubMsgResult, Uint128, WasmMsg
 };
+use injective_cosmwasm::{
+    create_spot_market_order_msg, get_default_subaccount_id_for_checked_address, MarketId,
+    MarketStatus, OrderType, PriceLevel, SpotMarket, SpotOrder,
+};
+use injective_math::FPDecimal;
+use std::str::FromStr;
 
-use crate::contract::{execute, instantiate, query};
-use crate::operations::asset_into_swap_msg;
+use crate::contract::{execute, instantiate, query, reply};
+use crate::operations::{
+    asset_into_swap_msg, HelixSpotSwap, HELIX_SPOT_REPLY_ID, SWAP_OPERATION_REPLY_ID,
+};
 use choice::mock_querier::mock_dependencies;
 
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
-use choice::asset::{Asset, AssetInfo, PairInfo};
+use choice::asset::{Asset, AssetInfo, PairInfo, PairType};
 use choice::pair::ExecuteMsg as PairExecuteMsg;
+use choice::querier::query_pairs_by_asset;
 use choice::router::{
-    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
-    SimulateSwapOperationsResponse, SwapOperation,
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, FindBestRouteResponse, InstantiateMsg, QueryMsg,
+    Referral, ReferralAssetStats, ReferralStatsResponse, RouteShare, SimulateSwapOperationsResponse, SplitRoute, SwapOperation,
 };
 
 #[test]
@@ -21,6 +31,8 @@ fn proper_initialization() {
 
     let msg = InstantiateMsg {
         choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: None,
     };
 
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -30,20 +42,28 @@ fn proper_initialization() {
 
     // it worked, let's query the state
     let config: ConfigResponse =
-        from_json(&query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
     assert_eq!(deps.api.addr_make("choicefactory").to_string(), config.choice_factory.as_str());
 }
 
 #[test]
 fn execute_swap_operations() {
     let mut deps = mock_dependencies(&[]);
-    deps.querier.with_token_balances(&[(
-        &deps.api.addr_make("asset0002").to_string(),
-        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1000000u128))],
-    )]);
+    deps.querier.with_token_balances(&[
+        (
+            &deps.api.addr_make("asset0001").to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(500u128))],
+        ),
+        (
+            &deps.api.addr_make("asset0002").to_string(),
+            &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1000000u128))],
+        ),
+    ]);
 
     let msg = InstantiateMsg {
         choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: None,
     };
 
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -56,6 +76,7 @@ fn execute_swap_operations() {
         minimum_receive: None,
         to: None,
         deadline: None,
+        referral: None,
     };
 
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -74,6 +95,8 @@ fn execute_swap_operations() {
                 ask_asset_info: AssetInfo::Token {
                     contract_addr: deps.api.addr_make("asset0001").to_string(),
                 },
+                max_spread: None,
+                belief_price: None,
             },
             SwapOperation::Choice {
                 offer_asset_info: AssetInfo::Token {
@@ -82,6 +105,8 @@ fn execute_swap_operations() {
                 ask_asset_info: AssetInfo::NativeToken {
                     denom: "inj".to_string(),
                 },
+                max_spread: None,
+                belief_price: None,
             },
             SwapOperation::Choice {
                 offer_asset_info: AssetInfo::NativeToken {
@@ -90,14 +115,20 @@ fn execute_swap_operations() {
                 ask_asset_info: AssetInfo::Token {
                     contract_addr: deps.api.addr_make("asset0002").to_string(),
                 },
+                max_spread: None,
+                belief_price: None,
             },
         ],
         minimum_receive: Some(Uint128::from(1000000u128)),
         to: None,
         deadline: None,
+        referral: None,
     };
 
-    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let info = message_info(
+        &deps.api.addr_make("addr0000"),
+        &[coin(1000000u128, "ukrw")],
+    );
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(
         res.messages,
@@ -113,9 +144,14 @@ fn execute_swap_operations() {
                         ask_asset_info: AssetInfo::Token {
                             contract_addr: deps.api.addr_make("asset0001").to_string(),
                         },
+                        max_spread: None,
+                        belief_price: None,
                     },
                     to: None,
                     deadline: None,
+                    amount: Some(Uint128::from(1000000u128)),
+                    prev_balance: None,
+                    refund_to: Some(deps.api.addr_make("addr0000").to_string()),
                 })
                 .unwrap(),
             })),
@@ -130,9 +166,14 @@ fn execute_swap_operations() {
                         ask_asset_info: AssetInfo::NativeToken {
                             denom: "inj".to_string(),
                         },
+                        max_spread: None,
+                        belief_price: None,
                     },
                     to: None,
                     deadline: None,
+                    amount: None,
+                    prev_balance: Some(Uint128::from(500u128)),
+                    refund_to: Some(deps.api.addr_make("addr0000").to_string()),
                 })
                 .unwrap(),
             })),
@@ -147,9 +188,14 @@ fn execute_swap_operations() {
                         ask_asset_info: AssetInfo::Token {
                             contract_addr: deps.api.addr_make("asset0002").to_string(),
                         },
+                        max_spread: None,
+                        belief_price: None,
                     },
                     to: Some(deps.api.addr_make("addr0000").to_string()),
                     deadline: None,
+                    amount: None,
+                    prev_balance: Some(Uint128::zero()),
+                    refund_to: Some(deps.api.addr_make("addr0000").to_string()),
                 })
                 .unwrap(),
             })),
@@ -175,12 +221,14 @@ fn execute_swap_operations() {
         msg: to_json_binary(&Cw20HookMsg::ExecuteSwapOperations {
             operations: vec![
                 SwapOperation::Choice {
-                    offer_asset_info: AssetInfo::NativeToken {
-                        denom: "ukrw".to_string(),
+                    offer_asset_info: AssetInfo::Token {
+                        contract_addr: deps.api.addr_make("asset0000").to_string(),
                     },
                     ask_asset_info: AssetInfo::Token {
                         contract_addr: deps.api.addr_make("asset0001").to_string(),
                     },
+                    max_spread: None,
+                    belief_price: None,
                 },
                 SwapOperation::Choice {
                     offer_asset_info: AssetInfo::Token {
@@ -189,6 +237,8 @@ fn execute_swap_operations() {
                     ask_asset_info: AssetInfo::NativeToken {
                         denom: "inj".to_string(),
                     },
+                    max_spread: None,
+                    belief_price: None,
                 },
                 SwapOperation::Choice {
                     offer_asset_info: AssetInfo::NativeToken {
@@ -197,11 +247,14 @@ fn execute_swap_operations() {
                     ask_asset_info: AssetInfo::Token {
                         contract_addr: deps.api.addr_make("asset0002").to_string(),
                     },
+                    max_spread: None,
+                    belief_price: None,
                 },
             ],
             minimum_receive: None,
             to: Some(deps.api.addr_make("addr0002").to_string()),
             deadline: None,
+            referral: None,
         })
         .unwrap(),
     });
@@ -216,15 +269,20 @@ fn execute_swap_operations() {
                 funds: vec![],
                 msg: to_json_binary(&ExecuteMsg::ExecuteSwapOperation {
                     operation: SwapOperation::Choice {
-                        offer_asset_info: AssetInfo::NativeToken {
-                            denom: "ukrw".to_string(),
+                        offer_asset_info: AssetInfo::Token {
+                            contract_addr: deps.api.addr_make("asset0000").to_string(),
                         },
                         ask_asset_info: AssetInfo::Token {
                             contract_addr: deps.api.addr_make("asset0001").to_string(),
                         },
+                        max_spread: None,
+                        belief_price: None,
                     },
                     to: None,
                     deadline: None,
+                    amount: Some(Uint128::from(1000000u128)),
+                    prev_balance: None,
+                    refund_to: Some(deps.api.addr_make("addr0000").to_string()),
                 })
                 .unwrap(),
             })),
@@ -239,9 +297,14 @@ fn execute_swap_operations() {
                         ask_asset_info: AssetInfo::NativeToken {
                             denom: "inj".to_string(),
                         },
+                        max_spread: None,
+                        belief_price: None,
                     },
                     to: None,
                     deadline: None,
+                    amount: None,
+                    prev_balance: Some(Uint128::from(500u128)),
+                    refund_to: Some(deps.api.addr_make("addr0000").to_string()),
                 })
                 .unwrap(),
             })),
@@ -256,9 +319,14 @@ fn execute_swap_operations() {
                         ask_asset_info: AssetInfo::Token {
                             contract_addr: deps.api.addr_make("asset0002").to_string(),
                         },
+                        max_spread: None,
+                        belief_price: None,
                     },
                     to: Some(deps.api.addr_make("addr0002").to_string()),
                     deadline: None,
+                    amount: None,
+                    prev_balance: Some(Uint128::zero()),
+                    refund_to: Some(deps.api.addr_make("addr0000").to_string()),
                 })
                 .unwrap(),
             }))
@@ -266,11 +334,164 @@ fn execute_swap_operations() {
     );
 }
 
+#[test]
+fn execute_swap_operations_offer_funds() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    deps.querier.with_choice_factory(
+        &[(
+            &("uusd".to_string() + deps.api.addr_make("asset0000").as_str()),
+            &PairInfo {
+                asset_infos: [
+                    AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    AssetInfo::Token {
+                        contract_addr: deps.api.addr_make("asset0000").to_string(),
+                    },
+                ],
+                contract_addr: deps.api.addr_make("pair0000").to_string(),
+                liquidity_token: deps.api.addr_make("liquidity0000").to_string(),
+                asset_decimals: [6u8, 6u8],
+                burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+                fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+                commission_rate: Decimal::permille(3),
+                pair_type: PairType::Xyk {},
+                paused: false,
+            },
+        )],
+        &[("uusd".to_string(), 6u8)],
+    );
+
+    let operation = SwapOperation::Choice {
+        offer_asset_info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        ask_asset_info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+        },
+        max_spread: None,
+        belief_price: None,
+    };
+    let msg = ExecuteMsg::ExecuteSwapOperations {
+        operations: vec![operation.clone()],
+        minimum_receive: None,
+        to: None,
+        deadline: None,
+        referral: None,
+    };
+
+    // only the offer asset of the first operation may be sent
+    for funds in [
+        vec![],
+        vec![coin(0u128, "uusd")],
+        vec![coin(1000u128, "ukrw")],
+        vec![coin(1000u128, "uusd"), coin(1000u128, "ukrw")],
+    ] {
+        let info = message_info(&deps.api.addr_make("addr0000"), &funds);
+        match execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err() {
+            StdError::GenericErr { msg, .. } => {
+                assert_eq!(msg, "funds mismatch; must send only the offer asset uusd")
+            }
+            _ => panic!("DO NOT ENTER HERE"),
+        }
+    }
+
+    // a cw20 offer must match the first operation
+    let hook_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: Uint128::from(1000u128),
+        msg: to_json_binary(&Cw20HookMsg::ExecuteSwapOperations {
+            operations: vec![operation.clone()],
+            minimum_receive: None,
+            to: None,
+            deadline: None,
+            referral: None,
+        })
+        .unwrap(),
+    });
+    let info = message_info(&deps.api.addr_make("asset0000"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, hook_msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => {
+            assert_eq!(msg, "invalid operations; offer asset mismatch")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the first hop swaps the sent amount, not the router balance
+    deps.querier.with_balance(&[(
+        &MOCK_CONTRACT_ADDR.to_string(),
+        vec![coin(5000u128, "uusd")],
+    )]);
+    let info = message_info(&deps.api.addr_make("addr0000"), &[coin(1000u128, "uusd")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            funds: vec![],
+            msg: to_json_binary(&ExecuteMsg::ExecuteSwapOperation {
+                operation: operation.clone(),
+                to: Some(deps.api.addr_make("addr0000").to_string()),
+                deadline: None,
+                amount: Some(Uint128::from(1000u128)),
+                prev_balance: None,
+                refund_to: Some(deps.api.addr_make("addr0000").to_string()),
+            })
+            .unwrap(),
+        }))],
+    );
+
+    // later hops swap only the balance above the snapshot
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: operation.clone(),
+        to: None,
+        deadline: None,
+        amount: None,
+        prev_balance: Some(Uint128::from(4000u128)),
+        refund_to: None,
+    };
+    let info = message_info(&deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            asset_into_swap_msg(
+                deps.as_ref(),
+                deps.api.addr_make("pair0000"),
+                Asset {
+                    info: AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    amount: Uint128::from(1000u128),
+                },
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap(),
+            SWAP_OPERATION_REPLY_ID,
+        )
+        .with_payload(to_json_binary(&operation).unwrap())],
+    );
+}
+
 #[test]
 fn execute_swap_operation() {
     let mut deps = mock_dependencies(&[]);
     let msg = InstantiateMsg {
         choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: None,
     };
 
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
@@ -295,6 +516,9 @@ fn execute_swap_operation() {
                 asset_decimals: [6u8, 6u8],
                 burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                 fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                commission_rate: Decimal::permille(3),
+                pair_type: PairType::Xyk {},
+                paused: false,
             },
         )],
         &[("uusd".to_string(), 6u8)],
@@ -308,17 +532,23 @@ fn execute_swap_operation() {
         .to_vec(),
     )]);
 
-    let msg = ExecuteMsg::ExecuteSwapOperation {
-        operation: SwapOperation::Choice {
-            offer_asset_info: AssetInfo::NativeToken {
-                denom: "uusd".to_string(),
-            },
-            ask_asset_info: AssetInfo::Token {
-                contract_addr: deps.api.addr_make("asset0000").to_string(),
-            },
+    let operation = SwapOperation::Choice {
+        offer_asset_info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        ask_asset_info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
         },
+        max_spread: None,
+        belief_price: None,
+    };
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: operation.clone(),
         to: None,
         deadline: None,
+        amount: None,
+        prev_balance: None,
+        refund_to: None,
     };
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
@@ -335,7 +565,7 @@ fn execute_swap_operation() {
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(
+        vec![SubMsg::reply_on_error(
             asset_into_swap_msg(
                 deps.as_ref(),
                 Addr::unchecked(deps.api.addr_make("pair0000")),
@@ -348,31 +578,40 @@ fn execute_swap_operation() {
                 None,
                 None,
                 None,
+                None,
             )
-            .unwrap()
-        )],
+            .unwrap(),
+            SWAP_OPERATION_REPLY_ID,
+        )
+        .with_payload(to_json_binary(&operation).unwrap())],
     );
 
     // optional to address
     // swap_send
-    let msg = ExecuteMsg::ExecuteSwapOperation {
-        operation: SwapOperation::Choice {
-            offer_asset_info: AssetInfo::NativeToken {
-                denom: "uusd".to_string(),
-            },
-            ask_asset_info: AssetInfo::Token {
-                contract_addr: deps.api.addr_make("asset0000").to_string(),
-            },
+    let operation = SwapOperation::Choice {
+        offer_asset_info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
         },
+        ask_asset_info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+        },
+        max_spread: None,
+        belief_price: None,
+    };
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: operation.clone(),
         to: Some(deps.api.addr_make("addr0000").to_string()),
         deadline: None,
+        amount: None,
+        prev_balance: None,
+        refund_to: None,
     };
 
     let info = message_info( &deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(
+        vec![SubMsg::reply_on_error(
             asset_into_swap_msg(
                 deps.as_ref(),
                 Addr::unchecked(deps.api.addr_make("pair0000")),
@@ -383,11 +622,14 @@ fn execute_swap_operation() {
                     amount: Uint128::from(1000000u128)
                 },
                 None,
+                None,
                 Some(deps.api.addr_make("addr0000").to_string()),
                 None,
             )
-            .unwrap()
-        )],
+            .unwrap(),
+            SWAP_OPERATION_REPLY_ID,
+        )
+        .with_payload(to_json_binary(&operation).unwrap())],
     );
     deps.querier.with_choice_factory(
         &[(
@@ -406,6 +648,9 @@ fn execute_swap_operation() {
                 asset_decimals: [6u8, 6u8],
                 burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                 fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                commission_rate: Decimal::permille(3),
+                pair_type: PairType::Xyk {},
+                paused: false,
             },
         )],
         &[("uusd".to_string(), 6u8)],
@@ -415,24 +660,31 @@ fn execute_swap_operation() {
         &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1000000u128))],
     )]);
 
-    let msg = ExecuteMsg::ExecuteSwapOperation {
-        operation: SwapOperation::Choice {
-            offer_asset_info: AssetInfo::Token {
-                contract_addr: deps.api.addr_make("asset").to_string(),
-            },
-            ask_asset_info: AssetInfo::NativeToken {
-                denom: "uusd".to_string(),
-            },
+    let operation = SwapOperation::Choice {
+        offer_asset_info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset").to_string(),
+        },
+        ask_asset_info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
         },
+        max_spread: None,
+        belief_price: None,
+    };
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: operation.clone(),
         to: Some(deps.api.addr_make("addr0000").to_string()),
         deadline: None,
+        amount: None,
+        prev_balance: None,
+        refund_to: None,
     };
 
     let info = message_info(&deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: deps.api.addr_make("asset").to_string(),
             funds: vec![],
             msg: to_json_binary(&Cw20ExecuteMsg::Send {
@@ -453,108 +705,1661 @@ fn execute_swap_operation() {
                 .unwrap()
             })
             .unwrap()
-        }))]
+            }),
+            SWAP_OPERATION_REPLY_ID,
+        )
+        .with_payload(to_json_binary(&operation).unwrap())]
     );
 }
 
 #[test]
-fn query_buy_with_routes() {
+fn update_config() {
     let mut deps = mock_dependencies(&[]);
-
     let msg = InstantiateMsg {
         choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: Some(Decimal::percent(1)),
+        max_referral_commission_bps: None,
     };
 
     let info = message_info(&deps.api.addr_make("addr0000"), &[]);
-
-    // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-    let msg = QueryMsg::SimulateSwapOperations {
-        offer_amount: Uint128::from(1000000u128),
-        operations: vec![
-            SwapOperation::Choice {
-                offer_asset_info: AssetInfo::NativeToken {
-                    denom: "ukrw".to_string(),
-                },
-                ask_asset_info: AssetInfo::Token {
-                    contract_addr: deps.api.addr_make("asset0000").to_string(),
-                },
-            },
-            SwapOperation::Choice {
-                offer_asset_info: AssetInfo::Token {
-                    contract_addr: deps.api.addr_make("asset0000").to_string(),
-                },
-                ask_asset_info: AssetInfo::NativeToken {
-                    denom: "inj".to_string(),
-                },
-            },
-        ],
-    };
-
-    deps.querier.with_choice_factory(
-        &[
-            (
-                &format!("ukrw{}", deps.api.addr_make("asset0000")),
-                &PairInfo {
-                    asset_infos: [
-                        AssetInfo::NativeToken {
-                            denom: "ukrw".to_string(),
-                        },
-                        AssetInfo::Token {
-                            contract_addr: deps.api.addr_make("asset0000").to_string(),
-                        },
-                    ],
-                    contract_addr: deps.api.addr_make("pair0000").to_string(),
-                    liquidity_token: deps.api.addr_make("liquidity0000").to_string(),
-                    asset_decimals: [6u8, 6u8],
-                    burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
-                    fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
-                },
-            ),
-            (
-                &format!("{}inj", deps.api.addr_make("asset0000")),
-                &PairInfo {
-                    asset_infos: [
-                        AssetInfo::Token {
-                            contract_addr: deps.api.addr_make("asset0000").to_string(),
-                        },
-                        AssetInfo::NativeToken {
-                            denom: "inj".to_string(),
-                        },
-                    ],
-                    contract_addr: deps.api.addr_make("pair0001").to_string(),
-                    liquidity_token: deps.api.addr_make("liquidity0001").to_string(),
-                    asset_decimals: [6u8, 6u8],
-                    burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
-                    fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
-                },
-            ),
-        ],
-        &[("ukrw".to_string(), 6u8), ("inj".to_string(), 6u8)],
-    );
-
-    let res: SimulateSwapOperationsResponse =
-        from_json(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
-    assert_eq!(
-        res,
-        SimulateSwapOperationsResponse {
-            amount: Uint128::from(1000000u128)
-        }
-    );
-}
+    let config: ConfigResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.max_spread, Some(Decimal::percent(1)));
 
-#[test]
-fn query_reverse_routes_with_from_native() {
-    let mut deps = mock_dependencies(&[]);
+    let admin = deps.api.addr_make("admin");
+    deps.querier
+        .with_contract_admin(MOCK_CONTRACT_ADDR, admin.as_str());
 
-    let msg = InstantiateMsg {
-        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+    let msg = ExecuteMsg::UpdateConfig {
+        max_spread: Some(Decimal::percent(5)),
+        clear_max_spread: None,
+        max_referral_commission_bps: None,
     };
 
-    let target_amount = 1000000u128;
-
-    let info = message_info(&deps.api.addr_make("addr0000"), &[coin(10000000, "ukrw")]);
+    // only the contract admin can update
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "unauthorized"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = message_info(&admin, &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let config: ConfigResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.max_spread, Some(Decimal::percent(5)));
+
+    // a max spread of 1 or more would disable the per hop protection
+    let msg = ExecuteMsg::UpdateConfig {
+        max_spread: Some(Decimal::one()),
+        clear_max_spread: None,
+        max_referral_commission_bps: None,
+    };
+    let info = message_info(&admin, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "max spread must be below 1"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::UpdateConfig {
+        max_spread: Some(Decimal::percent(5)),
+        clear_max_spread: Some(true),
+        max_referral_commission_bps: None,
+    };
+    let info = message_info(&admin, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "max spread cannot be set and cleared at once")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::UpdateConfig {
+        max_spread: None,
+        clear_max_spread: Some(true),
+        max_referral_commission_bps: None,
+    };
+    let info = message_info(&admin, &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let config: ConfigResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.max_spread, None);
+}
+
+#[test]
+fn execute_swap_operation_max_spread() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: Some(Decimal::percent(1)),
+        max_referral_commission_bps: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    deps.querier.with_choice_factory(
+        &[(
+            &("uusd".to_string() + deps.api.addr_make("asset0000").as_str()),
+            &PairInfo {
+                asset_infos: [
+                    AssetInfo::NativeToken {
+                        denom: "uusd".to_string(),
+                    },
+                    AssetInfo::Token {
+                        contract_addr: deps.api.addr_make("asset0000").to_string(),
+                    },
+                ],
+                contract_addr: deps.api.addr_make("pair0000").to_string(),
+                liquidity_token: deps.api.addr_make("liquidity0000").to_string(),
+                asset_decimals: [6u8, 6u8],
+                burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+                fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+                commission_rate: Decimal::permille(3),
+                pair_type: PairType::Xyk {},
+                paused: false,
+            },
+        )],
+        &[("uusd".to_string(), 6u8)],
+    );
+
+    let offer_asset = Asset {
+        info: AssetInfo::NativeToken {
+            denom: "uusd".to_string(),
+        },
+        amount: Uint128::from(1000000u128),
+    };
+
+    // the router max spread applies to hops without their own
+    let operation = SwapOperation::Choice {
+        offer_asset_info: offer_asset.info.clone(),
+        ask_asset_info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+        },
+        max_spread: None,
+        belief_price: None,
+    };
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: operation.clone(),
+        to: None,
+        deadline: None,
+        amount: Some(offer_asset.amount),
+        prev_balance: None,
+        refund_to: None,
+    };
+
+    let info = message_info(&deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            asset_into_swap_msg(
+                deps.as_ref(),
+                deps.api.addr_make("pair0000"),
+                offer_asset.clone(),
+                Some(Decimal::percent(1)),
+                None,
+                None,
+                None,
+            )
+            .unwrap(),
+            SWAP_OPERATION_REPLY_ID,
+        )
+        .with_payload(to_json_binary(&operation).unwrap())],
+    );
+
+    // the hop overrides the router max spread
+    let operation = SwapOperation::Choice {
+        offer_asset_info: offer_asset.info.clone(),
+        ask_asset_info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+        },
+        max_spread: Some(Decimal::percent(5)),
+        belief_price: Some(Decimal::percent(50)),
+    };
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: operation.clone(),
+        to: None,
+        deadline: None,
+        amount: Some(offer_asset.amount),
+        prev_balance: None,
+        refund_to: None,
+    };
+
+    let info = message_info(&deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            asset_into_swap_msg(
+                deps.as_ref(),
+                deps.api.addr_make("pair0000"),
+                offer_asset,
+                Some(Decimal::percent(5)),
+                Some(Decimal::percent(50)),
+                None,
+                None,
+            )
+            .unwrap(),
+            SWAP_OPERATION_REPLY_ID,
+        )
+        .with_payload(to_json_binary(&operation).unwrap())],
+    );
+
+    // a failing hop reports its assets
+    let reply_msg = Reply {
+        id: SWAP_OPERATION_REPLY_ID,
+        payload: to_json_binary(&operation).unwrap(),
+        gas_used: 0,
+        result: SubMsgResult::Err("Operation exceeds max spread limit".to_string()),
+    };
+    let res = reply(deps.as_mut(), mock_env(), reply_msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            format!(
+                "swap operation uusd -> {} failed: Operation exceeds max spread limit",
+                deps.api.addr_make("asset0000")
+            )
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn execute_swap_operations_with_referral() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: Some(10001),
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "max referral commission must not exceed 10000 bps")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: Some(100),
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let referrer = deps.api.addr_make("referrer");
+    let operation = SwapOperation::Choice {
+        offer_asset_info: AssetInfo::NativeToken {
+            denom: "ukrw".to_string(),
+        },
+        ask_asset_info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+        },
+        max_spread: None,
+        belief_price: None,
+    };
+
+    let msg = ExecuteMsg::ExecuteSwapOperations {
+        operations: vec![operation.clone()],
+        minimum_receive: None,
+        to: None,
+        deadline: None,
+        referral: Some(Referral {
+            address: referrer.to_string(),
+            commission_bps: 101,
+        }),
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[coin(1000000, "ukrw")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "referral commission exceeds the max of 100 bps")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the commission is sent before the offer is swapped
+    let msg = ExecuteMsg::ExecuteSwapOperations {
+        operations: vec![operation.clone()],
+        minimum_receive: None,
+        to: None,
+        deadline: None,
+        referral: Some(Referral {
+            address: referrer.to_string(),
+            commission_bps: 50,
+        }),
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[coin(1000000, "ukrw")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: referrer.to_string(),
+                amount: vec![coin(5000, "ukrw")],
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_json_binary(&ExecuteMsg::ExecuteSwapOperation {
+                    operation: operation.clone(),
+                    to: Some(deps.api.addr_make("addr0000").to_string()),
+                    deadline: None,
+                    amount: Some(Uint128::from(995000u128)),
+                    prev_balance: None,
+                    refund_to: Some(deps.api.addr_make("addr0000").to_string()),
+                })
+                .unwrap(),
+            })),
+        ],
+    );
+
+    let token_operation = SwapOperation::Choice {
+        offer_asset_info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+        },
+        ask_asset_info: AssetInfo::NativeToken {
+            denom: "ukrw".to_string(),
+        },
+        max_spread: None,
+        belief_price: None,
+    };
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: Uint128::from(2000000u128),
+        msg: to_json_binary(&Cw20HookMsg::ExecuteSwapOperations {
+            operations: vec![token_operation.clone()],
+            minimum_receive: None,
+            to: None,
+            deadline: None,
+            referral: Some(Referral {
+                address: referrer.to_string(),
+                commission_bps: 50,
+            }),
+        })
+        .unwrap(),
+    });
+    let info = message_info(&deps.api.addr_make("asset0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+            funds: vec![],
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: referrer.to_string(),
+                amount: Uint128::from(10000u128),
+            })
+            .unwrap(),
+        })),
+    );
+
+    // a second native referral adds up
+    let msg = ExecuteMsg::ExecuteSwapOperations {
+        operations: vec![operation],
+        minimum_receive: None,
+        to: None,
+        deadline: None,
+        referral: Some(Referral {
+            address: referrer.to_string(),
+            commission_bps: 100,
+        }),
+    };
+    let info = message_info(&deps.api.addr_make("addr0001"), &[coin(500000, "ukrw")]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res: ReferralStatsResponse = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ReferralStats {
+                referrer: referrer.to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        ReferralStatsResponse {
+            referrer: referrer.to_string(),
+            stats: vec![
+                ReferralAssetStats {
+                    asset_info: token_operation.get_offer_asset_info(),
+                    volume: Uint128::from(2000000u128),
+                    commission: Uint128::from(10000u128),
+                },
+                ReferralAssetStats {
+                    asset_info: AssetInfo::NativeToken {
+                        denom: "ukrw".to_string(),
+                    },
+                    volume: Uint128::from(1500000u128),
+                    commission: Uint128::from(10000u128),
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn execute_split_swap() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&deps.api.addr_make("addr0000").to_string(), &Uint128::zero())],
+    )]);
+
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let inj = AssetInfo::NativeToken {
+        denom: "inj".to_string(),
+    };
+    let ukrw = AssetInfo::NativeToken {
+        denom: "ukrw".to_string(),
+    };
+    let asset0000 = AssetInfo::Token {
+        contract_addr: deps.api.addr_make("asset0000").to_string(),
+    };
+    let direct = vec![SwapOperation::Choice {
+        offer_asset_info: inj.clone(),
+        ask_asset_info: asset0000.clone(),
+        max_spread: None,
+        belief_price: None,
+    }];
+    let through_ukrw = vec![
+        SwapOperation::Choice {
+            offer_asset_info: inj.clone(),
+            ask_asset_info: ukrw.clone(),
+            max_spread: None,
+            belief_price: None,
+        },
+        SwapOperation::Choice {
+            offer_asset_info: ukrw.clone(),
+            ask_asset_info: asset0000.clone(),
+            max_spread: None,
+            belief_price: None,
+        },
+    ];
+    let routes = vec![
+        SplitRoute {
+            share: RouteShare::Amount {
+                amount: Uint128::from(100u128),
+            },
+            operations: direct.clone(),
+        },
+        SplitRoute {
+            share: RouteShare::Weight {
+                weight: Decimal::one(),
+            },
+            operations: direct.clone(),
+        },
+        SplitRoute {
+            share: RouteShare::Weight {
+                weight: Decimal::percent(200),
+            },
+            operations: through_ukrw.clone(),
+        },
+    ];
+
+    // the fixed amount is taken first, the rest is split 1:2
+    let msg = ExecuteMsg::ExecuteSplitSwap {
+        routes: routes.clone(),
+        minimum_receive: Some(Uint128::from(900u128)),
+        to: None,
+        deadline: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[coin(1100u128, "inj")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let sender = deps.api.addr_make("addr0000").to_string();
+    let swap_operation_msg = |operation: SwapOperation,
+                              to: Option<String>,
+                              amount: Option<u128>,
+                              prev_balance: Option<u128>| {
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            funds: vec![],
+            msg: to_json_binary(&ExecuteMsg::ExecuteSwapOperation {
+                operation,
+                to,
+                deadline: None,
+                amount: amount.map(Uint128::from),
+                prev_balance: prev_balance.map(Uint128::from),
+                refund_to: Some(sender.clone()),
+            })
+            .unwrap(),
+        }))
+    };
+    let receiver = Some(deps.api.addr_make("addr0000").to_string());
+    assert_eq!(
+        res.messages,
+        vec![
+            swap_operation_msg(direct[0].clone(), receiver.clone(), Some(100), None),
+            swap_operation_msg(direct[0].clone(), receiver.clone(), Some(333), None),
+            swap_operation_msg(through_ukrw[0].clone(), None, Some(667), None),
+            swap_operation_msg(through_ukrw[1].clone(), receiver.clone(), None, Some(0)),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_json_binary(&ExecuteMsg::AssertMinimumReceive {
+                    asset_info: asset0000.clone(),
+                    prev_balance: Uint128::zero(),
+                    minimum_receive: Uint128::from(900u128),
+                    receiver: deps.api.addr_make("addr0000").to_string(),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    // fixed amounts above the offer
+    let msg = ExecuteMsg::ExecuteSplitSwap {
+        routes: routes.clone(),
+        minimum_receive: None,
+        to: None,
+        deadline: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[coin(99u128, "inj")]);
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "route amounts exceed the offer amount"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // routes must end in the same asset
+    let msg = ExecuteMsg::ExecuteSplitSwap {
+        routes: vec![
+            routes[0].clone(),
+            SplitRoute {
+                share: RouteShare::Weight {
+                    weight: Decimal::one(),
+                },
+                operations: vec![through_ukrw[0].clone()],
+            },
+        ],
+        minimum_receive: None,
+        to: None,
+        deadline: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[coin(1100u128, "inj")]);
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(
+            msg,
+            "invalid routes; routes must share the offer and ask assets"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // token offer through the hook
+    let token_routes = vec![
+        SplitRoute {
+            share: RouteShare::Weight {
+                weight: Decimal::one(),
+            },
+            operations: vec![SwapOperation::Choice {
+                offer_asset_info: asset0000.clone(),
+                ask_asset_info: inj.clone(),
+                max_spread: None,
+                belief_price: None,
+            }],
+        },
+        SplitRoute {
+            share: RouteShare::Weight {
+                weight: Decimal::one(),
+            },
+            operations: vec![
+                SwapOperation::Choice {
+                    offer_asset_info: asset0000.clone(),
+                    ask_asset_info: ukrw.clone(),
+                    max_spread: None,
+                    belief_price: None,
+                },
+                SwapOperation::Choice {
+                    offer_asset_info: ukrw.clone(),
+                    ask_asset_info: inj.clone(),
+                    max_spread: None,
+                    belief_price: None,
+                },
+            ],
+        },
+    ];
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: Uint128::from(1001u128),
+        msg: to_json_binary(&Cw20HookMsg::ExecuteSplitSwap {
+            routes: token_routes.clone(),
+            minimum_receive: None,
+            to: None,
+            deadline: None,
+        })
+        .unwrap(),
+    });
+    let info = message_info(&deps.api.addr_make("asset0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            swap_operation_msg(token_routes[0].operations[0].clone(), receiver.clone(), Some(500), None),
+            swap_operation_msg(token_routes[1].operations[0].clone(), None, Some(501), None),
+            swap_operation_msg(token_routes[1].operations[1].clone(), receiver, None, Some(0)),
+        ]
+    );
+
+    // a different token cannot pay for the routes
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: Uint128::from(1001u128),
+        msg: to_json_binary(&Cw20HookMsg::ExecuteSplitSwap {
+            routes: token_routes,
+            minimum_receive: None,
+            to: None,
+            deadline: None,
+        })
+        .unwrap(),
+    });
+    let info = message_info(&deps.api.addr_make("asset0001"), &[]);
+    match execute(deps.as_mut(), mock_env(), info, msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => {
+            assert_eq!(msg, "invalid routes; offer asset mismatch")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn execute_swap_operations_exact_out() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &deps.api.addr_make("asset0000").to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::zero())],
+    )]);
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let ukrw = AssetInfo::NativeToken {
+        denom: "ukrw".to_string(),
+    };
+    let inj = AssetInfo::NativeToken {
+        denom: "inj".to_string(),
+    };
+    let asset0000 = AssetInfo::Token {
+        contract_addr: deps.api.addr_make("asset0000").to_string(),
+    };
+    deps.querier.with_choice_factory(
+        &[
+            (
+                &format!("ukrw{}", deps.api.addr_make("asset0000")),
+                &PairInfo {
+                    asset_infos: [ukrw.clone(), asset0000.clone()],
+                    contract_addr: deps.api.addr_make("pair0000").to_string(),
+                    liquidity_token: deps.api.addr_make("liquidity0000").to_string(),
+                    asset_decimals: [6u8, 6u8],
+                    burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+                    fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+                    commission_rate: Decimal::permille(3),
+                    pair_type: PairType::Xyk {},
+                    paused: false,
+                },
+            ),
+            (
+                &format!("{}inj", deps.api.addr_make("asset0000")),
+                &PairInfo {
+                    asset_infos: [asset0000.clone(), inj.clone()],
+                    contract_addr: deps.api.addr_make("pair0001").to_string(),
+                    liquidity_token: deps.api.addr_make("liquidity0001").to_string(),
+                    asset_decimals: [6u8, 6u8],
+                    burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+                    fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+                    commission_rate: Decimal::permille(3),
+                    pair_type: PairType::Xyk {},
+                    paused: false,
+                },
+            ),
+        ],
+        &[("ukrw".to_string(), 6u8), ("inj".to_string(), 6u8)],
+    );
+
+    let operations = vec![
+        SwapOperation::Choice {
+            offer_asset_info: ukrw.clone(),
+            ask_asset_info: asset0000.clone(),
+            max_spread: None,
+            belief_price: None,
+        },
+        SwapOperation::Choice {
+            offer_asset_info: asset0000.clone(),
+            ask_asset_info: inj.clone(),
+            max_spread: None,
+            belief_price: None,
+        },
+    ];
+
+    // the mock pairs reverse simulate one to one
+    let msg = ExecuteMsg::ExecuteSwapOperationsExactOut {
+        operations: operations.clone(),
+        ask_amount: Uint128::from(1000000u128),
+        max_offer: Some(Uint128::from(999999u128)),
+        to: None,
+        deadline: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[coin(1500000, "ukrw")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "assertion failed; max offer amount: 999999, offer amount: 1000000"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::ExecuteSwapOperationsExactOut {
+        operations: operations.clone(),
+        ask_amount: Uint128::from(1000000u128),
+        max_offer: None,
+        to: None,
+        deadline: None,
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[coin(500000, "ukrw")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(
+            msg,
+            "insufficient offer amount; sent: 500000, required: 1000000"
+        ),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[coin(1500000, "ukrw")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_json_binary(&ExecuteMsg::ExecuteSwapOperation {
+                    operation: operations[0].clone(),
+                    to: None,
+                    deadline: None,
+                    amount: Some(Uint128::from(1000000u128)),
+                    prev_balance: None,
+                    refund_to: Some(deps.api.addr_make("addr0000").to_string()),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_json_binary(&ExecuteMsg::ExecuteSwapOperation {
+                    operation: operations[1].clone(),
+                    to: Some(deps.api.addr_make("addr0000").to_string()),
+                    deadline: None,
+                    amount: None,
+                    prev_balance: Some(Uint128::zero()),
+                    refund_to: Some(deps.api.addr_make("addr0000").to_string()),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_json_binary(&ExecuteMsg::AssertMinimumReceive {
+                    asset_info: inj.clone(),
+                    prev_balance: Uint128::zero(),
+                    minimum_receive: Uint128::from(1000000u128),
+                    receiver: deps.api.addr_make("addr0000").to_string(),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: deps.api.addr_make("addr0000").to_string(),
+                amount: vec![coin(500000, "ukrw")],
+            })),
+        ],
+    );
+
+    // token offers come through the receive hook
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: deps.api.addr_make("addr0000").to_string(),
+        amount: Uint128::from(1200000u128),
+        msg: to_json_binary(&Cw20HookMsg::ExecuteSwapOperationsExactOut {
+            operations: vec![operations[1].clone()],
+            ask_amount: Uint128::from(1000000u128),
+            max_offer: None,
+            to: Some(deps.api.addr_make("addr0001").to_string()),
+            deadline: None,
+        })
+        .unwrap(),
+    });
+    let info = message_info(&deps.api.addr_make("asset0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_json_binary(&ExecuteMsg::ExecuteSwapOperation {
+                    operation: operations[1].clone(),
+                    to: Some(deps.api.addr_make("addr0001").to_string()),
+                    deadline: None,
+                    amount: Some(Uint128::from(1000000u128)),
+                    prev_balance: None,
+                    refund_to: Some(deps.api.addr_make("addr0000").to_string()),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_json_binary(&ExecuteMsg::AssertMinimumReceive {
+                    asset_info: inj,
+                    prev_balance: Uint128::zero(),
+                    minimum_receive: Uint128::from(1000000u128),
+                    receiver: deps.api.addr_make("addr0001").to_string(),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+                funds: vec![],
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: deps.api.addr_make("addr0000").to_string(),
+                    amount: Uint128::from(200000u128),
+                })
+                .unwrap(),
+            })),
+        ],
+    );
+}
+
+fn inj_usdt_spot_market() -> SpotMarket {
+    SpotMarket {
+        ticker: "INJ/USDT".to_string(),
+        base_denom: "inj".to_string(),
+        quote_denom: "usdt".to_string(),
+        maker_fee_rate: FPDecimal::from_str("0.0005").unwrap(),
+        taker_fee_rate: FPDecimal::from_str("0.001").unwrap(),
+        relayer_fee_share_rate: FPDecimal::from_str("0.4").unwrap(),
+        market_id: MarketId::new(
+            "0x0611780ba69656949525013d947713300f56c37b6175e02f26bffa495c3208fe",
+        )
+        .unwrap(),
+        status: MarketStatus::Active,
+        min_price_tick_size: FPDecimal::from_str("0.01").unwrap(),
+        min_quantity_tick_size: FPDecimal::from(1000u128),
+        min_notional: FPDecimal::ZERO,
+    }
+}
+
+// bids at 2 and 1.9, asks at 2.1 and 2.2, 1000000 inj each
+fn inj_usdt_orderbook() -> (Vec<PriceLevel>, Vec<PriceLevel>) {
+    (
+        vec![
+            PriceLevel::new(FPDecimal::from(2u128), FPDecimal::from(1000000u128)),
+            PriceLevel::new(FPDecimal::from_str("1.9").unwrap(), FPDecimal::from(1000000u128)),
+        ],
+        vec![
+            PriceLevel::new(FPDecimal::from_str("2.1").unwrap(), FPDecimal::from(1000000u128)),
+            PriceLevel::new(FPDecimal::from_str("2.2").unwrap(), FPDecimal::from(1000000u128)),
+        ],
+    )
+}
+
+#[test]
+fn execute_helix_spot_operation() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let market = inj_usdt_spot_market();
+    let (buys, sells) = inj_usdt_orderbook();
+    deps.querier.with_spot_markets(&[&market]);
+    deps.querier
+        .with_spot_orderbooks(&[(&market.market_id, buys, sells)]);
+
+    let operation = SwapOperation::HelixSpot {
+        market_id: market.market_id.as_str().to_string(),
+        offer_asset_info: AssetInfo::NativeToken {
+            denom: "inj".to_string(),
+        },
+        ask_asset_info: AssetInfo::NativeToken {
+            denom: "usdt".to_string(),
+        },
+        worst_price: FPDecimal::from_str("1.9").unwrap(),
+    };
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: operation.clone(),
+        to: Some(deps.api.addr_make("addr0000").to_string()),
+        deadline: None,
+        amount: Some(Uint128::from(1500000u128)),
+        prev_balance: None,
+        refund_to: Some(deps.api.addr_make("addr0001").to_string()),
+    };
+
+    // subaccounts derive from 20 byte injective addresses
+    let contract_addr = Addr::unchecked("inj14hj2tavq8fpesdwxxcu44rty3hh90vhujaxlnz");
+    let mut env = mock_env();
+    env.contract.address = contract_addr.clone();
+    deps.querier
+        .with_balance(&[(&contract_addr.to_string(), vec![coin(1500500, "inj")])]);
+
+    let info = message_info(&contract_addr, &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_always(
+            create_spot_market_order_msg(
+                contract_addr.clone(),
+                SpotOrder::new(
+                    FPDecimal::from_str("1.9").unwrap(),
+                    FPDecimal::from(1500000u128),
+                    OrderType::SellAtomic,
+                    &market.market_id,
+                    get_default_subaccount_id_for_checked_address(&contract_addr),
+                    None,
+                    None,
+                ),
+            ),
+            HELIX_SPOT_REPLY_ID,
+        )
+        .with_payload(
+            to_json_binary(&HelixSpotSwap {
+                operation: operation.clone(),
+                ask_balance: Uint128::zero(),
+                offer_balance: Uint128::from(500u128),
+                to: Some(deps.api.addr_make("addr0000").to_string()),
+                refund_to: Some(deps.api.addr_make("addr0001").to_string()),
+            })
+            .unwrap()
+        )],
+    );
+
+    // tokens cannot go through the orderbook
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: SwapOperation::HelixSpot {
+            market_id: market.market_id.as_str().to_string(),
+            offer_asset_info: AssetInfo::Token {
+                contract_addr: deps.api.addr_make("asset0000").to_string(),
+            },
+            ask_asset_info: AssetInfo::NativeToken {
+                denom: "usdt".to_string(),
+            },
+            worst_price: FPDecimal::from_str("1.9").unwrap(),
+        },
+        to: None,
+        deadline: None,
+        amount: Some(Uint128::from(1500000u128)),
+        prev_balance: None,
+        refund_to: None,
+    };
+    let info = message_info(&contract_addr, &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "helix spot operations only trade native tokens")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the reply forwards the order return to the receiver
+    deps.querier.with_balance(&[(
+        &contract_addr.to_string(),
+        vec![coin(500, "inj"), coin(2944100, "usdt")],
+    )]);
+
+    #[allow(deprecated)]
+    let reply_msg = Reply {
+        id: HELIX_SPOT_REPLY_ID,
+        payload: to_json_binary(&HelixSpotSwap {
+            operation: operation.clone(),
+            ask_balance: Uint128::zero(),
+            offer_balance: Uint128::from(500u128),
+            to: Some(deps.api.addr_make("addr0000").to_string()),
+            refund_to: Some(deps.api.addr_make("addr0001").to_string()),
+        })
+        .unwrap(),
+        gas_used: 0,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+            msg_responses: vec![],
+        }),
+    };
+    let res = reply(deps.as_mut(), env.clone(), reply_msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: deps.api.addr_make("addr0000").to_string(),
+            amount: vec![coin(2944100, "usdt")],
+        }))],
+    );
+
+    // a buy reserves the fee at the worst price, the quote it did not spend
+    // goes back to the sender
+    let buy_inj = SwapOperation::HelixSpot {
+        market_id: market.market_id.as_str().to_string(),
+        offer_asset_info: AssetInfo::NativeToken {
+            denom: "usdt".to_string(),
+        },
+        ask_asset_info: AssetInfo::NativeToken {
+            denom: "inj".to_string(),
+        },
+        worst_price: FPDecimal::from_str("2.2").unwrap(),
+    };
+    deps.querier.with_balance(&[(
+        &contract_addr.to_string(),
+        vec![coin(500, "inj"), coin(1000000, "usdt")],
+    )]);
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: buy_inj.clone(),
+        to: Some(deps.api.addr_make("addr0000").to_string()),
+        deadline: None,
+        amount: None,
+        prev_balance: None,
+        refund_to: Some(deps.api.addr_make("addr0001").to_string()),
+    };
+    let info = message_info(&contract_addr, &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let payload = HelixSpotSwap {
+        operation: buy_inj,
+        ask_balance: Uint128::from(500u128),
+        offer_balance: Uint128::zero(),
+        to: Some(deps.api.addr_make("addr0000").to_string()),
+        refund_to: Some(deps.api.addr_make("addr0001").to_string()),
+    };
+    assert_eq!(res.messages[0].payload, to_json_binary(&payload).unwrap());
+
+    // 454000 inj filled at 2.1
+    deps.querier.with_balance(&[(
+        &contract_addr.to_string(),
+        vec![coin(454500, "inj"), coin(45646, "usdt")],
+    )]);
+
+    #[allow(deprecated)]
+    let reply_msg = Reply {
+        id: HELIX_SPOT_REPLY_ID,
+        payload: to_json_binary(&payload).unwrap(),
+        gas_used: 0,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+            msg_responses: vec![],
+        }),
+    };
+    let res = reply(deps.as_mut(), env.clone(), reply_msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: deps.api.addr_make("addr0000").to_string(),
+                amount: vec![coin(454000, "inj")],
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: deps.api.addr_make("addr0001").to_string(),
+                amount: vec![coin(45646, "usdt")],
+            })),
+        ],
+    );
+
+    let reply_msg = Reply {
+        id: HELIX_SPOT_REPLY_ID,
+        payload: to_json_binary(&HelixSpotSwap {
+            operation,
+            ask_balance: Uint128::zero(),
+            offer_balance: Uint128::zero(),
+            to: None,
+            refund_to: None,
+        })
+        .unwrap(),
+        gas_used: 0,
+        result: SubMsgResult::Err("order not filled".to_string()),
+    };
+    let res = reply(deps.as_mut(), env, reply_msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "swap operation inj -> usdt failed: order not filled")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn query_helix_spot_simulation() {
+    let mut deps = mock_dependencies(&[]);
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let market = inj_usdt_spot_market();
+    let (buys, sells) = inj_usdt_orderbook();
+    deps.querier.with_spot_markets(&[&market]);
+    deps.querier
+        .with_spot_orderbooks(&[(&market.market_id, buys, sells)]);
+    deps.querier.with_choice_factory(
+        &[(
+            &"ukrwinj".to_string(),
+            &PairInfo {
+                asset_infos: [
+                    AssetInfo::NativeToken {
+                        denom: "ukrw".to_string(),
+                    },
+                    AssetInfo::NativeToken {
+                        denom: "inj".to_string(),
+                    },
+                ],
+                contract_addr: deps.api.addr_make("pair0000").to_string(),
+                liquidity_token: deps.api.addr_make("liquidity0000").to_string(),
+                asset_decimals: [6u8, 6u8],
+                burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+                fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+                commission_rate: Decimal::permille(3),
+                pair_type: PairType::Xyk {},
+                paused: false,
+            },
+        )],
+        &[("ukrw".to_string(), 6u8), ("inj".to_string(), 6u8)],
+    );
+
+    let sell_inj = SwapOperation::HelixSpot {
+        market_id: market.market_id.as_str().to_string(),
+        offer_asset_info: AssetInfo::NativeToken {
+            denom: "inj".to_string(),
+        },
+        ask_asset_info: AssetInfo::NativeToken {
+            denom: "usdt".to_string(),
+        },
+        worst_price: FPDecimal::from_str("1.9").unwrap(),
+    };
+    let buy_inj = SwapOperation::HelixSpot {
+        market_id: market.market_id.as_str().to_string(),
+        offer_asset_info: AssetInfo::NativeToken {
+            denom: "usdt".to_string(),
+        },
+        ask_asset_info: AssetInfo::NativeToken {
+            denom: "inj".to_string(),
+        },
+        worst_price: FPDecimal::from_str("2.2").unwrap(),
+    };
+
+    // the pair returns one to one, the bids fill 1000000 at 2 and
+    // 500000 at 1.9, less the 0.2% atomic taker fee
+    let msg = QueryMsg::SimulateSwapOperations {
+        offer_amount: Uint128::from(1500000u128),
+        operations: vec![
+            SwapOperation::Choice {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "ukrw".to_string(),
+                },
+                ask_asset_info: AssetInfo::NativeToken {
+                    denom: "inj".to_string(),
+                },
+                max_spread: None,
+                belief_price: None,
+            },
+            sell_inj.clone(),
+        ],
+    };
+    let res: SimulateSwapOperationsResponse =
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.amount, Uint128::from(2944100u128));
+
+    let msg = QueryMsg::ReverseSimulateSwapOperations {
+        ask_amount: Uint128::from(2944100u128),
+        operations: vec![sell_inj.clone()],
+    };
+    let res: SimulateSwapOperationsResponse =
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.amount, Uint128::from(1500000u128));
+
+    // buys reserve the fee at the worst price and round to the quantity tick
+    let msg = QueryMsg::SimulateSwapOperations {
+        offer_amount: Uint128::from(4000000u128),
+        operations: vec![buy_inj.clone()],
+    };
+    let res: SimulateSwapOperationsResponse =
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.amount, Uint128::from(1814000u128));
+
+    let msg = QueryMsg::ReverseSimulateSwapOperations {
+        ask_amount: Uint128::from(1814000u128),
+        operations: vec![buy_inj],
+    };
+    let res: SimulateSwapOperationsResponse =
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(res.amount, Uint128::from(3998782u128));
+
+    // the bids above the worst price hold 2000000 inj
+    let msg = QueryMsg::SimulateSwapOperations {
+        offer_amount: Uint128::from(3000000u128),
+        operations: vec![sell_inj],
+    };
+    match query(deps.as_ref(), mock_env(), msg) {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "not enough liquidity in the helix spot orderbook")
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn query_simulate_split_swap() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let inj = AssetInfo::NativeToken {
+        denom: "inj".to_string(),
+    };
+    let ukrw = AssetInfo::NativeToken {
+        denom: "ukrw".to_string(),
+    };
+    let asset0000 = AssetInfo::Token {
+        contract_addr: deps.api.addr_make("asset0000").to_string(),
+    };
+
+    let pair_info = |asset_infos: [AssetInfo; 2], pair: &str| PairInfo {
+        asset_infos,
+        contract_addr: deps.api.addr_make(pair).to_string(),
+        liquidity_token: format!("factory/{}/lp", deps.api.addr_make(pair)),
+        asset_decimals: [6u8, 6u8],
+        burn_address: deps.api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
+        paused: false,
+    };
+    let pairs = [
+        (
+            format!("{}{}", inj, asset0000),
+            pair_info([inj.clone(), asset0000.clone()], "pair0000"),
+        ),
+        (
+            format!("{}{}", inj, ukrw),
+            pair_info([inj.clone(), ukrw.clone()], "pair0001"),
+        ),
+        (
+            format!("{}{}", ukrw, asset0000),
+            pair_info([ukrw.clone(), asset0000.clone()], "pair0002"),
+        ),
+    ];
+    deps.querier.with_choice_factory(
+        &pairs.iter().map(|(key, pair)| (key, pair)).collect::<Vec<_>>(),
+        &[],
+    );
+    deps.querier.with_pair_return_rates(&[
+        (&deps.api.addr_make("pair0000").to_string(), Decimal::percent(90)),
+        (&deps.api.addr_make("pair0001").to_string(), Decimal::percent(200)),
+        (&deps.api.addr_make("pair0002").to_string(), Decimal::percent(50)),
+    ]);
+
+    let msg = QueryMsg::SimulateSplitSwap {
+        offer_amount: Uint128::from(1000u128),
+        routes: vec![
+            SplitRoute {
+                share: RouteShare::Weight {
+                    weight: Decimal::percent(40),
+                },
+                operations: vec![SwapOperation::Choice {
+                    offer_asset_info: inj.clone(),
+                    ask_asset_info: asset0000.clone(),
+                    max_spread: None,
+                    belief_price: None,
+                }],
+            },
+            SplitRoute {
+                share: RouteShare::Weight {
+                    weight: Decimal::percent(60),
+                },
+                operations: vec![
+                    SwapOperation::Choice {
+                        offer_asset_info: inj,
+                        ask_asset_info: ukrw.clone(),
+                        max_spread: None,
+                        belief_price: None,
+                    },
+                    SwapOperation::Choice {
+                        offer_asset_info: ukrw,
+                        ask_asset_info: asset0000,
+                        max_spread: None,
+                        belief_price: None,
+                    },
+                ],
+            },
+        ],
+    };
+
+    // 400 * 0.9 + 600 * 2 * 0.5
+    let res: SimulateSwapOperationsResponse =
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        SimulateSwapOperationsResponse {
+            amount: Uint128::from(960u128)
+        }
+    );
+}
+
+#[test]
+fn query_buy_with_routes() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+
+    // we can just call .unwrap() to assert this was a success
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = QueryMsg::SimulateSwapOperations {
+        offer_amount: Uint128::from(1000000u128),
+        operations: vec![
+            SwapOperation::Choice {
+                offer_asset_info: AssetInfo::NativeToken {
+                    denom: "ukrw".to_string(),
+                },
+                ask_asset_info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0000").to_string(),
+                },
+                max_spread: None,
+                belief_price: None,
+            },
+            SwapOperation::Choice {
+                offer_asset_info: AssetInfo::Token {
+                    contract_addr: deps.api.addr_make("asset0000").to_string(),
+                },
+                ask_asset_info: AssetInfo::NativeToken {
+                    denom: "inj".to_string(),
+                },
+                max_spread: None,
+                belief_price: None,
+            },
+        ],
+    };
+
+    deps.querier.with_choice_factory(
+        &[
+            (
+                &format!("ukrw{}", deps.api.addr_make("asset0000")),
+                &PairInfo {
+                    asset_infos: [
+                        AssetInfo::NativeToken {
+                            denom: "ukrw".to_string(),
+                        },
+                        AssetInfo::Token {
+                            contract_addr: deps.api.addr_make("asset0000").to_string(),
+                        },
+                    ],
+                    contract_addr: deps.api.addr_make("pair0000").to_string(),
+                    liquidity_token: deps.api.addr_make("liquidity0000").to_string(),
+                    asset_decimals: [6u8, 6u8],
+                    burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
+                    fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                    commission_rate: Decimal::permille(3),
+                    pair_type: PairType::Xyk {},
+                    paused: false,
+                },
+            ),
+            (
+                &format!("{}inj", deps.api.addr_make("asset0000")),
+                &PairInfo {
+                    asset_infos: [
+                        AssetInfo::Token {
+                            contract_addr: deps.api.addr_make("asset0000").to_string(),
+                        },
+                        AssetInfo::NativeToken {
+                            denom: "inj".to_string(),
+                        },
+                    ],
+                    contract_addr: deps.api.addr_make("pair0001").to_string(),
+                    liquidity_token: deps.api.addr_make("liquidity0001").to_string(),
+                    asset_decimals: [6u8, 6u8],
+                    burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
+                    fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                    commission_rate: Decimal::permille(3),
+                    pair_type: PairType::Xyk {},
+                    paused: false,
+                },
+            ),
+        ],
+        &[("ukrw".to_string(), 6u8), ("inj".to_string(), 6u8)],
+    );
+
+    let res: SimulateSwapOperationsResponse =
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        SimulateSwapOperationsResponse {
+            amount: Uint128::from(1000000u128)
+        }
+    );
+}
+
+#[test]
+fn query_find_best_route() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: None,
+    };
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let ukrw = AssetInfo::NativeToken {
+        denom: "ukrw".to_string(),
+    };
+    let uusd = AssetInfo::NativeToken {
+        denom: "uusd".to_string(),
+    };
+    let inj = AssetInfo::NativeToken {
+        denom: "inj".to_string(),
+    };
+    let asset0000 = AssetInfo::Token {
+        contract_addr: deps.api.addr_make("asset0000").to_string(),
+    };
+
+    let api = deps.api;
+    let pair_info = |asset_infos: [AssetInfo; 2], pair: &str, paused: bool| PairInfo {
+        asset_infos,
+        contract_addr: api.addr_make(pair).to_string(),
+        liquidity_token: format!("factory/{}/lp", api.addr_make(pair)),
+        asset_decimals: [6u8, 6u8],
+        burn_address: api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: api.addr_make("feeaddr0000").to_string(),
+        commission_rate: Decimal::permille(3),
+        pair_type: PairType::Xyk {},
+        paused,
+    };
+    let pairs = [
+        (
+            format!("{}{}", ukrw, inj),
+            pair_info([ukrw.clone(), inj.clone()], "pair0000", false),
+        ),
+        (
+            format!("{}{}", ukrw, asset0000),
+            pair_info([ukrw.clone(), asset0000.clone()], "pair0001", false),
+        ),
+        (
+            format!("{}{}", asset0000, inj),
+            pair_info([asset0000.clone(), inj.clone()], "pair0002", false),
+        ),
+        (
+            format!("{}{}", ukrw, uusd),
+            pair_info([ukrw.clone(), uusd.clone()], "pair0003", true),
+        ),
+        (
+            format!("{}{}", uusd, inj),
+            pair_info([uusd.clone(), inj.clone()], "pair0004", false),
+        ),
+    ];
+    deps.querier.with_choice_factory(
+        &pairs.iter().map(|(key, pair)| (key, pair)).collect::<Vec<_>>(),
+        &[],
+    );
+    deps.querier.with_pair_return_rates(&[
+        (&deps.api.addr_make("pair0000").to_string(), Decimal::percent(90)),
+        (&deps.api.addr_make("pair0001").to_string(), Decimal::percent(200)),
+        (&deps.api.addr_make("pair0002").to_string(), Decimal::percent(50)),
+        (&deps.api.addr_make("pair0003").to_string(), Decimal::percent(1000)),
+    ]);
+
+    let offer_asset = Asset {
+        info: ukrw.clone(),
+        amount: Uint128::from(1000000u128),
+    };
+
+    // the route through asset0000 beats the direct pair, the paused pair is skipped
+    let msg = QueryMsg::FindBestRoute {
+        offer_asset: offer_asset.clone(),
+        ask_asset_info: inj.clone(),
+        max_hops: None,
+    };
+    let res: FindBestRouteResponse =
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        FindBestRouteResponse {
+            operations: vec![
+                SwapOperation::Choice {
+                    offer_asset_info: ukrw.clone(),
+                    ask_asset_info: asset0000.clone(),
+                    max_spread: None,
+                    belief_price: None,
+                },
+                SwapOperation::Choice {
+                    offer_asset_info: asset0000.clone(),
+                    ask_asset_info: inj.clone(),
+                    max_spread: None,
+                    belief_price: None,
+                },
+            ],
+            amount: Uint128::from(1000000u128),
+        }
+    );
+
+    // a single hop only finds the direct pair
+    let msg = QueryMsg::FindBestRoute {
+        offer_asset: offer_asset.clone(),
+        ask_asset_info: inj.clone(),
+        max_hops: Some(1),
+    };
+    let res: FindBestRouteResponse =
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        FindBestRouteResponse {
+            operations: vec![SwapOperation::Choice {
+                offer_asset_info: ukrw.clone(),
+                ask_asset_info: inj.clone(),
+                max_spread: None,
+                belief_price: None,
+            }],
+            amount: Uint128::from(900000u128),
+        }
+    );
+
+    // only the first pairs of an asset are expanded, the direct pair is always tried
+    let mut crowded_pairs = pairs.to_vec();
+    for (i, c) in ('a'..='l').enumerate() {
+        let denom = AssetInfo::NativeToken {
+            denom: format!("a{}", c),
+        };
+        crowded_pairs.push((
+            format!("{}{}", ukrw, denom),
+            pair_info([ukrw.clone(), denom], &format!("pair1{:03}", i), false),
+        ));
+    }
+    deps.querier.with_choice_factory(
+        &crowded_pairs
+            .iter()
+            .map(|(key, pair)| (key, pair))
+            .collect::<Vec<_>>(),
+        &[],
+    );
+    let res = query_pairs_by_asset(
+        &deps.as_ref().querier,
+        deps.api.addr_make("choicefactory"),
+        &ukrw,
+        None,
+        None,
+    )
+    .unwrap();
+    assert!(!res.pairs.iter().any(|pair| pair.asset_infos[1] == inj));
+
+    let msg = QueryMsg::FindBestRoute {
+        offer_asset: offer_asset.clone(),
+        ask_asset_info: inj.clone(),
+        max_hops: Some(1),
+    };
+    let res: FindBestRouteResponse =
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+    assert_eq!(
+        res.operations,
+        vec![SwapOperation::Choice {
+            offer_asset_info: ukrw.clone(),
+            ask_asset_info: inj.clone(),
+            max_spread: None,
+            belief_price: None,
+        }]
+    );
+
+    let msg = QueryMsg::FindBestRoute {
+        offer_asset: offer_asset.clone(),
+        ask_asset_info: AssetInfo::NativeToken {
+            denom: "ueur".to_string(),
+        },
+        max_hops: None,
+    };
+    match query(deps.as_ref(), mock_env(), msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "no route found"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // the global pause of the factory halts every pair
+    deps.querier.with_factory_paused(true);
+    let msg = QueryMsg::FindBestRoute {
+        offer_asset: offer_asset.clone(),
+        ask_asset_info: inj.clone(),
+        max_hops: Some(1),
+    };
+    match query(deps.as_ref(), mock_env(), msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "no route found"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    deps.querier.with_factory_paused(false);
+
+    let msg = QueryMsg::FindBestRoute {
+        offer_asset,
+        ask_asset_info: inj,
+        max_hops: Some(5),
+    };
+    match query(deps.as_ref(), mock_env(), msg).unwrap_err() {
+        StdError::GenericErr { msg, .. } => assert_eq!(msg, "max_hops must be between 1 and 4"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn query_reverse_routes_with_from_native() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: None,
+    };
+
+    let target_amount = 1000000u128;
+
+    let info = message_info(&deps.api.addr_make("addr0000"), &[coin(10000000, "ukrw")]);
 
     // we can just call .unwrap() to assert this was a success
     let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -582,6 +2387,8 @@ fn query_reverse_routes_with_from_native() {
             ask_asset_info: AssetInfo::Token {
                 contract_addr: deps.api.addr_make("asset0000").to_string(),
             },
+            max_spread: None,
+            belief_price: None,
         }],
     };
 
@@ -603,6 +2410,9 @@ fn query_reverse_routes_with_from_native() {
                     asset_decimals: [8u8, 6u8],
                     burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                     fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                    commission_rate: Decimal::permille(3),
+                    pair_type: PairType::Xyk {},
+                    paused: false,
                 },
             ),
             (
@@ -621,6 +2431,9 @@ fn query_reverse_routes_with_from_native() {
                     asset_decimals: [8u8, 6u8],
                     burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                     fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                    commission_rate: Decimal::permille(3),
+                    pair_type: PairType::Xyk {},
+                    paused: false,
                 },
             ),
         ],
@@ -628,7 +2441,7 @@ fn query_reverse_routes_with_from_native() {
     );
 
     let res: SimulateSwapOperationsResponse =
-        from_json(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
 
     assert_eq!(
         res,
@@ -639,17 +2452,23 @@ fn query_reverse_routes_with_from_native() {
 
     let offer_amount = res.amount;
 
-    let msg = ExecuteMsg::ExecuteSwapOperation {
-        operation: SwapOperation::Choice {
-            offer_asset_info: AssetInfo::NativeToken {
-                denom: "ukrw".to_string(),
-            },
-            ask_asset_info: AssetInfo::Token {
-                contract_addr: deps.api.addr_make("asset0000").to_string(),
-            },
+    let operation = SwapOperation::Choice {
+        offer_asset_info: AssetInfo::NativeToken {
+            denom: "ukrw".to_string(),
+        },
+        ask_asset_info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
         },
+        max_spread: None,
+        belief_price: None,
+    };
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: operation.clone(),
         to: None,
         deadline: None,
+        amount: None,
+        prev_balance: None,
+        refund_to: None,
     };
     let info = message_info(&deps.api.addr_make("addr0"), &[coin(offer_amount.u128(), "ukrw")]);
     let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
@@ -663,7 +2482,7 @@ fn query_reverse_routes_with_from_native() {
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        vec![SubMsg::reply_on_error(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: deps.api.addr_make("pair0000").to_string(),
             funds: vec![coin(target_amount, "ukrw")],
             msg: to_json_binary(&PairExecuteMsg::Swap {
@@ -679,7 +2498,8 @@ fn query_reverse_routes_with_from_native() {
                 deadline: None,
             })
             .unwrap(),
-        })),],
+        }), SWAP_OPERATION_REPLY_ID)
+        .with_payload(to_json_binary(&operation).unwrap())],
     );
 }
 
@@ -689,6 +2509,8 @@ fn query_reverse_routes_with_to_native() {
 
     let msg = InstantiateMsg {
         choice_factory: deps.api.addr_make("choicefactory").to_string(),
+        max_spread: None,
+        max_referral_commission_bps: None,
     };
 
     let target_amount = 1000000u128;
@@ -718,6 +2540,8 @@ fn query_reverse_routes_with_to_native() {
             ask_asset_info: AssetInfo::NativeToken {
                 denom: "ukrw".to_string(),
             },
+            max_spread: None,
+            belief_price: None,
         }],
     };
 
@@ -739,6 +2563,9 @@ fn query_reverse_routes_with_to_native() {
                     asset_decimals: [8u8, 6u8],
                     burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                     fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                    commission_rate: Decimal::permille(3),
+                    pair_type: PairType::Xyk {},
+                    paused: false,
                 },
             ),
             (
@@ -757,6 +2584,9 @@ fn query_reverse_routes_with_to_native() {
                     asset_decimals: [8u8, 6u8],
                     burn_address: deps.api.addr_make("burnaddr0000").to_string(), // New field
                     fee_wallet_address: deps.api.addr_make("feeaddr0000").to_string(), // New field
+                    commission_rate: Decimal::permille(3),
+                    pair_type: PairType::Xyk {},
+                    paused: false,
                 },
             ),
         ],
@@ -764,7 +2594,7 @@ fn query_reverse_routes_with_to_native() {
     );
 
     let res: SimulateSwapOperationsResponse =
-        from_json(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
 
     assert_eq!(
         res,
@@ -786,14 +2616,17 @@ fn query_reverse_routes_with_to_native() {
                 ask_asset_info: AssetInfo::NativeToken {
                     denom: "ukrw".to_string(),
                 },
+                max_spread: None,
+                belief_price: None,
             }],
             minimum_receive: None,
             to: None,
             deadline: None,
+            referral: None,
         })
         .unwrap(),
     });
-    let info = message_info(&deps.api.addr_make("addr0"), &[]);
+    let info = message_info(&deps.api.addr_make("asset0000"), &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     assert_eq!(
@@ -809,25 +2642,36 @@ fn query_reverse_routes_with_to_native() {
                     ask_asset_info: AssetInfo::NativeToken {
                         denom: "ukrw".to_string(),
                     },
+                    max_spread: None,
+                    belief_price: None,
                 },
                 to: Some(deps.api.addr_make("addr0").to_string()),
                 deadline: None,
+                amount: Some(offer_amount),
+                prev_balance: None,
+                refund_to: Some(deps.api.addr_make("addr0").to_string()),
             })
             .unwrap(),
         })),],
     );
 
-    let msg = ExecuteMsg::ExecuteSwapOperation {
-        operation: SwapOperation::Choice {
-            offer_asset_info: AssetInfo::Token {
-                contract_addr: deps.api.addr_make("asset0000").to_string(),
-            },
-            ask_asset_info: AssetInfo::NativeToken {
-                denom: "ukrw".to_string(),
-            },
+    let operation = SwapOperation::Choice {
+        offer_asset_info: AssetInfo::Token {
+            contract_addr: deps.api.addr_make("asset0000").to_string(),
+        },
+        ask_asset_info: AssetInfo::NativeToken {
+            denom: "ukrw".to_string(),
         },
+        max_spread: None,
+        belief_price: None,
+    };
+    let msg = ExecuteMsg::ExecuteSwapOperation {
+        operation: operation.clone(),
         to: None,
         deadline: None,
+        amount: None,
+        prev_balance: None,
+        refund_to: None,
     };
 
     let info = message_info(&deps.api.addr_validate(MOCK_CONTRACT_ADDR).unwrap(), &[]);
@@ -835,7 +2679,7 @@ fn query_reverse_routes_with_to_native() {
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+        vec![SubMsg::reply_on_error(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: deps.api.addr_make("asset0000").to_string(),
             funds: vec![],
             msg: to_json_binary(&Cw20ExecuteMsg::Send {
@@ -856,7 +2700,8 @@ fn query_reverse_routes_with_to_native() {
                 .unwrap(),
             })
             .unwrap(),
-        }))],
+        }), SWAP_OPERATION_REPLY_ID)
+        .with_payload(to_json_binary(&operation).unwrap())],
     );
 }
 
@@ -1,6 +1,7 @@
 #![cfg(test)]
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use cosmwasm_std::testing::{mock_dependencies, mock_env};
     use cosmwasm_std::{Addr, MessageInfo, Binary, from_json, Coin};
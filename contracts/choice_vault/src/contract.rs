@@ -0,0 +1,798 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, Coin, CosmosMsg, Decimal, Decimal256, Deps, DepsMut, Env,
+    Isqrt, MessageInfo, Response, StdError, StdResult, Uint128, Uint256, WasmMsg,
+};
+use cw2::set_contract_version;
+use cw20::Cw20ExecuteMsg;
+use std::convert::TryInto;
+
+use crate::state::{Config, CONFIG};
+
+use choice::asset::{Asset, AssetInfo, PairType};
+use choice::pair::{
+    Cw20HookMsg as PairCw20HookMsg, ExecuteMsg as PairExecuteMsg, PoolResponse,
+    QueryMsg as PairQueryMsg, TwapResponse,
+};
+use choice::querier::{query_pair_info_from_pair, query_token_factory_denom_total_supply};
+use choice::router::{
+    Cw20HookMsg as RouterCw20HookMsg, ExecuteMsg as RouterExecuteMsg, SwapOperation,
+};
+use choice::staking::{
+    ConfigResponse as FarmConfigResponse, ExecuteMsg as FarmExecuteMsg,
+    QueryMsg as FarmQueryMsg, StakerInfoResponse,
+};
+use choice::vault::{
+    CallbackMsg, CompoundRoute, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    StateResponse,
+};
+use injective_cosmwasm::msg::{
+    create_burn_tokens_msg, create_mint_tokens_msg, create_new_denom_msg,
+    create_set_token_metadata_msg,
+};
+use injective_cosmwasm::{InjectiveMsgWrapper, InjectiveQueryWrapper};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:choice-vault";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Highest share of the rewards the compound caller can be paid
+const MAX_BOUNTY_RATE: Decimal = Decimal::percent(10);
+/// Shares of the first deposit locked in the vault forever, so the share
+/// price can not be inflated against the next depositor
+const MINIMUM_SHARES: u128 = 1_000;
+/// Window of the pair TWAP the provided liquidity is valued at
+const TWAP_WINDOW_SECONDS: u64 = 600;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let farm = deps.api.addr_validate(&msg.farm)?;
+    let pair = deps.api.addr_validate(&msg.pair)?;
+    let router = deps.api.addr_validate(&msg.router)?;
+
+    let pair_info = query_pair_info_from_pair(&deps.querier, pair.clone())?;
+    let farm_config: FarmConfigResponse =
+        deps.querier.query_wasm_smart(farm.to_string(), &FarmQueryMsg::Config {})?;
+    if farm_config.staking_token != pair_info.liquidity_token {
+        return Err(StdError::generic_err(
+            "farm does not stake the pair liquidity token",
+        ));
+    }
+    // withdrawals pay out the unbonded liquidity in the same transaction
+    if farm_config.unbonding_period != 0 {
+        return Err(StdError::generic_err(
+            "farm unbonding period is not supported",
+        ));
+    }
+
+    assert_bounty_rate(msg.bounty_rate)?;
+    assert_max_spread(msg.max_spread)?;
+    assert_compound_routes(&pair_info.asset_infos, &msg.compound_routes)?;
+
+    let subdenom = "share".to_string();
+    let share_denom = format!("factory/{}/{}", env.contract.address, subdenom);
+
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            farm: deps.api.addr_canonicalize(farm.as_str())?,
+            pair: deps.api.addr_canonicalize(pair.as_str())?,
+            router: deps.api.addr_canonicalize(router.as_str())?,
+            asset_infos: pair_info.asset_infos,
+            lp_denom: pair_info.liquidity_token,
+            share_denom: share_denom.clone(),
+            bounty_rate: msg.bounty_rate,
+            max_spread: msg.max_spread,
+            compound_routes: msg.compound_routes,
+        },
+    )?;
+
+    let create_msg = create_new_denom_msg(env.contract.address.to_string(), subdenom);
+
+    let metadata_msg = create_set_token_metadata_msg(
+        share_denom.clone(),
+        "choice vault share".to_string(),
+        "uVS".to_string(),
+        6,
+    );
+
+    Ok(Response::new()
+        .add_messages(vec![create_msg, metadata_msg])
+        .add_attribute("share_denom", share_denom))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    match msg {
+        ExecuteMsg::Deposit {} => deposit(deps, env, info),
+        ExecuteMsg::Withdraw {} => withdraw(deps, env, info),
+        ExecuteMsg::Compound {} => compound(deps, env, info),
+        ExecuteMsg::UpdateConfig {
+            bounty_rate,
+            max_spread,
+            compound_routes,
+        } => update_config(deps, env, info, bounty_rate, max_spread, compound_routes),
+        ExecuteMsg::Callback(msg) => {
+            if info.sender != env.contract.address {
+                return Err(StdError::generic_err("unauthorized"));
+            }
+
+            match msg {
+                CallbackMsg::SwapRewards { caller } => {
+                    let caller = deps.api.addr_validate(&caller)?;
+                    swap_rewards(deps, env, caller)
+                }
+                CallbackMsg::ProvideLiquidity {} => provide_liquidity(deps, env),
+                CallbackMsg::Bond {} => bond_liquidity(deps, env),
+                CallbackMsg::MintShares { depositor, amount } => {
+                    let depositor = deps.api.addr_validate(&depositor)?;
+                    mint_shares(deps, env, depositor, amount)
+                }
+            }
+        }
+    }
+}
+
+pub fn deposit(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let amount = must_pay(&info, &config.lp_denom)?;
+
+    let total_shares =
+        query_token_factory_denom_total_supply(&deps.querier, config.share_denom.clone())?;
+
+    // the rewards earned so far belong to the current shares, compound them
+    // with the deposit held back and mint once the deposit is bonded too
+    if !total_shares.is_zero() {
+        return Ok(Response::new()
+            .add_messages(compound_msgs(deps.as_ref(), &env, &config, &info.sender)?)
+            .add_message(callback_msg(
+                &env,
+                CallbackMsg::MintShares {
+                    depositor: info.sender.to_string(),
+                    amount,
+                },
+            )?)
+            .add_attributes(vec![
+                ("action", "deposit"),
+                ("sender", info.sender.as_str()),
+                ("amount", &amount.to_string()),
+            ]));
+    }
+
+    if amount <= Uint128::from(MINIMUM_SHARES) {
+        return Err(StdError::generic_err(
+            "first deposit must exceed the minimum shares",
+        ));
+    }
+    let shares = amount - Uint128::from(MINIMUM_SHARES);
+
+    Ok(Response::new()
+        .add_message(bond_msg(deps.as_ref(), &config, amount)?)
+        // Mint the minimum shares to lock forever (to protect the vault)
+        .add_message(create_mint_tokens_msg(
+            env.contract.address.clone(),
+            Coin {
+                denom: config.share_denom.clone(),
+                amount: MINIMUM_SHARES.into(),
+            },
+            env.contract.address.to_string(),
+        ))
+        .add_message(create_mint_tokens_msg(
+            env.contract.address,
+            Coin {
+                denom: config.share_denom,
+                amount: shares,
+            },
+            info.sender.to_string(),
+        ))
+        .add_attributes(vec![
+            ("action", "deposit"),
+            ("sender", info.sender.as_str()),
+            ("amount", &amount.to_string()),
+            ("shares", &shares.to_string()),
+        ]))
+}
+
+/// Shares track the bonded liquidity, which grows with every compound.
+/// `Bond` bonded the deposit with the compounded liquidity, so it is left
+/// out of the liquidity the shares are priced against
+pub fn mint_shares(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    depositor: Addr,
+    amount: Uint128,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    let total_shares =
+        query_token_factory_denom_total_supply(&deps.querier, config.share_denom.clone())?;
+    let total_bond_amount =
+        query_bond_amount(deps.as_ref(), &env, &config)?.checked_sub(amount)?;
+
+    let shares = if total_bond_amount.is_zero() {
+        amount
+    } else {
+        amount.multiply_ratio(total_shares, total_bond_amount)
+    };
+    if shares.is_zero() {
+        return Err(StdError::generic_err("deposit too small to mint shares"));
+    }
+
+    Ok(Response::new()
+        .add_message(create_mint_tokens_msg(
+            env.contract.address,
+            Coin {
+                denom: config.share_denom,
+                amount: shares,
+            },
+            depositor.to_string(),
+        ))
+        .add_attributes(vec![
+            ("action", "mint_shares"),
+            ("depositor", depositor.as_str()),
+            ("amount", &amount.to_string()),
+            ("shares", &shares.to_string()),
+        ]))
+}
+
+pub fn withdraw(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let shares = must_pay(&info, &config.share_denom)?;
+
+    let total_shares =
+        query_token_factory_denom_total_supply(&deps.querier, config.share_denom.clone())?;
+    let total_bond_amount = query_bond_amount(deps.as_ref(), &env, &config)?;
+
+    let amount = shares.multiply_ratio(total_bond_amount, total_shares);
+    if amount.is_zero() {
+        return Err(StdError::generic_err("withdraw too small to release liquidity"));
+    }
+
+    Ok(Response::new()
+        .add_message(create_burn_tokens_msg(
+            env.contract.address,
+            Coin {
+                denom: config.share_denom.clone(),
+                amount: shares,
+            },
+        ))
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(&config.farm)?.to_string(),
+            msg: to_json_binary(&FarmExecuteMsg::Unbond { amount })?,
+            funds: vec![],
+        }))
+        .add_message(
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: config.lp_denom,
+                },
+                amount,
+            }
+            .into_msg(info.sender.clone())?,
+        )
+        .add_attributes(vec![
+            ("action", "withdraw"),
+            ("sender", info.sender.as_str()),
+            ("shares", &shares.to_string()),
+            ("amount", &amount.to_string()),
+        ]))
+}
+
+/// Claim the farm rewards and compound them through the callbacks,
+/// each step runs after the messages of the previous one settled
+pub fn compound(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    let config: Config = CONFIG.load(deps.storage)?;
+
+    // compounding without shares would hand the liquidity to the next depositor
+    let total_shares =
+        query_token_factory_denom_total_supply(&deps.querier, config.share_denom.clone())?;
+    if total_shares.is_zero() {
+        return Err(StdError::generic_err("no shares to compound"));
+    }
+
+    Ok(Response::new()
+        .add_messages(compound_msgs(deps.as_ref(), &env, &config, &info.sender)?)
+        .add_attributes(vec![("action", "compound"), ("caller", info.sender.as_str())]))
+}
+
+/// Claim the farm rewards and run the compound steps, paying the bounty to `caller`
+fn compound_msgs(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: &Env,
+    config: &Config,
+    caller: &Addr,
+) -> StdResult<Vec<CosmosMsg<InjectiveMsgWrapper>>> {
+    Ok(vec![
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(&config.farm)?.to_string(),
+            msg: to_json_binary(&FarmExecuteMsg::Withdraw {})?,
+            funds: vec![],
+        }),
+        callback_msg(
+            env,
+            CallbackMsg::SwapRewards {
+                caller: caller.to_string(),
+            },
+        )?,
+        callback_msg(env, CallbackMsg::ProvideLiquidity {})?,
+        callback_msg(env, CallbackMsg::Bond {})?,
+    ])
+}
+
+pub fn swap_rewards(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    caller: Addr,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let farm_config: FarmConfigResponse = deps.querier.query_wasm_smart(
+        deps.api.addr_humanize(&config.farm)?.to_string(),
+        &FarmQueryMsg::Config {},
+    )?;
+    let router = deps.api.addr_humanize(&config.router)?;
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    let mut bounties: Vec<Asset> = vec![];
+    for stream in farm_config.reward_streams {
+        let reward_token = stream.reward_token;
+        let is_pair_asset = config.asset_infos.iter().any(|info| info.equal(&reward_token));
+        let route = config
+            .compound_routes
+            .iter()
+            .find(|route| route.reward_token.equal(&reward_token));
+
+        // rewards without a route stay in the vault until one is set
+        if !is_pair_asset && route.is_none() {
+            continue;
+        }
+
+        let balance =
+            reward_token.query_pool(&deps.querier, deps.api, env.contract.address.clone())?;
+        if balance.is_zero() {
+            continue;
+        }
+
+        let bounty = balance.mul_floor(config.bounty_rate);
+        if !bounty.is_zero() {
+            let bounty = Asset {
+                info: reward_token.clone(),
+                amount: bounty,
+            };
+            messages.push(bounty.clone().into_msg(caller.clone())?);
+            bounties.push(bounty);
+        }
+
+        // pair assets are provided as they are
+        if let Some(route) = route {
+            messages.push(route_msg(
+                &router,
+                route,
+                Asset {
+                    info: reward_token,
+                    amount: balance.checked_sub(bounty)?,
+                },
+                config.max_spread,
+            )?);
+        }
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        ("action", "swap_rewards"),
+        ("caller", caller.as_str()),
+        ("bounty", &format_assets(&bounties)),
+    ]))
+}
+
+pub fn provide_liquidity(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pair = deps.api.addr_humanize(&config.pair)?;
+    let pair_type = query_pair_info_from_pair(&deps.querier, pair.clone())?.pair_type;
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    let mut provided: Vec<Asset> = vec![];
+    for asset_info in config.asset_infos.iter() {
+        let amount =
+            asset_info.query_pool(&deps.querier, deps.api, env.contract.address.clone())?;
+        if amount.is_zero() {
+            continue;
+        }
+
+        let offer_asset = Asset {
+            info: asset_info.clone(),
+            amount,
+        };
+        let min_share = Some(twap_min_share(
+            deps.as_ref(),
+            &pair,
+            &pair_type,
+            &offer_asset,
+            config.max_spread,
+        )?);
+        messages.push(match asset_info {
+            AssetInfo::NativeToken { denom } => CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: pair.to_string(),
+                msg: to_json_binary(&PairExecuteMsg::ProvideSingleSided {
+                    offer_asset: offer_asset.clone(),
+                    min_share,
+                    receiver: None,
+                    deadline: None,
+                })?,
+                funds: vec![Coin {
+                    denom: denom.clone(),
+                    amount,
+                }],
+            }),
+            AssetInfo::Token { contract_addr } => CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.clone(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: pair.to_string(),
+                    amount,
+                    msg: to_json_binary(&PairCw20HookMsg::ProvideSingleSided {
+                        min_share,
+                        receiver: None,
+                        deadline: None,
+                    })?,
+                })?,
+                funds: vec![],
+            }),
+        });
+        provided.push(offer_asset);
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        ("action", "provide_liquidity"),
+        ("assets", &format_assets(&provided)),
+    ]))
+}
+
+pub fn bond_liquidity(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let amount = deps
+        .querier
+        .query_balance(env.contract.address, config.lp_denom.clone())?
+        .amount;
+
+    let mut messages: Vec<CosmosMsg<InjectiveMsgWrapper>> = vec![];
+    if !amount.is_zero() {
+        messages.push(bond_msg(deps.as_ref(), &config, amount)?);
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        ("action", "bond"),
+        ("amount", &amount.to_string()),
+    ]))
+}
+
+pub fn update_config(
+    deps: DepsMut<InjectiveQueryWrapper>,
+    env: Env,
+    info: MessageInfo,
+    bounty_rate: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    compound_routes: Option<Vec<CompoundRoute>>,
+) -> StdResult<Response<InjectiveMsgWrapper>> {
+    // the vault has no owner, the contract admin manages it
+    let contract_info = deps
+        .querier
+        .query_wasm_contract_info(env.contract.address)?;
+    if contract_info.admin != Some(info.sender) {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    if let Some(bounty_rate) = bounty_rate {
+        assert_bounty_rate(bounty_rate)?;
+        config.bounty_rate = bounty_rate;
+    }
+
+    if let Some(max_spread) = max_spread {
+        assert_max_spread(max_spread)?;
+        config.max_spread = max_spread;
+    }
+
+    if let Some(compound_routes) = compound_routes {
+        assert_compound_routes(&config.asset_infos, &compound_routes)?;
+        config.compound_routes = compound_routes;
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::new().add_attribute("action", "update_config"))
+}
+
+fn assert_bounty_rate(bounty_rate: Decimal) -> StdResult<()> {
+    if bounty_rate > MAX_BOUNTY_RATE {
+        return Err(StdError::generic_err(format!(
+            "bounty rate must not exceed {}",
+            MAX_BOUNTY_RATE
+        )));
+    }
+
+    Ok(())
+}
+
+fn assert_max_spread(max_spread: Decimal) -> StdResult<()> {
+    if max_spread >= Decimal::one() {
+        return Err(StdError::generic_err("max spread must be below 1"));
+    }
+
+    Ok(())
+}
+
+/// Routes start from a reward token, at most one each, and end in a pair asset
+fn assert_compound_routes(
+    asset_infos: &[AssetInfo; 2],
+    compound_routes: &[CompoundRoute],
+) -> StdResult<()> {
+    for (i, route) in compound_routes.iter().enumerate() {
+        if asset_infos.iter().any(|info| info.equal(&route.reward_token)) {
+            return Err(StdError::generic_err(
+                "pair assets are provided without a route",
+            ));
+        }
+
+        if compound_routes[..i]
+            .iter()
+            .any(|prev| prev.reward_token.equal(&route.reward_token))
+        {
+            return Err(StdError::generic_err("duplicate compound route"));
+        }
+
+        let (first, last) = match (route.operations.first(), route.operations.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err(StdError::generic_err("must provide operations")),
+        };
+        if !first.get_offer_asset_info().equal(&route.reward_token) {
+            return Err(StdError::generic_err(
+                "compound route must start from its reward token",
+            ));
+        }
+
+        let target_asset_info = last.get_target_asset_info();
+        if !asset_infos.iter().any(|info| info.equal(&target_asset_info)) {
+            return Err(StdError::generic_err(
+                "compound route must end in a pair asset",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn must_pay(info: &MessageInfo, denom: &str) -> StdResult<Uint128> {
+    match info.funds.as_slice() {
+        [coin] if coin.denom == denom && !coin.amount.is_zero() => Ok(coin.amount),
+        _ => Err(StdError::generic_err(format!(
+            "funds mismatch; must send only {}",
+            denom
+        ))),
+    }
+}
+
+fn query_bond_amount(
+    deps: Deps<InjectiveQueryWrapper>,
+    env: &Env,
+    config: &Config,
+) -> StdResult<Uint128> {
+    let staker_info: StakerInfoResponse = deps.querier.query_wasm_smart(
+        deps.api.addr_humanize(&config.farm)?.to_string(),
+        &FarmQueryMsg::StakerInfo {
+            staker: env.contract.address.to_string(),
+            block_time: None,
+        },
+    )?;
+
+    Ok(staker_info.bond_amount)
+}
+
+fn bond_msg(
+    deps: Deps<InjectiveQueryWrapper>,
+    config: &Config,
+    amount: Uint128,
+) -> StdResult<CosmosMsg<InjectiveMsgWrapper>> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: deps.api.addr_humanize(&config.farm)?.to_string(),
+        msg: to_json_binary(&FarmExecuteMsg::Bond {
+            amount,
+            lock_duration: None,
+        })?,
+        funds: vec![Coin {
+            denom: config.lp_denom.clone(),
+            amount,
+        }],
+    }))
+}
+
+/// Shares `offer_asset` is worth with the pool valued at the TWAP, less
+/// `max_spread`. A pool pushed off the TWAP mints fewer and fails the provide
+fn twap_min_share(
+    deps: Deps<InjectiveQueryWrapper>,
+    pair: &Addr,
+    pair_type: &PairType,
+    offer_asset: &Asset,
+    max_spread: Decimal,
+) -> StdResult<Uint128> {
+    let pool: PoolResponse = deps
+        .querier
+        .query_wasm_smart(pair.to_string(), &PairQueryMsg::Pool {})?;
+    let (offer_pool, ask_pool) = if pool.assets[0].info.equal(&offer_asset.info) {
+        (&pool.assets[0], &pool.assets[1])
+    } else {
+        (&pool.assets[1], &pool.assets[0])
+    };
+
+    // price of the other asset in units of the offer asset
+    let twap: TwapResponse = deps.querier.query_wasm_smart(
+        pair.to_string(),
+        &PairQueryMsg::Twap {
+            asset_info: ask_pool.info.clone(),
+            window_seconds: TWAP_WINDOW_SECONDS,
+        },
+    )?;
+    let price = Decimal256::from(twap.price);
+
+    // pool value in the offer asset, a constant product pool is valued by its
+    // invariant so reserves pushed off the TWAP add nothing. The TWAP of a
+    // stable pair is its stableswap price, which stays near the peg however
+    // imbalanced the pool
+    let offer_reserve = Uint256::from(offer_pool.amount);
+    let ask_reserve = Uint256::from(ask_pool.amount);
+    let pool_value = match pair_type {
+        PairType::Xyk {} => {
+            (offer_reserve * ask_reserve).mul_floor(price).isqrt() * Uint256::from(2u8)
+        }
+        PairType::Stable { .. } => offer_reserve + ask_reserve.mul_floor(price),
+    };
+    if pool_value.is_zero() {
+        return Ok(Uint128::zero());
+    }
+
+    let share = Uint256::from(offer_asset.amount)
+        .multiply_ratio(pool.total_share, pool_value)
+        .mul_floor(Decimal256::one() - Decimal256::from(max_spread));
+    Ok(share.try_into()?)
+}
+
+/// Swap `offer_asset` along the route, the router pays the vault back
+fn route_msg(
+    router: &Addr,
+    route: &CompoundRoute,
+    offer_asset: Asset,
+    max_spread: Decimal,
+) -> StdResult<CosmosMsg<InjectiveMsgWrapper>> {
+    let operations: Vec<SwapOperation> = route
+        .operations
+        .iter()
+        .cloned()
+        .map(|operation| match operation {
+            SwapOperation::Choice {
+                offer_asset_info,
+                ask_asset_info,
+                max_spread: hop_max_spread,
+                belief_price,
+            } => SwapOperation::Choice {
+                offer_asset_info,
+                ask_asset_info,
+                max_spread: hop_max_spread.or(Some(max_spread)),
+                belief_price,
+            },
+            operation => operation,
+        })
+        .collect();
+
+    match offer_asset.info {
+        AssetInfo::NativeToken { denom } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: router.to_string(),
+            msg: to_json_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+                operations,
+                minimum_receive: None,
+                to: None,
+                deadline: None,
+                referral: None,
+            })?,
+            funds: vec![Coin {
+                denom,
+                amount: offer_asset.amount,
+            }],
+        })),
+        AssetInfo::Token { contract_addr } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                contract: router.to_string(),
+                amount: offer_asset.amount,
+                msg: to_json_binary(&RouterCw20HookMsg::ExecuteSwapOperations {
+                    operations,
+                    minimum_receive: None,
+                    to: None,
+                    deadline: None,
+                    referral: None,
+                })?,
+            })?,
+            funds: vec![],
+        })),
+    }
+}
+
+fn callback_msg(env: &Env, msg: CallbackMsg) -> StdResult<CosmosMsg<InjectiveMsgWrapper>> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_json_binary(&ExecuteMsg::Callback(msg))?,
+        funds: vec![],
+    }))
+}
+
+fn format_assets(assets: &[Asset]) -> String {
+    assets
+        .iter()
+        .map(|asset| asset.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps<InjectiveQueryWrapper>, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::State {} => to_json_binary(&query_state(deps, env)?),
+    }
+}
+
+pub fn query_config(deps: Deps<InjectiveQueryWrapper>) -> StdResult<ConfigResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let resp = ConfigResponse {
+        farm: deps.api.addr_humanize(&config.farm)?.to_string(),
+        pair: deps.api.addr_humanize(&config.pair)?.to_string(),
+        router: deps.api.addr_humanize(&config.router)?.to_string(),
+        lp_denom: config.lp_denom,
+        share_denom: config.share_denom,
+        bounty_rate: config.bounty_rate,
+        max_spread: config.max_spread,
+        compound_routes: config.compound_routes,
+    };
+
+    Ok(resp)
+}
+
+pub fn query_state(deps: Deps<InjectiveQueryWrapper>, env: Env) -> StdResult<StateResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let resp = StateResponse {
+        total_shares: query_token_factory_denom_total_supply(
+            &deps.querier,
+            config.share_denom.clone(),
+        )?,
+        total_bond_amount: query_bond_amount(deps, &env, &config)?,
+    };
+
+    Ok(resp)
+}
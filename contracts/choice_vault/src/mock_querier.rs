@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, Coin, ContractInfoResponse, ContractResult, Decimal,
+    Empty, OwnedDeps, Querier, QuerierResult, QueryRequest, SystemError, SystemResult, Uint128,
+    WasmQuery,
+};
+use cw20::BalanceResponse;
+use injective_cosmwasm::tokenfactory::response::TokenFactoryDenomSupplyResponse;
+use injective_cosmwasm::{InjectiveQuery, InjectiveQueryWrapper, InjectiveRoute};
+
+use choice::asset::{AssetInfo, PairInfo};
+use choice::pair::{PoolResponse, TwapResponse};
+use choice::staking::{ConfigResponse as FarmConfigResponse, StakerInfoResponse};
+
+/// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
+/// this uses our CustomQuerier.
+pub fn mock_dependencies(
+    contract_balance: &[Coin],
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper> {
+    let custom_querier: WasmMockQuerier =
+        WasmMockQuerier::new(MockQuerier::new(&[(MOCK_CONTRACT_ADDR, contract_balance)]));
+
+    OwnedDeps {
+        api: MockApi::default(),
+        storage: MockStorage::default(),
+        querier: custom_querier,
+        custom_query_type: PhantomData,
+    }
+}
+
+pub struct WasmMockQuerier {
+    base: MockQuerier<Empty>,
+    pair_info: Option<PairInfo>,
+    pool: Option<PoolResponse>,
+    // asset -> twap price in units of the other asset
+    twap_prices: HashMap<String, Decimal>,
+    farm_config: Option<FarmConfigResponse>,
+    // staker -> bonded amount
+    bond_amounts: HashMap<String, Uint128>,
+    // denom -> total supply
+    denom_supplies: HashMap<String, Uint128>,
+    // token contract -> holder -> balance
+    token_balances: HashMap<String, HashMap<String, Uint128>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Pair {},
+    Pool {},
+    Twap {
+        asset_info: AssetInfo,
+        window_seconds: u64,
+    },
+    Config {},
+    StakerInfo {
+        staker: String,
+        block_time: Option<u64>,
+    },
+    Balance {
+        address: String,
+    },
+}
+
+impl Querier for WasmMockQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<InjectiveQueryWrapper> = match from_json(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        self.handle_query(&request)
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn handle_query(&self, request: &QueryRequest<InjectiveQueryWrapper>) -> QuerierResult {
+        match &request {
+            QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr,
+                msg,
+            }) => match from_json(msg) {
+                Ok(QueryMsg::Pair {}) => SystemResult::Ok(ContractResult::from(to_json_binary(
+                    self.pair_info.as_ref().expect("pair not mocked"),
+                ))),
+                Ok(QueryMsg::Pool {}) => SystemResult::Ok(ContractResult::from(to_json_binary(
+                    self.pool.as_ref().expect("pool not mocked"),
+                ))),
+                Ok(QueryMsg::Twap {
+                    asset_info,
+                    window_seconds,
+                }) => SystemResult::Ok(ContractResult::from(to_json_binary(&TwapResponse {
+                    price: self.twap_prices[&asset_info.to_string()],
+                    window_seconds,
+                }))),
+                Ok(QueryMsg::Config {}) => SystemResult::Ok(ContractResult::from(to_json_binary(
+                    self.farm_config.as_ref().expect("farm not mocked"),
+                ))),
+                Ok(QueryMsg::StakerInfo { staker, .. }) => {
+                    SystemResult::Ok(ContractResult::from(to_json_binary(&StakerInfoResponse {
+                        bond_amount: self.bond_amounts.get(&staker).copied().unwrap_or_default(),
+                        bond_weight: Uint128::zero(),
+                        locks: vec![],
+                        rewards: vec![],
+                        staker,
+                    })))
+                }
+                Ok(QueryMsg::Balance { address }) => {
+                    let balance = self
+                        .token_balances
+                        .get(contract_addr)
+                        .and_then(|balances| balances.get(&address))
+                        .copied()
+                        .unwrap_or_default();
+                    SystemResult::Ok(ContractResult::from(to_json_binary(&BalanceResponse {
+                        balance,
+                    })))
+                }
+                _ => panic!("query not mocked"),
+            },
+            QueryRequest::Custom(InjectiveQueryWrapper {
+                route: InjectiveRoute::Tokenfactory,
+                query_data: InjectiveQuery::TokenFactoryDenomTotalSupply { denom },
+            }) => SystemResult::Ok(ContractResult::from(to_json_binary(
+                &TokenFactoryDenomSupplyResponse {
+                    total_supply: self.denom_supplies.get(denom).copied().unwrap_or_default(),
+                },
+            ))),
+            QueryRequest::Custom(_) => panic!("query not mocked"),
+            _ => {
+                // MockQuerier doesn't support Custom, round-trip the rest into Empty
+                let request: QueryRequest<Empty> =
+                    from_json(to_json_binary(request).unwrap()).unwrap();
+                self.base.handle_query(&request)
+            }
+        }
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn new(base: MockQuerier<Empty>) -> Self {
+        WasmMockQuerier {
+            base,
+            pair_info: None,
+            pool: None,
+            twap_prices: HashMap::new(),
+            farm_config: None,
+            bond_amounts: HashMap::new(),
+            denom_supplies: HashMap::new(),
+            token_balances: HashMap::new(),
+        }
+    }
+
+    pub fn with_pair_info(&mut self, pair_info: PairInfo) {
+        self.pair_info = Some(pair_info);
+    }
+
+    pub fn with_pool(&mut self, pool: PoolResponse) {
+        self.pool = Some(pool);
+    }
+
+    pub fn with_twap_price(&mut self, asset_info: &AssetInfo, price: Decimal) {
+        self.twap_prices.insert(asset_info.to_string(), price);
+    }
+
+    pub fn with_farm_config(&mut self, farm_config: FarmConfigResponse) {
+        self.farm_config = Some(farm_config);
+    }
+
+    pub fn with_bond_amount(&mut self, staker: &str, amount: Uint128) {
+        self.bond_amounts.insert(staker.to_string(), amount);
+    }
+
+    pub fn with_denom_supply(&mut self, denom: &str, total_supply: Uint128) {
+        self.denom_supplies.insert(denom.to_string(), total_supply);
+    }
+
+    pub fn with_balance(&mut self, addr: &str, balance: Vec<Coin>) {
+        self.base.bank.update_balance(addr, balance);
+    }
+
+    pub fn with_token_balance(&mut self, token: &str, holder: &str, balance: Uint128) {
+        self.token_balances
+            .entry(token.to_string())
+            .or_default()
+            .insert(holder.to_string(), balance);
+    }
+
+    pub fn with_contract_admin(&mut self, contract_addr: &str, admin: &str) {
+        let contract_addr = contract_addr.to_string();
+        let admin = Addr::unchecked(admin);
+        self.base.update_wasm(move |request| match request {
+            WasmQuery::ContractInfo { contract_addr: addr } if *addr == contract_addr => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&ContractInfoResponse::new(
+                        1,
+                        admin.clone(),
+                        Some(admin.clone()),
+                        false,
+                        None,
+                    ))
+                    .unwrap(),
+                ))
+            }
+            _ => SystemResult::Err(SystemError::NoSuchContract {
+                addr: contract_addr.clone(),
+            }),
+        });
+    }
+}
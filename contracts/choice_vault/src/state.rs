@@ -0,0 +1,23 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use choice::asset::AssetInfo;
+use choice::vault::CompoundRoute;
+use cosmwasm_std::{CanonicalAddr, Decimal};
+use cw_storage_plus::Item;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Config {
+    pub farm: CanonicalAddr,
+    pub pair: CanonicalAddr,
+    pub router: CanonicalAddr,
+    /// Assets of the pair, what the rewards get swapped to
+    pub asset_infos: [AssetInfo; 2],
+    pub lp_denom: String,
+    pub share_denom: String,
+    pub bounty_rate: Decimal,
+    pub max_spread: Decimal,
+    pub compound_routes: Vec<CompoundRoute>,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
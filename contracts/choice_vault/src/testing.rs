@@ -0,0 +1,822 @@
+use cosmwasm_std::testing::{message_info, mock_env, MockApi, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    coin, from_json, to_json_binary, Addr, BankMsg, CosmosMsg, Decimal, OwnedDeps, StdError,
+    Uint128, WasmMsg,
+};
+use cosmwasm_std::testing::MockStorage;
+use cw20::Cw20ExecuteMsg;
+use injective_cosmwasm::msg::{
+    create_burn_tokens_msg, create_mint_tokens_msg, create_new_denom_msg,
+    create_set_token_metadata_msg,
+};
+use injective_cosmwasm::InjectiveQueryWrapper;
+
+use crate::contract::{execute, instantiate, query};
+use crate::mock_querier::{mock_dependencies, WasmMockQuerier};
+
+use choice::asset::{default_commission_rate, Asset, AssetInfo, PairInfo, PairType};
+use choice::pair::{
+    Cw20HookMsg as PairCw20HookMsg, ExecuteMsg as PairExecuteMsg, PoolResponse,
+};
+use choice::router::{Cw20HookMsg as RouterCw20HookMsg, SwapOperation};
+use choice::staking::{
+    ConfigResponse as FarmConfigResponse, ExecuteMsg as FarmExecuteMsg, RewardStream,
+};
+use choice::vault::{
+    CallbackMsg, CompoundRoute, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    StateResponse,
+};
+
+const LP_DENOM: &str = "factory/pair0000/lp";
+
+fn share_denom() -> String {
+    format!("factory/{}/share", MOCK_CONTRACT_ADDR)
+}
+
+fn pair_token(api: &MockApi) -> AssetInfo {
+    AssetInfo::Token {
+        contract_addr: api.addr_make("asset0000").to_string(),
+    }
+}
+
+fn reward_token(api: &MockApi) -> AssetInfo {
+    AssetInfo::Token {
+        contract_addr: api.addr_make("reward0000").to_string(),
+    }
+}
+
+fn compound_route(api: &MockApi) -> CompoundRoute {
+    CompoundRoute {
+        reward_token: reward_token(api),
+        operations: vec![SwapOperation::Choice {
+            offer_asset_info: reward_token(api),
+            ask_asset_info: pair_token(api),
+            max_spread: None,
+            belief_price: None,
+        }],
+    }
+}
+
+fn mock_vault_dependencies(
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier, InjectiveQueryWrapper> {
+    let mut deps = mock_dependencies(&[]);
+    let api = deps.api;
+    deps.querier.with_pair_info(PairInfo {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "inj".to_string(),
+            },
+            pair_token(&api),
+        ],
+        asset_decimals: [18u8, 6u8],
+        contract_addr: api.addr_make("pair0000").to_string(),
+        liquidity_token: LP_DENOM.to_string(),
+        burn_address: api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: api.addr_make("feeaddr0000").to_string(),
+        commission_rate: default_commission_rate(),
+        pair_type: PairType::Xyk {},
+        paused: false,
+    });
+    deps.querier.with_farm_config(FarmConfigResponse {
+        staking_token: LP_DENOM.to_string(),
+        reward_streams: vec![
+            RewardStream {
+                reward_token: reward_token(&api),
+                distribution_schedule: vec![],
+            },
+            RewardStream {
+                reward_token: AssetInfo::NativeToken {
+                    denom: "inj".to_string(),
+                },
+                distribution_schedule: vec![],
+            },
+            RewardStream {
+                reward_token: AssetInfo::NativeToken {
+                    denom: "uatom".to_string(),
+                },
+                distribution_schedule: vec![],
+            },
+        ],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: 0,
+    });
+    deps
+}
+
+fn instantiate_msg(api: &MockApi) -> InstantiateMsg {
+    InstantiateMsg {
+        farm: api.addr_make("farm0000").to_string(),
+        pair: api.addr_make("pair0000").to_string(),
+        router: api.addr_make("router0000").to_string(),
+        bounty_rate: Decimal::percent(1),
+        max_spread: Decimal::percent(5),
+        compound_routes: vec![compound_route(api)],
+    }
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_vault_dependencies();
+    let msg = instantiate_msg(&deps.api);
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+
+    let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>(),
+        vec![
+            create_new_denom_msg(MOCK_CONTRACT_ADDR.to_string(), "share".to_string()),
+            create_set_token_metadata_msg(
+                share_denom(),
+                "choice vault share".to_string(),
+                "uVS".to_string(),
+                6,
+            ),
+        ]
+    );
+
+    let config: ConfigResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config,
+        ConfigResponse {
+            farm: deps.api.addr_make("farm0000").to_string(),
+            pair: deps.api.addr_make("pair0000").to_string(),
+            router: deps.api.addr_make("router0000").to_string(),
+            lp_denom: LP_DENOM.to_string(),
+            share_denom: share_denom(),
+            bounty_rate: Decimal::percent(1),
+            max_spread: Decimal::percent(5),
+            compound_routes: vec![compound_route(&deps.api)],
+        }
+    );
+}
+
+#[test]
+fn instantiate_validation() {
+    let mut deps = mock_vault_dependencies();
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+
+    let mut msg = instantiate_msg(&deps.api);
+    msg.bounty_rate = Decimal::percent(11);
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "bounty rate must not exceed 0.1")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    let mut msg = instantiate_msg(&deps.api);
+    msg.max_spread = Decimal::one();
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "max spread must be below 1"),
+        _ => panic!("Must return generic error"),
+    }
+
+    let mut msg = instantiate_msg(&deps.api);
+    msg.compound_routes[0].operations = vec![SwapOperation::Choice {
+        offer_asset_info: reward_token(&deps.api),
+        ask_asset_info: AssetInfo::NativeToken {
+            denom: "uatom".to_string(),
+        },
+        max_spread: None,
+        belief_price: None,
+    }];
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "compound route must end in a pair asset")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    let mut msg = instantiate_msg(&deps.api);
+    msg.compound_routes.push(compound_route(&deps.api));
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "duplicate compound route"),
+        _ => panic!("Must return generic error"),
+    }
+
+    let farm_config = FarmConfigResponse {
+        staking_token: LP_DENOM.to_string(),
+        reward_streams: vec![],
+        lock_tiers: vec![],
+        early_exit: None,
+        unbonding_period: 86400,
+    };
+    deps.querier.with_farm_config(farm_config.clone());
+    let msg = instantiate_msg(&deps.api);
+    let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "farm unbonding period is not supported")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    deps.querier.with_farm_config(FarmConfigResponse {
+        staking_token: "factory/pair0001/lp".to_string(),
+        unbonding_period: 0,
+        ..farm_config
+    });
+    let msg = instantiate_msg(&deps.api);
+    let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "farm does not stake the pair liquidity token")
+        }
+        _ => panic!("Must return generic error"),
+    }
+}
+
+#[test]
+fn deposit_and_withdraw() {
+    let mut deps = mock_vault_dependencies();
+    let msg = instantiate_msg(&deps.api);
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let depositor = deps.api.addr_make("depositor0000");
+    let farm = deps.api.addr_make("farm0000").to_string();
+
+    // wrong funds
+    let info = message_info(&depositor, &[coin(100, "inj")]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, format!("funds mismatch; must send only {}", LP_DENOM))
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    // the first deposit must cover the locked minimum shares
+    let info = message_info(&depositor, &[coin(1000, LP_DENOM)]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "first deposit must exceed the minimum shares")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    // the first deposit mints shares 1:1, less the minimum shares locked in the vault
+    let info = message_info(&depositor, &[coin(1100, LP_DENOM)]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: farm.clone(),
+                msg: to_json_binary(&FarmExecuteMsg::Bond {
+                    amount: Uint128::from(1100u128),
+                    lock_duration: None,
+                })
+                .unwrap(),
+                funds: vec![coin(1100, LP_DENOM)],
+            }),
+            create_mint_tokens_msg(
+                Addr::unchecked(MOCK_CONTRACT_ADDR),
+                coin(1000, share_denom()),
+                MOCK_CONTRACT_ADDR.to_string(),
+            ),
+            create_mint_tokens_msg(
+                Addr::unchecked(MOCK_CONTRACT_ADDR),
+                coin(100, share_denom()),
+                depositor.to_string(),
+            ),
+        ]
+    );
+
+    // compounding grew the bonded liquidity to 1650 for the 1100 shares
+    deps.querier.with_denom_supply(&share_denom(), Uint128::from(1100u128));
+    deps.querier.with_bond_amount(MOCK_CONTRACT_ADDR, Uint128::from(1650u128));
+
+    // the rewards earned so far are compounded before the deposit is priced
+    let info = message_info(&depositor, &[coin(30, LP_DENOM)]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+    let callback = |msg: CallbackMsg| {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            msg: to_json_binary(&ExecuteMsg::Callback(msg)).unwrap(),
+            funds: vec![],
+        })
+    };
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: farm.clone(),
+                msg: to_json_binary(&FarmExecuteMsg::Withdraw {}).unwrap(),
+                funds: vec![],
+            }),
+            callback(CallbackMsg::SwapRewards {
+                caller: depositor.to_string(),
+            }),
+            callback(CallbackMsg::ProvideLiquidity {}),
+            callback(CallbackMsg::Bond {}),
+            callback(CallbackMsg::MintShares {
+                depositor: depositor.to_string(),
+                amount: Uint128::from(30u128),
+            }),
+        ]
+    );
+
+    // `Bond` bonded the deposit with the compounded liquidity
+    deps.querier.with_bond_amount(MOCK_CONTRACT_ADDR, Uint128::from(1680u128));
+    let info = message_info(&Addr::unchecked(MOCK_CONTRACT_ADDR), &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::Callback(CallbackMsg::MintShares {
+            depositor: depositor.to_string(),
+            amount: Uint128::from(30u128),
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>(),
+        vec![create_mint_tokens_msg(
+            Addr::unchecked(MOCK_CONTRACT_ADDR),
+            coin(20, share_denom()),
+            depositor.to_string(),
+        )]
+    );
+
+    deps.querier.with_denom_supply(&share_denom(), Uint128::from(1120u128));
+    let state: StateResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap()).unwrap();
+    assert_eq!(
+        state,
+        StateResponse {
+            total_shares: Uint128::from(1120u128),
+            total_bond_amount: Uint128::from(1680u128),
+        }
+    );
+
+    let info = message_info(&depositor, &[coin(10, share_denom())]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Withdraw {}).unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>(),
+        vec![
+            create_burn_tokens_msg(
+                Addr::unchecked(MOCK_CONTRACT_ADDR),
+                coin(10, share_denom()),
+            ),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: farm,
+                msg: to_json_binary(&FarmExecuteMsg::Unbond {
+                    amount: Uint128::from(15u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: depositor.to_string(),
+                amount: vec![coin(15, LP_DENOM)],
+            }),
+        ]
+    );
+}
+
+#[test]
+fn deposit_share_inflation() {
+    let mut deps = mock_vault_dependencies();
+    let msg = instantiate_msg(&deps.api);
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // the attacker keeps a single share of the first deposit
+    let attacker = deps.api.addr_make("attacker0000");
+    let info = message_info(&attacker, &[coin(1001, LP_DENOM)]);
+    let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+    assert_eq!(
+        res.messages[2].msg,
+        create_mint_tokens_msg(
+            Addr::unchecked(MOCK_CONTRACT_ADDR),
+            coin(1, share_denom()),
+            attacker.to_string(),
+        )
+    );
+
+    // and inflates the bonded liquidity behind it with a 10000 donation
+    deps.querier.with_denom_supply(&share_denom(), Uint128::from(1001u128));
+    deps.querier.with_bond_amount(MOCK_CONTRACT_ADDR, Uint128::from(11001u128));
+
+    // the locked shares keep the victim's deposit from rounding down to nothing,
+    // 5000 * 1001 / 11001
+    let victim = deps.api.addr_make("victim0000");
+    let info = message_info(&victim, &[coin(5000, LP_DENOM)]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+    deps.querier.with_bond_amount(MOCK_CONTRACT_ADDR, Uint128::from(16001u128));
+    let info = message_info(&Addr::unchecked(MOCK_CONTRACT_ADDR), &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::Callback(CallbackMsg::MintShares {
+            depositor: victim.to_string(),
+            amount: Uint128::from(5000u128),
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        create_mint_tokens_msg(
+            Addr::unchecked(MOCK_CONTRACT_ADDR),
+            coin(454, share_denom()),
+            victim.to_string(),
+        )
+    );
+}
+
+#[test]
+fn compound() {
+    let mut deps = mock_vault_dependencies();
+    let msg = instantiate_msg(&deps.api);
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let caller = deps.api.addr_make("caller0000");
+    let info = message_info(&caller, &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Compound {});
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "no shares to compound"),
+        _ => panic!("Must return generic error"),
+    }
+
+    deps.querier.with_denom_supply(&share_denom(), Uint128::from(100u128));
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::Compound {}).unwrap();
+    let callback = |msg: CallbackMsg| {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+            msg: to_json_binary(&ExecuteMsg::Callback(msg)).unwrap(),
+            funds: vec![],
+        })
+    };
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: deps.api.addr_make("farm0000").to_string(),
+                msg: to_json_binary(&FarmExecuteMsg::Withdraw {}).unwrap(),
+                funds: vec![],
+            }),
+            callback(CallbackMsg::SwapRewards {
+                caller: caller.to_string(),
+            }),
+            callback(CallbackMsg::ProvideLiquidity {}),
+            callback(CallbackMsg::Bond {}),
+        ]
+    );
+
+    // only the vault runs the callbacks
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::Callback(CallbackMsg::Bond {}),
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return generic error"),
+    }
+}
+
+#[test]
+fn compound_callbacks() {
+    let mut deps = mock_vault_dependencies();
+    let msg = instantiate_msg(&deps.api);
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let caller = deps.api.addr_make("caller0000");
+    let reward = deps.api.addr_make("reward0000").to_string();
+    let router = deps.api.addr_make("router0000").to_string();
+
+    // uatom has no route and stays in the vault
+    deps.querier.with_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![coin(200, "inj"), coin(50, "uatom")],
+    );
+    deps.querier
+        .with_token_balance(&reward, MOCK_CONTRACT_ADDR, Uint128::from(1000u128));
+
+    let info = message_info(&Addr::unchecked(MOCK_CONTRACT_ADDR), &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::Callback(CallbackMsg::SwapRewards {
+            caller: caller.to_string(),
+        }),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: reward.clone(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: caller.to_string(),
+                    amount: Uint128::from(10u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: reward.clone(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: router,
+                    amount: Uint128::from(990u128),
+                    msg: to_json_binary(&RouterCw20HookMsg::ExecuteSwapOperations {
+                        // hops without their own max spread use the vault one
+                        operations: vec![SwapOperation::Choice {
+                            offer_asset_info: reward_token(&deps.api),
+                            ask_asset_info: pair_token(&deps.api),
+                            max_spread: Some(Decimal::percent(5)),
+                            belief_price: None,
+                        }],
+                        minimum_receive: None,
+                        to: None,
+                        deadline: None,
+                        referral: None,
+                    })
+                    .unwrap(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: caller.to_string(),
+                amount: vec![coin(2, "inj")],
+            }),
+        ]
+    );
+    assert_eq!(
+        res.attributes[2].value,
+        format!("10{}, 2inj", reward)
+    );
+
+    // the router paid out the swapped rewards
+    let pair = deps.api.addr_make("pair0000").to_string();
+    let asset = deps.api.addr_make("asset0000").to_string();
+    deps.querier
+        .with_balance(MOCK_CONTRACT_ADDR, vec![coin(198, "inj"), coin(50, "uatom")]);
+    deps.querier
+        .with_token_balance(&asset, MOCK_CONTRACT_ADDR, Uint128::from(300u128));
+    // 1 asset0000 = 0.25 inj over the twap window
+    deps.querier.with_pool(PoolResponse {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "inj".to_string(),
+                },
+                amount: Uint128::from(1000u128),
+            },
+            Asset {
+                info: pair_token(&deps.api),
+                amount: Uint128::from(4000u128),
+            },
+        ],
+        total_share: Uint128::from(2000u128),
+    });
+    deps.querier
+        .with_twap_price(&pair_token(&deps.api), Decimal::percent(25));
+    deps.querier.with_twap_price(
+        &AssetInfo::NativeToken {
+            denom: "inj".to_string(),
+        },
+        Decimal::from_ratio(4u128, 1u128),
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::Callback(CallbackMsg::ProvideLiquidity {}),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: pair.clone(),
+                msg: to_json_binary(&PairExecuteMsg::ProvideSingleSided {
+                    offer_asset: Asset {
+                        info: AssetInfo::NativeToken {
+                            denom: "inj".to_string(),
+                        },
+                        amount: Uint128::from(198u128),
+                    },
+                    // 198 * 2000 / 2000 * 0.95
+                    min_share: Some(Uint128::from(188u128)),
+                    receiver: None,
+                    deadline: None,
+                })
+                .unwrap(),
+                funds: vec![coin(198, "inj")],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: asset,
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: pair,
+                    amount: Uint128::from(300u128),
+                    msg: to_json_binary(&PairCw20HookMsg::ProvideSingleSided {
+                        // 300 * 2000 / 8000 * 0.95
+                        min_share: Some(Uint128::from(71u128)),
+                        receiver: None,
+                        deadline: None,
+                    })
+                    .unwrap(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    deps.querier.with_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![coin(70, LP_DENOM), coin(50, "uatom")],
+    );
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::Callback(CallbackMsg::Bond {}),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>(),
+        vec![CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_make("farm0000").to_string(),
+            msg: to_json_binary(&FarmExecuteMsg::Bond {
+                amount: Uint128::from(70u128),
+                lock_duration: None,
+            })
+            .unwrap(),
+            funds: vec![coin(70, LP_DENOM)],
+        })]
+    );
+}
+
+#[test]
+fn provide_liquidity_stable_pair() {
+    let mut deps = mock_vault_dependencies();
+    let api = deps.api;
+    deps.querier.with_pair_info(PairInfo {
+        asset_infos: [
+            AssetInfo::NativeToken {
+                denom: "inj".to_string(),
+            },
+            pair_token(&api),
+        ],
+        asset_decimals: [18u8, 6u8],
+        contract_addr: api.addr_make("pair0000").to_string(),
+        liquidity_token: LP_DENOM.to_string(),
+        burn_address: api.addr_make("burnaddr0000").to_string(),
+        fee_wallet_address: api.addr_make("feeaddr0000").to_string(),
+        commission_rate: default_commission_rate(),
+        pair_type: PairType::Stable { amp: 100 },
+        paused: false,
+    });
+    let msg = instantiate_msg(&deps.api);
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let pair = deps.api.addr_make("pair0000").to_string();
+    let asset = deps.api.addr_make("asset0000").to_string();
+    deps.querier.with_balance(MOCK_CONTRACT_ADDR, vec![coin(198, "inj")]);
+    deps.querier
+        .with_token_balance(&asset, MOCK_CONTRACT_ADDR, Uint128::from(300u128));
+
+    // an imbalanced stable pool is valued at its stable price, not the 1:2 reserve ratio
+    deps.querier.with_pool(PoolResponse {
+        assets: [
+            Asset {
+                info: AssetInfo::NativeToken {
+                    denom: "inj".to_string(),
+                },
+                amount: Uint128::from(1000u128),
+            },
+            Asset {
+                info: pair_token(&deps.api),
+                amount: Uint128::from(2000u128),
+            },
+        ],
+        total_share: Uint128::from(3000u128),
+    });
+    deps.querier
+        .with_twap_price(&pair_token(&deps.api), Decimal::percent(80));
+    deps.querier.with_twap_price(
+        &AssetInfo::NativeToken {
+            denom: "inj".to_string(),
+        },
+        Decimal::percent(125),
+    );
+
+    let info = message_info(&Addr::unchecked(MOCK_CONTRACT_ADDR), &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::Callback(CallbackMsg::ProvideLiquidity {}),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.into_iter().map(|m| m.msg).collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: pair.clone(),
+                msg: to_json_binary(&PairExecuteMsg::ProvideSingleSided {
+                    offer_asset: Asset {
+                        info: AssetInfo::NativeToken {
+                            denom: "inj".to_string(),
+                        },
+                        amount: Uint128::from(198u128),
+                    },
+                    // 198 * 3000 / (1000 + 2000 * 0.8) * 0.95
+                    min_share: Some(Uint128::from(216u128)),
+                    receiver: None,
+                    deadline: None,
+                })
+                .unwrap(),
+                funds: vec![coin(198, "inj")],
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: asset,
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: pair,
+                    amount: Uint128::from(300u128),
+                    msg: to_json_binary(&PairCw20HookMsg::ProvideSingleSided {
+                        // 300 * 3000 / (2000 + 1000 * 1.25) * 0.95
+                        min_share: Some(Uint128::from(262u128)),
+                        receiver: None,
+                        deadline: None,
+                    })
+                    .unwrap(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+}
+
+#[test]
+fn update_config() {
+    let mut deps = mock_vault_dependencies();
+    let msg = instantiate_msg(&deps.api);
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let admin = deps.api.addr_make("admin0000");
+    deps.querier
+        .with_contract_admin(MOCK_CONTRACT_ADDR, admin.as_str());
+
+    let msg = ExecuteMsg::UpdateConfig {
+        bounty_rate: Some(Decimal::percent(2)),
+        max_spread: Some(Decimal::percent(3)),
+        compound_routes: Some(vec![]),
+    };
+    let info = message_info(&deps.api.addr_make("addr0000"), &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => assert_eq!(msg, "unauthorized"),
+        _ => panic!("Must return generic error"),
+    }
+
+    let info = message_info(&admin, &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::UpdateConfig {
+            bounty_rate: None,
+            max_spread: None,
+            compound_routes: Some(vec![CompoundRoute {
+                reward_token: AssetInfo::NativeToken {
+                    denom: "inj".to_string(),
+                },
+                operations: vec![],
+            }]),
+        },
+    );
+    match res {
+        Err(StdError::GenericErr { msg, .. }) => {
+            assert_eq!(msg, "pair assets are provided without a route")
+        }
+        _ => panic!("Must return generic error"),
+    }
+
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let config: ConfigResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(config.bounty_rate, Decimal::percent(2));
+    assert_eq!(config.max_spread, Decimal::percent(3));
+    assert_eq!(config.compound_routes, vec![]);
+}
@@ -4,7 +4,7 @@ use std::fmt;
 
 use crate::querier::{query_balance, query_native_decimals, query_token_balance, query_token_info};
 use cosmwasm_std::{
-    to_json_binary, Addr, Api, BankMsg, CanonicalAddr, Coin, CosmosMsg, MessageInfo, QuerierWrapper,
+    to_json_binary, Addr, Api, BankMsg, CanonicalAddr, Coin, CosmosMsg, Decimal, MessageInfo, QuerierWrapper,
     StdError, StdResult, SubMsg, Uint128, WasmMsg, CustomQuery
 };
 use cw20::Cw20ExecuteMsg;
@@ -256,6 +256,12 @@ pub struct PairInfo {
     pub asset_decimals: [u8; 2],
     pub burn_address: String, // New field
     pub fee_wallet_address: String, // New field
+    pub commission_rate: Decimal,
+    pub pair_type: PairType,
+    /// Swaps and provides are halted, withdrawals still work. The pair also
+    /// reports the global pause of the factory
+    #[serde(default)]
+    pub paused: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -266,6 +272,45 @@ pub struct PairInfoRaw {
     pub asset_decimals: [u8; 2],
     pub burn_address: CanonicalAddr, // New field
     pub fee_wallet_address: CanonicalAddr, // New field
+    /// Pairs stored before fee tiers existed charged a fixed 0.3%
+    #[serde(default = "default_commission_rate")]
+    pub commission_rate: Decimal,
+    /// Pairs stored before stable pairs existed are constant product
+    #[serde(default)]
+    pub pair_type: PairType,
+    #[serde(default)]
+    pub paused: bool,
+}
+
+/// Invariant a pair prices its swaps with
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PairType {
+    /// Constant product, x * y = k
+    Xyk {},
+    /// Curve stableswap invariant for pegged assets,
+    /// `amp` is the initial amplification coefficient
+    Stable { amp: u64 },
+}
+
+impl Default for PairType {
+    fn default() -> Self {
+        PairType::Xyk {}
+    }
+}
+
+impl fmt::Display for PairType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PairType::Xyk {} => write!(f, "xyk"),
+            PairType::Stable { .. } => write!(f, "stable"),
+        }
+    }
+}
+
+/// Commission rate == 0.3%
+pub fn default_commission_rate() -> Decimal {
+    Decimal::permille(3)
 }
 
 impl PairInfoRaw {
@@ -280,6 +325,9 @@ impl PairInfoRaw {
             asset_decimals: self.asset_decimals,
             burn_address: api.addr_humanize(&self.burn_address)?.to_string(),
             fee_wallet_address: api.addr_humanize(&self.fee_wallet_address)?.to_string(),
+            commission_rate: self.commission_rate,
+            pair_type: self.pair_type.clone(),
+            paused: self.paused,
         })
     }
 
@@ -1,7 +1,10 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::asset::{Asset, AssetInfo, PairInfo};
+use crate::asset::{Asset, AssetInfo, PairInfo, PairType};
+use crate::pair::ProtocolFeeMode;
+
+use cosmwasm_std::{Decimal, StdError, StdResult};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -11,6 +14,10 @@ pub struct InstantiateMsg {
 
     pub burn_address: String, // New field for the burn address
     pub fee_wallet_address: String, // New field for the fee wallet address
+
+    /// Whitelisted commission rates a pair can be created with,
+    /// the first one is used when CreatePair does not specify a rate
+    pub commission_rates: Vec<Decimal>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -24,10 +31,15 @@ pub enum ExecuteMsg {
 
         burn_address: Option<String>, // New field
         fee_wallet_address: Option<String>, // New field
+        commission_rates: Option<Vec<Decimal>>,
     },
     /// CreatePair instantiates pair contract
     CreatePair {
         assets: [Asset; 2],
+        /// Must be one of the whitelisted commission rates
+        commission_rate: Option<Decimal>,
+        /// Defaults to a constant product pair
+        pair_type: Option<PairType>,
     },
     AddNativeTokenDecimals {
         denom: String,
@@ -37,6 +49,41 @@ pub enum ExecuteMsg {
         contract: String,
         code_id: Option<u64>,
     },
+    /// UpdateFeeSplit changes how swap commissions are shared between
+    /// liquidity providers, the fee wallet and the burn auction
+    UpdateFeeSplit {
+        fee_split: FeeSplit,
+    },
+    /// RampAmp starts ramping the amplification of a stable pair
+    RampAmp {
+        pair: String,
+        future_amp: u64,
+        future_time: u64,
+    },
+    /// StopRampAmp stops the amplification ramp of a stable pair
+    StopRampAmp {
+        pair: String,
+    },
+    /// UpdateProtocolFeeMode switches how a pair pays the protocol share of its commission
+    UpdateProtocolFeeMode {
+        pair: String,
+        mode: ProtocolFeeMode,
+    },
+    /// UpdateGuardian appoints the guardian who can pause and unpause pairs
+    /// besides the owner, or removes it when `guardian` is not given
+    UpdateGuardian {
+        guardian: Option<String>,
+    },
+    /// PausePair halts swaps and provides of a pair, or of all pairs through
+    /// a global flag the pairs check when `pair` is not given, owner or guardian only
+    PausePair {
+        pair: Option<String>,
+    },
+    /// UnpausePair resumes a pair, or lifts the global pause when `pair` is not given,
+    /// pairs paused one by one stay paused
+    UnpausePair {
+        pair: Option<String>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -50,9 +97,18 @@ pub enum QueryMsg {
         start_after: Option<[AssetInfo; 2]>,
         limit: Option<u32>,
     },
+    /// Pairs trading `asset_info`, ordered like `Pairs`
+    PairsByAsset {
+        asset_info: AssetInfo,
+        start_after: Option<[AssetInfo; 2]>,
+        limit: Option<u32>,
+    },
     NativeTokenDecimals {
         denom: String,
     },
+    FeeSplit {},
+    /// Whether all pairs are paused
+    Paused {},
 }
 
 // We define a custom struct for each query response
@@ -64,6 +120,8 @@ pub struct ConfigResponse {
 
     pub burn_address: String, // New field
     pub fee_wallet_address: String, // New field
+    pub commission_rates: Vec<Decimal>,
+    pub guardian: Option<String>,
 }
 
 /// We currently take no arguments for migrations
@@ -80,3 +138,46 @@ pub struct PairsResponse {
 pub struct NativeTokenDecimalsResponse {
     pub decimals: u8,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PausedResponse {
+    pub paused: bool,
+}
+
+/// Shares of the swap commission, they must sum to 1
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeSplit {
+    /// Kept in the pool for liquidity providers
+    pub lp: Decimal,
+    pub fee_wallet: Decimal,
+    /// Sent to the burn auction
+    pub burn: Decimal,
+}
+
+impl Default for FeeSplit {
+    /// 2/3 to liquidity providers, 1/6 to the fee wallet and 1/6 to the burn auction
+    fn default() -> Self {
+        let sixth = Decimal::from_ratio(1u128, 6u128);
+        FeeSplit {
+            lp: Decimal::one() - sixth - sixth,
+            fee_wallet: sixth,
+            burn: sixth,
+        }
+    }
+}
+
+impl FeeSplit {
+    pub fn validate(&self) -> StdResult<()> {
+        let total = self
+            .lp
+            .checked_add(self.fee_wallet)
+            .and_then(|total| total.checked_add(self.burn))
+            .map_err(|_| StdError::generic_err("fee split shares must sum to 1"))?;
+
+        if total != Decimal::one() {
+            return Err(StdError::generic_err("fee split shares must sum to 1"));
+        }
+
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@ pub mod router;
 pub mod token;
 pub mod util;
 pub mod staking;
+pub mod vault;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub mod mock_querier;
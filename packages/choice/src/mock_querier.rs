@@ -1,16 +1,24 @@
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    from_json, to_json_binary, Binary, Coin, ContractResult, Empty, OwnedDeps, Querier, QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery
+    from_json, to_json_binary, Addr, Binary, Coin, ContractInfoResponse, ContractResult, Decimal, Empty, OwnedDeps, Querier, QuerierResult, QueryRequest, SystemError, SystemResult, Uint128, WasmQuery
 };
 use injective_cosmwasm::tokenfactory::response::TokenFactoryCreateDenomFeeResponse;
-use injective_cosmwasm::{HandlesDenomSupplyQuery, InjectiveQuery, InjectiveRoute, HandlesFeeQuery};
+use injective_cosmwasm::{
+    create_spot_multi_market_handler, HandlesDenomSupplyQuery, HandlesFeeQuery,
+    HandlesPriceLevelsQuery, InjectiveQuery, InjectiveRoute, MarketId, OrderSide, PriceLevel,
+    SpotMarket,
+};
+use injective_cosmwasm::exchange::response::QueryOrderbookResponse;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::panic;
 
-use crate::asset::{AssetInfo, PairInfo};
-use crate::factory::{NativeTokenDecimalsResponse, QueryMsg as FactoryQueryMsg};
+use crate::asset::{default_commission_rate, AssetInfo, PairInfo, PairType};
+use crate::factory::{
+    FeeSplit, NativeTokenDecimalsResponse, PairsResponse, PausedResponse,
+    QueryMsg as FactoryQueryMsg,
+};
 use crate::pair::QueryMsg as PairQueryMsg;
 use crate::pair::{ReverseSimulationResponse, SimulationResponse};
 use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
@@ -77,6 +85,10 @@ pub(crate) fn balances_to_map(
 pub struct ChoiceFactoryQuerier {
     pairs: HashMap<String, PairInfo>,
     native_token_decimals: HashMap<String, u8>,
+    fee_split: FeeSplit,
+    paused: bool,
+    /// Simulated return per offer unit by pair contract, 1 when unset
+    return_rates: HashMap<String, Decimal>,
 }
 
 impl ChoiceFactoryQuerier {
@@ -84,6 +96,9 @@ impl ChoiceFactoryQuerier {
         ChoiceFactoryQuerier {
             pairs: pairs_to_map(pairs),
             native_token_decimals: native_token_decimals_to_map(native_token_decimals),
+            fee_split: FeeSplit::default(),
+            paused: false,
+            return_rates: HashMap::new(),
         }
     }
 }
@@ -165,8 +180,79 @@ impl WasmMockQuerier {
                         }),
                     }
                 }
+                Ok(FactoryQueryMsg::FeeSplit {}) => SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&self.choice_factory_querier.fee_split).unwrap(),
+                )),
+                Ok(FactoryQueryMsg::Paused {}) => SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&PausedResponse {
+                        paused: self.choice_factory_querier.paused,
+                    })
+                    .unwrap(),
+                )),
+                Ok(FactoryQueryMsg::Pairs { start_after, limit }) => {
+                    let start_after = start_after.map(|asset_infos| {
+                        let key = [asset_infos[0].to_string(), asset_infos[1].to_string()].join("");
+                        let mut sort_key: Vec<char> = key.chars().collect();
+                        sort_key.sort_by(|a, b| b.cmp(a));
+                        String::from_iter(sort_key.iter())
+                    });
+
+                    let mut keys: Vec<&String> = self.choice_factory_querier.pairs.keys().collect();
+                    keys.sort();
+
+                    let pairs: Vec<PairInfo> = keys
+                        .into_iter()
+                        .filter(|key| start_after.as_ref().is_none_or(|start| *key > start))
+                        .take(limit.unwrap_or(10).min(30) as usize)
+                        .map(|key| self.choice_factory_querier.pairs[key].clone())
+                        .collect();
+
+                    SystemResult::Ok(ContractResult::Ok(
+                        to_json_binary(&PairsResponse { pairs }).unwrap(),
+                    ))
+                }
+                Ok(FactoryQueryMsg::PairsByAsset {
+                    asset_info,
+                    start_after,
+                    limit,
+                }) => {
+                    let start_after = start_after.map(|asset_infos| {
+                        let key = [asset_infos[0].to_string(), asset_infos[1].to_string()].join("");
+                        let mut sort_key: Vec<char> = key.chars().collect();
+                        sort_key.sort_by(|a, b| b.cmp(a));
+                        String::from_iter(sort_key.iter())
+                    });
+
+                    let mut keys: Vec<&String> = self.choice_factory_querier.pairs.keys().collect();
+                    keys.sort();
+
+                    let pairs: Vec<PairInfo> = keys
+                        .into_iter()
+                        .filter(|key| start_after.as_ref().is_none_or(|start| *key > start))
+                        .map(|key| &self.choice_factory_querier.pairs[key])
+                        .filter(|pair| pair.asset_infos.iter().any(|info| info.equal(&asset_info)))
+                        .take(limit.unwrap_or(10).min(30) as usize)
+                        .cloned()
+                        .collect();
+
+                    SystemResult::Ok(ContractResult::Ok(
+                        to_json_binary(&PairsResponse { pairs }).unwrap(),
+                    ))
+                }
                 _ => match from_json(msg) {
                     Ok(PairQueryMsg::Pair {}) => {
+                        // pairs registered in the factory answer for themselves
+                        if let Some(pair_info) = self
+                            .choice_factory_querier
+                            .pairs
+                            .values()
+                            .find(|pair| pair.contract_addr == *contract_addr)
+                        {
+                            return SystemResult::Ok(ContractResult::from(to_json_binary(
+                                pair_info,
+                            )));
+                        }
+
                         let pair_addr = deps.api.addr_make("pair0000").to_string();
                         let liquidity_token = deps.api.addr_make("liquidity0000").to_string();
                         let burn_address = deps.api.addr_make("burnaddr0000").to_string();
@@ -187,12 +273,22 @@ impl WasmMockQuerier {
                                 liquidity_token,
                                 burn_address,
                                 fee_wallet_address,
+                                commission_rate: default_commission_rate(),
+                                pair_type: PairType::Xyk {},
+                                paused: false,
                             })
                         ))
                     }
                     Ok(PairQueryMsg::Simulation { offer_asset }) => {
+                        let return_rate = self
+                            .choice_factory_querier
+                            .return_rates
+                            .get(contract_addr)
+                            .cloned()
+                            .unwrap_or_else(Decimal::one);
+
                         SystemResult::Ok(ContractResult::from(to_json_binary(&SimulationResponse {
-                            return_amount: offer_asset.amount,
+                            return_amount: offer_asset.amount.mul_floor(return_rate),
                             commission_amount: Uint128::zero(),
                             spread_amount: Uint128::zero(),
                         })))
@@ -375,6 +471,31 @@ impl HandlesDenomSupplyQuery for MockDenomSupplyHandler {
 }
 
 
+// Spot orderbook handler returning the requested side of the market.
+pub struct SpotOrderbookHandler {
+    pub orderbooks: HashMap<MarketId, (Vec<PriceLevel>, Vec<PriceLevel>)>,
+}
+
+impl HandlesPriceLevelsQuery for SpotOrderbookHandler {
+    fn handle(&self, market_id: MarketId, order_side: OrderSide) -> QuerierResult {
+        let (buys, sells) = match self.orderbooks.get(&market_id) {
+            Some(orderbook) => orderbook.clone(),
+            None => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("No orderbook for market: {}", market_id.as_str()),
+                    request: Binary::default(),
+                })
+            }
+        };
+
+        let response = QueryOrderbookResponse {
+            buys_price_level: if order_side != OrderSide::Sell { buys } else { vec![] },
+            sells_price_level: if order_side != OrderSide::Buy { sells } else { vec![] },
+        };
+        SystemResult::Ok(ContractResult::from(to_json_binary(&response)))
+    }
+}
+
 // Create a mock fee handler that stores fees in a map (denom -> fee).
 #[derive(Clone, Default)]
 pub struct MockFeeHandler {
@@ -430,6 +551,68 @@ impl WasmMockQuerier {
         self.choice_factory_querier = ChoiceFactoryQuerier::new(pairs, native_token_decimals);
     }
 
+    // configure the fee split returned by the factory
+    pub fn with_fee_split(&mut self, fee_split: FeeSplit) {
+        self.choice_factory_querier.fee_split = fee_split;
+    }
+
+    // configure the global pause of the factory
+    pub fn with_factory_paused(&mut self, paused: bool) {
+        self.choice_factory_querier.paused = paused;
+    }
+
+    // configure the simulated return per offer unit of pairs
+    pub fn with_pair_return_rates(&mut self, return_rates: &[(&String, Decimal)]) {
+        for (pair, return_rate) in return_rates.iter() {
+            self.choice_factory_querier
+                .return_rates
+                .insert(pair.to_string(), *return_rate);
+        }
+    }
+
+    // configure the injective exchange spot markets
+    pub fn with_spot_markets(&mut self, markets: &[&SpotMarket]) {
+        self.inj.spot_market_response_handler = create_spot_multi_market_handler(
+            markets
+                .iter()
+                .map(|market| (market.market_id.clone(), (*market).clone()))
+                .collect(),
+        );
+    }
+
+    // configure the spot orderbooks as (market, buys, sells)
+    pub fn with_spot_orderbooks(&mut self, orderbooks: &[(&MarketId, Vec<PriceLevel>, Vec<PriceLevel>)]) {
+        self.inj.spot_market_orderbook_response_handler = Some(Box::new(SpotOrderbookHandler {
+            orderbooks: orderbooks
+                .iter()
+                .map(|(market_id, buys, sells)| ((*market_id).clone(), (buys.clone(), sells.clone())))
+                .collect(),
+        }));
+    }
+
+    // configure the admin returned by contract info queries
+    pub fn with_contract_admin(&mut self, contract_addr: &str, admin: &str) {
+        let contract_addr = contract_addr.to_string();
+        let admin = Addr::unchecked(admin);
+        self.base.update_wasm(move |request| match request {
+            WasmQuery::ContractInfo { contract_addr: addr } if *addr == contract_addr => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&ContractInfoResponse::new(
+                        1,
+                        admin.clone(),
+                        Some(admin.clone()),
+                        false,
+                        None,
+                    ))
+                    .unwrap(),
+                ))
+            }
+            _ => SystemResult::Err(SystemError::NoSuchContract {
+                addr: contract_addr.clone(),
+            }),
+        });
+    }
+
     pub fn with_balance(&mut self, balances: &[(&String, Vec<Coin>)]) {
         for (addr, balance) in balances {
             self.base.bank.update_balance(addr.to_string(), balance.clone());
@@ -1,9 +1,9 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use crate::asset::{Asset, AssetInfo};
+use crate::asset::{Asset, AssetInfo, PairType};
 
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Binary, Decimal, Uint128, Uint256};
 use cw20::Cw20ReceiveMsg;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -16,6 +16,10 @@ pub struct InstantiateMsg {
 
     pub burn_address: String, // New field
     pub fee_wallet_address: String, // New field
+
+    /// Fraction of every swap return kept as commission, must be less than 1
+    pub commission_rate: Decimal,
+    pub pair_type: PairType,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -29,6 +33,14 @@ pub enum ExecuteMsg {
         deadline: Option<u64>,
         slippage_tolerance: Option<Decimal>,
     },
+    /// ProvideSingleSided swaps part of `offer_asset` to the other asset
+    /// and provides both as liquidity in one call
+    ProvideSingleSided {
+        offer_asset: Asset,
+        min_share: Option<Uint128>,
+        receiver: Option<String>,
+        deadline: Option<u64>,
+    },
     /// Swap an offer asset to the other
     Swap {
         offer_asset: Asset,
@@ -43,7 +55,68 @@ pub enum ExecuteMsg {
         amount: Uint128,
         min_assets: Option<[Asset; 2]>,
         deadline: Option<u64>,
+        /// Swap the other leg to this asset and return it alone
+        ask_asset: Option<AssetInfo>,
+    },
+
+    /// RampAmp moves the amplification of a stable pair linearly
+    /// from its current value to `future_amp` at `future_time`, factory only
+    RampAmp {
+        future_amp: u64,
+        future_time: u64,
+    },
+    /// StopRampAmp freezes the amplification at its current value, factory only
+    StopRampAmp {},
+    /// UpdateProtocolFeeMode switches how the protocol share of
+    /// the commission is paid, factory only
+    UpdateProtocolFeeMode {
+        mode: ProtocolFeeMode,
+    },
+    /// CollectProtocolFees withdraws the accrued protocol shares and sends
+    /// the assets to the fee wallet and the burn auction
+    CollectProtocolFees {},
+    /// FlashSwap sends `ask_asset` to `contract` and calls it back with
+    /// `FlashCallbackMsg::FlashCallback`, the callback must leave the pool
+    /// with its invariant restored plus the commission on `ask_asset`
+    FlashSwap {
+        ask_asset: Asset,
+        contract: String,
+        msg: Binary,
     },
+    /// Pause halts swaps and provides, factory only
+    Pause {},
+    /// Unpause resumes swaps and provides, factory only
+    Unpause {},
+}
+
+/// Message sent to the contract of a flash swap
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashCallbackMsg {
+    FlashCallback {
+        sender: String,
+        ask_asset: Asset,
+        /// Owed in the ask asset on top of restoring the invariant
+        commission_amount: Uint128,
+        msg: Binary,
+    },
+}
+
+/// How the fee wallet and burn shares of the commission are paid
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolFeeMode {
+    /// Sent out on every swap
+    Transfer {},
+    /// Left in the pool and minted as LP shares to the pair on provide and
+    /// withdraw, like the Uniswap v2 kLast mechanism
+    Accrue {},
+}
+
+impl Default for ProtocolFeeMode {
+    fn default() -> Self {
+        ProtocolFeeMode::Transfer {}
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -56,6 +129,22 @@ pub enum Cw20HookMsg {
         to: Option<String>,
         deadline: Option<u64>,
     },
+    /// ProvideLiquidity deposits the sent token as its leg of `assets`.
+    /// A native leg follows as the funds of an `ExecuteMsg::ProvideLiquidity`
+    /// with the same `assets` in the same transaction, otherwise the deposit
+    /// is refunded to the sender by the next execute of the pair
+    ProvideLiquidity {
+        assets: [Asset; 2],
+        receiver: Option<String>,
+        deadline: Option<u64>,
+        slippage_tolerance: Option<Decimal>,
+    },
+    /// ProvideSingleSided provides the sent token like `ExecuteMsg::ProvideSingleSided`
+    ProvideSingleSided {
+        min_share: Option<Uint128>,
+        receiver: Option<String>,
+        deadline: Option<u64>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -65,6 +154,28 @@ pub enum QueryMsg {
     Pool {},
     Simulation { offer_asset: Asset },
     ReverseSimulation { ask_asset: Asset },
+    /// SimulateProvide previews a `ProvideSingleSided` of `offer_asset`
+    SimulateProvide { offer_asset: Asset },
+    /// SimulateProvideLiquidity previews a `ProvideLiquidity` of `assets`
+    SimulateProvideLiquidity { assets: [Asset; 2] },
+    /// SimulateWithdrawLiquidity previews a `WithdrawLiquidity` of `amount` shares
+    SimulateWithdrawLiquidity { amount: Uint128 },
+    /// Amp returns the amplification of a stable pair
+    Amp {},
+    /// CumulativePrices returns the price accumulators up to the current block
+    CumulativePrices {},
+    /// Twap returns the time weighted average price of `asset_info`
+    /// in units of the other asset over at least `window_seconds`
+    Twap {
+        asset_info: AssetInfo,
+        window_seconds: u64,
+    },
+    /// ProtocolFees returns the protocol fee mode and the shares waiting for collection
+    ProtocolFees {},
+    /// Stats returns the running totals of the swaps
+    Stats {},
+    /// VolumeHistory returns the daily volume of the last `buckets` days, newest first
+    VolumeHistory { buckets: u32 },
 }
 
 // We define a custom struct for each query response
@@ -90,6 +201,97 @@ pub struct ReverseSimulationResponse {
     pub commission_amount: Uint128,
 }
 
+/// SimulateProvideResponse returns single sided provide simulation response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulateProvideResponse {
+    pub share: Uint128,
+    /// Part of the offer amount swapped to the other asset
+    pub swap_amount: Uint128,
+    pub return_amount: Uint128,
+    pub spread_amount: Uint128,
+    pub commission_amount: Uint128,
+    /// Dust left over by the pool ratio, sent back to the sender
+    pub refund_assets: [Asset; 2],
+}
+
+/// SimulateProvideLiquidityResponse returns provide liquidity simulation response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulateProvideLiquidityResponse {
+    /// Shares minted to the receiver, net of the minimum liquidity locked on the first provide
+    pub share: Uint128,
+    pub refund_assets: [Asset; 2],
+}
+
+/// SimulateWithdrawLiquidityResponse returns withdraw liquidity simulation response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulateWithdrawLiquidityResponse {
+    pub assets: [Asset; 2],
+}
+
+/// AmpResponse returns the current amplification and its ramp target
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AmpResponse {
+    pub amp: u64,
+    pub future_amp: u64,
+    pub future_time: u64,
+}
+
+/// CumulativePricesResponse returns the time weighted sums of the pool prices,
+/// scaled by 1e18 and wrapping on overflow like Uniswap v2. Stable pairs sum
+/// their stableswap spot price instead of the reserve ratio
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct CumulativePricesResponse {
+    pub assets: [Asset; 2],
+    /// Sum of the price of asset 0 in units of asset 1
+    pub price0_cumulative: Uint256,
+    /// Sum of the price of asset 1 in units of asset 0
+    pub price1_cumulative: Uint256,
+    pub block_time: u64,
+}
+
+/// TwapResponse returns a time weighted average price
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TwapResponse {
+    pub price: Decimal,
+    /// Seconds actually averaged over, never shorter than the requested window
+    pub window_seconds: u64,
+}
+
+/// ProtocolFeesResponse returns the accrued protocol shares
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ProtocolFeesResponse {
+    pub mode: ProtocolFeeMode,
+    /// LP shares minted to the pair and not collected yet
+    pub shares: Uint128,
+}
+
+/// StatsResponse returns the running totals of the swaps, the fees are
+/// counted in the asset they were taken in
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct StatsResponse {
+    /// Amounts offered to and returned by the pair
+    pub volume: [Asset; 2],
+    pub commission: [Asset; 2],
+    pub burn: [Asset; 2],
+    pub fee_wallet: [Asset; 2],
+    pub swap_count: u64,
+}
+
+/// VolumeBucketResponse returns the volume of one day
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct VolumeBucketResponse {
+    /// Start of the day, in seconds
+    pub start: u64,
+    pub volume: [Asset; 2],
+    pub swap_count: u64,
+}
+
+/// VolumeHistoryResponse returns the daily volume, newest first
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct VolumeHistoryResponse {
+    pub buckets: Vec<VolumeBucketResponse>,
+}
+
 /// We currently take no arguments for migrations
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct MigrateMsg {}
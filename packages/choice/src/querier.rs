@@ -1,5 +1,8 @@
 use crate::asset::{Asset, AssetInfo, PairInfo};
-use crate::factory::{NativeTokenDecimalsResponse, QueryMsg as FactoryQueryMsg};
+use crate::factory::{
+    FeeSplit, NativeTokenDecimalsResponse, PairsResponse, PausedResponse,
+    QueryMsg as FactoryQueryMsg,
+};
 use crate::pair::{QueryMsg as PairQueryMsg, ReverseSimulationResponse, SimulationResponse};
 
 use injective_cosmwasm::querier::InjectiveQuerier;
@@ -66,7 +69,7 @@ pub fn query_token_factory_denom_total_supply(
     querier: &QuerierWrapper<InjectiveQueryWrapper>,
     denom: String,
 ) -> StdResult<Uint128> {
-    let querier: InjectiveQuerier<'_> = InjectiveQuerier::new(&querier);
+    let querier: InjectiveQuerier<'_> = InjectiveQuerier::new(querier);
     let query_msg: TokenFactoryDenomSupplyResponse = querier.query_token_factory_denom_total_supply(&denom).unwrap();
     let total_share: Uint128 = query_msg.total_supply;
     Ok(total_share)
@@ -75,7 +78,7 @@ pub fn query_token_factory_denom_total_supply(
 pub fn query_token_factory_denom_create_fee(
     querier: &QuerierWrapper<InjectiveQueryWrapper>
 ) -> StdResult<Vec<Coin>> {
-    let querier: InjectiveQuerier<'_> = InjectiveQuerier::new(&querier);
+    let querier: InjectiveQuerier<'_> = InjectiveQuerier::new(querier);
     let query_msg: TokenFactoryCreateDenomFeeResponse = querier.query_token_factory_creation_fee().unwrap();
     let fee: Vec<Coin> = query_msg.fee;
     Ok(fee)
@@ -94,6 +97,29 @@ pub fn query_native_decimals<Q: CustomQuery>(
     Ok(res.decimals)
 }
 
+pub fn query_fee_split<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    factory_contract: Addr,
+) -> StdResult<FeeSplit> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_json_binary(&FactoryQueryMsg::FeeSplit {})?,
+    }))
+}
+
+/// Whether the factory paused all of its pairs
+pub fn query_factory_paused<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    factory_contract: Addr,
+) -> StdResult<bool> {
+    let res: PausedResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_json_binary(&FactoryQueryMsg::Paused {})?,
+    }))?;
+
+    Ok(res.paused)
+}
+
 pub fn query_pair_info<Q: CustomQuery>(
     querier: &QuerierWrapper<Q>,
     factory_contract: Addr,
@@ -107,6 +133,35 @@ pub fn query_pair_info<Q: CustomQuery>(
     }))
 }
 
+pub fn query_pairs<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    factory_contract: Addr,
+    start_after: Option<[AssetInfo; 2]>,
+    limit: Option<u32>,
+) -> StdResult<PairsResponse> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_json_binary(&FactoryQueryMsg::Pairs { start_after, limit })?,
+    }))
+}
+
+pub fn query_pairs_by_asset<Q: CustomQuery>(
+    querier: &QuerierWrapper<Q>,
+    factory_contract: Addr,
+    asset_info: &AssetInfo,
+    start_after: Option<[AssetInfo; 2]>,
+    limit: Option<u32>,
+) -> StdResult<PairsResponse> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: factory_contract.to_string(),
+        msg: to_json_binary(&FactoryQueryMsg::PairsByAsset {
+            asset_info: asset_info.clone(),
+            start_after,
+            limit,
+        })?,
+    }))
+}
+
 pub fn simulate<Q: CustomQuery>(
     querier: &QuerierWrapper<Q>,
     pair_contract: Addr,
@@ -1,14 +1,26 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Decimal, Uint128};
 use cw20::Cw20ReceiveMsg;
+use injective_math::FPDecimal;
 
-use crate::asset::AssetInfo;
+use crate::asset::{Asset, AssetInfo};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct InstantiateMsg {
     pub choice_factory: String,
+    /// Max spread of the hops not setting their own
+    pub max_spread: Option<Decimal>,
+    /// Max commission a referral can take, none when unset
+    pub max_referral_commission_bps: Option<u16>,
+}
+
+/// Integrator taking a commission out of the offer asset
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Referral {
+    pub address: String,
+    pub commission_bps: u16,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -17,35 +29,103 @@ pub enum SwapOperation {
     Choice {
         offer_asset_info: AssetInfo,
         ask_asset_info: AssetInfo,
+        /// Overrides the router max spread for this hop
+        max_spread: Option<Decimal>,
+        belief_price: Option<Decimal>,
+    },
+    /// Atomic market order on an Injective exchange spot market,
+    /// the router settles the output once the order is filled
+    HelixSpot {
+        market_id: String,
+        offer_asset_info: AssetInfo,
+        ask_asset_info: AssetInfo,
+        /// Lowest price accepted when selling the base denom,
+        /// highest when buying it
+        worst_price: FPDecimal,
     },
 }
 
 impl SwapOperation {
+    pub fn get_offer_asset_info(&self) -> AssetInfo {
+        match self {
+            SwapOperation::Choice { offer_asset_info, .. } => offer_asset_info.clone(),
+            SwapOperation::HelixSpot { offer_asset_info, .. } => offer_asset_info.clone(),
+        }
+    }
+
     pub fn get_target_asset_info(&self) -> AssetInfo {
         match self {
             SwapOperation::Choice { ask_asset_info, .. } => ask_asset_info.clone(),
+            SwapOperation::HelixSpot { ask_asset_info, .. } => ask_asset_info.clone(),
         }
     }
 }
 
+/// Part of the offer amount a split route swaps
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RouteShare {
+    /// Fixed amount of the offer asset
+    Amount { amount: Uint128 },
+    /// Share of what the fixed amounts leave, relative to the other weights
+    Weight { weight: Decimal },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SplitRoute {
+    pub share: RouteShare,
+    pub operations: Vec<SwapOperation>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
-    /// Execute multiple BuyOperation
+    /// UpdateConfig sets the router max spread and max referral
+    /// commission, contract admin only. `clear_max_spread` drops the
+    /// router max spread so hops fall back to the pair default
+    UpdateConfig {
+        max_spread: Option<Decimal>,
+        clear_max_spread: Option<bool>,
+        max_referral_commission_bps: Option<u16>,
+    },
+    /// Execute multiple BuyOperation, the funds sent must be exactly
+    /// the offer asset of the first operation
     ExecuteSwapOperations {
         operations: Vec<SwapOperation>,
         minimum_receive: Option<Uint128>,
         to: Option<String>,
         deadline: Option<u64>,
+        referral: Option<Referral>,
+    },
+    /// Spread the offer asset sent over several routes to the same ask asset
+    /// and check `minimum_receive` on the merged return
+    ExecuteSplitSwap {
+        routes: Vec<SplitRoute>,
+        minimum_receive: Option<Uint128>,
+        to: Option<String>,
+        deadline: Option<u64>,
+    },
+    /// Swap only the offer needed to receive exactly `ask_amount`,
+    /// the unspent offer asset is refunded to the sender
+    ExecuteSwapOperationsExactOut {
+        operations: Vec<SwapOperation>,
+        ask_amount: Uint128,
+        max_offer: Option<Uint128>,
+        to: Option<String>,
+        deadline: Option<u64>,
     },
 
     /// Internal use
-    /// Swap all offer tokens to ask token
+    /// Swap `amount`, or the offer tokens held above `prev_balance`,
+    /// to ask token. Offer tokens a helix spot order leaves unspent go to `refund_to`
     ExecuteSwapOperation {
         operation: SwapOperation,
         to: Option<String>,
         deadline: Option<u64>,
+        amount: Option<Uint128>,
+        prev_balance: Option<Uint128>,
+        refund_to: Option<String>,
     },
     /// Internal use
     /// Check the swap amount is exceed minimum_receive
@@ -65,6 +145,20 @@ pub enum Cw20HookMsg {
         minimum_receive: Option<Uint128>,
         to: Option<String>,
         deadline: Option<u64>,
+        referral: Option<Referral>,
+    },
+    ExecuteSplitSwap {
+        routes: Vec<SplitRoute>,
+        minimum_receive: Option<Uint128>,
+        to: Option<String>,
+        deadline: Option<u64>,
+    },
+    ExecuteSwapOperationsExactOut {
+        operations: Vec<SwapOperation>,
+        ask_amount: Uint128,
+        max_offer: Option<Uint128>,
+        to: Option<String>,
+        deadline: Option<u64>,
     },
 }
 
@@ -80,12 +174,32 @@ pub enum QueryMsg {
         ask_amount: Uint128,
         operations: Vec<SwapOperation>,
     },
+    /// SimulateSplitSwap returns the merged return of the routes, each route
+    /// is simulated on its own so pools shared by routes are not accounted for
+    SimulateSplitSwap {
+        offer_amount: Uint128,
+        routes: Vec<SplitRoute>,
+    },
+    /// FindBestRoute searches the factory pairs for the operations returning
+    /// the most `ask_asset_info` for `offer_asset`, with at most `max_hops` swaps
+    FindBestRoute {
+        offer_asset: Asset,
+        ask_asset_info: AssetInfo,
+        max_hops: Option<u32>,
+    },
+    /// ReferralStats returns the volume and commission a referrer
+    /// accumulated per offer asset
+    ReferralStats {
+        referrer: String,
+    },
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct ConfigResponse {
     pub choice_factory: String,
+    pub max_spread: Option<Decimal>,
+    pub max_referral_commission_bps: u16,
 }
 
 // We define a custom struct for each query response
@@ -94,6 +208,26 @@ pub struct SimulateSwapOperationsResponse {
     pub amount: Uint128,
 }
 
+/// FindBestRouteResponse returns the best route and its simulated return amount
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FindBestRouteResponse {
+    pub operations: Vec<SwapOperation>,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ReferralAssetStats {
+    pub asset_info: AssetInfo,
+    pub volume: Uint128,
+    pub commission: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ReferralStatsResponse {
+    pub referrer: String,
+    pub stats: Vec<ReferralAssetStats>,
+}
+
 /// We currently take no arguments for migrations
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct MigrateMsg {}
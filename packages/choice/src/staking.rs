@@ -7,37 +7,109 @@ use crate::asset::AssetInfo;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
-    pub reward_token: AssetInfo,
     pub staking_token: AssetInfo,
+    pub reward_streams: Vec<RewardStream>,
+    /// Lock durations bonds can choose besides unlocked bonding
+    pub lock_tiers: Vec<LockTier>,
+    pub early_exit: Option<EarlyExit>,
+    /// Seconds unbonded tokens wait before they can be claimed,
+    /// none pays them out at once
+    pub unbonding_period: Option<u64>,
+}
+
+/// Reward token paid to the stakers over its own distribution schedule
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardStream {
+    pub reward_token: AssetInfo,
     pub distribution_schedule: Vec<(u64, u64, Uint128)>,
 }
 
+/// Bonds locked for `duration` seconds earn rewards on
+/// `multiplier` times their amount
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LockTier {
+    pub duration: u64,
+    pub multiplier: Decimal,
+}
+
+/// Locked bonds may leave early, paying `penalty_rate` of the
+/// released amount to the auction contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EarlyExit {
+    pub penalty_rate: Decimal,
+    pub auction_contract: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LockPosition {
+    pub amount: Uint128,
+    pub multiplier: Decimal,
+    pub unlock_time: u64,
+}
+
+/// Unbonded tokens claimable from `release_time`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UnbondClaim {
+    pub amount: Uint128,
+    pub release_time: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
+    /// Bond the native staking token, locked for one of the
+    /// configured tier durations when given
     Bond {
         amount: Uint128,
+        lock_duration: Option<u64>,
     },
+    /// Unbond from unlocked and expired positions
     Unbond {
         amount: Uint128,
     },
-    /// Withdraw pending rewards
+    /// Unbond including locked positions, soonest expiry first,
+    /// paying the early exit penalty on the locked part
+    EarlyUnbond {
+        amount: Uint128,
+    },
+    /// Pay out unbonded tokens past their release time
+    Claim {},
+    /// Withdraw pending rewards of every reward token
     Withdraw {},
+    /// End the boost of the expired locks of `staker` at their unlock time,
+    /// callable by anyone
+    ReleaseExpiredLocks {
+        staker: String,
+    },
     /// Owner operation to stop distribution on current staking contract
     /// and send remaining tokens to the new contract
     MigrateStaking {
         new_staking_contract: String,
     },
+    /// Replace the schedule of `reward_token`, a new reward token
+    /// starts a new stream, up to 10 streams
     UpdateConfig {
+        reward_token: AssetInfo,
         distribution_schedule: Vec<(u64, u64, Uint128)>,
     },
+    /// Fund future distributions of the attached native token, the
+    /// funds must match the schedule total and the token have a stream
+    AddRewards {
+        schedule: Vec<(u64, u64, Uint128)>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum Cw20HookMsg {
-    Bond {},
+    Bond {
+        lock_duration: Option<u64>,
+    },
+    /// Fund future distributions of the sent cw20 token, which must have a stream
+    AddRewards {
+        schedule: Vec<(u64, u64, Uint128)>,
+    },
 }
 
 /// migrate struct for distribution schedule
@@ -56,14 +128,21 @@ pub enum QueryMsg {
         staker: String,
         block_time: Option<u64>,
     },
+    /// Rewards owed to stakers against the balance held for them
+    RewardSolvency {},
+    PendingUnbonds {
+        staker: String,
+    },
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ConfigResponse {
-    pub reward_token: String,
     pub staking_token: String,
-    pub distribution_schedule: Vec<(u64, u64, Uint128)>,
+    pub reward_streams: Vec<RewardStream>,
+    pub lock_tiers: Vec<LockTier>,
+    pub early_exit: Option<EarlyExit>,
+    pub unbonding_period: u64,
 }
 
 // We define a custom struct for each query response
@@ -71,6 +150,13 @@ pub struct ConfigResponse {
 pub struct StateResponse {
     pub last_distributed: u64,
     pub total_bond_amount: Uint128,
+    pub total_bond_weight: Uint128,
+    pub rewards: Vec<RewardStateResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardStateResponse {
+    pub reward_token: AssetInfo,
     pub global_reward_index: Decimal,
 }
 
@@ -78,7 +164,35 @@ pub struct StateResponse {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct StakerInfoResponse {
     pub staker: String,
-    pub reward_index: Decimal,
     pub bond_amount: Uint128,
+    /// Bond amount boosted by the lock multipliers
+    pub bond_weight: Uint128,
+    pub locks: Vec<LockPosition>,
+    pub rewards: Vec<StakerRewardResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakerRewardResponse {
+    pub reward_token: AssetInfo,
+    pub reward_index: Decimal,
     pub pending_reward: Uint128,
-}
\ No newline at end of file
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardSolvencyResponse {
+    pub rewards: Vec<RewardBalanceResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardBalanceResponse {
+    pub reward_token: AssetInfo,
+    /// Unclaimed and still scheduled rewards
+    pub promised: Uint128,
+    /// Balance of the farm, excluding bonded staking tokens
+    pub held: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingUnbondsResponse {
+    pub staker: String,
+    pub claims: Vec<UnbondClaim>,
+}
@@ -1,4 +1,4 @@
-use crate::asset::{Asset, AssetInfo, AssetInfoRaw, AssetRaw, PairInfo};
+use crate::asset::{Asset, AssetInfo, AssetInfoRaw, AssetRaw, PairInfo, PairType};
 use crate::mock_querier::mock_dependencies;
 use crate::querier::{
     query_balance, query_pair_info, query_token_balance, query_token_info,
@@ -6,7 +6,7 @@ use crate::querier::{
 
 use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
 use cosmwasm_std::{
-    coin, to_json_binary, Addr, Api, BankMsg, Coin, CosmosMsg, MessageInfo, StdError, SubMsg, Uint128,
+    coin, to_json_binary, Addr, Api, BankMsg, Coin, CosmosMsg, Decimal, MessageInfo, StdError, SubMsg, Uint128,
     WasmMsg,
 };
 use cw20::Cw20ExecuteMsg;
@@ -331,7 +331,7 @@ fn test_asset_to_raw() {
         AssetRaw {
             amount: Uint128::from(1u128),
             info: AssetInfoRaw::Token {
-                contract_addr: deps.api.addr_canonicalize(&deps.api.addr_make("contract0000").to_string()).unwrap()
+                contract_addr: deps.api.addr_canonicalize(deps.api.addr_make("contract0000").as_ref()).unwrap()
             }
         }
     );
@@ -358,18 +358,18 @@ fn test_asset_info_raw_equal() {
 
     let deps = mock_dependencies(&[]);
     assert!(!native_asset_info_raw.equal(&AssetInfoRaw::Token {
-        contract_addr: deps.api.addr_canonicalize(&deps.api.addr_make("contract0000").to_string()).unwrap()
+        contract_addr: deps.api.addr_canonicalize(deps.api.addr_make("contract0000").as_ref()).unwrap()
     }));
 
     let token_asset_info_raw = AssetInfoRaw::Token {
-        contract_addr: deps.api.addr_canonicalize(&deps.api.addr_make("contract0000").to_string()).unwrap(),
+        contract_addr: deps.api.addr_canonicalize(deps.api.addr_make("contract0000").as_ref()).unwrap(),
     };
     assert!(token_asset_info_raw.equal(&AssetInfoRaw::Token {
-        contract_addr: deps.api.addr_canonicalize(&deps.api.addr_make("contract0000").to_string()).unwrap()
+        contract_addr: deps.api.addr_canonicalize(deps.api.addr_make("contract0000").as_ref()).unwrap()
     }));
 
     assert!(!token_asset_info_raw.equal(&AssetInfoRaw::Token {
-        contract_addr: deps.api.addr_canonicalize(&deps.api.addr_make("contract000").to_string()).unwrap()
+        contract_addr: deps.api.addr_canonicalize(deps.api.addr_make("contract000").as_ref()).unwrap()
     }));
 
     assert!(!token_asset_info_raw.equal(&AssetInfoRaw::NativeToken {
@@ -397,7 +397,10 @@ fn query_choice_pair_contract() {
                 liquidity_token: deps.api.addr_make("liquidity0000").to_string(),
                 asset_decimals: [6u8, 6u8],
                 burn_address: deps.api.addr_make("burn0000").to_string(),
-                fee_wallet_address: deps.api.addr_make("fee_wallet_address0000").to_string()
+                fee_wallet_address: deps.api.addr_make("fee_wallet_address0000").to_string(),
+                commission_rate: Decimal::permille(3),
+                pair_type: PairType::Xyk {},
+                paused: false,
             },
         )],
         &[("uusd".to_string(), 6u8)],
@@ -0,0 +1,96 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Decimal, Uint128};
+
+use crate::asset::AssetInfo;
+use crate::router::SwapOperation;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct InstantiateMsg {
+    /// Farm staking the liquidity token of `pair`
+    pub farm: String,
+    pub pair: String,
+    pub router: String,
+    /// Share of the claimed rewards paid to the `Compound` caller
+    pub bounty_rate: Decimal,
+    /// Spread allowed on the compound swaps, and on the liquidity provided
+    /// against its value at the pair TWAP
+    pub max_spread: Decimal,
+    pub compound_routes: Vec<CompoundRoute>,
+}
+
+/// Router operations swapping a reward token to one of the pair assets,
+/// hops without their own max spread use the vault one
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct CompoundRoute {
+    pub reward_token: AssetInfo,
+    pub operations: Vec<SwapOperation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Bond the attached liquidity tokens in the farm and mint vault shares,
+    /// compounding the rewards earned so far first
+    Deposit {},
+    /// Burn the attached vault shares and pay out their liquidity tokens
+    Withdraw {},
+    /// Claim the farm rewards, swap them to the pair assets, provide
+    /// liquidity and bond it back, paying the bounty to the caller
+    Compound {},
+    /// UpdateConfig sets the bounty rate, the max spread and the
+    /// compound routes, contract admin only
+    UpdateConfig {
+        bounty_rate: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        compound_routes: Option<Vec<CompoundRoute>>,
+    },
+
+    /// Internal use
+    Callback(CallbackMsg),
+}
+
+/// Compound steps run by the vault on itself once the previous step settled
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CallbackMsg {
+    /// Pay the bounty out of the claimed rewards and route the rest
+    /// to the pair assets
+    SwapRewards { caller: String },
+    /// Provide the pair assets held as liquidity
+    ProvideLiquidity {},
+    /// Bond the liquidity tokens held in the farm
+    Bond {},
+    /// Mint the shares of a deposit bonded by `Bond`, priced once the
+    /// rewards earned before it compounded
+    MintShares { depositor: String, amount: Uint128 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    State {},
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ConfigResponse {
+    pub farm: String,
+    pub pair: String,
+    pub router: String,
+    pub lp_denom: String,
+    pub share_denom: String,
+    pub bounty_rate: Decimal,
+    pub max_spread: Decimal,
+    pub compound_routes: Vec<CompoundRoute>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct StateResponse {
+    pub total_shares: Uint128,
+    /// Liquidity tokens the vault has bonded in the farm
+    pub total_bond_amount: Uint128,
+}